@@ -0,0 +1,38 @@
+//! Notes imported from an [Anki] deck package (`.apkg`) or collection package
+//! (`.colpkg`).
+//!
+//! Anki notes have no fixed schema, so when importing, the user chooses which
+//! fields of the note hold the headword, reading, definition and audio.
+//!
+//! [Anki]: https://apps.ankiweb.net/
+
+use {
+    super::yomichan_audio::Audio,
+    serde::{Deserialize, Serialize},
+};
+
+/// Definition of a term, taken from a field of an Anki note.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct Note {
+    /// Name of the note type that this note uses, e.g. `Basic`.
+    pub note_type: String,
+    /// HTML content of the definition field.
+    ///
+    /// This may reference images from the deck's media, which are imported
+    /// as dictionary media.
+    pub definition: String,
+    /// Tags which the note was tagged with in Anki.
+    pub tags: Vec<String>,
+}
+
+/// Pronunciation audio of a term, taken from a `[sound:...]` reference in a
+/// field of an Anki note.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct NoteAudio {
+    /// Audio data.
+    pub audio: Audio,
+}
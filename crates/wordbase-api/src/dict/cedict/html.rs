@@ -0,0 +1,63 @@
+use {
+    super::Meaning,
+    crate::dict::zho::Tone,
+    maud::{Markup, html},
+};
+
+/// Renders a [`Meaning`] to HTML.
+///
+/// The pinyin is rendered before the glosses, with each syllable colored by
+/// its tone. If the traditional and simplified forms of the word differ, both
+/// are shown.
+#[must_use]
+pub fn render_html(meaning: &Meaning) -> Markup {
+    html! {
+        span lang="zh-Latn-pinyin" {
+            @for (index, syllable) in meaning.pinyin.iter().enumerate() {
+                @if index > 0 {
+                    " "
+                }
+                span style=[syllable.tone.map(tone_style)] { (syllable.text) }
+            }
+        }
+        @if meaning.traditional != meaning.simplified {
+            " "
+            span lang="zh" style="opacity: 55%" {
+                (meaning.traditional) "｜" (meaning.simplified)
+            }
+        }
+        br;
+        (meaning.glosses.join("; "))
+    }
+}
+
+fn tone_style(tone: Tone) -> &'static str {
+    match tone {
+        Tone::First => "color: #e30000",
+        Tone::Second => "color: #02b31c",
+        Tone::Third => "color: #1510f0",
+        Tone::Fourth => "color: #8900bf",
+        Tone::Neutral => "color: #777777",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::dict::zho::pinyin};
+
+    #[test]
+    fn meaning() {
+        let meaning = Meaning {
+            traditional: "中國".into(),
+            simplified: "中国".into(),
+            pinyin: pinyin("Zhong1 guo2"),
+            glosses: vec!["China".into()],
+        };
+        assert_eq!(
+            "<span lang=\"zh-Latn-pinyin\"><span style=\"color: #e30000\">Zhōng</span> \
+             <span style=\"color: #02b31c\">guó</span></span> \
+             <span lang=\"zh\" style=\"opacity: 55%\">中國｜中国</span><br>China",
+            render_html(&meaning).0
+        );
+    }
+}
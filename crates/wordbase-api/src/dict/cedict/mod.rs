@@ -0,0 +1,40 @@
+//! [CC-CEDICT] Chinese-English dictionary format.
+//!
+//! See the [CC-CEDICT syntax] for details on how entries are stored.
+//!
+//! [CC-CEDICT]: https://www.mdbg.net/chinese/dictionary?page=cedict
+//! [CC-CEDICT syntax]: https://cc-cedict.org/wiki/format:syntax
+
+#[cfg(feature = "render-html")]
+mod html;
+#[cfg(feature = "render-html")]
+pub use html::render_html;
+
+use {
+    super::zho::Syllable,
+    serde::{Deserialize, Serialize},
+};
+
+/// Single line of the dictionary, storing all meanings of a word with a
+/// specific pronunciation.
+///
+/// The same record is linked to both the traditional and simplified forms of
+/// the word.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct Meaning {
+    /// Word written in traditional characters.
+    pub traditional: String,
+    /// Word written in simplified characters.
+    ///
+    /// This may be the same as [`Meaning::traditional`].
+    pub simplified: String,
+    /// Pronunciation of the word, with one syllable per character.
+    pub pinyin: Vec<Syllable>,
+    /// English definitions of the word, in the order given by the dictionary.
+    ///
+    /// These may contain references to other words in CC-CEDICT's
+    /// `traditional|simplified[pin1 yin1]` format, e.g. `CL:個|个[ge4]`.
+    pub glosses: Vec<String>,
+}
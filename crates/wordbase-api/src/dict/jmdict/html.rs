@@ -0,0 +1,101 @@
+use {
+    super::{Name, Sense, Tag},
+    itertools::Itertools,
+    maud::{Markup, html},
+};
+
+/// Renders a single [`Sense`] of a [`Word`] to HTML.
+///
+/// Tags are rendered as pills before the glosses, and cross-references are
+/// rendered after them.
+///
+/// [`Word`]: super::Word
+#[must_use]
+pub fn render_sense_html(sense: &Sense) -> Markup {
+    let restrictions = sense
+        .restrict_headwords
+        .iter()
+        .chain(&sense.restrict_readings)
+        .join("、");
+    html! {
+        (tags(sense.pos.iter().chain(&sense.field).chain(&sense.misc).chain(&sense.dialect)))
+        @if !restrictions.is_empty() {
+            span style="opacity: 55%" { "※" (restrictions) " " }
+        }
+        (sense.glosses.iter().map(|gloss| &gloss.text).join("; "))
+        @for info in &sense.info {
+            br;
+            small style="opacity: 55%" { (info) }
+        }
+        (refs("→", &sense.xref))
+        (refs("⇔", &sense.antonyms))
+    }
+}
+
+/// Renders all translations of a [`Name`] to HTML.
+#[must_use]
+pub fn render_name_html(name: &Name) -> Markup {
+    html! {
+        @for translation in &name.translations {
+            div {
+                (tags(translation.name_type.iter()))
+                (translation.details.join("; "))
+                (refs("→", &translation.xref))
+            }
+        }
+    }
+}
+
+fn tags<'a>(tags: impl Iterator<Item = &'a Tag>) -> Markup {
+    html! {
+        @for tag in tags {
+            span class="pill" title=(tag.description) {
+                span class="text" { (tag.code) }
+            }
+            " "
+        }
+    }
+}
+
+fn refs(prefix: &str, refs: &[String]) -> Markup {
+    html! {
+        @if !refs.is_empty() {
+            br;
+            small style="opacity: 55%" { (prefix) " " (refs.join("、")) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::dict::jmdict::Gloss};
+
+    #[test]
+    fn sense() {
+        let sense = Sense {
+            pos: vec![Tag {
+                code: "n".into(),
+                description: "noun (common) (futsuumeishi)".into(),
+            }],
+            xref: vec!["猫・ねこ・1".into()],
+            glosses: vec![
+                Gloss {
+                    text: "cat".into(),
+                    lang: "eng".into(),
+                    kind: None,
+                },
+                Gloss {
+                    text: "<feline>".into(),
+                    lang: "eng".into(),
+                    kind: None,
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            r#"<span class="pill" title="noun (common) (futsuumeishi)"><span class="text">n</span></span> cat; &lt;feline&gt;<br><small style="opacity: 55%">→ 猫・ねこ・1</small>"#,
+            render_sense_html(&sense).0
+        );
+    }
+}
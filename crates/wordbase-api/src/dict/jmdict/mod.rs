@@ -0,0 +1,127 @@
+//! [JMdict] and [JMnedict] dictionary formats, imported directly from the
+//! EDRDG XML releases.
+//!
+//! See the [JMdict DTD] for the meaning of each element.
+//!
+//! [JMdict]: https://www.edrdg.org/jmdict/j_jmdict.html
+//! [JMnedict]: https://www.edrdg.org/enamdict/enamdict_doc.html
+//! [JMdict DTD]: https://www.edrdg.org/jmdict/jmdict_dtd_h.html
+
+#[cfg(feature = "render-html")]
+mod html;
+#[cfg(feature = "render-html")]
+pub use html::{render_name_html, render_sense_html};
+
+use serde::{Deserialize, Serialize};
+
+/// Single JMdict entry (`<entry>`), storing all of the senses of a word.
+///
+/// The same record is linked to every valid headword/reading pair of the
+/// entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct Word {
+    /// Unique sequence number of the entry (`<ent_seq>`).
+    ///
+    /// This is stable across JMdict releases, so it can be used to link to
+    /// the entry on other sites, or to group records which come from the same
+    /// entry.
+    pub ent_seq: u64,
+    /// Senses (`<sense>`) of this entry, in the order given by the dictionary.
+    pub senses: Vec<Sense>,
+}
+
+/// Single meaning of a [`Word`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct Sense {
+    /// If non-empty, this sense only applies to these headwords (`<stagk>`).
+    pub restrict_headwords: Vec<String>,
+    /// If non-empty, this sense only applies to these readings (`<stagr>`).
+    pub restrict_readings: Vec<String>,
+    /// Part-of-speech tags (`<pos>`).
+    ///
+    /// JMdict only lists these on the first sense of a run of senses which
+    /// share the same parts of speech. On import, these are copied into every
+    /// sense, so this is never empty unless the entry has no parts of speech
+    /// at all.
+    pub pos: Vec<Tag>,
+    /// Field of application tags (`<field>`), e.g. `comp` for computing.
+    pub field: Vec<Tag>,
+    /// Miscellaneous tags (`<misc>`), e.g. `uk` for "usually written using
+    /// kana alone".
+    pub misc: Vec<Tag>,
+    /// Dialect tags (`<dial>`), e.g. `ksb` for Kansai-ben.
+    pub dialect: Vec<Tag>,
+    /// Cross-references to other entries (`<xref>`), in JMdict's
+    /// `headword・reading・sense number` format.
+    pub xref: Vec<String>,
+    /// Antonyms of this sense (`<ant>`), in the same format as
+    /// [`Sense::xref`].
+    pub antonyms: Vec<String>,
+    /// Extra information about this sense (`<s_inf>`).
+    pub info: Vec<String>,
+    /// Glosses (`<gloss>`) of this sense in all target languages present in
+    /// the release.
+    pub glosses: Vec<Gloss>,
+}
+
+/// Entity-based tag used in a [`Sense`] or [`Translation`].
+///
+/// JMdict represents these as XML entities, e.g. `&n;` which expands to
+/// `noun (common) (futsuumeishi)`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct Tag {
+    /// Short code of this tag (the entity name), e.g. `n`.
+    pub code: String,
+    /// Human-readable description of this tag (the entity expansion), e.g.
+    /// `noun (common) (futsuumeishi)`.
+    pub description: String,
+}
+
+/// Single translation of a [`Sense`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct Gloss {
+    /// Translated text.
+    pub text: String,
+    /// ISO 639-2 code of the language that [`Gloss::text`] is written in.
+    ///
+    /// If the release does not specify a language, this is `eng`.
+    pub lang: String,
+    /// Type of gloss (`g_type`), e.g. `lit` for a literal translation, or
+    /// `expl` for an explanation.
+    pub kind: Option<String>,
+}
+
+/// Single JMnedict entry (`<entry>`), storing the translations of a proper
+/// noun.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct Name {
+    /// Unique sequence number of the entry (`<ent_seq>`).
+    ///
+    /// See [`Word::ent_seq`].
+    pub ent_seq: u64,
+    /// Translations (`<trans>`) of this entry.
+    pub translations: Vec<Translation>,
+}
+
+/// Single translation of a [`Name`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct Translation {
+    /// What kind of name this is (`<name_type>`), e.g. `surname` or `place`.
+    pub name_type: Vec<Tag>,
+    /// Cross-references to other entries (`<xref>`).
+    pub xref: Vec<String>,
+    /// Translated text (`<trans_det>`).
+    pub details: Vec<String>,
+}
@@ -0,0 +1,48 @@
+//! [KANJIDIC2] kanji dictionary, imported directly from the EDRDG XML release.
+//!
+//! See the [KANJIDIC2 DTD] for the meaning of each element.
+//!
+//! [KANJIDIC2]: https://www.edrdg.org/wiki/index.php/KANJIDIC_Project
+//! [KANJIDIC2 DTD]: https://www.edrdg.org/kanjidic/kanjidic2_dtdh.html
+
+use serde::{Deserialize, Serialize};
+
+/// Single KANJIDIC2 entry (`<character>`), storing information about one
+/// kanji.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct Character {
+    /// The kanji itself (`<literal>`).
+    pub literal: String,
+    /// On'yomi readings, in katakana (`<reading r_type="ja_on">`).
+    pub onyomi: Vec<String>,
+    /// Kun'yomi readings, in hiragana (`<reading r_type="ja_kun">`).
+    ///
+    /// Okurigana is separated from the reading by a `.`, e.g. `あたら.しい`.
+    pub kunyomi: Vec<String>,
+    /// Readings only used in names (`<nanori>`).
+    pub nanori: Vec<String>,
+    /// English meanings (`<meaning>` without an `m_lang`).
+    pub meanings: Vec<String>,
+    /// School grade in which this kanji is taught (`<grade>`).
+    ///
+    /// - 1 to 6: grades of elementary school
+    /// - 8: the rest of the Jōyō kanji
+    /// - 9 and 10: Jinmeiyō kanji, used in names
+    pub grade: Option<u8>,
+    /// Level of the pre-2010 Japanese Language Proficiency Test which this
+    /// kanji is in (`<jlpt>`), from 4 (easiest) to 1 (hardest).
+    pub jlpt: Option<u8>,
+    /// Number of strokes needed to write this kanji (`<stroke_count>`).
+    ///
+    /// KANJIDIC2 may also list common miscounts, but this is always the
+    /// accepted count.
+    pub stroke_count: Option<u8>,
+    /// Rank of this kanji among the 2,500 most used kanji in newspapers
+    /// (`<freq>`), where 1 is the most used.
+    pub frequency: Option<u32>,
+    /// Number of this kanji's radical in the classical Kangxi system
+    /// (`<rad_value rad_type="classical">`).
+    pub radical: Option<u8>,
+}
@@ -0,0 +1,26 @@
+//! [MDict] dictionary format.
+//!
+//! MDict dictionaries are distributed as an `.mdx` file, storing the HTML
+//! entries, and optionally one or more `.mdd` files, storing the resources
+//! (images, stylesheets, audio, etc.) used by those entries. Resources are
+//! stored as dictionary media, and entries reference them by path.
+//!
+//! [MDict]: https://www.mdict.cn
+
+use serde::{Deserialize, Serialize};
+
+/// Single entry in an `.mdx` file.
+///
+/// Entries which are only links to other entries (`@@@LINK=...`) are not
+/// stored as their own records - instead, the linked entry's record is also
+/// linked to the term of the linking entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct Entry {
+    /// HTML content of this entry.
+    ///
+    /// This is the raw HTML from the dictionary, which may reference media by
+    /// relative path, e.g. `<img src="images/cat.png">`.
+    pub html: String,
+}
@@ -1,5 +1,14 @@
 //! Supported dictionary kinds.
 
+pub mod anki;
+pub mod cedict;
+pub mod jmdict;
 pub mod jpn;
+pub mod kanjidic;
+pub mod mdict;
+pub mod stardict;
+pub mod tatoeba;
+pub mod wiktionary;
 pub mod yomichan_audio;
 pub mod yomitan;
+pub mod zho;
@@ -0,0 +1,50 @@
+use {
+    super::{Definition, DefinitionPart},
+    maud::{Markup, PreEscaped, html},
+};
+
+/// Renders all parts of a [`Definition`] to HTML.
+///
+/// Pango markup is mostly a subset of HTML (`<b>`, `<i>`, `<sub>`, etc.), so
+/// it is passed through as-is.
+#[must_use]
+pub fn render_html(definition: &Definition) -> Markup {
+    html! {
+        @for part in &definition.parts {
+            @match part {
+                DefinitionPart::Text(text) => {
+                    div style="white-space: pre-wrap" { (text) }
+                }
+                DefinitionPart::Pango(markup) => {
+                    div style="white-space: pre-wrap" { (PreEscaped(markup)) }
+                }
+                DefinitionPart::Html(markup) => {
+                    div { (PreEscaped(markup)) }
+                }
+                DefinitionPart::Phonetic(text) => {
+                    div style="opacity: 55%" { "[" (text) "]" }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parts() {
+        let definition = Definition {
+            parts: vec![
+                DefinitionPart::Phonetic("kæt".into()),
+                DefinitionPart::Text("a <small> mammal".into()),
+                DefinitionPart::Html("<b>cat</b>".into()),
+            ],
+        };
+        assert_eq!(
+            r#"<div style="opacity: 55%">[kæt]</div><div style="white-space: pre-wrap">a &lt;small&gt; mammal</div><div><b>cat</b></div>"#,
+            render_html(&definition).0
+        );
+    }
+}
@@ -0,0 +1,44 @@
+//! [StarDict] dictionary format.
+//!
+//! See the [StarDict file format] for details on how entries are stored.
+//!
+//! [StarDict]: https://github.com/huzheng001/stardict-3
+//! [StarDict file format]: https://github.com/huzheng001/stardict-3/blob/master/dict/doc/StarDictFileFormat
+
+#[cfg(feature = "render-html")]
+mod html;
+#[cfg(feature = "render-html")]
+pub use html::render_html;
+
+use serde::{Deserialize, Serialize};
+
+/// Single entry in the `.dict` file, pointed to by a word in the `.idx` file.
+///
+/// Words from the `.syn` file are linked to the same record as the word they
+/// are a synonym of.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct Definition {
+    /// Typed data fields of this entry, in the order that they are stored.
+    ///
+    /// Binary fields (sounds, pictures, etc.) are not stored.
+    pub parts: Vec<DefinitionPart>,
+}
+
+/// Single typed data field of a [`Definition`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+pub enum DefinitionPart {
+    /// Plain text (`m` or `l`).
+    Text(String),
+    /// [Pango markup] (`g`).
+    ///
+    /// [Pango markup]: https://docs.gtk.org/Pango/pango_markup.html
+    Pango(String),
+    /// HTML (`h`).
+    Html(String),
+    /// Pronunciation of the word (`t` for English phonetics, `y` for Chinese
+    /// pinyin or Japanese kana).
+    Phonetic(String),
+}
@@ -0,0 +1,40 @@
+//! [Tatoeba] example sentences, imported from the sentence and link exports.
+//!
+//! See the [Tatoeba downloads page] for a description of each export file.
+//!
+//! [Tatoeba]: https://tatoeba.org/
+//! [Tatoeba downloads page]: https://tatoeba.org/en/downloads
+
+use serde::{Deserialize, Serialize};
+
+/// Single sentence, along with its translations into other languages.
+///
+/// Sentences are not linked to terms like other records, so they never show
+/// up as the result of a lookup. Instead, they are indexed by the words they
+/// contain, and fetched using `Engine::example_sentences`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct Sentence {
+    /// Tatoeba ID of this sentence.
+    pub id: u64,
+    /// ISO 639-3 code of the language that this sentence is in, e.g. `jpn`.
+    pub lang: String,
+    /// Text of the sentence.
+    pub text: String,
+    /// Translations of this sentence which were included in the export.
+    pub translations: Vec<SentenceTranslation>,
+}
+
+/// Translation of a [`Sentence`] into another language.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct SentenceTranslation {
+    /// Tatoeba ID of the translated sentence.
+    pub id: u64,
+    /// ISO 639-3 code of the language of the translation.
+    pub lang: String,
+    /// Text of the translated sentence.
+    pub text: String,
+}
@@ -0,0 +1,60 @@
+use {
+    super::Lexeme,
+    maud::{Markup, html},
+};
+
+/// Renders a [`Lexeme`] to HTML.
+///
+/// The part of speech is rendered first, then a numbered list of senses with
+/// their qualifiers and examples, and finally the etymology.
+#[must_use]
+pub fn render_html(lexeme: &Lexeme) -> Markup {
+    html! {
+        i { (lexeme.pos) }
+        ol {
+            @for sense in &lexeme.senses {
+                li {
+                    @if !sense.tags.is_empty() {
+                        span style="opacity: 55%" { "(" (sense.tags.join(", ")) ") " }
+                    }
+                    (sense.glosses.join(": "))
+                    @if !sense.examples.is_empty() {
+                        ul {
+                            @for example in &sense.examples {
+                                li style="opacity: 75%" { (example) }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        @if let Some(etymology) = &lexeme.etymology {
+            div style="opacity: 55%; font-size: 0.9em" { (etymology) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::dict::wiktionary::LexemeSense};
+
+    #[test]
+    fn lexeme() {
+        let lexeme = Lexeme {
+            word: "run".into(),
+            pos: "verb".into(),
+            etymology: Some("From Old English rinnan.".into()),
+            senses: vec![LexemeSense {
+                glosses: vec!["To move swiftly.".into()],
+                tags: vec!["intransitive".into()],
+                examples: vec!["I run every day.".into()],
+            }],
+        };
+        assert_eq!(
+            "<i>verb</i><ol><li><span style=\"opacity: 55%\">(intransitive) </span>\
+             To move swiftly.<ul><li style=\"opacity: 75%\">I run every day.</li></ul></li></ol>\
+             <div style=\"opacity: 55%; font-size: 0.9em\">From Old English rinnan.</div>",
+            render_html(&lexeme).0
+        );
+    }
+}
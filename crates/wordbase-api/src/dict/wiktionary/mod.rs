@@ -0,0 +1,50 @@
+//! [Wiktionary] entries, imported from the machine-readable JSONL dumps on
+//! [kaikki.org], which are extracted from Wiktionary by [Wiktextract].
+//!
+//! Pronunciations from the `sounds` of an entry are stored separately, as
+//! [`yomitan::Phonetic`] records.
+//!
+//! [Wiktionary]: https://www.wiktionary.org/
+//! [kaikki.org]: https://kaikki.org/
+//! [Wiktextract]: https://github.com/tatuylonen/wiktextract
+//! [`yomitan::Phonetic`]: crate::dict::yomitan::Phonetic
+
+#[cfg(feature = "render-html")]
+mod html;
+#[cfg(feature = "render-html")]
+pub use html::render_html;
+
+use serde::{Deserialize, Serialize};
+
+/// Single word entry, describing one part of speech of a word in one language.
+///
+/// The same record is linked to the word itself, and to every inflected form
+/// of the word listed in the entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct Lexeme {
+    /// Word in its dictionary form.
+    pub word: String,
+    /// Part of speech, e.g. `noun` or `verb`.
+    pub pos: String,
+    /// Description of where the word comes from (`etymology_text`).
+    pub etymology: Option<String>,
+    /// Meanings of the word, in the order given by Wiktionary.
+    pub senses: Vec<LexemeSense>,
+}
+
+/// Single meaning of a [`Lexeme`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct LexemeSense {
+    /// Definitions of this meaning.
+    ///
+    /// For a sub-sense, the definition of its parent sense comes first.
+    pub glosses: Vec<String>,
+    /// Qualifiers of this meaning, e.g. `informal` or `transitive`.
+    pub tags: Vec<String>,
+    /// Example sentences using the word in this meaning.
+    pub examples: Vec<String>,
+}
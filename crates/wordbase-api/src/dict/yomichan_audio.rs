@@ -91,6 +91,46 @@ pub struct Shinmeikai8 {
     pub pitch_pattern: Option<NormString>,
 }
 
+/// [Daijisen] audio.
+///
+/// [Daijisen]: https://en.wikipedia.org/wiki/Daijisen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct Daijisen {
+    /// Audio data.
+    pub audio: Audio,
+    /// Pitch position of this pronunciation.
+    ///
+    /// See [`yomitan::Pitch::position`].
+    ///
+    /// [`yomitan::Pitch::position`]: crate::dict::yomitan::Pitch::position
+    pub pitch_number: Option<PitchPosition>,
+    /// Pitch pattern of this pronunciation.
+    ///
+    /// The downstep is indicated by a `＼`, for example `読＼む`.
+    pub pitch_pattern: Option<NormString>,
+}
+
+/// [Oubunsha] Japanese dictionary audio.
+///
+/// [Oubunsha]: https://en.wikipedia.org/wiki/Obunsha
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct Oubunsha {
+    /// Audio data.
+    pub audio: Audio,
+    /// Pitch position of this pronunciation.
+    ///
+    /// See [`yomitan::Pitch::position`].
+    ///
+    /// [`yomitan::Pitch::position`]: crate::dict::yomitan::Pitch::position
+    pub pitch_number: Option<PitchPosition>,
+    /// Pitch pattern of this pronunciation.
+    ///
+    /// The downstep is indicated by a `＼`, for example `読＼む`.
+    pub pitch_pattern: Option<NormString>,
+}
+
 #[cfg(feature = "uniffi")]
 const _: () = {
     use data_encoding::BASE64;
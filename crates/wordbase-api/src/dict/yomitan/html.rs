@@ -1,5 +1,5 @@
 use {
-    super::structured::{Content, ContentStyle, Element},
+    super::structured::{Content, ContentStyle, Element, ImageElement, SizeUnits},
     itertools::Itertools,
     maud::{Markup, PreEscaped, Render, html},
     std::fmt,
@@ -161,24 +161,10 @@ impl Render for Rendering<'_, Element> {
             Element::Details(elem) => styled!(elem, details),
             Element::Summary(elem) => styled!(elem, summary),
             Element::Img(elem) => html! {
-                img path=(elem.base.path)
-                    width=[elem.base.width]
-                    height=[elem.base.height]
-                    preferred-width=[elem.base.preferred_width]
-                    preferred-height=[elem.base.preferred_height]
+                img src=(elem.base.path)
                     title=[&elem.base.title]
-                    alt=[&elem.base.alt]
-                    description=[&elem.base.description]
-                    pixelated=[elem.base.pixelated]
-                    image-rendering=[elem.base.image_rendering]
-                    image-appearance=[elem.base.image_appearance]
-                    background=[elem.base.background]
-                    collapsed=[elem.base.collapsed]
-                    collapsible=[elem.base.collapsible]
-                    vertical-align=[elem.vertical_align]
-                    border=[&(elem.border)]
-                    border-radius=[&(elem.border_radius)]
-                    size-units=[elem.size_units];
+                    alt=[elem.base.alt.as_ref().or(elem.base.description.as_ref())]
+                    style=(img_style_css(elem));
             },
             Element::A(elem) => html! {
                 a href=(elem.href) lang=[&elem.lang] {
@@ -195,6 +181,40 @@ fn style_css(s: &ContentStyle) -> String {
     css
 }
 
+/// Converts the image-specific sizing and appearance fields into CSS, since
+/// `<img>` has no attributes for most of them.
+fn img_style_css(elem: &ImageElement) -> String {
+    let mut css = String::new();
+    _ = write_img_style_css(&mut css, elem);
+    css
+}
+
+fn write_img_style_css(w: &mut impl fmt::Write, elem: &ImageElement) -> fmt::Result {
+    let base = &elem.base;
+    let units = elem.size_units.unwrap_or(SizeUnits::Px);
+    if let Some(width) = base.preferred_width.or(base.width) {
+        write!(w, "width:{width}{units};")?;
+    }
+    if let Some(height) = base.preferred_height.or(base.height) {
+        write!(w, "height:{height}{units};")?;
+    }
+    if base.pixelated == Some(true) {
+        write!(w, "image-rendering:pixelated;")?;
+    } else if let Some(image_rendering) = base.image_rendering {
+        write!(w, "image-rendering:{image_rendering};")?;
+    }
+    if let Some(vertical_align) = elem.vertical_align {
+        write!(w, "vertical-align:{vertical_align};")?;
+    }
+    if let Some(border) = &elem.border {
+        write!(w, "border:{border};")?;
+    }
+    if let Some(border_radius) = &elem.border_radius {
+        write!(w, "border-radius:{border_radius};")?;
+    }
+    Ok(())
+}
+
 #[expect(
     clippy::cognitive_complexity,
     reason = "macro invocations lead to internal cognitive complexity"
@@ -258,7 +278,7 @@ fn write_style_css(w: &mut impl fmt::Write, s: &ContentStyle) -> fmt::Result {
 
 #[cfg(test)]
 mod tests {
-    use crate::dict::yomitan::structured::StyledElement;
+    use crate::dict::yomitan::structured::{ImageElementBase, StyledElement};
 
     use super::*;
 
@@ -279,6 +299,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn img() {
+        let img = Element::Img(ImageElement {
+            base: ImageElementBase {
+                path: "img/diagram.png".into(),
+                width: Some(4.0),
+                preferred_width: Some(2.5),
+                alt: Some("diagram".into()),
+                pixelated: Some(true),
+                ..Default::default()
+            },
+            size_units: Some(SizeUnits::Em),
+            ..Default::default()
+        });
+        let content = Content::Element(Box::new(img));
+
+        assert_eq!(
+            r#"<img src="img/diagram.png" alt="diagram" style="width:2.5em;image-rendering:pixelated;">"#,
+            render_html(&content).0
+        );
+    }
+
     #[test]
     fn li_in_li() {
         fn li(elem: StyledElement) -> Content {
@@ -33,6 +33,23 @@ pub struct Glossary {
     pub tags: Vec<GlossaryTag>,
     /// Structured glossary content.
     pub content: Vec<structured::Content>,
+    /// Tags applied to the term itself rather than this glossary, such as
+    /// `common` or `ateji`.
+    #[serde(default)]
+    pub term_tags: Vec<GlossaryTag>,
+    /// Part-of-speech rules used by Yomitan to validate deinflections, such as
+    /// `v5` or `adj-i`.
+    #[serde(default)]
+    pub rules: Vec<String>,
+    /// Sequence number which groups glossaries belonging to the same entry.
+    ///
+    /// Dictionaries converted from [JMdict] use the JMdict entry sequence
+    /// number here, so glossaries with the same sequence describe the same
+    /// word.
+    ///
+    /// [JMdict]: https://www.edrdg.org/jmdict/j_jmdict.html
+    #[serde(default)]
+    pub sequence: i64,
 }
 
 /// How often this term appears in this dictionary's corpus.
@@ -82,6 +99,92 @@ pub struct Pitch {
     pub devoice: Vec<PitchPosition>,
 }
 
+/// Phonetic transcription of a term in the [International Phonetic Alphabet].
+///
+/// [International Phonetic Alphabet]: https://en.wikipedia.org/wiki/International_Phonetic_Alphabet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct Phonetic {
+    /// IPA transcription, as written by the dictionary, e.g. `/həˈloʊ/`.
+    pub ipa: String,
+    /// Tags applied to this transcription, such as the dialect it is
+    /// pronounced in.
+    pub tags: Vec<GlossaryTag>,
+}
+
+/// Marks a term as an inflected form of another term.
+///
+/// Dictionaries for languages without built-in deinflection support may ship
+/// their own inflection tables as these. When the engine finds a redirect
+/// during a lookup, it looks up [`Redirect::uninflected`] instead, and sets
+/// [`RecordEntry::inflection_rule_chain`] on the records it finds.
+///
+/// [`RecordEntry::inflection_rule_chain`]: crate::RecordEntry::inflection_rule_chain
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct Redirect {
+    /// Uninflected form of the term, e.g. `run` for `running`.
+    pub uninflected: String,
+    /// Names of the inflection rules applied to [`Redirect::uninflected`] to
+    /// get the inflected term, in the order they were applied.
+    pub rule_chain: Vec<String>,
+}
+
+/// Information about a single kanji character, such as its readings and
+/// meanings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct Kanji {
+    /// On'yomi (Chinese-derived) readings of this kanji, usually in katakana.
+    pub onyomi: Vec<String>,
+    /// Kun'yomi (native Japanese) readings of this kanji, usually in
+    /// hiragana.
+    pub kunyomi: Vec<String>,
+    /// Tags applied to this kanji, such as its school grade.
+    pub tags: Vec<GlossaryTag>,
+    /// What this kanji means, written in the dictionary's source language.
+    pub meanings: Vec<String>,
+    /// Miscellaneous statistics about this kanji, such as its stroke count or
+    /// index in other dictionaries.
+    ///
+    /// These are sorted by [`GlossaryTag::order`].
+    pub stats: Vec<KanjiStat>,
+}
+
+/// Single statistic about a [`Kanji`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct KanjiStat {
+    /// What this statistic represents.
+    ///
+    /// If the dictionary does not define a tag for this statistic, this only
+    /// has a [`GlossaryTag::name`].
+    pub tag: GlossaryTag,
+    /// Value of this statistic, e.g. `12` for a stroke count.
+    pub value: String,
+}
+
+/// How often a kanji appears in this dictionary's corpus.
+///
+/// This is the kanji equivalent of [`Frequency`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct KanjiFrequency {
+    /// Raw integer ranking value.
+    ///
+    /// See [`Frequency::value`].
+    pub value: Option<FrequencyValue>,
+    /// Human-readable display form of [`KanjiFrequency::value`].
+    ///
+    /// Prefer displaying this to users if one is present.
+    pub display: Option<String>,
+}
+
 /// Categorises a [`Glossary`] entry for a given [`Term`].
 ///
 /// [`Term`]: crate::Term
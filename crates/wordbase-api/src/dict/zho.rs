@@ -0,0 +1,196 @@
+//! Shared types and logic for Chinese dictionaries.
+
+use serde::{Deserialize, Serialize};
+
+/// Tone of a Mandarin syllable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+pub enum Tone {
+    /// 阴平: high and level, marked as `ā`.
+    First,
+    /// 阳平: rising, marked as `á`.
+    Second,
+    /// 上声: dipping, marked as `ǎ`.
+    Third,
+    /// 去声: falling, marked as `à`.
+    Fourth,
+    /// 轻声: light and short, unmarked.
+    Neutral,
+}
+
+impl Tone {
+    /// Gets the tone from its number in numbered pinyin, where `5` is the
+    /// neutral tone.
+    #[must_use]
+    pub const fn from_number(n: u8) -> Option<Self> {
+        match n {
+            1 => Some(Self::First),
+            2 => Some(Self::Second),
+            3 => Some(Self::Third),
+            4 => Some(Self::Fourth),
+            5 => Some(Self::Neutral),
+            _ => None,
+        }
+    }
+}
+
+/// Single syllable of [pinyin] romanization.
+///
+/// [pinyin]: https://en.wikipedia.org/wiki/Pinyin
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[serde(deny_unknown_fields)]
+pub struct Syllable {
+    /// Syllable with its tone written as a diacritic, e.g. `zhōng`.
+    pub text: String,
+    /// Tone of the syllable, if this is a syllable with a tone at all.
+    ///
+    /// Non-syllables like punctuation or Latin letters have no tone.
+    pub tone: Option<Tone>,
+}
+
+/// Converts space-separated numbered pinyin (e.g. `zhong1 guo2`) into
+/// syllables with tone marks.
+#[must_use]
+pub fn pinyin(numbered: &str) -> Vec<Syllable> {
+    numbered.split_whitespace().map(syllable).collect()
+}
+
+/// Converts a single syllable of numbered pinyin (e.g. `lu:4`) into one with a
+/// tone mark (`lǜ`).
+///
+/// `u:` is written as `ü`, and so is `v` in a syllable with a tone number
+/// (e.g. `lv4`). A `v` without a tone number is left alone, since CC-CEDICT
+/// uses these for Latin letters, like `V` in `V字领`. If there is no tone
+/// number, the syllable is returned with only `ü` replaced.
+#[must_use]
+pub fn syllable(numbered: &str) -> Syllable {
+    let (letters, tone) = match numbered.as_bytes().last() {
+        Some(&digit @ b'1'..=b'5') => (
+            &numbered[..numbered.len() - 1],
+            Tone::from_number(digit - b'0'),
+        ),
+        _ => (numbered, None),
+    };
+    let letters = letters.replace("u:", "ü").replace("U:", "Ü");
+    let letters = if tone.is_some() {
+        letters.replace('v', "ü").replace('V', "Ü")
+    } else {
+        letters
+    };
+
+    let Some(mark) = tone.and_then(|tone| match tone {
+        Tone::First => Some(0),
+        Tone::Second => Some(1),
+        Tone::Third => Some(2),
+        Tone::Fourth => Some(3),
+        Tone::Neutral => None,
+    }) else {
+        return Syllable {
+            text: letters,
+            tone,
+        };
+    };
+
+    // `a` and `e` always take the mark, then the `o` in `ou`,
+    // then whichever vowel comes last
+    let lower = letters.to_lowercase().chars().collect::<Vec<_>>();
+    let vowel_index = ['a', 'e']
+        .iter()
+        .find_map(|vowel| lower.iter().position(|c| c == vowel))
+        .or_else(|| lower.windows(2).position(|pair| pair == ['o', 'u']))
+        .or_else(|| lower.iter().rposition(|c| "iouü".contains(*c)));
+    let text = letters
+        .chars()
+        .enumerate()
+        .map(|(index, c)| {
+            if Some(index) == vowel_index {
+                marked(c, mark)
+            } else {
+                c
+            }
+        })
+        .collect();
+    Syllable { text, tone }
+}
+
+fn marked(vowel: char, mark: usize) -> char {
+    let marks = match vowel {
+        'a' => ['ā', 'á', 'ǎ', 'à'],
+        'e' => ['ē', 'é', 'ě', 'è'],
+        'i' => ['ī', 'í', 'ǐ', 'ì'],
+        'o' => ['ō', 'ó', 'ǒ', 'ò'],
+        'u' => ['ū', 'ú', 'ǔ', 'ù'],
+        'ü' => ['ǖ', 'ǘ', 'ǚ', 'ǜ'],
+        'A' => ['Ā', 'Á', 'Ǎ', 'À'],
+        'E' => ['Ē', 'É', 'Ě', 'È'],
+        'I' => ['Ī', 'Í', 'Ǐ', 'Ì'],
+        'O' => ['Ō', 'Ó', 'Ǒ', 'Ò'],
+        'U' => ['Ū', 'Ú', 'Ǔ', 'Ù'],
+        'Ü' => ['Ǖ', 'Ǘ', 'Ǚ', 'Ǜ'],
+        _ => return vowel,
+    };
+    marks[mark]
+}
+
+/// Checks if a character is a [CJK unified ideograph][han], which is how
+/// Chinese words are written.
+///
+/// [han]: https://en.wikipedia.org/wiki/CJK_Unified_Ideographs
+#[must_use]
+pub const fn is_han(c: char) -> bool {
+    matches!(c,
+        '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{f900}'..='\u{faff}'
+        | '\u{20000}'..='\u{2ebef}'
+        | '\u{30000}'..='\u{3134f}'
+        // 〇
+        | '\u{3007}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marked(numbered: &str) -> String {
+        pinyin(numbered)
+            .into_iter()
+            .map(|syllable| syllable.text)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn tone_marks() {
+        assert_eq!("zhōng guó", marked("zhong1 guo2"));
+        assert_eq!("hǎo", marked("hao3"));
+        assert_eq!("lǜ", marked("lu:4"));
+        assert_eq!("lǚ", marked("lv3"));
+        assert_eq!("duì", marked("dui4"));
+        assert_eq!("liù", marked("liu4"));
+        assert_eq!("gǒu", marked("gou3"));
+        assert_eq!("Běi jīng", marked("Bei3 jing1"));
+        assert_eq!("de", marked("de5"));
+        assert_eq!("r", marked("r5"));
+        assert_eq!("A", marked("A"));
+    }
+
+    #[test]
+    fn latin_letters() {
+        assert_eq!("V zì lǐng", marked("V zi4 ling3"));
+        assert_eq!("D V D", marked("D V D"));
+        assert_eq!("v", marked("v"));
+    }
+
+    #[test]
+    fn tones() {
+        assert_eq!(
+            vec![Some(Tone::First), Some(Tone::Neutral), None],
+            pinyin("ma1 ma5 ,")
+                .into_iter()
+                .map(|syllable| syllable.tone)
+                .collect::<Vec<_>>()
+        );
+    }
+}
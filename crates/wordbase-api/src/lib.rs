@@ -116,12 +116,53 @@ macro_rules! for_kinds { ($macro:ident) => { $macro!(
         Glossary,
         Frequency,
         Pitch,
+        Kanji,
+        KanjiFrequency,
+        Phonetic,
+        Redirect,
     },
     YomichanAudio(yomichan_audio) {
         Forvo,
         Jpod,
         Nhk16,
         Shinmeikai8,
+        Daijisen,
+        Oubunsha,
+    },
+    Jmdict(jmdict) {
+        Word,
+        Name,
+    },
+    Stardict(stardict) {
+        Definition,
+    },
+    Mdict(mdict) {
+        Entry,
+    },
+    // DSL markup is converted to structured content,
+    // so its records are stored as `YomitanGlossary`s
+    Dsl(dsl) {},
+    Cedict(cedict) {
+        Meaning,
+    },
+    Kanjidic(kanjidic) {
+        Character,
+    },
+    // KRADFILE only describes which radicals make up each kanji,
+    // which is stored in its own table rather than as records
+    Kradfile(kradfile) {},
+    Wiktionary(wiktionary) {
+        Lexeme,
+    },
+    Tatoeba(tatoeba) {
+        Sentence,
+    },
+    // plain frequency lists hold the same data as Yomitan frequency banks,
+    // so their records are stored as `YomitanFrequency`s
+    FrequencyList(frequency_list) {},
+    Anki(anki) {
+        Note,
+        NoteAudio,
     },
 ); } }
 
@@ -263,10 +304,9 @@ pub struct DictionaryMeta {
     pub kind: DictionaryKind,
     /// Human-readable display name.
     ///
-    /// This value is **not guaranteed to be unique** across all dictionaries,
-    /// however you may treat this as a stable identifier for a dictionary in
-    /// its unimported form (i.e. the archive itself), and use this to detect if
-    /// you attempt to import an already-imported dictionary.
+    /// This value is **not guaranteed to be unique** across all dictionaries.
+    /// To detect if you attempt to import an already-imported dictionary, use
+    /// [`DictionaryMeta::archive_hash`] and [`DictionaryMeta::index_hash`].
     pub name: String,
     /// Arbitrary version string.
     ///
@@ -279,6 +319,31 @@ pub struct DictionaryMeta {
     pub url: Option<String>,
     /// Attribution information for the content of this dictionary.
     pub attribution: Option<String>,
+    /// Whether newer revisions of this dictionary can be fetched from
+    /// [`DictionaryMeta::index_url`].
+    #[serde(default)]
+    pub is_updatable: bool,
+    /// URL of the index of the latest revision of this dictionary, used to
+    /// check if an update is available.
+    #[serde(default)]
+    pub index_url: Option<String>,
+    /// URL to download the latest revision of this dictionary from.
+    #[serde(default)]
+    pub download_url: Option<String>,
+    /// SHA-256 hash of the archive which this dictionary was imported from, as
+    /// a lowercase hex string.
+    ///
+    /// Dictionaries with the same archive hash were imported from identical
+    /// archives.
+    #[serde(default)]
+    pub archive_hash: Option<String>,
+    /// SHA-256 hash of the parts of this dictionary's index which stay the same
+    /// across revisions, as a lowercase hex string.
+    ///
+    /// Dictionaries with the same index hash, but different archive hashes,
+    /// are different revisions of the same dictionary.
+    #[serde(default)]
+    pub index_hash: Option<String>,
 }
 
 impl DictionaryMeta {
@@ -292,6 +357,11 @@ impl DictionaryMeta {
             description: None,
             url: None,
             attribution: None,
+            is_updatable: false,
+            index_url: None,
+            download_url: None,
+            archive_hash: None,
+            index_hash: None,
         }
     }
 }
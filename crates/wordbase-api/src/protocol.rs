@@ -69,6 +69,15 @@ pub struct RecordEntry {
     /// [`FrequencyValue`] of the record, as found in [`RecordEntry::source`]'s
     /// frequency information.
     pub source_sorting_frequency: Option<FrequencyValue>,
+    /// If this record was found by following a [`Redirect`] from an inflected
+    /// form of [`RecordEntry::term`], names of the inflection rules which were
+    /// applied to get to that inflected form.
+    ///
+    /// This is empty if the record was found directly.
+    ///
+    /// [`Redirect`]: crate::dict::yomitan::Redirect
+    #[serde(default)]
+    pub inflection_rule_chain: Vec<String>,
 }
 
 /// A (half-open) range bounded inclusively below and exclusively above
@@ -1,10 +1,19 @@
 use {
     anyhow::{Context, Result},
     ascii_table::AsciiTable,
-    futures::TryStreamExt,
+    futures::{Stream, StreamExt, TryStreamExt},
     std::{path::PathBuf, sync::Arc, time::Instant},
-    tracing::info,
-    wordbase::{Dictionary, DictionaryId, Engine, Profile, import::ImportEvent},
+    tokio::{fs::File, io::BufWriter},
+    tracing::{info, warn},
+    wordbase::{
+        Dictionary, DictionaryId, Engine, Profile,
+        import::{
+            self, AnkiFields, AnkiNoteType, Directory, ImportError, ImportEvent, ImportJobId,
+            ImportJobInfo, ImportOptions, ImportQueueEvent, OpenArchive, ValidationIssue,
+            ValidationReport,
+        },
+        update::DictionaryUpdate,
+    },
 };
 
 pub fn ls(engine: &Engine, profile: &Profile) -> Vec<Arc<Dictionary>> {
@@ -79,11 +88,191 @@ pub fn info(engine: &Engine, dict_id: DictionaryId) -> Result<()> {
     Ok(())
 }
 
-pub async fn import(engine: &Engine, profile: &Profile, path: PathBuf) -> Result<()> {
+pub async fn import(
+    engine: &Engine,
+    profile: &Profile,
+    mut paths: Vec<PathBuf>,
+    options: ImportOptions,
+) -> Result<()> {
+    if paths.len() > 1 {
+        return import_many(engine, profile, paths, options).await;
+    }
+    let path = paths.pop().context("no paths to import")?;
+    if path.is_dir() {
+        let dir = Directory::new(path);
+        let events = engine.import_dictionary_with_options(dir, options);
+        run_import(engine, profile, events).await
+    } else {
+        let path = Arc::new(path);
+        let events = engine.import_dictionary_with_options(path, options);
+        run_import(engine, profile, events).await
+    }
+}
+
+async fn import_many(
+    engine: &Engine,
+    profile: &Profile,
+    paths: Vec<PathBuf>,
+    options: ImportOptions,
+) -> Result<()> {
     let start = Instant::now();
+    let archives = paths.iter().map(|path| {
+        if path.is_dir() {
+            Arc::new(Directory::new(path.clone())) as Arc<dyn OpenArchive>
+        } else {
+            Arc::new(Arc::new(path.clone()))
+        }
+    });
+
+    let events = engine.import_dictionaries(archives, options);
+    tokio::pin!(events);
+    while let Some(event) = events.next().await {
+        match event {
+            ImportQueueEvent::Started { total, .. } => {
+                info!("Importing {total} dictionaries");
+            }
+            ImportQueueEvent::Job { index, event, .. } => {
+                let path = paths[usize::try_from(index)?].display();
+                match event {
+                    ImportEvent::ParsedMeta(meta) => {
+                        info!(
+                            "{path}: importing {:?} version {:?}",
+                            meta.name, meta.version
+                        );
+                    }
+                    ImportEvent::JobStarted(job_id) => {
+                        info!("{path}: started import job {}", job_id.0);
+                    }
+                    ImportEvent::Warning(warning) => {
+                        warn!("{path}: skipped {}", format_issue(&warning));
+                    }
+                    ImportEvent::Done(id) => {
+                        info!("{path}: imported as {id:?}");
+                        engine
+                            .enable_dictionary(profile.id, id)
+                            .await
+                            .context("failed to enable dictionary")?;
+                    }
+                    ImportEvent::DeterminedKind(_) | ImportEvent::Progress(_) => {}
+                }
+            }
+            ImportQueueEvent::Failed { index, error, .. } => {
+                let path = paths[usize::try_from(index)?].display();
+                warn!("{path}: failed to import: {error}");
+            }
+            ImportQueueEvent::Progress {
+                frac, done, total, ..
+            } => {
+                info!("{:.02}% imported ({done}/{total} done)", frac * 100.0);
+            }
+            ImportQueueEvent::Done {
+                imported, failed, ..
+            } => {
+                info!("Imported {imported} dictionaries, {failed} failed");
+            }
+        }
+    }
+
+    let elapsed = Instant::now().duration_since(start);
+    info!("Import complete in {elapsed:?}");
+    Ok(())
+}
+
+pub async fn validate(engine: &Engine, path: PathBuf) -> Result<ValidationReport> {
+    let report = if path.is_dir() {
+        engine.validate_dictionary(Directory::new(path)).await?
+    } else {
+        engine.validate_dictionary(Arc::new(path)).await?
+    };
 
+    info!("Detected as {:?} dictionary", report.kind);
+    if let Some(meta) = &report.meta {
+        info!("{:?} version {:?}", meta.name, meta.version);
+    }
+    for bank in &report.banks {
+        info!("  {}: {} rows", bank.path, bank.rows);
+    }
+    for count in &report.records {
+        info!("  {:?}: {} records", count.kind, count.count);
+    }
+    log_issues("Errors", &report.errors);
+    log_issues("Unknown fields", &report.unknown_fields);
+    log_issues("Missing media", &report.missing_media);
+    log_issues("Missing tags", &report.missing_tags);
+
+    if report.is_valid() {
+        info!("No problems found");
+    }
+    Ok(report)
+}
+
+fn log_issues(title: &str, issues: &[ValidationIssue]) {
+    if issues.is_empty() {
+        return;
+    }
+    info!("");
+    info!("--- {title} ({}) ---", issues.len());
+    info!("");
+    for issue in issues {
+        info!("{}", format_issue(issue));
+    }
+}
+
+fn format_issue(issue: &ValidationIssue) -> String {
+    let path = issue.path.as_deref().unwrap_or("archive");
+    issue.index.map_or_else(
+        || format!("{path}: {}", issue.message),
+        |index| format!("{path} row {index}: {}", issue.message),
+    )
+}
+
+pub async fn import_anki(
+    engine: &Engine,
+    profile: &Profile,
+    path: PathBuf,
+    fields: AnkiFields,
+    options: ImportOptions,
+) -> Result<()> {
     let path = Arc::new(path);
-    let import_events = engine.import_dictionary(path);
+    run_import(
+        engine,
+        profile,
+        engine.import_anki_deck(path, fields, options),
+    )
+    .await
+}
+
+pub async fn export(engine: &Engine, dict_id: DictionaryId, path: PathBuf) -> Result<()> {
+    let start = Instant::now();
+    let file = File::create(&path)
+        .await
+        .with_context(|| format!("failed to create `{}`", path.display()))?;
+    engine
+        .export_dictionary(dict_id, BufWriter::new(file))
+        .await?;
+    let elapsed = Instant::now().duration_since(start);
+    info!("Export complete in {elapsed:?}");
+    Ok(())
+}
+
+pub async fn anki_note_types(path: PathBuf) -> Result<Vec<AnkiNoteType>> {
+    let note_types = import::anki_note_types(&Arc::new(path)).await?;
+    for note_type in &note_types {
+        println!("{}: {}", note_type.name, note_type.fields.join(", "));
+        if let Some(fields) = &note_type.guessed_fields {
+            println!("  guessed: {fields:?}");
+        }
+    }
+    Ok(note_types)
+}
+
+async fn run_import(
+    engine: &Engine,
+    profile: &Profile,
+    import_events: impl Stream<Item = Result<ImportEvent, ImportError>>,
+) -> Result<()> {
+    let start = Instant::now();
+
     tokio::pin!(import_events);
     while let Some(event) = import_events
         .try_next()
@@ -97,9 +286,15 @@ pub async fn import(engine: &Engine, profile: &Profile, path: PathBuf) -> Result
             ImportEvent::ParsedMeta(meta) => {
                 info!("Importing {:?} version {:?}", meta.name, meta.version);
             }
+            ImportEvent::JobStarted(job_id) => {
+                info!("Started import job {}", job_id.0);
+            }
             ImportEvent::Progress(progress) => {
                 info!("{:.02}% imported", progress.frac * 100.0);
             }
+            ImportEvent::Warning(warning) => {
+                warn!("Skipped {}", format_issue(&warning));
+            }
             ImportEvent::Done(id) => {
                 info!("Imported as {id:?}");
                 engine
@@ -115,6 +310,139 @@ pub async fn import(engine: &Engine, profile: &Profile, path: PathBuf) -> Result
     Ok(())
 }
 
+pub async fn check_updates(engine: &Engine) -> Result<Vec<DictionaryUpdate>> {
+    let updates = engine.check_dictionary_updates().await?;
+    let dictionaries = engine.dictionaries();
+    for update in &updates {
+        let name = dictionaries
+            .get(&update.id)
+            .map_or("?", |dictionary| dictionary.meta.name.as_str());
+        println!(
+            "{}. {name}: {} -> {}",
+            update.id.0,
+            update.current_version.as_deref().unwrap_or("?"),
+            update.latest_version
+        );
+    }
+    Ok(updates)
+}
+
+pub async fn update(engine: &Engine, dict_id: DictionaryId, options: ImportOptions) -> Result<()> {
+    let Some(update) = engine.check_dictionary_update(dict_id).await? else {
+        info!("Dictionary is already up to date");
+        return Ok(());
+    };
+
+    run_reimport(engine.update_dictionary(update, options)).await
+}
+
+pub async fn reimport(
+    engine: &Engine,
+    dict_id: DictionaryId,
+    path: PathBuf,
+    options: ImportOptions,
+) -> Result<()> {
+    if path.is_dir() {
+        let dir = Directory::new(path);
+        run_reimport(engine.reimport_dictionary(dict_id, dir, options)).await
+    } else {
+        let path = Arc::new(path);
+        run_reimport(engine.reimport_dictionary(dict_id, path, options)).await
+    }
+}
+
+async fn run_reimport(
+    import_events: impl Stream<Item = Result<ImportEvent, ImportError>>,
+) -> Result<()> {
+    let start = Instant::now();
+
+    tokio::pin!(import_events);
+    while let Some(event) = import_events
+        .try_next()
+        .await
+        .context("failed to re-import dictionary")?
+    {
+        match event {
+            ImportEvent::DeterminedKind(kind) => {
+                info!("Kind: {kind:?}");
+            }
+            ImportEvent::ParsedMeta(meta) => {
+                info!("Re-importing {:?} version {:?}", meta.name, meta.version);
+            }
+            ImportEvent::JobStarted(job_id) => {
+                info!("Started import job {}", job_id.0);
+            }
+            ImportEvent::Progress(progress) => {
+                info!("{:.02}% imported", progress.frac * 100.0);
+            }
+            ImportEvent::Warning(warning) => {
+                warn!("Skipped {}", format_issue(&warning));
+            }
+            ImportEvent::Done(id) => {
+                info!("Re-imported into {id:?}");
+            }
+        }
+    }
+
+    let elapsed = Instant::now().duration_since(start);
+    info!("Re-import complete in {elapsed:?}");
+    Ok(())
+}
+
+pub async fn jobs(engine: &Engine) -> Result<Vec<ImportJobInfo>> {
+    let jobs = engine.import_jobs().await?;
+    for job in &jobs {
+        print!(
+            "{}. {:?} version {:?}: {} entries done",
+            job.id.0, job.meta.name, job.meta.version, job.entries_done
+        );
+        match job.replacing {
+            Some(id) => println!(", replacing {}", id.0),
+            None => println!(),
+        }
+    }
+    Ok(jobs)
+}
+
+pub async fn resume(
+    engine: &Engine,
+    profile: &Profile,
+    job_id: ImportJobId,
+    path: PathBuf,
+) -> Result<()> {
+    if path.is_dir() {
+        run_resume(engine, profile, job_id, Directory::new(path)).await
+    } else {
+        run_resume(engine, profile, job_id, Arc::new(path)).await
+    }
+}
+
+async fn run_resume(
+    engine: &Engine,
+    profile: &Profile,
+    job_id: ImportJobId,
+    open_archive: impl OpenArchive + 'static,
+) -> Result<()> {
+    let job = engine
+        .import_jobs()
+        .await?
+        .into_iter()
+        .find(|job| job.id == job_id)
+        .context("no import job with this ID")?;
+    let events = engine.resume_import(job_id, open_archive);
+    if job.replacing.is_some() {
+        run_reimport(events).await
+    } else {
+        run_import(engine, profile, events).await
+    }
+}
+
+pub async fn cancel(engine: &Engine, job_id: ImportJobId) -> Result<()> {
+    engine.cancel_import(job_id).await?;
+    info!("Import cancelled");
+    Ok(())
+}
+
 pub async fn swap_positions(engine: &Engine, a_id: DictionaryId, b_id: DictionaryId) -> Result<()> {
     engine.swap_dictionary_positions(a_id, b_id).await?;
     Ok(())
@@ -130,6 +458,13 @@ pub async fn disable(engine: &Engine, profile: &Profile, dict_id: DictionaryId)
     Ok(())
 }
 
+pub async fn set_sorting(engine: &Engine, profile: &Profile, dict_id: DictionaryId) -> Result<()> {
+    engine
+        .set_sorting_dictionary(profile.id, Some(dict_id))
+        .await?;
+    Ok(())
+}
+
 pub async fn rm(engine: &Engine, dict_id: DictionaryId) -> Result<()> {
     let start = Instant::now();
     engine.remove_dictionary(dict_id).await?;
@@ -48,6 +48,7 @@ fn render(mut w: impl Write, dictionaries: &Dictionaries, term: &render::RecordT
 
     let tags = iter::empty()
         .chain(tags_pitch(term))
+        .chain(tags_phonetic(term))
         .chain(tags_audio(dictionaries, term))
         .chain(tags_frequency(dictionaries, term))
         .map(|tag| format!("[{tag}]"))
@@ -139,6 +140,13 @@ fn render_pitch(morae: Option<&[&str]>, position: PitchPosition, pitch: &render:
         .join(" ")
 }
 
+fn tags_phonetic<'a>(term: &'a render::RecordTerm) -> impl Iterator<Item = String> + 'a {
+    term.info
+        .phonetics
+        .iter()
+        .map(|phonetic| phonetic.ipa.clone())
+}
+
 fn tags_audio(
     dictionaries: &Dictionaries,
     term: &render::RecordTerm,
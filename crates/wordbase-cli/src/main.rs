@@ -12,7 +12,10 @@ use {
     std::{io, path::PathBuf},
     tracing::level_filters::LevelFilter,
     tracing_subscriber::EnvFilter,
-    wordbase::{DictionaryId, Engine, ProfileId},
+    wordbase::{
+        DictionaryId, Engine, ProfileId,
+        import::{AnkiFields, ImportJobId, ImportOptions},
+    },
 };
 
 #[derive(Debug, clap::Parser)]
@@ -55,6 +58,26 @@ enum Command {
         /// Lemma to look up
         lemma: String,
     },
+    /// Fetch kanji records for a single character
+    LookupKanji {
+        /// Kanji to look up
+        kanji: char,
+    },
+    /// Find kanji which contain all of the given radicals
+    KanjiByRadicals {
+        /// Radicals which the kanji must contain, e.g. `口木`
+        radicals: String,
+    },
+    /// Fetch example sentences which contain a term
+    ExampleSentences {
+        /// Headword of the term
+        headword: String,
+        /// Reading of the term
+        reading: Option<String>,
+        /// Maximum number of sentences to fetch
+        #[arg(long, default_value_t = 10)]
+        limit: u32,
+    },
     /// Deinflect some text and return its lemmas
     Deinflect {
         /// Text to deinflect
@@ -120,10 +143,88 @@ enum DictCommand {
         /// Dictionary ID
         dict_id: i64,
     },
-    /// Import a dictionary file from the filesystem
+    /// Import dictionary files from the filesystem
     Import {
-        /// Path to the dictionary file
+        /// Paths to the dictionary files, or to directories they have been
+        /// unpacked into
+        ///
+        /// If more than one is given, they are imported concurrently.
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        /// Skip rows which fail to import, instead of failing the whole
+        /// import
+        #[arg(long)]
+        lenient: bool,
+    },
+    /// Check a dictionary file for problems without importing it
+    ///
+    /// Every problem is reported for Yomitan dictionaries, but other formats
+    /// stop at the first error.
+    Validate {
+        /// Path to the dictionary file, or to a directory it has been
+        /// unpacked into
+        path: PathBuf,
+    },
+    /// Replace the data of a dictionary with a newer revision, keeping its
+    /// position and profile settings
+    Reimport {
+        /// Dictionary ID
+        dict_id: i64,
+        /// Path to the dictionary file, or to a directory it has been
+        /// unpacked into
+        path: PathBuf,
+        /// Skip rows which fail to import, instead of failing the whole
+        /// import
+        #[arg(long)]
+        lenient: bool,
+    },
+    /// Export a Yomitan dictionary as a Yomitan `.zip` file
+    Export {
+        /// Dictionary ID
+        dict_id: i64,
+        /// Path to write the `.zip` file to
+        path: PathBuf,
+    },
+    /// List the note types and fields of an Anki package
+    AnkiNoteTypes {
+        /// Path to the `.apkg` or `.colpkg` file
+        path: PathBuf,
+    },
+    /// Import an Anki package, using the given note fields
+    ImportAnki {
+        /// Path to the `.apkg` or `.colpkg` file
         path: PathBuf,
+        /// Field holding the headword
+        #[arg(long)]
+        headword: String,
+        /// Field holding the reading
+        #[arg(long)]
+        reading: Option<String>,
+        /// Field holding the definition
+        #[arg(long)]
+        definition: Option<String>,
+        /// Field holding `[sound:...]` audio references
+        #[arg(long)]
+        audio: Option<String>,
+        /// Skip rows which fail to import, instead of failing the whole
+        /// import
+        #[arg(long)]
+        lenient: bool,
+    },
+    /// List imports which were interrupted before they finished
+    Jobs,
+    /// Continue an interrupted import
+    Resume {
+        /// Import job ID
+        job_id: i64,
+        /// Path to the same dictionary file, or directory, that the import
+        /// was started with
+        path: PathBuf,
+    },
+    /// Cancel an interrupted import, deleting the data it has imported
+    Cancel {
+        /// Import job ID
+        job_id: i64,
     },
     /// Modify the state of a dictionary
     Set {
@@ -132,6 +233,17 @@ enum DictCommand {
         #[command(subcommand)]
         command: DictSetCommand,
     },
+    /// List dictionaries which have a newer revision available
+    CheckUpdates,
+    /// Download and install the newer revision of a dictionary
+    Update {
+        /// Dictionary ID
+        dict_id: i64,
+        /// Skip rows which fail to import, instead of failing the whole
+        /// import
+        #[arg(long)]
+        lenient: bool,
+    },
     /// Swap positions of two dictionaries
     Swap {
         /// First dictionary ID
@@ -152,6 +264,9 @@ enum DictSetCommand {
     Enabled,
     /// Disable the dictionary for the selected profile
     Disabled,
+    /// Sort lookup results by this dictionary's frequencies in the selected
+    /// profile
+    Sorting,
 }
 
 #[derive(Debug, clap::Parser)]
@@ -218,6 +333,29 @@ async fn main() -> Result<()> {
             args.output,
             query::lookup_lemma(&engine, &*require_profile()?, &lemma).await?,
         ),
+        Command::LookupKanji { kanji } => output(
+            args.output,
+            query::lookup_kanji(&engine, &*require_profile()?, kanji).await?,
+        ),
+        Command::KanjiByRadicals { radicals } => output(
+            args.output,
+            query::kanji_by_radicals(&engine, &*require_profile()?, &radicals).await?,
+        ),
+        Command::ExampleSentences {
+            headword,
+            reading,
+            limit,
+        } => output(
+            args.output,
+            query::example_sentences(
+                &engine,
+                &*require_profile()?,
+                &headword,
+                reading.as_deref(),
+                limit,
+            )
+            .await?,
+        ),
         Command::Render { text } => {
             query::render(&engine, &*require_profile()?, &text).await?;
         }
@@ -251,8 +389,51 @@ async fn main() -> Result<()> {
             command: DictCommand::Info { dict_id },
         } => dict::info(&engine, DictionaryId(dict_id))?,
         Command::Dict {
-            command: DictCommand::Import { path },
-        } => dict::import(&engine, &*require_profile()?, path).await?,
+            command: DictCommand::Import { paths, lenient },
+        } => {
+            let options = ImportOptions { lenient };
+            dict::import(&engine, &*require_profile()?, paths, options).await?;
+        }
+        Command::Dict {
+            command: DictCommand::Validate { path },
+        } => output(args.output, dict::validate(&engine, path).await?),
+        Command::Dict {
+            command:
+                DictCommand::Reimport {
+                    dict_id,
+                    path,
+                    lenient,
+                },
+        } => {
+            let options = ImportOptions { lenient };
+            dict::reimport(&engine, DictionaryId(dict_id), path, options).await?;
+        }
+        Command::Dict {
+            command: DictCommand::Export { dict_id, path },
+        } => dict::export(&engine, DictionaryId(dict_id), path).await?,
+        Command::Dict {
+            command: DictCommand::AnkiNoteTypes { path },
+        } => output(args.output, dict::anki_note_types(path).await?),
+        Command::Dict {
+            command:
+                DictCommand::ImportAnki {
+                    path,
+                    headword,
+                    reading,
+                    definition,
+                    audio,
+                    lenient,
+                },
+        } => {
+            let fields = AnkiFields {
+                headword,
+                reading,
+                definition,
+                audio,
+            };
+            let options = ImportOptions { lenient };
+            dict::import_anki(&engine, &*require_profile()?, path, fields, options).await?;
+        }
         Command::Dict {
             command:
                 DictCommand::Set {
@@ -267,6 +448,31 @@ async fn main() -> Result<()> {
                     command: DictSetCommand::Disabled,
                 },
         } => dict::disable(&engine, &*require_profile()?, DictionaryId(dict_id)).await?,
+        Command::Dict {
+            command:
+                DictCommand::Set {
+                    dict_id,
+                    command: DictSetCommand::Sorting,
+                },
+        } => dict::set_sorting(&engine, &*require_profile()?, DictionaryId(dict_id)).await?,
+        Command::Dict {
+            command: DictCommand::Jobs,
+        } => output(args.output, dict::jobs(&engine).await?),
+        Command::Dict {
+            command: DictCommand::Resume { job_id, path },
+        } => dict::resume(&engine, &*require_profile()?, ImportJobId(job_id), path).await?,
+        Command::Dict {
+            command: DictCommand::Cancel { job_id },
+        } => dict::cancel(&engine, ImportJobId(job_id)).await?,
+        Command::Dict {
+            command: DictCommand::CheckUpdates,
+        } => output(args.output, dict::check_updates(&engine).await?),
+        Command::Dict {
+            command: DictCommand::Update { dict_id, lenient },
+        } => {
+            let options = ImportOptions { lenient };
+            dict::update(&engine, DictionaryId(dict_id), options).await?;
+        }
         Command::Dict {
             command: DictCommand::Swap { a_id, b_id },
         } => dict::swap_positions(&engine, DictionaryId(a_id), DictionaryId(b_id)).await?,
@@ -2,7 +2,7 @@ use {
     anyhow::{Context, Result},
     std::time::Instant,
     tracing::info,
-    wordbase::{Engine, Profile, render::RenderConfig},
+    wordbase::{Engine, Profile, Term, render::RenderConfig, sentence::ExampleSentence},
 };
 
 pub fn deinflect(engine: &Engine, text: &str) {
@@ -19,6 +19,45 @@ pub async fn lookup_lemma(engine: &Engine, profile: &Profile, lemma: &str) -> Re
     Ok(())
 }
 
+pub async fn lookup_kanji(engine: &Engine, profile: &Profile, kanji: char) -> Result<()> {
+    for result in engine.lookup_kanji(profile.id, kanji).await? {
+        println!("{result:#?}");
+    }
+    Ok(())
+}
+
+pub async fn kanji_by_radicals(
+    engine: &Engine,
+    profile: &Profile,
+    radicals: &str,
+) -> Result<Vec<char>> {
+    let radicals = radicals
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<_>>();
+    let kanji = engine.kanji_by_radicals(profile.id, &radicals).await?;
+    println!("{}", kanji.iter().collect::<String>());
+    Ok(kanji)
+}
+
+pub async fn example_sentences(
+    engine: &Engine,
+    profile: &Profile,
+    headword: &str,
+    reading: Option<&str>,
+    limit: u32,
+) -> Result<Vec<ExampleSentence>> {
+    let term = Term::from_parts(Some(headword), reading).context("term is empty")?;
+    let sentences = engine.example_sentences(profile.id, &term, limit).await?;
+    for example in &sentences {
+        println!("{}", example.sentence.text);
+        for translation in &example.sentence.translations {
+            println!("  {}", translation.text);
+        }
+    }
+    Ok(sentences)
+}
+
 pub async fn render(engine: &Engine, profile: &Profile, text: &str) -> Result<()> {
     let start = Instant::now();
     let records = engine.lookup(profile.id, text, 0).await?;
@@ -28,6 +67,7 @@ pub async fn render(engine: &Engine, profile: &Profile, text: &str) -> Result<()
     let start = Instant::now();
     let body = engine
         .render_html_body(
+            profile.id,
             &records,
             &RenderConfig {
                 s_add_note: "Add Note".into(),
@@ -50,6 +90,7 @@ pub async fn render(engine: &Engine, profile: &Profile, text: &str) -> Result<()
                 .into(),
             },
         )
+        .await
         .context("failed to render HTML")?;
 
     let document = format!(
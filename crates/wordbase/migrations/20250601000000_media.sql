@@ -0,0 +1,8 @@
+-- files bundled with a dictionary (images, stylesheets, audio, etc.),
+-- referenced by path from the content of that dictionary's records
+CREATE TABLE media (
+    source      INTEGER NOT NULL REFERENCES dictionary(id),
+    path        TEXT    NOT NULL,
+    data        BLOB    NOT NULL,
+    UNIQUE (source, path)
+);
@@ -0,0 +1,3 @@
+-- `YomitanKanji` and `YomitanKanjiFrequency` were added after `YomitanPitch`,
+-- so all record kinds after them have been shifted up by 2
+UPDATE record SET kind = kind + 2 WHERE kind > 2;
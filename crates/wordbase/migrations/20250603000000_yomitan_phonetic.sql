@@ -0,0 +1,3 @@
+-- `YomitanPhonetic` was added after `YomitanKanjiFrequency`,
+-- so all record kinds after it have been shifted up by 1
+UPDATE record SET kind = kind + 1 WHERE kind > 4;
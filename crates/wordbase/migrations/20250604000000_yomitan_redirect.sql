@@ -0,0 +1,3 @@
+-- `YomitanRedirect` was added after `YomitanPhonetic`,
+-- so all record kinds after it have been shifted up by 1
+UPDATE record SET kind = kind + 1 WHERE kind > 5;
@@ -0,0 +1,11 @@
+-- radicals (components) which make up each kanji, used to find a kanji
+-- by its parts when the user can't type it
+CREATE TABLE kanji_radical (
+    source      INTEGER NOT NULL REFERENCES dictionary(id),
+    kanji       TEXT    NOT NULL,
+    radical     TEXT    NOT NULL,
+    UNIQUE (source, kanji, radical)
+);
+CREATE INDEX kanji_radical_source ON kanji_radical(source);
+CREATE INDEX kanji_radical_query ON kanji_radical(radical, kanji);
+CREATE INDEX kanji_radical_kanji ON kanji_radical(kanji);
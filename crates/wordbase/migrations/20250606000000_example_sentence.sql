@@ -0,0 +1,12 @@
+-- links example sentences (stored in `record`) to the terms which they
+-- contain; this is separate from `term_record` so that sentences don't show up
+-- as results of normal lookups
+CREATE TABLE example_sentence (
+    source      INTEGER NOT NULL REFERENCES dictionary(id),
+    headword    TEXT    NOT NULL,
+    reading     TEXT,
+    record      INTEGER NOT NULL REFERENCES record(id) DEFERRABLE INITIALLY DEFERRED,
+    UNIQUE (source, headword, reading, record)
+);
+CREATE INDEX example_sentence_source ON example_sentence(source);
+CREATE INDEX example_sentence_query ON example_sentence(headword, source);
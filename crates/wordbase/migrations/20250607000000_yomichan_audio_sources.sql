@@ -0,0 +1,3 @@
+-- `YomichanAudioDaijisen` and `YomichanAudioOubunsha` were added after
+-- `YomichanAudioShinmeikai8`, so all record kinds after them have been shifted up by 2
+UPDATE record SET kind = kind + 2 WHERE kind > 10;
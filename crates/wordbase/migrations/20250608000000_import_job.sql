@@ -0,0 +1,19 @@
+-- dictionary imports which have started but not finished yet; while a job
+-- exists, its dictionary is hidden and may only hold the data of completed
+-- entries (banks, files, etc.) of the archive
+CREATE TABLE import_job (
+    -- never reused, since a finished job may still be registered as running
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    dictionary  INTEGER NOT NULL REFERENCES dictionary(id),
+    -- dictionary which the imported data is swapped into once done
+    replacing   INTEGER,
+    UNIQUE (dictionary)
+);
+
+-- entries of the archive which have been fully imported and committed,
+-- so they can be skipped when resuming the job
+CREATE TABLE import_job_entry (
+    job         INTEGER NOT NULL REFERENCES import_job(id),
+    path        TEXT    NOT NULL,
+    UNIQUE (job, path)
+);
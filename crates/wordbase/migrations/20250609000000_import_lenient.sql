@@ -0,0 +1,3 @@
+-- whether rows which fail to import are skipped with a warning,
+-- instead of failing the whole import
+ALTER TABLE import_job ADD COLUMN lenient INTEGER NOT NULL DEFAULT 0;
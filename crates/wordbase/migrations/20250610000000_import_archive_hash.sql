@@ -0,0 +1,3 @@
+-- SHA-256 of the archive being imported, once it has been hashed, so that
+-- resuming the job doesn't need to hash the archive again
+ALTER TABLE import_job ADD COLUMN archive_hash TEXT;
@@ -64,6 +64,7 @@ impl Engine {
                 ("IsClickCard", String::new()),
                 ("IsSentenceCard", "x".into()),
                 ("PitchPosition", pitch_positions(&entries)),
+                ("PhoneticTranscriptions", phonetic_transcriptions(&entries)),
                 ("Frequency", frequency_list(&entries, dict_name)),
                 ("FreqSort", frequency_harmonic_mean(&entries)),
             ]
@@ -156,6 +157,9 @@ fn term_audio(entries: &[&RecordEntry]) -> Option<Vec<u8>> {
             Record::YomichanAudioJpod(audio) => Some(&audio.audio),
             Record::YomichanAudioNhk16(audio) => Some(&audio.audio),
             Record::YomichanAudioShinmeikai8(audio) => Some(&audio.audio),
+            Record::YomichanAudioDaijisen(audio) => Some(&audio.audio),
+            Record::YomichanAudioOubunsha(audio) => Some(&audio.audio),
+            Record::AnkiNoteAudio(audio) => Some(&audio.audio),
             _ => None,
         })
         .map(|audio| audio.data.to_vec())
@@ -184,6 +188,26 @@ fn glossaries(entries: &[&RecordEntry]) -> Vec<String> {
                 }
                 .0,
             ),
+            Record::JmdictWord(word) => Some(
+                html! {
+                    ul {
+                        @for sense in &word.senses {
+                            li {
+                                (dict::jmdict::render_sense_html(sense))
+                            }
+                        }
+                    }
+                }
+                .0,
+            ),
+            Record::JmdictName(name) => Some(dict::jmdict::render_name_html(name).0),
+            Record::StardictDefinition(definition) => {
+                Some(dict::stardict::render_html(definition).0)
+            }
+            Record::MdictEntry(entry) => Some(entry.html.clone()),
+            Record::CedictMeaning(meaning) => Some(dict::cedict::render_html(meaning).0),
+            Record::WiktionaryLexeme(lexeme) => Some(dict::wiktionary::render_html(lexeme).0),
+            Record::AnkiNote(note) => Some(note.definition.clone()),
             _ => None,
         })
         .collect::<Vec<_>>()
@@ -217,6 +241,18 @@ fn pitch_positions(entries: &[&RecordEntry]) -> String {
         .join("")
 }
 
+fn phonetic_transcriptions(entries: &[&RecordEntry]) -> String {
+    entries
+        .iter()
+        .filter_map(|entry| match &entry.record {
+            Record::YomitanPhonetic(dict::yomitan::Phonetic { ipa, .. }) => Some(ipa.as_str()),
+            _ => None,
+        })
+        .collect::<IndexSet<_>>()
+        .into_iter()
+        .join(", ")
+}
+
 fn frequency_list<'a>(
     entries: &[&RecordEntry],
     dict_name: impl Fn(DictionaryId) -> &'a str,
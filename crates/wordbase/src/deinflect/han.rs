@@ -0,0 +1,78 @@
+use {
+    super::{Deinflection, Deinflector},
+    wordbase_api::dict::{jpn, zho},
+};
+
+/// Longest word, in characters, that we look up in a run of Chinese
+/// characters.
+const MAX_WORD_LEN: usize = 8;
+
+/// Splits Chinese text into candidate words.
+///
+/// Chinese is written without spaces between words, so we look up every
+/// prefix of the run of Chinese characters at the cursor, longest first.
+///
+/// Japanese text also uses these characters, but is already handled by
+/// Lindera, so this does nothing for sentences which contain any kana.
+#[derive(Debug)]
+pub struct Han;
+
+impl Deinflector for Han {
+    fn deinflect<'a>(
+        &'a self,
+        sentence: &'a str,
+        cursor: usize,
+    ) -> impl Iterator<Item = Deinflection<'a>> {
+        let run = if sentence.chars().any(jpn::is_kana) {
+            ""
+        } else {
+            let text = sentence.get(cursor..).unwrap_or_default();
+            let len = text
+                .char_indices()
+                .take_while(|(_, c)| zho::is_han(*c))
+                .take(MAX_WORD_LEN)
+                .last()
+                .map_or(0, |(index, c)| index + c.len_utf8());
+            &text[..len]
+        };
+
+        let mut ends = run
+            .char_indices()
+            .map(|(index, c)| index + c.len_utf8())
+            .collect::<Vec<_>>();
+        ends.reverse();
+        ends.into_iter().map(move |end| {
+            let word = &run[..end];
+            Deinflection::new(cursor, word, word)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::deinflect::{sentence, tests::assert_deinflects},
+    };
+
+    #[test]
+    fn prefixes() {
+        let deinflector = Han;
+        let (text, cursor) = sentence!("我是" / "中国人。");
+        assert_deinflects(
+            &deinflector,
+            (text, cursor),
+            [
+                Deinflection::new(cursor, "中国人", "中国人"),
+                Deinflection::new(cursor, "中国", "中国"),
+                Deinflection::new(cursor, "中", "中"),
+            ],
+        );
+    }
+
+    #[test]
+    fn skip_japanese() {
+        let deinflector = Han;
+        assert_deinflects(&deinflector, sentence!(/ "日本語を話す"), []);
+    }
+}
@@ -1,3 +1,4 @@
+mod han;
 mod latin;
 mod lindera;
 
@@ -21,6 +22,7 @@ pub struct Deinflectors {
     identity: Identity,
     lindera: lindera::Lindera,
     latin: latin::Latin,
+    han: han::Han,
 }
 
 impl Deinflectors {
@@ -29,6 +31,7 @@ impl Deinflectors {
             identity: Identity,
             lindera: lindera::Lindera::new().context("failed to create Lindera deinflector")?,
             latin: latin::Latin,
+            han: han::Han,
         })
     }
 }
@@ -41,6 +44,7 @@ impl Engine {
             .chain(self.deinflectors.identity.deinflect(sentence, cursor))
             .chain(self.deinflectors.lindera.deinflect(sentence, cursor))
             .chain(self.deinflectors.latin.deinflect(sentence, cursor))
+            .chain(self.deinflectors.han.deinflect(sentence, cursor))
             .inspect(|deinflect| {
                 debug_assert!(
                     sentence.get(deinflect.span.clone()).is_some(),
@@ -4,7 +4,7 @@ use {
     derive_more::Deref,
     futures::TryStreamExt,
     serde::{Deserialize, Serialize},
-    sqlx::{Acquire, Pool, Sqlite},
+    sqlx::{Acquire, Pool, Sqlite, SqliteConnection, Transaction},
     std::{sync::Arc, time::Instant},
     tokio_stream::StreamExt,
     tracing::info,
@@ -41,55 +41,18 @@ impl Engine {
     }
 
     pub async fn remove_dictionary(&self, id: DictionaryId) -> Result<()> {
+        // an import may be holding the write lock for a while
+        let _inserting = self.insert_lock.lock().await;
         let mut conn = self.db.acquire().await?;
-
-        // FK constraints are slow to uphold when deleting in bulk like this
-        // so we disable them (for this connection only) to do a bulk delete
-        // it's now on us to uphold the constraints, but we're good programmers :)
-        sqlx::query!("PRAGMA foreign_keys = OFF")
-            .execute(&mut *conn)
-            .await
-            .context("failed to disable foreign keys")?;
-
+        disable_foreign_keys(&mut conn).await?;
         let mut tx = conn.begin().await.context("failed to begin transaction")?;
 
-        info!("Deleting term records for {id:?}");
         let start = Instant::now();
-
-        sqlx::query!("DELETE FROM term_record WHERE source = $1", id.0)
-            .execute(&mut *tx)
-            .await
-            .context("failed to delete term records")?;
-        info!("Deleting records");
-
-        sqlx::query!("DELETE FROM record WHERE source = $1", id.0)
-            .execute(&mut *tx)
-            .await
-            .context("failed to delete records")?;
-        info!("Deleting frequency records");
-
-        sqlx::query!("DELETE FROM frequency WHERE source = $1", id.0)
-            .execute(&mut *tx)
-            .await
-            .context("failed to delete frequency rows")?;
-        info!("Deleting dictionary record");
-
-        let result = sqlx::query!("DELETE FROM dictionary WHERE id = $1", id.0)
-            .execute(&mut *tx)
-            .await
-            .context("failed to delete dictionary row")?;
-        if result.rows_affected() == 0 {
-            bail!(NotFound);
-        }
+        delete_dictionary(&mut tx, id).await?;
         info!("Committing");
 
         tx.commit().await.context("failed to commit transaction")?;
-        info!("Vacuuming");
-
-        sqlx::query!("VACUUM")
-            .execute(&self.db)
-            .await
-            .context("failed to vacuum")?;
+        self.vacuum().await?;
 
         let end = Instant::now();
         info!("Finished delete in {:?}", end.duration_since(start));
@@ -101,6 +64,15 @@ impl Engine {
         Ok(())
     }
 
+    pub(crate) async fn vacuum(&self) -> Result<()> {
+        info!("Vacuuming");
+        sqlx::query!("VACUUM")
+            .execute(&self.db)
+            .await
+            .context("failed to vacuum")?;
+        Ok(())
+    }
+
     pub async fn swap_dictionary_positions(
         &self,
         a_id: DictionaryId,
@@ -210,10 +182,197 @@ impl Engine {
     }
 }
 
+// FK constraints are slow to uphold when deleting in bulk like this
+// so we disable them (for this connection only) to do a bulk delete
+// it's now on us to uphold the constraints, but we're good programmers :)
+pub(crate) async fn disable_foreign_keys(conn: &mut SqliteConnection) -> Result<()> {
+    sqlx::query!("PRAGMA foreign_keys = OFF")
+        .execute(&mut *conn)
+        .await
+        .context("failed to disable foreign keys")?;
+    Ok(())
+}
+
+/// Statement which begins a transaction with foreign keys disabled, for when
+/// the transaction can't be begun on a connection that we borrow and call
+/// [`disable_foreign_keys`] on.
+pub(crate) const BEGIN_WITHOUT_FOREIGN_KEYS: &str = "PRAGMA foreign_keys = OFF; BEGIN";
+
+/// Deletes a dictionary and all of its data.
+///
+/// Foreign keys must be disabled on this connection.
+pub(crate) async fn delete_dictionary(
+    tx: &mut Transaction<'_, Sqlite>,
+    id: DictionaryId,
+) -> Result<()> {
+    delete_dictionary_data(tx, id).await?;
+    info!("Deleting dictionary record");
+
+    let result = sqlx::query!("DELETE FROM dictionary WHERE id = $1", id.0)
+        .execute(&mut **tx)
+        .await
+        .context("failed to delete dictionary row")?;
+    if result.rows_affected() == 0 {
+        bail!(NotFound);
+    }
+    Ok(())
+}
+
+/// Replaces the data and meta of dictionary `id` with that of the newly
+/// imported `new_id`, then removes `new_id`.
+///
+/// `id` keeps its position, the profiles it's enabled in, and any profiles
+/// which sort by it. If `id` has been removed in the meantime, nothing is
+/// changed, so `new_id` is kept as a dictionary of its own.
+///
+/// Foreign keys must be disabled on this connection.
+pub(crate) async fn swap_in_dictionary(
+    tx: &mut Transaction<'_, Sqlite>,
+    id: DictionaryId,
+    new_id: DictionaryId,
+) -> Result<()> {
+    let exists = sqlx::query_scalar!(
+        "SELECT EXISTS(SELECT 1 FROM dictionary WHERE id = $1)",
+        id.0
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .context("failed to fetch if dictionary exists")?;
+    if exists == 0 {
+        return Ok(());
+    }
+
+    delete_dictionary_data(tx, id).await?;
+    info!("Moving data of {new_id:?} to {id:?}");
+    move_dictionary_data(tx, new_id, id).await?;
+
+    let result = sqlx::query!(
+        "UPDATE dictionary
+        SET meta = (SELECT meta FROM dictionary WHERE id = $2)
+        WHERE id = $1 AND EXISTS (SELECT 1 FROM dictionary WHERE id = $2)",
+        id.0,
+        new_id.0,
+    )
+    .execute(&mut **tx)
+    .await
+    .context("failed to update meta")?;
+    if result.rows_affected() == 0 {
+        bail!(NotFound);
+    }
+    sqlx::query!("DELETE FROM dictionary WHERE id = $1", new_id.0)
+        .execute(&mut **tx)
+        .await
+        .context("failed to delete new dictionary row")?;
+    Ok(())
+}
+
+/// Deletes all data of a dictionary, but not the dictionary itself.
+///
+/// Foreign keys must be disabled on this connection.
+async fn delete_dictionary_data(tx: &mut Transaction<'_, Sqlite>, id: DictionaryId) -> Result<()> {
+    info!("Deleting term records for {id:?}");
+    sqlx::query!("DELETE FROM term_record WHERE source = $1", id.0)
+        .execute(&mut **tx)
+        .await
+        .context("failed to delete term records")?;
+    info!("Deleting example sentences");
+
+    sqlx::query!("DELETE FROM example_sentence WHERE source = $1", id.0)
+        .execute(&mut **tx)
+        .await
+        .context("failed to delete example sentences")?;
+    info!("Deleting records");
+
+    sqlx::query!("DELETE FROM record WHERE source = $1", id.0)
+        .execute(&mut **tx)
+        .await
+        .context("failed to delete records")?;
+    info!("Deleting frequency records");
+
+    sqlx::query!("DELETE FROM frequency WHERE source = $1", id.0)
+        .execute(&mut **tx)
+        .await
+        .context("failed to delete frequency rows")?;
+    info!("Deleting media");
+
+    sqlx::query!("DELETE FROM media WHERE source = $1", id.0)
+        .execute(&mut **tx)
+        .await
+        .context("failed to delete media")?;
+    info!("Deleting kanji radicals");
+
+    sqlx::query!("DELETE FROM kanji_radical WHERE source = $1", id.0)
+        .execute(&mut **tx)
+        .await
+        .context("failed to delete kanji radicals")?;
+    Ok(())
+}
+
+/// Moves all data of dictionary `from` to dictionary `to`.
+///
+/// Foreign keys must be disabled on this connection.
+async fn move_dictionary_data(
+    tx: &mut Transaction<'_, Sqlite>,
+    from: DictionaryId,
+    to: DictionaryId,
+) -> Result<()> {
+    sqlx::query!(
+        "UPDATE term_record SET source = $2 WHERE source = $1",
+        from.0,
+        to.0
+    )
+    .execute(&mut **tx)
+    .await
+    .context("failed to move term records")?;
+    sqlx::query!(
+        "UPDATE example_sentence SET source = $2 WHERE source = $1",
+        from.0,
+        to.0
+    )
+    .execute(&mut **tx)
+    .await
+    .context("failed to move example sentences")?;
+    sqlx::query!(
+        "UPDATE record SET source = $2 WHERE source = $1",
+        from.0,
+        to.0
+    )
+    .execute(&mut **tx)
+    .await
+    .context("failed to move records")?;
+    sqlx::query!(
+        "UPDATE frequency SET source = $2 WHERE source = $1",
+        from.0,
+        to.0
+    )
+    .execute(&mut **tx)
+    .await
+    .context("failed to move frequency rows")?;
+    sqlx::query!(
+        "UPDATE media SET source = $2 WHERE source = $1",
+        from.0,
+        to.0
+    )
+    .execute(&mut **tx)
+    .await
+    .context("failed to move media")?;
+    sqlx::query!(
+        "UPDATE kanji_radical SET source = $2 WHERE source = $1",
+        from.0,
+        to.0
+    )
+    .execute(&mut **tx)
+    .await
+    .context("failed to move kanji radicals")?;
+    Ok(())
+}
+
 async fn fetch_owned(db: &Pool<Sqlite>) -> Result<Vec<Dictionary>> {
     sqlx::query!(
         "SELECT id, position, meta
         FROM dictionary
+        -- dictionaries which are still being imported are hidden
+        WHERE NOT EXISTS (SELECT 1 FROM import_job WHERE import_job.dictionary = dictionary.id)
         ORDER BY position"
     )
     .fetch(db)
@@ -287,3 +446,77 @@ const _: () = {
         }
     }
 };
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            import::{ImportError, ImportOptions},
+            test_util::done,
+        },
+    };
+
+    #[tokio::test]
+    async fn reimport_keeps_config() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let engine = Engine::new(data_dir.path()).await.unwrap();
+        let old_path = Arc::new(data_dir.path().join("old.tsv"));
+        let new_path = Arc::new(data_dir.path().join("new.tsv"));
+        std::fs::write(&*old_path, "1\t犬\n2\t猿\n").unwrap();
+        std::fs::write(&*new_path, "1\t猫\n2\t鳥\n3\t魚\n").unwrap();
+
+        let id = done(engine.import_dictionary(old_path.clone()))
+            .await
+            .unwrap();
+        let profile_id = *engine.profiles().keys().next().unwrap();
+        engine.enable_dictionary(profile_id, id).await.unwrap();
+        engine
+            .set_sorting_dictionary(profile_id, Some(id))
+            .await
+            .unwrap();
+        let position = engine.dictionaries()[&id].position;
+
+        assert!(matches!(
+            done(engine.import_dictionary(new_path.clone())).await,
+            Err(ImportError::AlreadyExists { id: existing_id }) if existing_id == id
+        ));
+        let mut events = engine.event_rx();
+        assert_eq!(
+            id,
+            done(engine.reimport_dictionary(id, new_path, ImportOptions::default()))
+                .await
+                .unwrap()
+        );
+        assert!(matches!(
+            events.try_recv(),
+            Ok(EngineEvent::Dictionary(DictionaryEvent::Updated { id: updated_id })) if updated_id == id
+        ));
+
+        let dictionaries = engine.dictionaries();
+        assert_eq!(1, dictionaries.len());
+        assert_eq!(position, dictionaries[&id].position);
+        let profile = engine.profiles()[&profile_id].clone();
+        assert_eq!(vec![id], profile.enabled_dictionaries);
+        assert_eq!(Some(id), profile.sorting_dictionary);
+
+        let headwords = sqlx::query_scalar::<_, String>(
+            "SELECT headword FROM frequency WHERE source = $1 ORDER BY headword",
+        )
+        .bind(id.0)
+        .fetch_all(&engine.db)
+        .await
+        .unwrap();
+        assert_eq!(vec!["猫", "魚", "鳥"], headwords);
+
+        assert!(
+            done(engine.reimport_dictionary(
+                DictionaryId(id.0 + 100),
+                old_path,
+                ImportOptions::default()
+            ))
+            .await
+            .is_err()
+        );
+    }
+}
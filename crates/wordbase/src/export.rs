@@ -0,0 +1,584 @@
+//! Exporting imported dictionaries back into a dictionary archive.
+//!
+//! Currently only [`DictionaryKind::Yomitan`] dictionaries can be exported,
+//! as a [Yomitan] (format 3) zip archive.
+//!
+//! [Yomitan]: https://github.com/yomidevs/yomitan/
+
+use {
+    crate::{Engine, IndexMap, NotFound, db},
+    anyhow::{Context, Result, bail},
+    async_zip::{Compression, ZipEntryBuilder, base::write::ZipFileWriter},
+    futures::{AsyncWrite, TryStreamExt},
+    serde_json::{Map, Value, json},
+    tokio::io::AsyncWriteExt,
+    tracing::{debug, info},
+    wordbase_api::{
+        DictionaryId, DictionaryKind, DictionaryMeta, FrequencyValue, NoHeadwordOrReading,
+        RecordKind, Term,
+        dict::{
+            jpn::PitchPosition,
+            yomitan::{
+                Frequency, Glossary, GlossaryTag, Kanji, KanjiFrequency, Phonetic, Pitch, Redirect,
+                structured,
+            },
+        },
+    },
+};
+
+/// Maximum number of rows written to a single bank file.
+const BANK_LEN: usize = 10_000;
+
+impl Engine {
+    /// Exports a [`DictionaryKind::Yomitan`] dictionary as a Yomitan zip
+    /// archive, writing it to `writer`.
+    ///
+    /// The archive contains an `index.json` built from the dictionary's meta,
+    /// term, term meta, kanji, kanji meta and tag banks built from its
+    /// records, and all of its media. Importing the exported archive gives the
+    /// same records as the ones which were exported.
+    pub async fn export_dictionary(
+        &self,
+        id: DictionaryId,
+        writer: impl tokio::io::AsyncWrite + Unpin,
+    ) -> Result<()> {
+        let dictionary = self.dictionaries().get(&id).cloned().ok_or(NotFound)?;
+        let meta = &dictionary.meta;
+        if meta.kind != DictionaryKind::Yomitan {
+            bail!(
+                "only `{:?}` dictionaries can be exported, not `{:?}`",
+                DictionaryKind::Yomitan,
+                meta.kind
+            );
+        }
+        info!("Exporting {:?} ({id:?})", meta.name);
+
+        let mut export = Export::new(ZipFileWriter::with_tokio(writer));
+        let mut records = sqlx::query!(
+            "SELECT term_record.headword, term_record.reading, record.kind, record.data
+            FROM term_record
+            INDEXED BY term_record_source
+            JOIN record ON record.id = term_record.record
+            WHERE term_record.source = $1
+            ORDER BY term_record.record",
+            id.0
+        )
+        .fetch(&self.db);
+        while let Some(record) = records.try_next().await.context("failed to fetch record")? {
+            let term =
+                Term::from_parts(record.headword, record.reading).ok_or(NoHeadwordOrReading)?;
+            export
+                .record(&term, record.kind, &record.data)
+                .await
+                .with_context(|| format!("failed to export record for {term:?}"))?;
+        }
+        drop(records);
+        export.flush_banks().await?;
+
+        debug!("Exporting media");
+        let mut media =
+            sqlx::query!("SELECT path, data FROM media WHERE source = $1", id.0).fetch(&self.db);
+        while let Some(file) = media.try_next().await.context("failed to fetch media")? {
+            export
+                .write(&file.path, &file.data, Compression::Stored)
+                .await?;
+        }
+        drop(media);
+
+        let index =
+            serde_json::to_vec(&index(meta, &export)).context("failed to serialize index")?;
+        export
+            .write("index.json", &index, Compression::Deflate)
+            .await?;
+
+        let mut writer = export
+            .zip
+            .close()
+            .await
+            .context("failed to finish archive")?
+            .into_inner();
+        writer.flush().await.context("failed to flush archive")?;
+        Ok(())
+    }
+}
+
+/// State of an in-progress Yomitan export.
+struct Export<W> {
+    zip: ZipFileWriter<W>,
+    term_bank: Bank,
+    term_meta_bank: Bank,
+    kanji_bank: Bank,
+    kanji_meta_bank: Bank,
+    /// Every tag referenced by an exported record, keyed by name.
+    tags: IndexMap<String, GlossaryTag>,
+    has_rank: bool,
+    has_occurrence: bool,
+}
+
+/// Rows of a single kind of bank, which are written out in chunks of
+/// [`BANK_LEN`].
+struct Bank {
+    prefix: &'static str,
+    rows: Vec<Value>,
+    files_written: usize,
+}
+
+impl Bank {
+    const fn new(prefix: &'static str) -> Self {
+        Self {
+            prefix,
+            rows: Vec::new(),
+            files_written: 0,
+        }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> Export<W> {
+    fn new(zip: ZipFileWriter<W>) -> Self {
+        Self {
+            zip,
+            term_bank: Bank::new("term_bank"),
+            term_meta_bank: Bank::new("term_meta_bank"),
+            kanji_bank: Bank::new("kanji_bank"),
+            kanji_meta_bank: Bank::new("kanji_meta_bank"),
+            tags: IndexMap::default(),
+            has_rank: false,
+            has_occurrence: false,
+        }
+    }
+
+    async fn write(&mut self, path: &str, data: &[u8], compression: Compression) -> Result<()> {
+        let entry = ZipEntryBuilder::new(path.to_owned().into(), compression);
+        self.zip
+            .write_entry_whole(entry, data)
+            .await
+            .with_context(|| format!("failed to write `{path}`"))
+    }
+
+    async fn push(&mut self, bank: fn(&mut Self) -> &mut Bank, row: Value) -> Result<()> {
+        bank(self).rows.push(row);
+        if bank(self).rows.len() >= BANK_LEN {
+            self.flush_bank(bank).await?;
+        }
+        Ok(())
+    }
+
+    async fn flush_bank(&mut self, bank: fn(&mut Self) -> &mut Bank) -> Result<()> {
+        let Bank {
+            prefix,
+            rows,
+            files_written,
+        } = bank(self);
+        if rows.is_empty() {
+            return Ok(());
+        }
+        *files_written += 1;
+        let path = format!("{prefix}_{files_written}.json");
+        let data = serde_json::to_vec(rows).context("failed to serialize bank")?;
+        rows.clear();
+        self.write(&path, &data, Compression::Deflate).await
+    }
+
+    async fn flush_banks(&mut self) -> Result<()> {
+        self.flush_bank(|e| &mut e.term_bank).await?;
+        self.flush_bank(|e| &mut e.term_meta_bank).await?;
+        self.flush_bank(|e| &mut e.kanji_bank).await?;
+        self.flush_bank(|e| &mut e.kanji_meta_bank).await?;
+
+        let tag_bank = self
+            .tags
+            .values()
+            .map(|tag| json!([tag.name, tag.category, tag.order, tag.description, 0]))
+            .collect::<Vec<_>>();
+        if !tag_bank.is_empty() {
+            let data = serde_json::to_vec(&tag_bank).context("failed to serialize tag bank")?;
+            self.write("tag_bank_1.json", &data, Compression::Deflate)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Adds tags to the tag bank, and gets their names as a space-separated
+    /// list.
+    fn tag_names<'a>(&mut self, tags: impl IntoIterator<Item = &'a GlossaryTag>) -> String {
+        tags.into_iter()
+            .map(|tag| {
+                self.tags
+                    .entry(tag.name.clone())
+                    .or_insert_with(|| tag.clone());
+                tag.name.as_str()
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn frequency_data(&mut self, value: Option<FrequencyValue>, display: Option<String>) -> Value {
+        let value = value.map(|value| match value {
+            FrequencyValue::Rank(n) => {
+                self.has_rank = true;
+                n
+            }
+            FrequencyValue::Occurrence(n) => {
+                self.has_occurrence = true;
+                n
+            }
+        });
+        match (value, display) {
+            (Some(value), Some(display)) => json!({ "value": value, "displayValue": display }),
+            (Some(value), None) => json!(value),
+            (None, display) => json!(display.unwrap_or_default()),
+        }
+    }
+
+    async fn record(&mut self, term: &Term, kind: i64, data: &[u8]) -> Result<()> {
+        const GLOSSARY: u32 = RecordKind::YomitanGlossary as u32;
+        const FREQUENCY: u32 = RecordKind::YomitanFrequency as u32;
+        const PITCH: u32 = RecordKind::YomitanPitch as u32;
+        const KANJI: u32 = RecordKind::YomitanKanji as u32;
+        const KANJI_FREQUENCY: u32 = RecordKind::YomitanKanjiFrequency as u32;
+        const PHONETIC: u32 = RecordKind::YomitanPhonetic as u32;
+        const REDIRECT: u32 = RecordKind::YomitanRedirect as u32;
+
+        // an empty reading means that the reading is the same as the expression
+        let (expression, reading) = match (term.headword(), term.reading()) {
+            (Some(headword), reading) => (headword.as_str(), reading.map(|r| r.as_str())),
+            (None, Some(reading)) => (reading.as_str(), None),
+            (None, None) => return Err(NoHeadwordOrReading.into()),
+        };
+
+        match u32::try_from(kind) {
+            Ok(GLOSSARY) => {
+                let record = db::deserialize::<Glossary>(data)?;
+                let tags = self.tag_names(&record.tags);
+                let term_tags = self.tag_names(&record.term_tags);
+                let content = record
+                    .content
+                    .iter()
+                    .map(glossary_content)
+                    .collect::<Result<Vec<_>>>()?;
+                let row = json!([
+                    expression,
+                    reading.unwrap_or_default(),
+                    tags,
+                    record.rules.join(" "),
+                    record.popularity,
+                    content,
+                    record.sequence,
+                    term_tags,
+                ]);
+                self.push(|e| &mut e.term_bank, row).await
+            }
+            Ok(REDIRECT) => {
+                let record = db::deserialize::<Redirect>(data)?;
+                let row = json!([
+                    expression,
+                    reading.unwrap_or_default(),
+                    "",
+                    "",
+                    0,
+                    [[record.uninflected, record.rule_chain]],
+                    0,
+                    "",
+                ]);
+                self.push(|e| &mut e.term_bank, row).await
+            }
+            Ok(FREQUENCY) => {
+                let record = db::deserialize::<Frequency>(data)?;
+                let frequency = self.frequency_data(record.value, record.display);
+                let data = match reading {
+                    Some(reading) => json!({ "reading": reading, "frequency": frequency }),
+                    None => frequency,
+                };
+                self.push(|e| &mut e.term_meta_bank, json!([expression, "freq", data]))
+                    .await
+            }
+            Ok(PITCH) => {
+                let record = db::deserialize::<Pitch>(data)?;
+                let mut pitch = Map::new();
+                pitch.insert("position".into(), json!(record.position.0));
+                if let Some(nasal) = pitch_positions(&record.nasal) {
+                    pitch.insert("nasal".into(), nasal);
+                }
+                if let Some(devoice) = pitch_positions(&record.devoice) {
+                    pitch.insert("devoice".into(), devoice);
+                }
+                let data = json!({
+                    "reading": reading.unwrap_or(expression),
+                    "pitches": [pitch],
+                });
+                self.push(
+                    |e| &mut e.term_meta_bank,
+                    json!([expression, "pitch", data]),
+                )
+                .await
+            }
+            Ok(PHONETIC) => {
+                let record = db::deserialize::<Phonetic>(data)?;
+                self.tag_names(&record.tags);
+                let tags = record
+                    .tags
+                    .iter()
+                    .map(|tag| tag.name.as_str())
+                    .collect::<Vec<_>>();
+                let data = json!({
+                    "reading": reading.unwrap_or(expression),
+                    "transcriptions": [{ "ipa": record.ipa, "tags": tags }],
+                });
+                self.push(|e| &mut e.term_meta_bank, json!([expression, "ipa", data]))
+                    .await
+            }
+            Ok(KANJI) => {
+                let record = db::deserialize::<Kanji>(data)?;
+                let tags = self.tag_names(&record.tags);
+                self.tag_names(record.stats.iter().map(|stat| &stat.tag));
+                let stats = record
+                    .stats
+                    .into_iter()
+                    .map(|stat| (stat.tag.name, Value::String(stat.value)))
+                    .collect::<Map<_, _>>();
+                let row = json!([
+                    expression,
+                    record.onyomi.join(" "),
+                    record.kunyomi.join(" "),
+                    tags,
+                    record.meanings,
+                    stats,
+                ]);
+                self.push(|e| &mut e.kanji_bank, row).await
+            }
+            Ok(KANJI_FREQUENCY) => {
+                let KanjiFrequency { value, display } = db::deserialize(data)?;
+                let frequency = self.frequency_data(value, display);
+                self.push(
+                    |e| &mut e.kanji_meta_bank,
+                    json!([expression, "freq", frequency]),
+                )
+                .await
+            }
+            _ => bail!("invalid record kind {kind}"),
+        }
+    }
+}
+
+fn index<W>(meta: &DictionaryMeta, export: &Export<W>) -> Value {
+    let mut index = Map::new();
+    index.insert("title".into(), json!(meta.name));
+    index.insert(
+        "revision".into(),
+        json!(meta.version.as_deref().unwrap_or_default()),
+    );
+    index.insert("format".into(), json!(3));
+    for (key, value) in [
+        ("description", &meta.description),
+        ("url", &meta.url),
+        ("attribution", &meta.attribution),
+        ("indexUrl", &meta.index_url),
+        ("downloadUrl", &meta.download_url),
+    ] {
+        if let Some(value) = value {
+            index.insert(key.into(), json!(value));
+        }
+    }
+    if meta.is_updatable && meta.index_url.is_some() && meta.download_url.is_some() {
+        index.insert("isUpdatable".into(), json!(true));
+    }
+    // the importer assumes rank-based if this is missing
+    if export.has_occurrence && !export.has_rank {
+        index.insert("frequencyMode".into(), json!("occurrence-based"));
+    } else if export.has_rank {
+        index.insert("frequencyMode".into(), json!("rank-based"));
+    }
+    Value::Object(index)
+}
+
+fn glossary_content(content: &structured::Content) -> Result<Value> {
+    if let structured::Content::String(text) = content {
+        return Ok(json!(text));
+    }
+    let mut content = serde_json::to_value(content).context("failed to serialize content")?;
+    // fields which aren't set are serialized as `null`,
+    // but Yomitan's schema doesn't allow that
+    remove_nulls(&mut content);
+    Ok(json!({ "type": "structured-content", "content": content }))
+}
+
+fn remove_nulls(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            object.retain(|_, value| !value.is_null());
+            object.values_mut().for_each(remove_nulls);
+        }
+        Value::Array(array) => array.iter_mut().for_each(remove_nulls),
+        _ => {}
+    }
+}
+
+fn pitch_positions(positions: &[PitchPosition]) -> Option<Value> {
+    match positions {
+        [] => None,
+        [position] => Some(json!(position.0)),
+        positions => Some(json!(
+            positions
+                .iter()
+                .map(|position| position.0)
+                .collect::<Vec<_>>()
+        )),
+    }
+}
+
+#[cfg(feature = "uniffi")]
+const _: () = {
+    use {
+        crate::{FfiResult, Wordbase},
+        std::os::fd::{FromRawFd, RawFd},
+        tokio::fs::File,
+    };
+
+    #[uniffi::export(async_runtime = "tokio")]
+    impl Wordbase {
+        pub async fn export_dictionary(&self, id: DictionaryId, fd: RawFd) -> FfiResult<()> {
+            // SAFETY: it is the FFI caller's responsibility
+            // to ensure that this fd is valid and open for writing
+            let file = unsafe { File::from_raw_fd(fd) };
+            Ok(self.0.export_dictionary(id, file).await?)
+        }
+    }
+};
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::test_util::{done, zip},
+        async_zip::base::read::mem::ZipFileReader,
+        std::{collections::BTreeMap, path::Path, sync::Arc},
+    };
+
+    /// Reads every bank in an archive, with the rows of each kind of bank
+    /// sorted, so that archives can be compared regardless of row order.
+    async fn banks(archive: Vec<u8>) -> BTreeMap<String, Vec<String>> {
+        let reader = ZipFileReader::new(archive).await.unwrap();
+        let mut banks = BTreeMap::<String, Vec<String>>::new();
+        for index in 0..reader.file().entries().len() {
+            let path = reader.file().entries()[index]
+                .filename()
+                .as_str()
+                .unwrap()
+                .to_owned();
+            let mut data = Vec::new();
+            reader
+                .reader_with_entry(index)
+                .await
+                .unwrap()
+                .read_to_end_checked(&mut data)
+                .await
+                .unwrap();
+
+            let Some((kind, _)) = path.rsplit_once('_').filter(|_| path.contains("_bank_")) else {
+                let data = String::from_utf8_lossy(&data).into_owned();
+                banks.entry(path).or_default().push(data);
+                continue;
+            };
+            let rows = serde_json::from_slice::<Vec<Value>>(&data).unwrap();
+            banks
+                .entry(kind.to_owned())
+                .or_default()
+                .extend(rows.iter().map(Value::to_string));
+        }
+        for rows in banks.values_mut() {
+            rows.sort();
+        }
+        banks
+    }
+
+    async fn import_and_export(path: &Path) -> Vec<u8> {
+        let data_dir = tempfile::tempdir().unwrap();
+        let engine = Engine::new(data_dir.path()).await.unwrap();
+        let id = done(engine.import_dictionary(Arc::new(path.to_owned())))
+            .await
+            .unwrap();
+
+        let mut archive = Vec::new();
+        engine.export_dictionary(id, &mut archive).await.unwrap();
+        archive
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let index = json!({
+            "title": "Round trip",
+            "revision": "1",
+            "format": 3,
+            "description": "Test dictionary",
+            "frequencyMode": "occurrence-based",
+        });
+        let tag_bank = json!([
+            ["n", "partOfSpeech", 0, "noun", 0],
+            ["common", "popular", 1, "common word", 0],
+            ["jlpt", "", 2, "JLPT level", 0],
+        ]);
+        let term_bank = json!([
+            ["犬", "いぬ", "n", "", 10, [
+                "dog",
+                { "type": "structured-content", "content": {
+                    "tag": "span",
+                    "style": { "fontWeight": "bold" },
+                    "content": ["canine", { "tag": "img", "path": "img/dog.png" }],
+                } },
+            ], 1, "common"],
+            ["いった", "", "", "", 0, [["いく", ["past"]]], 0, ""],
+        ]);
+        let term_meta_bank = json!([
+            ["犬", "freq", { "reading": "いぬ", "frequency": { "value": 5, "displayValue": "5★" } }],
+            ["猫", "freq", 7],
+            ["犬", "pitch", { "reading": "いぬ", "pitches": [{ "position": 2, "nasal": [1, 2] }] }],
+            ["dog", "ipa", { "reading": "dog", "transcriptions": [{ "ipa": "/dɒɡ/", "tags": ["uk"] }] }],
+        ]);
+        let kanji_bank = json!([
+            ["犬", "ケン", "いぬ いぬ-", "common", ["dog"], { "jlpt": "4", "strokes": "4" }],
+        ]);
+        let kanji_meta_bank = json!([["犬", "freq", 1234]]);
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let original = data_dir.path().join("original.zip");
+        let archive = zip([
+            ("index.json", index.to_string().into_bytes()),
+            ("tag_bank_1.json", tag_bank.to_string().into_bytes()),
+            ("term_bank_1.json", term_bank.to_string().into_bytes()),
+            (
+                "term_meta_bank_1.json",
+                term_meta_bank.to_string().into_bytes(),
+            ),
+            ("kanji_bank_1.json", kanji_bank.to_string().into_bytes()),
+            (
+                "kanji_meta_bank_1.json",
+                kanji_meta_bank.to_string().into_bytes(),
+            ),
+            ("img/dog.png", b"png".to_vec()),
+        ])
+        .await;
+        std::fs::write(&original, archive).unwrap();
+
+        let exported = import_and_export(&original).await;
+        let exported_path = data_dir.path().join("exported.zip");
+        std::fs::write(&exported_path, &exported).unwrap();
+        let reexported = import_and_export(&exported_path).await;
+
+        let banks = banks(exported).await;
+        assert_eq!(banks, self::banks(reexported).await);
+
+        let term_bank = &banks["term_bank"];
+        assert_eq!(2, term_bank.len());
+        assert!(term_bank.iter().any(|row| row.contains("canine")
+            && row.contains("img/dog.png")
+            && !row.contains("null")));
+        assert_eq!(4, banks["term_meta_bank"].len());
+        assert_eq!(1, banks["kanji_bank"].len());
+        assert_eq!(1, banks["kanji_meta_bank"].len());
+        assert!(banks.contains_key("img/dog.png"));
+
+        let index = serde_json::from_str::<Value>(&banks["index.json"][0]).unwrap();
+        assert_eq!("Round trip", index["title"]);
+        assert_eq!("occurrence-based", index["frequencyMode"]);
+    }
+}
@@ -0,0 +1,176 @@
+//! Reads the SQLite database of an Anki collection.
+//!
+//! Older collections (`collection.anki2` and `collection.anki21`) store note
+//! types and decks as JSON in the `col` table. Newer collections
+//! (`collection.anki21b`) store them in their own tables.
+
+use {
+    anyhow::{Context, Result},
+    futures::{Stream, StreamExt},
+    serde::Deserialize,
+    sqlx::{
+        Connection, SqliteConnection,
+        sqlite::{SqliteConnectOptions, SqliteJournalMode},
+    },
+    std::{collections::HashMap, path::Path},
+};
+
+/// ID of the deck that every collection starts with.
+const DEFAULT_DECK_ID: i64 = 1;
+
+#[derive(Debug, Clone)]
+pub struct NoteType {
+    pub id: i64,
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct RawNote {
+    pub note_type: i64,
+    pub fields: String,
+    pub tags: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyNoteType {
+    name: String,
+    flds: Vec<LegacyField>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyField {
+    name: String,
+    ord: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyDeck {
+    id: i64,
+    name: String,
+}
+
+pub struct Collection {
+    conn: SqliteConnection,
+    is_legacy: bool,
+}
+
+impl Collection {
+    pub async fn open(path: &Path) -> Result<Self> {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .read_only(true)
+            .journal_mode(SqliteJournalMode::Off);
+        let mut conn = SqliteConnection::connect_with(&options)
+            .await
+            .context("failed to open collection database")?;
+        let has_notetypes_table = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'notetypes')",
+        )
+        .fetch_one(&mut conn)
+        .await
+        .context("failed to check collection schema")?;
+        Ok(Self {
+            conn,
+            is_legacy: !has_notetypes_table,
+        })
+    }
+
+    pub async fn note_types(&mut self) -> Result<Vec<NoteType>> {
+        if self.is_legacy {
+            let models = sqlx::query_scalar::<_, String>("SELECT models FROM col")
+                .fetch_one(&mut self.conn)
+                .await
+                .context("failed to fetch note types")?;
+            let models = serde_json::from_str::<HashMap<String, LegacyNoteType>>(&models)
+                .context("failed to parse note types")?;
+            let mut note_types = models
+                .into_iter()
+                .map(|(id, mut model)| {
+                    model.flds.sort_by_key(|field| field.ord);
+                    Ok(NoteType {
+                        id: id.parse().context("invalid note type ID")?,
+                        name: model.name,
+                        fields: model.flds.into_iter().map(|field| field.name).collect(),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            note_types.sort_by_key(|note_type| note_type.id);
+            Ok(note_types)
+        } else {
+            let names =
+                sqlx::query_as::<_, (i64, String)>("SELECT id, name FROM notetypes ORDER BY id")
+                    .fetch_all(&mut self.conn)
+                    .await
+                    .context("failed to fetch note types")?;
+            let fields = sqlx::query_as::<_, (i64, String)>(
+                "SELECT ntid, name FROM fields ORDER BY ntid, ord",
+            )
+            .fetch_all(&mut self.conn)
+            .await
+            .context("failed to fetch note type fields")?;
+            Ok(names
+                .into_iter()
+                .map(|(id, name)| NoteType {
+                    id,
+                    name,
+                    fields: fields
+                        .iter()
+                        .filter(|(ntid, _)| *ntid == id)
+                        .map(|(_, name)| name.clone())
+                        .collect(),
+                })
+                .collect())
+        }
+    }
+
+    /// Gets the name of the top-level deck in this collection, ignoring the
+    /// `Default` deck.
+    pub async fn deck_name(&mut self) -> Result<Option<String>> {
+        let names = if self.is_legacy {
+            let decks = sqlx::query_scalar::<_, String>("SELECT decks FROM col")
+                .fetch_one(&mut self.conn)
+                .await
+                .context("failed to fetch decks")?;
+            serde_json::from_str::<HashMap<String, LegacyDeck>>(&decks)
+                .context("failed to parse decks")?
+                .into_values()
+                .filter(|deck| deck.id != DEFAULT_DECK_ID)
+                .map(|deck| deck.name)
+                .collect::<Vec<_>>()
+        } else {
+            sqlx::query_scalar::<_, String>("SELECT name FROM decks WHERE id != $1")
+                .bind(DEFAULT_DECK_ID)
+                .fetch_all(&mut self.conn)
+                .await
+                .context("failed to fetch decks")?
+        };
+        // subdecks are named `Parent::Child`, and in the newer schema the
+        // separator is stored as `\x1f`
+        Ok(names
+            .into_iter()
+            .map(|name| name.replace('\x1f', "::"))
+            .min_by_key(|name| (name.matches("::").count(), name.clone())))
+    }
+
+    pub async fn num_notes(&mut self) -> Result<u64> {
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM notes")
+            .fetch_one(&mut self.conn)
+            .await
+            .context("failed to count notes")?;
+        Ok(u64::try_from(count).unwrap_or_default())
+    }
+
+    pub fn notes(&mut self) -> impl Stream<Item = Result<RawNote>> + '_ {
+        sqlx::query_as::<_, (i64, String, String)>("SELECT mid, flds, tags FROM notes ORDER BY id")
+            .fetch(&mut self.conn)
+            .map(|row| {
+                let (note_type, fields, tags) = row.context("failed to fetch note")?;
+                Ok(RawNote {
+                    note_type,
+                    fields,
+                    tags,
+                })
+            })
+    }
+}
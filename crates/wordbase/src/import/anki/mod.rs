@@ -0,0 +1,673 @@
+mod collection;
+mod parse;
+
+use {
+    super::{
+        ImportContinue, ImportKind, ImportProgress, OpenArchive,
+        source::{self, ZipArchive},
+    },
+    crate::import::{Batches, continue_with, insert::Inserter},
+    anyhow::{Context, Result, bail},
+    async_compression::futures::bufread::ZstdDecoder,
+    collection::{Collection, NoteType, RawNote},
+    futures::{AsyncReadExt as _, TryStreamExt as _, future::BoxFuture, io::BufReader},
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::{BTreeSet, HashMap},
+        path::Path,
+        sync::Arc,
+    },
+    tempfile::NamedTempFile,
+    tokio::sync::mpsc,
+    tokio_util::compat::TokioAsyncWriteCompatExt,
+    tracing::{debug, trace},
+    wordbase_api::{
+        DictionaryKind, DictionaryMeta, Term,
+        dict::{
+            anki::{Note, NoteAudio},
+            yomichan_audio::{Audio, AudioFormat},
+        },
+    },
+};
+
+/// Imports an Anki package, using either the fields chosen by the user, or
+/// fields guessed from their names.
+#[derive(Debug, Default)]
+pub struct Anki {
+    pub fields: Option<AnkiFields>,
+}
+
+impl ImportKind for Anki {
+    fn is_of_kind(&self, open_archive: Arc<dyn OpenArchive>) -> BoxFuture<'_, Result<()>> {
+        Box::pin(validate(open_archive))
+    }
+
+    fn start_import(
+        &self,
+        open_archive: Arc<dyn OpenArchive>,
+        progress_tx: mpsc::Sender<ImportProgress>,
+    ) -> BoxFuture<Result<(DictionaryMeta, ImportContinue)>> {
+        let fields = self.fields.clone();
+        Box::pin(start_import(open_archive, progress_tx, fields))
+    }
+}
+
+/// Which fields of an Anki note hold which parts of a dictionary entry.
+///
+/// Fields are matched by name, ignoring case. Notes whose note type doesn't
+/// have the [`AnkiFields::headword`] field are skipped.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct AnkiFields {
+    /// Field holding the term itself.
+    ///
+    /// If this is written in Anki's furigana syntax (`食[た]べる`), the reading
+    /// is also taken from this field.
+    pub headword: String,
+    /// Field holding the reading of the term.
+    pub reading: Option<String>,
+    /// Field holding the definition of the term, which may contain HTML.
+    pub definition: Option<String>,
+    /// Field holding `[sound:...]` references to pronunciation audio.
+    pub audio: Option<String>,
+}
+
+/// Note type in an Anki package, returned by [`anki_note_types`].
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct AnkiNoteType {
+    /// Name of the note type, e.g. `Basic`.
+    pub name: String,
+    /// Names of the fields of this note type, in order.
+    pub fields: Vec<String>,
+    /// Fields that would be used if the deck were imported with
+    /// [`Engine::import_dictionary`], guessed from their names.
+    ///
+    /// [`Engine::import_dictionary`]: crate::Engine::import_dictionary
+    pub guessed_fields: Option<AnkiFields>,
+}
+
+const HEADWORD_FIELDS: &[&str] = &[
+    "expression",
+    "word",
+    "vocab",
+    "vocabulary",
+    "vocabulary-kanji",
+    "term",
+    "headword",
+    "kanji",
+    "front",
+];
+
+const READING_FIELDS: &[&str] = &[
+    "reading",
+    "word reading",
+    "vocabulary-kana",
+    "kana",
+    "furigana",
+    "word furigana",
+    "vocabulary-furigana",
+];
+
+const DEFINITION_FIELDS: &[&str] = &[
+    "meaning",
+    "word meaning",
+    "definition",
+    "glossary",
+    "vocabulary-english",
+    "english",
+    "back",
+];
+
+const AUDIO_FIELDS: &[&str] = &["audio", "word audio", "vocabulary-audio", "sound"];
+
+impl AnkiFields {
+    /// Guesses which fields to use from the names of a note type's fields.
+    #[must_use]
+    pub fn guess(fields: &[String]) -> Option<Self> {
+        let find = |names: &[&str]| {
+            names.iter().find_map(|name| {
+                fields
+                    .iter()
+                    .find(|field| field.eq_ignore_ascii_case(name))
+                    .cloned()
+            })
+        };
+        Some(Self {
+            headword: find(HEADWORD_FIELDS)?,
+            reading: find(READING_FIELDS),
+            definition: find(DEFINITION_FIELDS),
+            audio: find(AUDIO_FIELDS),
+        })
+    }
+}
+
+/// Positions of the [`AnkiFields`] in a specific note type.
+#[derive(Debug, Clone)]
+struct FieldIndices {
+    note_type: String,
+    headword: usize,
+    reading: Option<usize>,
+    definition: Option<usize>,
+    audio: Option<usize>,
+}
+
+impl FieldIndices {
+    fn new(note_type: &NoteType, fields: Option<&AnkiFields>) -> Option<Self> {
+        let guessed;
+        let fields = if let Some(fields) = fields {
+            fields
+        } else {
+            guessed = AnkiFields::guess(&note_type.fields)?;
+            &guessed
+        };
+        let position = |name: &str| {
+            note_type
+                .fields
+                .iter()
+                .position(|field| field.eq_ignore_ascii_case(name))
+        };
+        Some(Self {
+            note_type: note_type.name.clone(),
+            headword: position(&fields.headword)?,
+            reading: fields.reading.as_deref().and_then(position),
+            definition: fields.definition.as_deref().and_then(position),
+            audio: fields.audio.as_deref().and_then(position),
+        })
+    }
+}
+
+/// Lists the note types in an Anki package, so that the user can choose which
+/// fields to import with [`Engine::import_anki_deck`].
+///
+/// [`Engine::import_anki_deck`]: crate::Engine::import_anki_deck
+///
+/// # Errors
+///
+/// Errors if the archive is not an Anki package, or its collection can't be
+/// read.
+pub async fn anki_note_types(open_archive: &dyn OpenArchive) -> Result<Vec<AnkiNoteType>> {
+    let Package { mut collection, .. } = Package::open(open_archive).await?;
+    Ok(collection
+        .note_types()
+        .await?
+        .into_iter()
+        .map(|note_type| AnkiNoteType {
+            guessed_fields: AnkiFields::guess(&note_type.fields),
+            name: note_type.name,
+            fields: note_type.fields,
+        })
+        .collect())
+}
+
+/// Names of the collection database in a package, from newest to oldest
+/// format.
+///
+/// Packages exported by newer versions of Anki may contain both a new and an
+/// old collection, where the old one only contains a note asking the user to
+/// update Anki, so we always prefer the newest.
+const COLLECTION_NAMES: &[(&str, bool)] = &[
+    ("collection.anki21b", true),
+    ("collection.anki21", false),
+    ("collection.anki2", false),
+];
+
+const MEDIA_MAP_NAME: &str = "media";
+
+const NOTES_PER_PROGRESS: u64 = 500;
+
+/// Notes may hold their audio, so we hold fewer of them in memory at once.
+const NOTES_PER_BATCH: usize = 500;
+
+/// Media files can be large, so we hold fewer of them in memory at once.
+const MEDIA_PER_BATCH: usize = 100;
+
+const DEFAULT_NAME: &str = "Anki deck";
+
+/// Media files of a package.
+struct Media {
+    archive: ZipArchive,
+    entries: HashMap<String, usize>,
+    /// Maps media file names to the names of the files in the zip.
+    map: HashMap<String, String>,
+    /// If the files in the zip are compressed with zstd, which is the case
+    /// for packages in the newer format.
+    is_compressed: bool,
+}
+
+impl Media {
+    async fn read(&mut self, name: &str) -> Result<Option<Vec<u8>>> {
+        let Some(index) = self
+            .map
+            .get(name)
+            .and_then(|zip_name| self.entries.get(zip_name))
+        else {
+            return Ok(None);
+        };
+        let data = source::read_zip_entry(&mut self.archive, *index, name).await?;
+        if self.is_compressed {
+            Ok(Some(decompress(&data).await.with_context(|| {
+                format!("failed to decompress `{name}`")
+            })?))
+        } else {
+            Ok(Some(data))
+        }
+    }
+}
+
+struct Package {
+    collection: Collection,
+    media: Media,
+    /// Keeps the extracted collection database alive until we're done with it.
+    _collection_file: NamedTempFile,
+}
+
+impl Package {
+    async fn open(open_archive: &dyn OpenArchive) -> Result<Self> {
+        let mut archive = source::open_zip(open_archive).await?;
+        let entries = zip_entries(&archive);
+        let (index, name, is_compressed) = find_collection(&entries)?;
+
+        // SQLite can only open databases from a file, so we extract it first
+        let collection_file = NamedTempFile::new().context("failed to create temporary file")?;
+        {
+            let reader = archive
+                .reader_with_entry(index)
+                .await
+                .with_context(|| format!("failed to start reading `{name}`"))?;
+            let mut writer = tokio::fs::File::create(collection_file.path())
+                .await
+                .context("failed to open temporary file")?
+                .compat_write();
+            if is_compressed {
+                futures::io::copy(ZstdDecoder::new(BufReader::new(reader)), &mut writer).await
+            } else {
+                futures::io::copy(reader, &mut writer).await
+            }
+            .with_context(|| format!("failed to extract `{name}`"))?;
+        }
+        let collection = Collection::open(collection_file.path()).await?;
+
+        let map = match entries.get(MEDIA_MAP_NAME) {
+            Some(&index) => {
+                let data = source::read_zip_entry(&mut archive, index, MEDIA_MAP_NAME).await?;
+                if is_compressed {
+                    let data = decompress(&data)
+                        .await
+                        .context("failed to decompress media map")?;
+                    parse::media_map(&data)
+                } else {
+                    parse::legacy_media_map(&data)
+                }
+                .context("failed to parse media map")?
+            }
+            None => HashMap::new(),
+        };
+
+        Ok(Self {
+            collection,
+            media: Media {
+                archive,
+                entries,
+                map,
+                is_compressed,
+            },
+            _collection_file: collection_file,
+        })
+    }
+}
+
+fn zip_entries(archive: &ZipArchive) -> HashMap<String, usize> {
+    source::zip_files(archive)
+        .map(|(index, path)| (path.to_owned(), index))
+        .collect()
+}
+
+fn find_collection(entries: &HashMap<String, usize>) -> Result<(usize, &'static str, bool)> {
+    COLLECTION_NAMES
+        .iter()
+        .find_map(|&(name, is_compressed)| {
+            entries.get(name).map(|&index| (index, name, is_compressed))
+        })
+        .context("no Anki collection in archive")
+}
+
+async fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    ZstdDecoder::new(data)
+        .read_to_end(&mut decompressed)
+        .await?;
+    Ok(decompressed)
+}
+
+async fn validate(open_archive: Arc<dyn OpenArchive>) -> Result<()> {
+    let archive = source::open_zip(&*open_archive).await?;
+    find_collection(&zip_entries(&archive))?;
+    Ok(())
+}
+
+async fn start_import(
+    open_archive: Arc<dyn OpenArchive>,
+    progress_tx: mpsc::Sender<ImportProgress>,
+    fields: Option<AnkiFields>,
+) -> Result<(DictionaryMeta, ImportContinue)> {
+    let mut package = Package::open(&*open_archive).await?;
+
+    let note_types = package
+        .collection
+        .note_types()
+        .await?
+        .iter()
+        .filter_map(|note_type| {
+            FieldIndices::new(note_type, fields.as_ref()).map(|indices| (note_type.id, indices))
+        })
+        .collect::<HashMap<_, _>>();
+    if note_types.is_empty() {
+        if fields.is_some() {
+            bail!("no note type has the chosen headword field");
+        }
+        bail!("could not guess which fields hold the headword - choose the fields to import");
+    }
+    debug!("Importing note types {note_types:?}");
+
+    let name = package
+        .collection
+        .deck_name()
+        .await?
+        .unwrap_or_else(|| DEFAULT_NAME.to_owned());
+    let meta = DictionaryMeta::new(DictionaryKind::Anki, name);
+
+    Ok((
+        meta,
+        continue_with(move |mut job| async move {
+            let Package {
+                mut collection,
+                mut media,
+                _collection_file,
+            } = package;
+            let num_notes = collection.num_notes().await?;
+
+            // images are inserted in a consistent order, so that they are
+            // always split into the same batches
+            let mut images = BTreeSet::new();
+            let mut batches = Batches::new("notes", NOTES_PER_BATCH);
+            let mut notes_done = 0u64;
+            let mut notes = collection.notes();
+            while let Some(note) = notes.try_next().await? {
+                if let Some(indices) = note_types.get(&note.note_type) {
+                    let records = parse_note(&mut media, &mut images, indices, note)
+                        .await
+                        .context("failed to parse note")?;
+                    if let Some(batch) = records.and_then(|records| batches.push(records)) {
+                        job.insert_batch(batch, import_note).await?;
+                    }
+                }
+
+                notes_done += 1;
+                if notes_done % NOTES_PER_PROGRESS == 0 {
+                    let frac = (notes_done as f64) / (num_notes as f64);
+                    _ = progress_tx.try_send(ImportProgress { frac });
+                    trace!("{notes_done} / {num_notes} notes imported");
+                }
+            }
+            drop(notes);
+            job.insert_batch(batches.finish(), import_note).await?;
+
+            debug!("Importing {} referenced images", images.len());
+            let import = async |insert: &mut Inserter<'_, '_>, (name, data): (String, Vec<u8>)| {
+                insert
+                    .media(&name, &data)
+                    .await
+                    .with_context(|| format!("failed to insert media `{name}`"))
+            };
+            let mut batches = Batches::new("images", MEDIA_PER_BATCH);
+            for name in images {
+                let Some(data) = media.read(&name).await? else {
+                    debug!("Referenced image {name:?} is not in the package");
+                    continue;
+                };
+                if let Some(batch) = batches.push((name, data)) {
+                    job.insert_batch(batch, import).await?;
+                }
+            }
+            job.insert_batch(batches.finish(), import).await?;
+
+            let tx = job.begin().await?;
+            job.finish(tx).await
+        }),
+    ))
+}
+
+/// Records of a single note, which are found under the same term.
+struct NoteRecords {
+    term: Term,
+    note: Option<Note>,
+    audio: Vec<NoteAudio>,
+}
+
+/// Converts a note into its records, reading its audio from `media`, and
+/// adding the images that it references to `images`.
+async fn parse_note(
+    media: &mut Media,
+    images: &mut BTreeSet<String>,
+    indices: &FieldIndices,
+    note: RawNote,
+) -> Result<Option<NoteRecords>> {
+    let fields = parse::fields(&note.fields);
+    let field = |index: Option<usize>| index.and_then(|index| fields.get(index).copied());
+
+    let Some(headword) = field(Some(indices.headword)) else {
+        return Ok(None);
+    };
+    let headword = parse::plain_text(headword);
+    let (headword, furigana_reading) = match parse::furigana(&headword) {
+        Some((headword, reading)) => (headword, Some(reading)),
+        None => (headword, None),
+    };
+    let reading = field(indices.reading)
+        .map(parse::plain_text)
+        .map(|reading| parse::furigana(&reading).map_or(reading, |(_, reading)| reading))
+        .filter(|reading| !reading.is_empty())
+        .or(furigana_reading)
+        .filter(|reading| *reading != headword);
+    let Some(term) = Term::from_parts(Some(headword), reading) else {
+        return Ok(None);
+    };
+
+    let note_record = field(indices.definition)
+        .map(parse::without_sound_refs)
+        .filter(|definition| !definition.is_empty())
+        .map(|definition| {
+            images.extend(parse::image_refs(&definition).map(ToOwned::to_owned));
+            Note {
+                note_type: indices.note_type.clone(),
+                definition,
+                tags: note
+                    .tags
+                    .split_whitespace()
+                    .map(ToOwned::to_owned)
+                    .collect(),
+            }
+        });
+
+    let mut audio = Vec::new();
+    for name in field(indices.audio).into_iter().flat_map(parse::sound_refs) {
+        let Some(format) = audio_format(name) else {
+            debug!("Skipping audio {name:?} with unsupported format");
+            continue;
+        };
+        let Some(data) = media.read(name).await? else {
+            debug!("Referenced audio {name:?} is not in the package");
+            continue;
+        };
+        audio.push(NoteAudio {
+            audio: Audio {
+                format,
+                data: data.into(),
+            },
+        });
+    }
+    Ok(Some(NoteRecords {
+        term,
+        note: note_record,
+        audio,
+    }))
+}
+
+async fn import_note(insert: &mut Inserter<'_, '_>, records: NoteRecords) -> Result<()> {
+    let NoteRecords { term, note, audio } = records;
+    if let Some(note) = note {
+        let record_id = insert
+            .record(&note)
+            .await
+            .context("failed to insert note record")?;
+        insert
+            .term_record(term.clone(), record_id)
+            .await
+            .context("failed to insert term record")?;
+    }
+    for audio in audio {
+        let record_id = insert
+            .record(&audio)
+            .await
+            .context("failed to insert audio record")?;
+        insert
+            .term_record(term.clone(), record_id)
+            .await
+            .context("failed to insert term record")?;
+    }
+    Ok(())
+}
+
+fn audio_format(name: &str) -> Option<AudioFormat> {
+    let extension = Path::new(name).extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "mp3" => Some(AudioFormat::Mp3),
+        "ogg" | "opus" => Some(AudioFormat::Opus),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            Engine,
+            test_util::{done, import_until_progress, stalling_archive, zip},
+        },
+        serde_json::json,
+        sqlx::{Connection, SqliteConnection, sqlite::SqliteConnectOptions},
+    };
+
+    /// Name of the audio file which the first note after the first batch
+    /// references.
+    const AUDIO_ZIP_NAME: &str = "0";
+
+    /// Writes a package in the legacy format with a full batch of notes, and
+    /// one more note which also has audio.
+    async fn anki_package(path: &Path) {
+        let collection_file = NamedTempFile::new().unwrap();
+        let options = SqliteConnectOptions::new()
+            .filename(collection_file.path())
+            .create_if_missing(true);
+        let mut conn = SqliteConnection::connect_with(&options).await.unwrap();
+        sqlx::query("CREATE TABLE col (models TEXT NOT NULL, decks TEXT NOT NULL)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        sqlx::query(
+            "CREATE TABLE notes (
+                id      INTEGER PRIMARY KEY,
+                mid     INTEGER NOT NULL,
+                flds    TEXT    NOT NULL,
+                tags    TEXT    NOT NULL
+            )",
+        )
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+        let models = json!({ "1": { "name": "Basic", "flds": [
+            { "name": "Front", "ord": 0 },
+            { "name": "Back", "ord": 1 },
+            { "name": "Audio", "ord": 2 },
+        ] } });
+        let decks = json!({
+            "1": { "id": 1, "name": "Default" },
+            "2": { "id": 2, "name": "Vocab" },
+        });
+        sqlx::query("INSERT INTO col (models, decks) VALUES ($1, $2)")
+            .bind(models.to_string())
+            .bind(decks.to_string())
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        for id in 0..=NOTES_PER_BATCH {
+            let audio = if id == NOTES_PER_BATCH {
+                "[sound:word.mp3]"
+            } else {
+                ""
+            };
+            sqlx::query("INSERT INTO notes (id, mid, flds, tags) VALUES ($1, 1, $2, '')")
+                .bind(i64::try_from(id).unwrap())
+                .bind(format!("word{id}\x1fdefinition\x1f{audio}"))
+                .execute(&mut conn)
+                .await
+                .unwrap();
+        }
+        conn.close().await.unwrap();
+
+        let collection = tokio::fs::read(collection_file.path()).await.unwrap();
+        let media = json!({ AUDIO_ZIP_NAME: "word.mp3" }).to_string();
+        let package = zip([
+            ("collection.anki2", collection),
+            (MEDIA_MAP_NAME, media.into_bytes()),
+            (AUDIO_ZIP_NAME, b"audio".to_vec()),
+        ])
+        .await;
+        tokio::fs::write(path, package).await.unwrap();
+    }
+
+    async fn num_records(engine: &Engine) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM record")
+            .fetch_one(&engine.db)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn resume_after_interrupt() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let engine = Engine::new(data_dir.path()).await.unwrap();
+        let path = data_dir.path().join("deck.apkg");
+        anki_package(&path).await;
+
+        // progress is reported once the first batch of notes is imported, and
+        // reading the audio of the next note stalls
+        let archive = stalling_archive(path.clone(), |name| name == AUDIO_ZIP_NAME).await;
+        let (job_id, events) = import_until_progress(
+            &engine,
+            archive,
+            (DictionaryKind::Anki, Arc::new(Anki::default())),
+        )
+        .await;
+        // simulate the process being killed
+        drop(events);
+
+        let jobs = engine.import_jobs().await.unwrap();
+        assert_eq!(1, jobs.len());
+        assert_eq!("Vocab", jobs[0].meta.name);
+        assert_eq!(1, jobs[0].entries_done);
+        let batch_len = i64::try_from(NOTES_PER_BATCH).unwrap();
+        assert_eq!(batch_len, num_records(&engine).await);
+
+        let id = done(engine.resume_import(job_id, Arc::new(path)))
+            .await
+            .unwrap();
+        assert!(engine.import_jobs().await.unwrap().is_empty());
+        assert_eq!("Vocab", engine.dictionaries()[&id].meta.name);
+        // the last note has both a definition and audio
+        assert_eq!(batch_len + 2, num_records(&engine).await);
+    }
+}
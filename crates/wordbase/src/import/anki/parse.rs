@@ -0,0 +1,249 @@
+//! Parsers for the contents of Anki note fields, and for the media map of a
+//! deck package.
+
+use {
+    anyhow::{Context, Result, bail},
+    regex::Regex,
+    std::{collections::HashMap, sync::LazyLock},
+};
+
+/// Separator between the fields of a note, in the `notes.flds` column.
+const FIELD_SEPARATOR: char = '\x1f';
+
+static SOUND_REF: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[sound:([^\]]+)\]").expect("should be a valid regex"));
+
+static HTML_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<[^>]*>").expect("should be a valid regex"));
+
+static IMG_SRC: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"<img[^>]*\ssrc\s*=\s*["']?([^"'\s>]+)"#).expect("should be a valid regex")
+});
+
+pub fn fields(flds: &str) -> Vec<&str> {
+    flds.split(FIELD_SEPARATOR).collect()
+}
+
+/// Gets the file names of `[sound:...]` references in a field.
+pub fn sound_refs(field: &str) -> impl Iterator<Item = &str> {
+    SOUND_REF
+        .captures_iter(field)
+        .filter_map(|captures| Some(captures.get(1)?.as_str()))
+}
+
+/// Removes `[sound:...]` references from a field, since they are only
+/// meaningful to Anki.
+pub fn without_sound_refs(field: &str) -> String {
+    SOUND_REF.replace_all(field, "").trim().to_owned()
+}
+
+/// Gets the file names of images referenced by `<img src="...">` tags.
+pub fn image_refs(field: &str) -> impl Iterator<Item = &str> {
+    IMG_SRC
+        .captures_iter(field)
+        .filter_map(|captures| Some(captures.get(1)?.as_str()))
+}
+
+/// Converts the HTML content of a field to plain text.
+pub fn plain_text(field: &str) -> String {
+    let field = without_sound_refs(field);
+    let field = HTML_TAG.replace_all(&field, "");
+    html_escape::decode_html_entities(&field)
+        .replace('\u{a0}', " ")
+        .trim()
+        .to_owned()
+}
+
+/// Splits text in Anki's furigana syntax, e.g. `日本[にほん] 語[ご]`, into
+/// the text without readings, and the text in kana.
+///
+/// Each `[reading]` applies to the text before it, up to the previous space.
+/// If the text has no readings, returns [`None`].
+pub fn furigana(text: &str) -> Option<(String, String)> {
+    if !text.contains('[') {
+        return None;
+    }
+
+    let mut headword = String::new();
+    let mut reading = String::new();
+    for chunk in text.split_whitespace() {
+        let mut rest = chunk;
+        while let Some(start) = rest.find('[') {
+            let Some(len) = rest[start..].find(']') else {
+                break;
+            };
+            headword.push_str(&rest[..start]);
+            reading.push_str(&rest[start + 1..start + len]);
+            rest = &rest[start + len + 1..];
+        }
+        headword.push_str(rest);
+        reading.push_str(rest);
+    }
+    Some((headword, reading))
+}
+
+/// Parses the media map of a legacy package, which is a JSON object mapping
+/// the names of files in the zip to the names of the media files, e.g.
+/// `{"0": "cat.jpg"}`.
+pub fn legacy_media_map(data: &[u8]) -> Result<HashMap<String, String>> {
+    let map = serde_json::from_slice::<HashMap<String, String>>(data)?;
+    Ok(map
+        .into_iter()
+        .map(|(zip_name, name)| (name, zip_name))
+        .collect())
+}
+
+/// Parses the media map of a package in the newer format, which is a
+/// `MediaEntries` protobuf message:
+///
+/// ```protobuf
+/// message MediaEntries {
+///   repeated MediaEntry entries = 1;
+/// }
+///
+/// message MediaEntry {
+///   string name = 1;
+///   uint32 size = 2;
+///   bytes sha1 = 3;
+///   optional uint32 legacy_zip_filename = 255;
+/// }
+/// ```
+///
+/// Files are stored in the zip under their index in `entries`, unless the
+/// entry has a `legacy_zip_filename`.
+///
+/// Returns a map of media file names to the names of the files in the zip.
+pub fn media_map(data: &[u8]) -> Result<HashMap<String, String>> {
+    let mut map = HashMap::new();
+    let mut entries = Message(data);
+    let mut index = 0u64;
+    while let Some((field, value)) = entries.next_field().context("invalid media entries")? {
+        let (1, Value::Bytes(entry)) = (field, value) else {
+            continue;
+        };
+
+        let mut name = None;
+        let mut zip_name = index.to_string();
+        let mut entry = Message(entry);
+        while let Some((field, value)) = entry.next_field().context("invalid media entry")? {
+            match (field, value) {
+                (1, Value::Bytes(bytes)) => {
+                    name = Some(String::from_utf8(bytes.to_vec()).context("invalid name")?);
+                }
+                (255, Value::Varint(n)) => zip_name = n.to_string(),
+                _ => {}
+            }
+        }
+        if let Some(name) = name {
+            map.insert(name, zip_name);
+        }
+        index += 1;
+    }
+    Ok(map)
+}
+
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// Minimal protobuf reader, which only supports the wire types that we need.
+struct Message<'a>(&'a [u8]);
+
+impl<'a> Message<'a> {
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = self.0.split_first().context("truncated varint")?;
+            self.0 = rest;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        bail!("varint too long")
+    }
+
+    fn next_field(&mut self) -> Result<Option<(u64, Value<'a>)>> {
+        if self.0.is_empty() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let value = match key & 0x7 {
+            0 => Value::Varint(self.varint()?),
+            1 => {
+                self.0 = self.0.get(8..).context("truncated field")?;
+                Value::Fixed
+            }
+            2 => {
+                let len = usize::try_from(self.varint()?).context("field too long")?;
+                let bytes = self.0.get(..len).context("truncated field")?;
+                self.0 = &self.0[len..];
+                Value::Bytes(bytes)
+            }
+            5 => {
+                self.0 = self.0.get(4..).context("truncated field")?;
+                Value::Fixed
+            }
+            wire_type => bail!("unsupported wire type {wire_type}"),
+        };
+        Ok(Some((key >> 3, value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_text() {
+        assert_eq!(
+            "食べる",
+            plain_text("<b>食べる</b>&nbsp;[sound:taberu.mp3]")
+        );
+        assert_eq!(
+            vec!["taberu.mp3", "eat.ogg"],
+            sound_refs("[sound:taberu.mp3]<br>[sound:eat.ogg]").collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["cat.jpg", "dog.png"],
+            image_refs(r#"<img src="cat.jpg"><div><img class="x" src='dog.png' /></div>"#)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn furigana_syntax() {
+        assert_eq!(None, furigana("食べる"));
+        assert_eq!(
+            Some(("食べる".to_owned(), "たべる".to_owned())),
+            furigana("食[た]べる")
+        );
+        assert_eq!(
+            Some(("日本語".to_owned(), "にほんご".to_owned())),
+            furigana(" 日本[にほん] 語[ご]")
+        );
+    }
+
+    #[test]
+    fn media_maps() {
+        assert_eq!(
+            HashMap::from([("cat.jpg".to_owned(), "0".to_owned())]),
+            legacy_media_map(br#"{"0": "cat.jpg"}"#).unwrap()
+        );
+
+        // entries { name: "a.mp3", size: 3 }, entries { name: "b.jpg" }
+        let data = [
+            0x0a, 0x09, 0x0a, 0x05, b'a', b'.', b'm', b'p', b'3', 0x10, 0x03, //
+            0x0a, 0x07, 0x0a, 0x05, b'b', b'.', b'j', b'p', b'g',
+        ];
+        assert_eq!(
+            HashMap::from([
+                ("a.mp3".to_owned(), "0".to_owned()),
+                ("b.jpg".to_owned(), "1".to_owned()),
+            ]),
+            media_map(&data).unwrap()
+        );
+    }
+}
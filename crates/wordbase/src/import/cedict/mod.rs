@@ -0,0 +1,179 @@
+mod parse;
+
+use {
+    super::{ImportContinue, ImportKind, ImportProgress, OpenArchive, source},
+    crate::import::{Batches, continue_with, insert::Inserter},
+    anyhow::{Context, Result, bail},
+    futures::{AsyncBufReadExt as _, future::BoxFuture},
+    std::sync::{Arc, atomic},
+    tokio::sync::mpsc,
+    tracing::{debug, trace},
+    wordbase_api::{
+        DictionaryKind, DictionaryMeta, Term,
+        dict::{cedict::Meaning, zho},
+    },
+};
+
+pub struct Cedict;
+
+impl ImportKind for Cedict {
+    fn is_of_kind(&self, open_archive: Arc<dyn OpenArchive>) -> BoxFuture<'_, Result<()>> {
+        Box::pin(validate(open_archive))
+    }
+
+    fn start_import(
+        &self,
+        open_archive: Arc<dyn OpenArchive>,
+        progress_tx: mpsc::Sender<ImportProgress>,
+    ) -> BoxFuture<Result<(DictionaryMeta, ImportContinue)>> {
+        Box::pin(start_import(open_archive, progress_tx))
+    }
+}
+
+const LINES_PER_PROGRESS: usize = 1000;
+
+const LINES_PER_BATCH: usize = 5000;
+
+const URL: &str = "https://www.mdbg.net/chinese/dictionary?page=cedict";
+
+async fn validate(open_archive: Arc<dyn OpenArchive>) -> Result<()> {
+    let start = source::read_document_start(&*open_archive).await?;
+    if !start
+        .lines()
+        .any(|line| line.starts_with('#') && line.contains("CC-CEDICT"))
+    {
+        bail!("no CC-CEDICT header comment");
+    }
+    Ok(())
+}
+
+async fn start_import(
+    open_archive: Arc<dyn OpenArchive>,
+    progress_tx: mpsc::Sender<ImportProgress>,
+) -> Result<(DictionaryMeta, ImportContinue)> {
+    // the archive may be gzipped, e.g. `cedict_1_0_ts_utf-8_mdbg.txt.gz`
+    let (mut reader, cursor_pos, buf_len) = source::document_reader(&*open_archive).await?;
+
+    // the header is a block of comments at the start of the file;
+    // we keep the first non-comment line around so we don't lose it
+    let mut meta = DictionaryMeta::new(DictionaryKind::Cedict, "CC-CEDICT");
+    meta.description = Some("Community-maintained Chinese-English dictionary".into());
+    meta.url = Some(URL.into());
+    let mut publisher = None;
+    let mut license = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader
+            .read_line(&mut line)
+            .await
+            .context("failed to read header")?
+            == 0
+        {
+            break;
+        }
+        if !line.starts_with('#') {
+            break;
+        }
+        if let Some((key, value)) = parse::header_line(line.trim_end()) {
+            match key {
+                "date" => meta.version = Some(value.to_owned()),
+                "publisher" => publisher = Some(value.to_owned()),
+                "license" => license = Some(value.to_owned()),
+                _ => {}
+            }
+        }
+    }
+    meta.attribution = match (publisher, license) {
+        (Some(publisher), Some(license)) => Some(format!("{publisher}, licensed under {license}")),
+        (publisher, license) => publisher.or(license),
+    };
+
+    Ok((
+        meta,
+        continue_with(move |mut job| async move {
+            let mut batches = Batches::new("lines", LINES_PER_BATCH);
+            let mut lines_done = 0usize;
+            let mut line_num = 0usize;
+            loop {
+                line_num += 1;
+                let text = line.trim();
+                if !text.is_empty() && !text.starts_with('#') {
+                    let meaning = parse_line(text)
+                        .with_context(|| format!("failed to parse line {line_num}"))?;
+                    if let Some(batch) = batches.push(meaning) {
+                        job.insert_batch(batch, import_meaning).await?;
+                    }
+
+                    lines_done += 1;
+                    if lines_done % LINES_PER_PROGRESS == 0 {
+                        let cursor_pos = cursor_pos.load(atomic::Ordering::SeqCst);
+                        let frac = (cursor_pos as f64) / (buf_len as f64);
+                        _ = progress_tx.try_send(ImportProgress { frac });
+                        trace!("{lines_done} lines imported - {cursor_pos} / {buf_len} bytes read");
+                    }
+                }
+
+                line.clear();
+                if reader
+                    .read_line(&mut line)
+                    .await
+                    .context("failed to read line")?
+                    == 0
+                {
+                    break;
+                }
+            }
+            job.insert_batch(batches.finish(), import_meaning).await?;
+            debug!("{lines_done} total lines");
+
+            let tx = job.begin().await?;
+            job.finish(tx).await
+        }),
+    ))
+}
+
+/// Parses a line into its record, and the terms which it is found under.
+fn parse_line(text: &str) -> Result<(Meaning, Vec<Term>)> {
+    let line = parse::line(text)?;
+    let pinyin = zho::pinyin(line.pinyin);
+    let reading = pinyin
+        .iter()
+        .map(|syllable| syllable.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let record = Meaning {
+        traditional: line.traditional.to_owned(),
+        simplified: line.simplified.to_owned(),
+        pinyin,
+        glosses: line.glosses.into_iter().map(ToOwned::to_owned).collect(),
+    };
+
+    let mut headwords = vec![line.traditional];
+    if line.simplified != line.traditional {
+        headwords.push(line.simplified);
+    }
+    let terms = headwords
+        .into_iter()
+        .filter_map(|headword| Term::from_full(headword, reading.as_str()))
+        .collect();
+    Ok((record, terms))
+}
+
+async fn import_meaning(
+    insert: &mut Inserter<'_, '_>,
+    (record, terms): (Meaning, Vec<Term>),
+) -> Result<()> {
+    let record_id = insert
+        .record(&record)
+        .await
+        .context("failed to insert meaning record")?;
+    for term in terms {
+        insert
+            .term_record(term, record_id)
+            .await
+            .context("failed to insert term record")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,81 @@
+//! Parser for lines of the CC-CEDICT text file.
+//!
+//! See the [CC-CEDICT syntax].
+//!
+//! [CC-CEDICT syntax]: https://cc-cedict.org/wiki/format:syntax
+
+use anyhow::{Context, Result};
+
+/// Single entry line, in the format:
+///
+/// ```text
+/// 傳統 传统 [chuan2 tong3] /tradition/traditional/
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub struct Line<'a> {
+    pub traditional: &'a str,
+    pub simplified: &'a str,
+    /// Numbered pinyin.
+    pub pinyin: &'a str,
+    pub glosses: Vec<&'a str>,
+}
+
+pub fn line(text: &str) -> Result<Line<'_>> {
+    let (traditional, rest) = text.split_once(' ').context("missing traditional form")?;
+    let (simplified, rest) = rest.split_once(' ').context("missing simplified form")?;
+    let (pinyin, rest) = rest
+        .trim_start()
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+        .context("missing `[pinyin]`")?;
+    let glosses = rest
+        .trim()
+        .strip_prefix('/')
+        .context("missing `/glosses/`")?
+        .split('/')
+        .map(str::trim)
+        .filter(|gloss| !gloss.is_empty())
+        .collect();
+    Ok(Line {
+        traditional,
+        simplified,
+        pinyin,
+        glosses,
+    })
+}
+
+/// Parses a `#! key=value` header line.
+pub fn header_line(text: &str) -> Option<(&str, &str)> {
+    let (key, value) = text.strip_prefix("#!")?.split_once('=')?;
+    Some((key.trim(), value.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_line() {
+        assert_eq!(
+            Line {
+                traditional: "中國",
+                simplified: "中国",
+                pinyin: "Zhong1 guo2",
+                glosses: vec!["China"],
+            },
+            line("中國 中国 [Zhong1 guo2] /China/").unwrap()
+        );
+        assert_eq!(
+            vec!["to be", "(bound form) this", "CL:個|个[ge4]"],
+            line("是 是 [shi4] /to be/(bound form) this/CL:個|个[ge4]/")
+                .unwrap()
+                .glosses
+        );
+    }
+
+    #[test]
+    fn parse_header() {
+        assert_eq!(Some(("entries", "123")), header_line("#! entries=123"));
+        assert_eq!(None, header_line("# CC-CEDICT"));
+    }
+}
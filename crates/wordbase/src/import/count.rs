@@ -0,0 +1,80 @@
+//! Byte counting for reporting import progress.
+
+use {
+    futures::{AsyncBufRead, AsyncRead, AsyncSeek},
+    pin_project::pin_project,
+    std::{
+        pin::Pin,
+        sync::{
+            Arc,
+            atomic::{self, AtomicU64},
+        },
+        task::Poll,
+    },
+};
+
+/// Wraps a reader and counts how many bytes have been consumed from it.
+///
+/// Only bytes consumed via [`AsyncBufRead::consume`] are counted.
+#[pin_project]
+pub struct Count<T> {
+    #[pin]
+    inner: T,
+    pos: Arc<AtomicU64>,
+}
+
+impl<T> Count<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            pos: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn pos(&self) -> Arc<AtomicU64> {
+        self.pos.clone()
+    }
+}
+
+impl<T: AsyncSeek + Unpin> AsyncSeek for Count<T> {
+    fn poll_seek(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        pos: std::io::SeekFrom,
+    ) -> Poll<std::io::Result<u64>> {
+        self.project().inner.poll_seek(cx, pos)
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Count<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        self.project().inner.poll_read(cx, buf)
+    }
+
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        bufs: &mut [std::io::IoSliceMut<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        self.project().inner.poll_read_vectored(cx, bufs)
+    }
+}
+
+impl<T: AsyncBufRead + Unpin> AsyncBufRead for Count<T> {
+    fn poll_fill_buf<'a>(
+        self: Pin<&'a mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<std::io::Result<&'a [u8]>> {
+        self.project().inner.poll_fill_buf(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.project();
+        this.pos.fetch_add(amt as u64, atomic::Ordering::SeqCst);
+        this.inner.consume(amt);
+    }
+}
@@ -0,0 +1,151 @@
+//! Dictionaries which have been unpacked into a directory on the file system.
+
+use {
+    super::{Archive, OpenArchive},
+    anyhow::{Context, Result, bail},
+    futures::future::BoxFuture,
+    std::{
+        collections::HashSet,
+        path::{Path, PathBuf},
+    },
+    tokio::fs::{self, File},
+};
+
+/// Unpacked dictionary archive, imported in place from a directory.
+///
+/// Importers which support reading from a directory access its files through
+/// [`OpenArchive::directory`]. Any other importer fails to open it as an
+/// archive, so it will never be detected as that kind of dictionary.
+#[derive(Debug, Clone)]
+pub struct Directory {
+    root: PathBuf,
+}
+
+impl Directory {
+    #[must_use]
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Lists the paths of all regular files under this directory, relative to
+    /// the root and separated by `/`, like the entry paths in an archive.
+    ///
+    /// Symbolic links are followed, but each directory is only visited once,
+    /// so a link which loops back to one of its parents is skipped. Paths are
+    /// returned in sorted order.
+    pub async fn files(&self) -> Result<Vec<String>> {
+        let mut files = Vec::new();
+        let mut visited = HashSet::new();
+        let mut to_visit = vec![(self.root.clone(), String::new())];
+        while let Some((dir, prefix)) = to_visit.pop() {
+            let canonical = fs::canonicalize(&dir)
+                .await
+                .with_context(|| format!("failed to resolve directory `{}`", dir.display()))?;
+            if !visited.insert(canonical) {
+                continue;
+            }
+
+            let mut entries = fs::read_dir(&dir)
+                .await
+                .with_context(|| format!("failed to read directory `{}`", dir.display()))?;
+            while let Some(entry) = entries
+                .next_entry()
+                .await
+                .with_context(|| format!("failed to read entry of `{}`", dir.display()))?
+            {
+                let file_name = entry.file_name();
+                let file_name = file_name
+                    .to_str()
+                    .with_context(|| format!("path `{}` is not UTF-8", entry.path().display()))?;
+                let path = format!("{prefix}{file_name}");
+
+                let metadata = fs::metadata(entry.path())
+                    .await
+                    .with_context(|| format!("failed to read metadata of `{path}`"))?;
+                if metadata.is_dir() {
+                    to_visit.push((entry.path(), format!("{path}/")));
+                } else if metadata.is_file() {
+                    files.push(path);
+                }
+            }
+        }
+        files.sort_unstable();
+        Ok(files)
+    }
+
+    /// Checks if a file or directory exists at `path`, relative to the root.
+    pub async fn contains(&self, path: &str) -> bool {
+        fs::try_exists(self.root.join(path)).await.unwrap_or(false)
+    }
+
+    /// Opens the file at `path`, relative to the root.
+    pub async fn open(&self, path: &str) -> Result<File> {
+        File::open(self.root.join(path))
+            .await
+            .with_context(|| format!("failed to open `{path}`"))
+    }
+
+    /// Reads the file at `path`, relative to the root, into memory.
+    pub async fn read(&self, path: &str) -> Result<Vec<u8>> {
+        fs::read(self.root.join(path))
+            .await
+            .with_context(|| format!("failed to read `{path}`"))
+    }
+}
+
+impl OpenArchive for Directory {
+    fn open_archive(&self) -> BoxFuture<'_, Result<Box<dyn Archive>>> {
+        Box::pin(async {
+            bail!(
+                "`{}` is a directory, not an archive file",
+                self.root.display()
+            )
+        })
+    }
+
+    fn directory(&self) -> Option<&Directory> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn list_and_read() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("index.json"), "{}").unwrap();
+        std::fs::create_dir_all(root.path().join("media/a")).unwrap();
+        std::fs::write(root.path().join("media/a/1.png"), "png").unwrap();
+        std::fs::create_dir(root.path().join("empty")).unwrap();
+
+        let dir = Directory::new(root.path());
+        assert_eq!(
+            vec!["index.json", "media/a/1.png"],
+            dir.files().await.unwrap()
+        );
+        assert!(dir.contains("media/a/1.png").await);
+        assert!(dir.contains("media/a").await);
+        assert!(!dir.contains("media/b").await);
+        assert_eq!(b"png".as_slice(), dir.read("media/a/1.png").await.unwrap());
+        assert!(dir.open_archive().await.is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn symlink_cycle() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("media")).unwrap();
+        std::fs::write(root.path().join("media/1.png"), "png").unwrap();
+        std::os::unix::fs::symlink("..", root.path().join("media/self")).unwrap();
+
+        let dir = Directory::new(root.path());
+        assert_eq!(vec!["media/1.png"], dir.files().await.unwrap());
+    }
+}
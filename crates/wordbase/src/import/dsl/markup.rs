@@ -0,0 +1,361 @@
+//! Converts DSL card bodies into Yomitan structured content.
+
+use {
+    std::{collections::HashMap, path::Path},
+    wordbase_api::dict::yomitan::structured::{
+        Content, ContentStyle, Element, FontStyle, FontWeight, ImageElement, ImageElementBase,
+        LinkElement, NumberOrString, StyledElement, TextDecorationLine, VerticalAlign,
+    },
+};
+
+/// Color used by `[c]` when no color is given.
+const DEFAULT_COLOR: &str = "green";
+
+const EXAMPLE_COLOR: &str = "dimgray";
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "bmp", "svg", "webp"];
+
+/// Information used when converting a card body.
+#[derive(Debug)]
+pub struct Context<'a> {
+    /// What `~` expands to.
+    pub headword: &'a str,
+    /// Full names of the abbreviations used in `[p]` tags, from the
+    /// `_abrv.dsl` file.
+    pub abbreviations: &'a HashMap<String, String>,
+}
+
+/// Converts the body lines of a card into a single block of content, with one
+/// `<div>` per line.
+pub fn body(lines: &[&str], cx: &Context) -> Content {
+    Content::Content(lines.iter().map(|text| line(text, cx)).collect())
+}
+
+fn line(text: &str, cx: &Context) -> Content {
+    let (indent, text) = indent(text);
+    // `@` starts a sub-article, which we display as a heading within this card
+    let (is_subentry, text) = text
+        .trim_start()
+        .strip_prefix('@')
+        .map_or((false, text), |text| (true, text.trim_start()));
+
+    let (content, _) = inline(text, cx);
+    let style = ContentStyle {
+        margin_left: indent.map(|indent| NumberOrString::String(format!("{indent}em"))),
+        font_weight: is_subentry.then_some(FontWeight::Bold),
+        ..Default::default()
+    };
+    Content::Element(Box::new(Element::Div(StyledElement {
+        content: Some(content),
+        style: Some(style),
+        ..Default::default()
+    })))
+}
+
+/// Strips a leading `[mN]` indentation tag.
+fn indent(text: &str) -> (Option<u8>, &str) {
+    let Some(rest) = text.strip_prefix("[m") else {
+        return (None, text);
+    };
+    match rest.split_once(']') {
+        Some(("", rest)) => (Some(0), rest),
+        Some((level, rest)) => level
+            .parse::<u8>()
+            .map_or((None, text), |level| (Some(level), rest)),
+        None => (None, text),
+    }
+}
+
+/// Tag which has been opened but not yet closed.
+#[derive(Debug, Default)]
+struct Open {
+    name: String,
+    arg: Option<String>,
+    children: Vec<Content>,
+    /// Plain text of the children, with all markup removed.
+    text: String,
+}
+
+impl Open {
+    fn push_text(&mut self, text: &str) {
+        if let Some(Content::String(last)) = self.children.last_mut() {
+            last.push_str(text);
+        } else {
+            self.children.push(Content::String(text.to_owned()));
+        }
+        self.text.push_str(text);
+    }
+
+    fn push_char(&mut self, c: char) {
+        self.push_text(c.encode_utf8(&mut [0; 4]));
+    }
+}
+
+fn top(stack: &mut [Open]) -> &mut Open {
+    stack.last_mut().expect("root should never be popped")
+}
+
+/// Converts a line of inline markup to content, returning the content and its
+/// plain text.
+///
+/// Tags which are not closed by the end of the line are closed implicitly,
+/// and closing tags which don't match an open tag are ignored.
+pub fn inline(text: &str, cx: &Context) -> (Content, String) {
+    let mut stack = vec![Open::default()];
+    let mut rest = text;
+    loop {
+        let mut chars = rest.chars();
+        let Some(c) = chars.next() else {
+            break;
+        };
+        let after = chars.as_str();
+        rest = match c {
+            '\\' => {
+                let mut chars = after.chars();
+                if let Some(c) = chars.next() {
+                    top(&mut stack).push_char(c);
+                }
+                chars.as_str()
+            }
+            '~' => {
+                top(&mut stack).push_text(cx.headword);
+                after
+            }
+            '[' => {
+                if let Some((tag_text, after)) = after.split_once(']') {
+                    tag(&mut stack, tag_text, cx);
+                    after
+                } else {
+                    top(&mut stack).push_char(c);
+                    after
+                }
+            }
+            '<' => {
+                if let Some((target, after)) =
+                    after.strip_prefix('<').and_then(|s| s.split_once(">>"))
+                {
+                    let open = top(&mut stack);
+                    open.children
+                        .push(reference(target, vec![Content::String(target.into())]));
+                    open.text.push_str(target);
+                    after
+                } else {
+                    top(&mut stack).push_char(c);
+                    after
+                }
+            }
+            c => {
+                top(&mut stack).push_char(c);
+                after
+            }
+        };
+    }
+
+    while stack.len() > 1 {
+        close_top(&mut stack, cx);
+    }
+    let root = stack.pop().expect("root should never be popped");
+    (Content::Content(root.children), root.text)
+}
+
+fn tag(stack: &mut Vec<Open>, tag: &str, cx: &Context) {
+    if let Some(name) = tag.strip_prefix('/') {
+        let name = name.trim();
+        // `[/m]` closes any of `[m1]`..`[m9]`
+        let matches =
+            |open: &Open| open.name == name || (name == "m" && open.name.starts_with('m'));
+        let Some(index) = stack.iter().skip(1).rposition(matches) else {
+            return;
+        };
+        while stack.len() > index + 1 {
+            close_top(stack, cx);
+        }
+    } else {
+        let (name, arg) = match tag.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, Some(arg.trim().to_owned())),
+            None => (tag, None),
+        };
+        stack.push(Open {
+            name: name.to_owned(),
+            arg,
+            ..Default::default()
+        });
+    }
+}
+
+fn close_top(stack: &mut Vec<Open>, cx: &Context) {
+    let open = stack.pop().expect("should have an element to close");
+    let parent = top(stack);
+    parent.text.push_str(&open.text);
+    parent.children.push(element(open, cx));
+}
+
+fn element(open: Open, cx: &Context) -> Content {
+    let Open {
+        name,
+        arg,
+        mut children,
+        text,
+    } = open;
+    match name.as_str() {
+        "b" => span(
+            children,
+            ContentStyle {
+                font_weight: Some(FontWeight::Bold),
+                ..Default::default()
+            },
+        ),
+        "i" => span(
+            children,
+            ContentStyle {
+                font_style: Some(FontStyle::Italic),
+                ..Default::default()
+            },
+        ),
+        "u" => span(
+            children,
+            ContentStyle {
+                text_decoration_line: vec![TextDecorationLine::Underline],
+                ..Default::default()
+            },
+        ),
+        "c" => span(
+            children,
+            ContentStyle {
+                color: Some(arg.unwrap_or_else(|| DEFAULT_COLOR.into())),
+                ..Default::default()
+            },
+        ),
+        "sup" => span(
+            children,
+            ContentStyle {
+                vertical_align: Some(VerticalAlign::Super),
+                ..Default::default()
+            },
+        ),
+        "sub" => span(
+            children,
+            ContentStyle {
+                vertical_align: Some(VerticalAlign::Sub),
+                ..Default::default()
+            },
+        ),
+        "ex" => span(
+            children,
+            ContentStyle {
+                color: Some(EXAMPLE_COLOR.into()),
+                ..Default::default()
+            },
+        ),
+        // label, e.g. part of speech
+        "p" => Content::Element(Box::new(Element::Span(StyledElement {
+            content: Some(Content::Content(children)),
+            style: Some(ContentStyle {
+                font_style: Some(FontStyle::Italic),
+                color: Some(DEFAULT_COLOR.into()),
+                ..Default::default()
+            }),
+            title: cx.abbreviations.get(text.trim()).cloned(),
+            ..Default::default()
+        }))),
+        // stressed vowel
+        "'" => {
+            children.push(Content::String("\u{301}".into()));
+            Content::Content(children)
+        }
+        "s" => media(text.trim()),
+        "ref" => reference(&text, children),
+        "url" => Content::Element(Box::new(Element::A(LinkElement {
+            content: Some(Content::Content(children)),
+            href: text.trim().to_owned(),
+            ..Default::default()
+        }))),
+        // `[trn]`, `[com]`, `[*]`, `[lang]`, `[t]`, etc.
+        // only affect the semantics, or are just hints for Lingvo
+        _ => Content::Content(children),
+    }
+}
+
+fn span(children: Vec<Content>, style: ContentStyle) -> Content {
+    Content::Element(Box::new(Element::Span(StyledElement {
+        content: Some(Content::Content(children)),
+        style: Some(style),
+        ..Default::default()
+    })))
+}
+
+fn reference(target: &str, children: Vec<Content>) -> Content {
+    Content::Element(Box::new(Element::A(LinkElement {
+        content: Some(Content::Content(children)),
+        href: format!("?query={}", target.trim()),
+        ..Default::default()
+    })))
+}
+
+/// Embeds a media file from the dictionary's resources.
+fn media(path: &str) -> Content {
+    let is_image = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()));
+    if is_image {
+        Content::Element(Box::new(Element::Img(ImageElement {
+            base: ImageElementBase {
+                path: path.to_owned(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })))
+    } else {
+        Content::Element(Box::new(Element::A(LinkElement {
+            content: Some(Content::String(path.to_owned())),
+            href: path.to_owned(),
+            ..Default::default()
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, wordbase_api::dict::yomitan::render_html};
+
+    fn render(text: &str) -> String {
+        let abbreviations = HashMap::from([("n".into(), "noun".into())]);
+        let cx = Context {
+            headword: "cat",
+            abbreviations: &abbreviations,
+        };
+        render_html(&body(&[text], &cx)).0
+    }
+
+    #[test]
+    fn formatting() {
+        assert_eq!(
+            "<div style=\"margin-left:1em;\">\
+             <span style=\"font-style:italic;color:green;\" title=\"noun\">n</span> \
+             <span style=\"color:red;\">a cat ~</span></div>",
+            render("[m1][p]n[/p] [c red]a ~ \\~[/c][/m]"),
+        );
+    }
+
+    #[test]
+    fn unclosed_and_stray_tags() {
+        assert_eq!(
+            "<div style=\"\"><span style=\"font-weight:bold;\">cat[x]</span></div>",
+            render("[b]~\\[x\\][/i]"),
+        );
+    }
+
+    #[test]
+    fn plain_text() {
+        let abbreviations = HashMap::new();
+        let cx = Context {
+            headword: "cat",
+            abbreviations: &abbreviations,
+        };
+        assert_eq!(
+            "a cat, see dog",
+            inline("[trn]a [i]~[/i][/trn], see <<dog>>", &cx).1
+        );
+    }
+}
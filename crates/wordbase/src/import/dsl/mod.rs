@@ -0,0 +1,387 @@
+mod markup;
+mod parse;
+
+use {
+    super::{
+        ImportContinue, ImportKind, ImportProgress, OpenArchive,
+        source::{self, Source, ZipArchive, open_source},
+    },
+    crate::import::{Batches, ImportJob, continue_with, insert::Inserter},
+    anyhow::{Context, Result, bail},
+    futures::future::BoxFuture,
+    std::{collections::HashMap, path::Path, sync::Arc},
+    tokio::sync::mpsc,
+    tracing::{debug, trace},
+    wordbase_api::{
+        DictionaryKind, DictionaryMeta, Term,
+        dict::yomitan::{Glossary, structured::Content},
+    },
+};
+
+pub struct Dsl;
+
+impl ImportKind for Dsl {
+    fn is_of_kind(&self, open_archive: Arc<dyn OpenArchive>) -> BoxFuture<'_, Result<()>> {
+        Box::pin(validate(open_archive))
+    }
+
+    fn start_import(
+        &self,
+        open_archive: Arc<dyn OpenArchive>,
+        progress_tx: mpsc::Sender<ImportProgress>,
+    ) -> BoxFuture<Result<(DictionaryMeta, ImportContinue)>> {
+        Box::pin(start_import(open_archive, progress_tx))
+    }
+}
+
+/// How many bytes from the start of a bare file we decode to check if it is a
+/// DSL file.
+const PROBE_LEN: u64 = 1024;
+
+const RECORDS_PER_PROGRESS: usize = 1000;
+
+const CARDS_PER_BATCH: usize = 5000;
+
+/// Media files can be large, so we hold fewer of them in memory at once.
+const MEDIA_PER_BATCH: usize = 100;
+
+/// Fraction of the import progress spent on importing cards, when there are
+/// also media files to import.
+const CARDS_PROGRESS: f64 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    Dsl,
+    /// `_abrv.dsl` - full names of the abbreviations used in `[p]` labels.
+    Abbreviations,
+    /// `.ann` - annotation, a plain text description of the dictionary.
+    Annotation,
+    /// Any other file, which cards may reference as media.
+    Media,
+}
+
+/// Determines what kind of file this path is, and if it is compressed
+/// (dictzip `.dz`).
+fn file_kind(path: &str) -> (FileKind, bool) {
+    let ext = |path: &Path| path.extension()?.to_str().map(str::to_ascii_lowercase);
+    let path = Path::new(path);
+    let (path, compressed) = match (ext(path).as_deref(), path.file_stem()) {
+        (Some("dz"), Some(stem)) => (Path::new(stem), true),
+        _ => (path, false),
+    };
+    let kind = match ext(path).as_deref() {
+        Some("dsl") => {
+            let is_abbreviations = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|stem| stem.to_ascii_lowercase().ends_with("_abrv"));
+            if is_abbreviations {
+                FileKind::Abbreviations
+            } else {
+                FileKind::Dsl
+            }
+        }
+        Some("ann") => FileKind::Annotation,
+        _ => FileKind::Media,
+    };
+    (kind, compressed)
+}
+
+/// Finds the entries of the zip which belong to the dictionary.
+///
+/// A DSL dictionary may either be imported as a bare `.dsl` or `.dsl.dz` file,
+/// or as a zip containing the `.dsl`, its `_abrv.dsl` and `.ann` companions,
+/// and any media files.
+fn zip_entries(archive: &ZipArchive) -> Result<Vec<(usize, String, FileKind, bool)>> {
+    let entries = source::zip_files(archive)
+        .map(|(index, path)| {
+            let (kind, compressed) = file_kind(path);
+            (index, path.to_owned(), kind, compressed)
+        })
+        .collect::<Vec<_>>();
+    match entries
+        .iter()
+        .filter(|(_, _, kind, _)| *kind == FileKind::Dsl)
+        .count()
+    {
+        0 => bail!("no `.dsl` file in archive"),
+        1 => Ok(entries),
+        _ => bail!("archive contains multiple `.dsl` files"),
+    }
+}
+
+/// Reads an entry of the zip, decompressing it if it is a `.dz` file.
+async fn read_zip_entry(
+    archive: &mut ZipArchive,
+    index: usize,
+    path: &str,
+    compressed: bool,
+) -> Result<Vec<u8>> {
+    let data = source::read_zip_entry(archive, index, path).await?;
+    if !compressed {
+        return Ok(data);
+    }
+    source::gunzip(&data)
+        .await
+        .with_context(|| format!("failed to decompress `{path}`"))
+}
+
+async fn validate(open_archive: Arc<dyn OpenArchive>) -> Result<()> {
+    match open_source(&*open_archive).await? {
+        Source::Bare(archive) => {
+            let start = source::read_bare(archive, PROBE_LEN).await?;
+            if !parse::is_dsl(&parse::decode(&start)) {
+                bail!("not a DSL file");
+            }
+        }
+        Source::Zip(archive) => {
+            zip_entries(&archive)?;
+        }
+    }
+    Ok(())
+}
+
+/// Text files of a DSL dictionary, decoded.
+#[derive(Debug, Default)]
+struct Files {
+    dsl_path: Option<String>,
+    dsl: String,
+    abbreviations: Option<String>,
+    annotation: Option<String>,
+}
+
+async fn read_files(source: Source) -> Result<Files> {
+    match source {
+        Source::Bare(archive) => {
+            let data = source::read_bare(archive, u64::MAX).await?;
+            let dsl = parse::decode(&data);
+            if !parse::is_dsl(&dsl) {
+                bail!("not a DSL file");
+            }
+            Ok(Files {
+                dsl,
+                ..Default::default()
+            })
+        }
+        Source::Zip(mut archive) => {
+            let mut files = Files::default();
+            for (index, path, kind, compressed) in zip_entries(&archive)? {
+                if kind == FileKind::Media {
+                    continue;
+                }
+                let data = read_zip_entry(&mut archive, index, &path, compressed).await?;
+                let text = parse::decode(&data);
+                match kind {
+                    FileKind::Dsl => {
+                        files.dsl = text;
+                        files.dsl_path = Some(path);
+                    }
+                    FileKind::Abbreviations => files.abbreviations = Some(text),
+                    FileKind::Annotation => files.annotation = Some(text),
+                    FileKind::Media => {}
+                }
+            }
+            Ok(files)
+        }
+    }
+}
+
+/// Parses an `_abrv.dsl` file into a map of abbreviations to their full names.
+fn abbreviations(text: &str) -> HashMap<String, String> {
+    let empty = HashMap::new();
+    let text = parse::strip_comments(text);
+    let mut abbreviations = HashMap::new();
+    for card in parse::cards(&text) {
+        let full_name = card
+            .body
+            .iter()
+            .map(|line| {
+                let cx = markup::Context {
+                    headword: "",
+                    abbreviations: &empty,
+                };
+                markup::inline(line, &cx).1
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        for headword in card.headwords {
+            abbreviations.insert(parse::headword_display(headword), full_name.clone());
+        }
+    }
+    abbreviations
+}
+
+async fn start_import(
+    open_archive: Arc<dyn OpenArchive>,
+    progress_tx: mpsc::Sender<ImportProgress>,
+) -> Result<(DictionaryMeta, ImportContinue)> {
+    let source = open_source(&*open_archive).await?;
+    let files = read_files(source).await?;
+    let header = parse::header(&files.dsl);
+
+    let name = header
+        .name
+        .clone()
+        .or_else(|| {
+            files.dsl_path.as_ref().and_then(|path| {
+                let path = Path::new(path).file_stem()?;
+                // `.dsl.dz` has 2 extensions
+                let path = Path::new(path).file_stem().unwrap_or(path);
+                path.to_str().map(ToOwned::to_owned)
+            })
+        })
+        .unwrap_or_else(|| "DSL".into());
+    let mut meta = DictionaryMeta::new(DictionaryKind::Dsl, name);
+    meta.description = files
+        .annotation
+        .as_deref()
+        .map(|annotation| annotation.trim_start_matches('\u{feff}').trim().to_owned())
+        .filter(|annotation| !annotation.is_empty())
+        .or_else(
+            || match (&header.index_language, &header.contents_language) {
+                (Some(from), Some(to)) => Some(format!("{from} → {to}")),
+                _ => None,
+            },
+        );
+
+    Ok((
+        meta,
+        continue_with(move |mut job| async move {
+            let abbreviations = files
+                .abbreviations
+                .as_deref()
+                .map(abbreviations)
+                .unwrap_or_default();
+            let text = parse::strip_comments(&files.dsl);
+            let cards = parse::cards(&text);
+            debug!(
+                "{} cards, {} abbreviations",
+                cards.len(),
+                abbreviations.len()
+            );
+
+            let has_media = files.dsl_path.is_some();
+            let cards_progress = if has_media { CARDS_PROGRESS } else { 1.0 };
+            let mut batches = Batches::new("cards", CARDS_PER_BATCH);
+            for (index, card) in cards.iter().enumerate() {
+                if let Some(records) = card_records(card, &abbreviations) {
+                    if let Some(batch) = batches.push(records) {
+                        job.insert_batch(batch, import_card).await?;
+                    }
+                }
+
+                if index % RECORDS_PER_PROGRESS == 0 {
+                    let frac = (index as f64) / (cards.len() as f64) * cards_progress;
+                    _ = progress_tx.try_send(ImportProgress { frac });
+                }
+            }
+            job.insert_batch(batches.finish(), import_card).await?;
+
+            if let Some(dsl_path) = &files.dsl_path {
+                let Source::Zip(mut archive) = open_source(&*open_archive).await? else {
+                    bail!("archive changed while importing");
+                };
+                import_media(&mut job, &mut archive, dsl_path, |frac| {
+                    let frac = (1.0 - cards_progress).mul_add(frac, cards_progress);
+                    _ = progress_tx.try_send(ImportProgress { frac });
+                })
+                .await?;
+            }
+
+            let tx = job.begin().await?;
+            job.finish(tx).await
+        }),
+    ))
+}
+
+/// Converts a card into its record, and the terms which it is found under.
+fn card_records(
+    card: &parse::Card<'_>,
+    abbreviations: &HashMap<String, String>,
+) -> Option<(Glossary, Vec<Term>)> {
+    let &first_headword = card.headwords.first()?;
+    let headword = parse::headword_display(first_headword);
+    let cx = markup::Context {
+        headword: &headword,
+        abbreviations,
+    };
+    let content: Content = markup::body(&card.body, &cx);
+    let record = Glossary {
+        popularity: 0,
+        tags: Vec::new(),
+        content: vec![content],
+        term_tags: Vec::new(),
+        rules: Vec::new(),
+        sequence: 0,
+    };
+
+    let terms = card
+        .headwords
+        .iter()
+        .flat_map(|headword| parse::headword_variants(headword))
+        .filter_map(Term::from_headword)
+        .collect();
+    Some((record, terms))
+}
+
+async fn import_card(
+    insert: &mut Inserter<'_, '_>,
+    (record, terms): (Glossary, Vec<Term>),
+) -> Result<()> {
+    let record_id = insert
+        .record(&record)
+        .await
+        .context("failed to insert record")?;
+    for term in terms {
+        insert
+            .term_record(term, record_id)
+            .await
+            .context("failed to insert term record")?;
+    }
+    Ok(())
+}
+
+async fn import_media(
+    job: &mut ImportJob,
+    archive: &mut ZipArchive,
+    dsl_path: &str,
+    progress: impl Fn(f64) + Send + Sync,
+) -> Result<()> {
+    // media is referenced relative to the `.dsl`
+    let root = Path::new(dsl_path)
+        .parent()
+        .unwrap_or_else(|| Path::new(""));
+    let entries = zip_entries(archive)?
+        .into_iter()
+        .filter(|(_, _, kind, _)| *kind == FileKind::Media)
+        .collect::<Vec<_>>();
+    let import = async |insert: &mut Inserter<'_, '_>, (path, data): (String, Vec<u8>)| {
+        insert
+            .media(&path, &data)
+            .await
+            .with_context(|| format!("failed to insert media `{path}`"))
+    };
+
+    let num_entries = entries.len();
+    let mut batches = Batches::new("media", MEDIA_PER_BATCH);
+    for (entries_done, (index, path, _, compressed)) in entries.into_iter().enumerate() {
+        let data = read_zip_entry(archive, index, &path, compressed).await?;
+        let path = if compressed {
+            path.strip_suffix(".dz").unwrap_or(&path).to_owned()
+        } else {
+            path
+        };
+        let relative = Path::new(&path)
+            .strip_prefix(root)
+            .ok()
+            .and_then(|path| path.to_str())
+            .unwrap_or(&path);
+        trace!("Importing {path:?} as media");
+        if let Some(batch) = batches.push((relative.to_owned(), data)) {
+            job.insert_batch(batch, import).await?;
+        }
+
+        progress(entries_done as f64 / num_entries as f64);
+    }
+    job.insert_batch(batches.finish(), import).await
+}
@@ -0,0 +1,294 @@
+//! Parser for the structure of ABBYY Lingvo DSL source files: the header,
+//! cards, and headwords.
+//!
+//! See the [DSL format description].
+//!
+//! [DSL format description]: https://documentation.help/ABBYY-Lingvo8/dsl_format.htm
+
+use {
+    encoding_rs::{
+        Encoding, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1250, WINDOWS_1251, WINDOWS_1252,
+        WINDOWS_1253, WINDOWS_1254, WINDOWS_1255, WINDOWS_1256, WINDOWS_1257, WINDOWS_1258,
+    },
+    std::borrow::Cow,
+};
+
+/// How many `(optional)` parts of a headword we expand into separate terms.
+///
+/// Each optional part doubles the number of variants, so we stop expanding
+/// after this many.
+const MAX_OPTIONAL_PARTS: usize = 4;
+
+/// Decodes the contents of a DSL file to text.
+///
+/// DSL files are usually UTF-16LE, but may also be UTF-8, or an 8-bit
+/// encoding declared by the `#CODEPAGE` header.
+pub fn decode(bytes: &[u8]) -> String {
+    let fallback = match bytes {
+        [0, b, ..] if *b != 0 => UTF_16BE,
+        [a, 0, ..] if *a != 0 => UTF_16LE,
+        _ if std::str::from_utf8(bytes).is_ok() => UTF_8,
+        _ => code_page(bytes).unwrap_or(WINDOWS_1252),
+    };
+    // `decode` prefers the encoding specified by a BOM, if there is one
+    let (text, _, _) = fallback.decode(bytes);
+    text.into_owned()
+}
+
+fn code_page(bytes: &[u8]) -> Option<&'static Encoding> {
+    let start = &bytes[..bytes.len().min(1024)];
+    let start = String::from_utf8_lossy(start);
+    let name = start
+        .lines()
+        .take_while(|line| line.starts_with('#'))
+        .find_map(|line| header_line(line).filter(|(key, _)| *key == "CODEPAGE"))?
+        .1;
+    Some(match name {
+        "EasternEuropean" => WINDOWS_1250,
+        "Cyrillic" => WINDOWS_1251,
+        "Greek" => WINDOWS_1253,
+        "Turkish" => WINDOWS_1254,
+        "Hebrew" => WINDOWS_1255,
+        "Arabic" => WINDOWS_1256,
+        "Baltic" => WINDOWS_1257,
+        "Vietnamese" => WINDOWS_1258,
+        _ => WINDOWS_1252,
+    })
+}
+
+fn header_line(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.strip_prefix('#')?.split_once(char::is_whitespace)?;
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value);
+    Some((key, value))
+}
+
+#[derive(Debug, Default)]
+pub struct Header {
+    pub name: Option<String>,
+    pub index_language: Option<String>,
+    pub contents_language: Option<String>,
+}
+
+/// Parses the `#KEY "value"` lines at the start of a DSL file.
+pub fn header(text: &str) -> Header {
+    let mut header = Header::default();
+    for line in text.trim_start_matches('\u{feff}').lines() {
+        if !line.starts_with('#') {
+            break;
+        }
+        let Some((key, value)) = header_line(line) else {
+            continue;
+        };
+        let value = Some(value.to_owned());
+        match key {
+            "NAME" => header.name = value,
+            "INDEX_LANGUAGE" => header.index_language = value,
+            "CONTENTS_LANGUAGE" => header.contents_language = value,
+            _ => {}
+        }
+    }
+    header
+}
+
+/// Checks if some text looks like the start of a DSL file.
+pub fn is_dsl(text: &str) -> bool {
+    text.trim_start_matches('\u{feff}').starts_with("#NAME")
+}
+
+/// Single article in a DSL file.
+#[derive(Debug, Default)]
+pub struct Card<'a> {
+    /// Raw headword lines, which may contain DSL markup.
+    pub headwords: Vec<&'a str>,
+    /// Indented body lines, with the indentation removed.
+    pub body: Vec<&'a str>,
+}
+
+/// Removes `{{comments}}`, which may span multiple lines.
+pub fn strip_comments(text: &str) -> Cow<'_, str> {
+    if !text.contains("{{") {
+        return Cow::Borrowed(text);
+    }
+
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        stripped.push_str(&rest[..start]);
+        rest = rest[start..]
+            .find("}}")
+            .map_or("", |end| &rest[start + end + 2..]);
+    }
+    stripped.push_str(rest);
+    Cow::Owned(stripped)
+}
+
+/// Splits text, with comments already stripped, into cards.
+///
+/// A card is one or more unindented headword lines, followed by indented body
+/// lines.
+pub fn cards(text: &str) -> Vec<Card<'_>> {
+    let mut cards = Vec::new();
+    let mut card = Card::default();
+    for line in text.trim_start_matches('\u{feff}').lines() {
+        if line.trim().is_empty() || (card.headwords.is_empty() && line.starts_with('#')) {
+            continue;
+        }
+        if line.starts_with([' ', '\t']) {
+            if !card.headwords.is_empty() {
+                card.body.push(line.trim());
+            }
+            continue;
+        }
+        if !card.body.is_empty() {
+            cards.push(std::mem::take(&mut card));
+        }
+        card.headwords.push(line.trim());
+    }
+    if !card.headwords.is_empty() {
+        cards.push(card);
+    }
+    cards
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    /// Always part of the headword.
+    Plain,
+    /// `{unsorted}` - displayed, but not part of the indexed headword.
+    Unsorted,
+    /// `(optional)` - the headword exists both with and without this part.
+    Optional,
+}
+
+/// Splits a headword into its different parts, resolving escapes.
+fn headword_parts(raw: &str) -> Vec<(Part, String)> {
+    let mut parts = vec![(Part::Plain, String::new())];
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        let part = match c {
+            '\\' => {
+                if let Some(c) = chars.next() {
+                    push_char(&mut parts, c);
+                }
+                continue;
+            }
+            '{' => Part::Unsorted,
+            '(' => Part::Optional,
+            '}' | ')' => Part::Plain,
+            c => {
+                push_char(&mut parts, c);
+                continue;
+            }
+        };
+        parts.push((part, String::new()));
+    }
+    parts.retain(|(_, text)| !text.is_empty());
+    parts
+}
+
+fn push_char(parts: &mut [(Part, String)], c: char) {
+    if let Some((_, text)) = parts.last_mut() {
+        text.push(c);
+    }
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Gets the main form of a headword, with all optional parts included and
+/// unsorted parts removed.
+///
+/// This is also what `~` refers to in the card body.
+pub fn headword_display(raw: &str) -> String {
+    let text = headword_parts(raw)
+        .into_iter()
+        .filter(|(part, _)| *part != Part::Unsorted)
+        .map(|(_, text)| text)
+        .collect::<String>();
+    normalize_whitespace(&text)
+}
+
+/// Expands a headword into all the forms it should be indexed under.
+///
+/// `colo(u)r` expands to `colour` and `color`, and `{to }run` is indexed as
+/// `run`.
+pub fn headword_variants(raw: &str) -> Vec<String> {
+    let parts = headword_parts(raw)
+        .into_iter()
+        .filter(|(part, _)| *part != Part::Unsorted)
+        .collect::<Vec<_>>();
+    let num_optional = parts
+        .iter()
+        .filter(|(part, _)| *part == Part::Optional)
+        .count()
+        .min(MAX_OPTIONAL_PARTS);
+
+    let mut variants = Vec::new();
+    // bit `i` set = exclude the `i`th optional part
+    for mask in 0..(1usize << num_optional) {
+        let mut optional_index = 0;
+        let text = parts
+            .iter()
+            .filter(|(part, _)| {
+                if *part != Part::Optional {
+                    return true;
+                }
+                let include = optional_index >= num_optional || mask & (1 << optional_index) == 0;
+                optional_index += 1;
+                include
+            })
+            .map(|(_, text)| text.as_str())
+            .collect::<String>();
+        let text = normalize_whitespace(&text);
+        if !text.is_empty() && !variants.contains(&text) {
+            variants.push(text);
+        }
+    }
+    variants
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_cards() {
+        let text = "#NAME \"Test\"\n\
+            #INDEX_LANGUAGE \"English\"\n\
+            \n\
+            cat\n\
+            \t[m1]a feline {{comment\n\
+            spanning lines}}animal[/m]\n\
+            dog\n\
+            hound\n\
+            \t[m1]a canine[/m]\n";
+        assert_eq!(Some("Test"), header(text).name.as_deref());
+
+        let text = strip_comments(text);
+        let cards = cards(&text);
+        assert_eq!(2, cards.len());
+        assert_eq!(vec!["cat"], cards[0].headwords);
+        assert_eq!(vec!["[m1]a feline animal[/m]"], cards[0].body);
+        assert_eq!(vec!["dog", "hound"], cards[1].headwords);
+    }
+
+    #[test]
+    fn variants() {
+        assert_eq!(vec!["colour", "color"], headword_variants("colo(u)r"));
+        assert_eq!(vec!["run"], headword_variants("{to }run"));
+        assert_eq!("run", headword_display("{to }run"));
+        assert_eq!(vec!["a (b)"], headword_variants("a \\(b\\)"));
+    }
+
+    #[test]
+    fn decode_utf16() {
+        let bytes = [0xff, 0xfe, b'#', 0, b'N', 0];
+        assert_eq!("#N", decode(&bytes));
+        assert_eq!("#N", decode(&bytes[2..]));
+    }
+}
@@ -0,0 +1,325 @@
+//! Detecting if an archive has already been imported.
+//!
+//! When an archive is imported, two hashes are stored in its meta:
+//! - [`DictionaryMeta::archive_hash`] over the bytes of the archive, which
+//!   matches if the exact same archive is imported again, even if its file was
+//!   renamed
+//! - [`DictionaryMeta::index_hash`] over the parts of the parsed meta which
+//!   identify the dictionary, but not its revision, which matches if another
+//!   revision of the same dictionary is imported
+//!
+//! A revision is only told apart as newer if both it and the imported
+//! dictionary have versions made up of numbers, like `2024.06.01` - otherwise,
+//! we can't tell which one is newer.
+//!
+//! A dictionary which only has the same name as the archive, but a different
+//! index hash, is a different dictionary, so the archive can still be
+//! imported. Dictionaries which were imported before these hashes existed
+//! match by name alone.
+//!
+//! Hashing a large archive means reading all of it, so the archive hash is
+//! computed alongside the import, and stored on the import job so that a
+//! resumed job doesn't need to hash it again. An identical archive parses to
+//! an identical meta, so the archive only needs to be hashed up front if a
+//! dictionary with the same name already has an archive hash.
+
+use {
+    super::{InsertLock, OpenArchive},
+    anyhow::{Context, Result},
+    serde::Serialize,
+    sha2::{Digest, Sha256},
+    sqlx::{Pool, Sqlite},
+    std::cmp::Ordering,
+    tokio::io::{AsyncRead, AsyncReadExt},
+    wordbase_api::{DictionaryId, DictionaryMeta},
+};
+
+/// Already-imported dictionary which an archive would duplicate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Existing {
+    /// Dictionary was imported from an identical archive.
+    Identical(DictionaryId),
+    /// Dictionary is the same or a newer revision of the same dictionary as
+    /// the archive, or we can't tell which one is newer.
+    Revision(DictionaryId),
+    /// Dictionary is an older revision of the same dictionary as the archive.
+    Older(DictionaryId),
+}
+
+/// Hashes the bytes of an archive.
+///
+/// For a directory, this hashes the path and contents of every file in it.
+pub(super) async fn hash_archive(open_archive: &dyn OpenArchive) -> Result<String> {
+    let mut hasher = Sha256::new();
+    if let Some(directory) = open_archive.directory() {
+        for path in directory.files().await? {
+            let mut file = directory.open(&path).await?;
+            let mut file_hasher = Sha256::new();
+            hash_reader(&mut file_hasher, &mut file)
+                .await
+                .with_context(|| format!("failed to read `{path}`"))?;
+            hasher.update(path.as_bytes());
+            hasher.update([0]);
+            hasher.update(file_hasher.finalize());
+        }
+    } else {
+        let mut archive = open_archive
+            .open_archive()
+            .await
+            .context("failed to open archive")?;
+        hash_reader(&mut hasher, &mut archive)
+            .await
+            .context("failed to read archive")?;
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+async fn hash_reader(hasher: &mut Sha256, reader: &mut (impl AsyncRead + Unpin)) -> Result<()> {
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let len = reader.read(&mut buf).await?;
+        if len == 0 {
+            return Ok(());
+        }
+        hasher.update(&buf[..len]);
+    }
+}
+
+/// Hashes the parts of a dictionary's meta which stay the same across
+/// revisions.
+///
+/// The version, description and attribution are left out, since these often
+/// change between revisions. Importers can pass any other identifying parts
+/// of their index which aren't in the meta, such as its author or languages,
+/// as `extra`.
+pub(super) fn hash_index(meta: &DictionaryMeta, extra: impl Serialize) -> String {
+    let identity = serde_json::json!([meta.kind, meta.name, meta.url, meta.index_url, extra]);
+    hex::encode(Sha256::digest(identity.to_string()))
+}
+
+/// Fetches the dictionaries other than `except` which importing `meta` could
+/// duplicate.
+///
+/// Since an identical archive parses to an identical meta, these all have the
+/// same name as `meta`.
+pub(super) async fn candidates(
+    db: &Pool<Sqlite>,
+    meta: &DictionaryMeta,
+    except: Option<DictionaryId>,
+) -> Result<Vec<(DictionaryId, DictionaryMeta)>> {
+    let except = except.map(|id| id.0);
+    sqlx::query!(
+        "SELECT id, meta FROM dictionary
+        WHERE id IS NOT $1 AND json_extract(meta, '$.name') = $2
+        ORDER BY id",
+        except,
+        meta.name,
+    )
+    .fetch_all(db)
+    .await
+    .context("failed to fetch dictionaries")?
+    .into_iter()
+    .map(|candidate| {
+        let existing = serde_json::from_str::<DictionaryMeta>(&candidate.meta)
+            .context("failed to deserialize dictionary meta")?;
+        Ok((DictionaryId(candidate.id), existing))
+    })
+    .collect()
+}
+
+/// Checks if the archive must be hashed to tell if it is identical to one of
+/// the `candidates`.
+pub(super) fn needs_archive_hash(candidates: &[(DictionaryId, DictionaryMeta)]) -> bool {
+    candidates
+        .iter()
+        .any(|(_, existing)| existing.archive_hash.is_some())
+}
+
+/// Finds which of the `candidates` importing `meta` would duplicate.
+///
+/// `meta` must already have its index hash set, and its archive hash too if
+/// [`needs_archive_hash`].
+pub(super) fn find_existing(
+    candidates: &[(DictionaryId, DictionaryMeta)],
+    meta: &DictionaryMeta,
+) -> Option<Existing> {
+    let mut revision = None;
+    let mut older = None;
+    for (id, existing) in candidates {
+        if existing.archive_hash.is_some() && existing.archive_hash == meta.archive_hash {
+            return Some(Existing::Identical(*id));
+        }
+        // without an index hash, all we know is that it has the same name
+        if existing.index_hash.is_none() || existing.index_hash == meta.index_hash {
+            let is_older = compare_versions(existing.version.as_deref(), meta.version.as_deref())
+                == Some(Ordering::Less);
+            if is_older {
+                older.get_or_insert(*id);
+            } else {
+                revision.get_or_insert(*id);
+            }
+        }
+    }
+    revision
+        .map(Existing::Revision)
+        .or_else(|| older.map(Existing::Older))
+}
+
+/// Compares two versions by the numbers in them, e.g. `1.10` is newer than
+/// `1.9`, and `2024.06.01.0` is newer than `2024.05.20.1`.
+///
+/// Returns [`None`] if either version is missing or has no numbers, unless
+/// both are the same.
+fn compare_versions(a: Option<&str>, b: Option<&str>) -> Option<Ordering> {
+    fn numbers(version: &str) -> Option<Vec<u64>> {
+        let numbers = version
+            .split(|c: char| !c.is_ascii_digit())
+            .filter(|part| !part.is_empty())
+            .map(|part| part.parse().ok())
+            .collect::<Option<Vec<u64>>>()?;
+        (!numbers.is_empty()).then_some(numbers)
+    }
+
+    let (a, b) = (a?, b?);
+    if a == b {
+        return Some(Ordering::Equal);
+    }
+    Some(numbers(a)?.cmp(&numbers(b)?))
+}
+
+/// Sets the archive hash in a dictionary's meta, once the archive has been
+/// hashed alongside its import.
+pub(super) async fn store_archive_hash(
+    db: &Pool<Sqlite>,
+    insert_lock: &InsertLock,
+    id: DictionaryId,
+    archive_hash: &str,
+) -> Result<()> {
+    let _inserting = insert_lock.lock().await;
+    sqlx::query!(
+        "UPDATE dictionary SET meta = json_set(meta, '$.archive_hash', $1) WHERE id = $2",
+        archive_hash,
+        id.0,
+    )
+    .execute(db)
+    .await
+    .context("failed to update dictionary meta")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            Engine,
+            import::ImportError,
+            test_util::{done, write_yomitan_zip},
+        },
+        serde_json::json,
+        std::{
+            path::{Path, PathBuf},
+            sync::Arc,
+        },
+    };
+
+    async fn yomitan_zip(path: &Path, revision: &str, url: &str) {
+        let index = json!({
+            "title": "Jitendex",
+            "revision": revision,
+            "format": 3,
+            "url": url,
+        });
+        let bank = json!([["犬", "いぬ", "", "", 0, ["dog"], 1, ""]]);
+        write_yomitan_zip(path, &index, &[bank]).await;
+    }
+
+    async fn import(engine: &Engine, path: PathBuf) -> Result<DictionaryId, ImportError> {
+        done(engine.import_dictionary(Arc::new(path))).await
+    }
+
+    #[tokio::test]
+    async fn distinguish_duplicates() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let engine = Engine::new(data_dir.path()).await.unwrap();
+        let path = |name: &str| data_dir.path().join(name);
+
+        yomitan_zip(&path("original.zip"), "1", "https://jitendex.org").await;
+        let id = import(&engine, path("original.zip")).await.unwrap();
+        let dictionaries = engine.dictionaries();
+        assert!(dictionaries[&id].meta.archive_hash.is_some());
+        assert!(dictionaries[&id].meta.index_hash.is_some());
+
+        tokio::fs::copy(path("original.zip"), path("renamed.zip"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            import(&engine, path("renamed.zip")).await,
+            Err(ImportError::AlreadyImported { id: existing_id }) if existing_id == id
+        ));
+
+        yomitan_zip(&path("newer.zip"), "2", "https://jitendex.org").await;
+        assert!(matches!(
+            import(&engine, path("newer.zip")).await,
+            Err(ImportError::NewerRevision { id: existing_id }) if existing_id == id
+        ));
+
+        yomitan_zip(&path("older.zip"), "0", "https://jitendex.org").await;
+        assert!(matches!(
+            import(&engine, path("older.zip")).await,
+            Err(ImportError::AlreadyExists { id: existing_id }) if existing_id == id
+        ));
+
+        let bank = json!([["猫", "ねこ", "", "", 0, ["cat"], 1, ""]]);
+        let index = json!({
+            "title": "Jitendex",
+            "revision": "1",
+            "format": 3,
+            "url": "https://jitendex.org",
+        });
+        write_yomitan_zip(&path("same.zip"), &index, &[bank]).await;
+        assert!(matches!(
+            import(&engine, path("same.zip")).await,
+            Err(ImportError::AlreadyExists { id: existing_id }) if existing_id == id
+        ));
+
+        let index = json!({
+            "title": "Jitendex",
+            "revision": "1",
+            "format": 3,
+            "url": "https://jitendex.org",
+            "author": "Someone else",
+        });
+        let bank = json!([["犬", "いぬ", "", "", 0, ["dog"], 1, ""]]);
+        write_yomitan_zip(&path("other_author.zip"), &index, &[bank]).await;
+        let other_author_id = import(&engine, path("other_author.zip")).await.unwrap();
+        assert_ne!(id, other_author_id);
+
+        yomitan_zip(&path("other.zip"), "1", "https://example.com").await;
+        let other_id = import(&engine, path("other.zip")).await.unwrap();
+        assert_ne!(id, other_id);
+        assert_eq!(3, engine.dictionaries().len());
+    }
+
+    #[test]
+    fn versions() {
+        assert_eq!(
+            Some(Ordering::Less),
+            compare_versions(Some("1.9"), Some("1.10"))
+        );
+        assert_eq!(
+            Some(Ordering::Greater),
+            compare_versions(Some("2024.06.01.0"), Some("2024.05.20.1"))
+        );
+        assert_eq!(
+            Some(Ordering::Equal),
+            compare_versions(Some("v2"), Some("v2"))
+        );
+        assert_eq!(
+            Some(Ordering::Less),
+            compare_versions(Some("v1"), Some("2"))
+        );
+        assert_eq!(None, compare_versions(Some("beta"), Some("1")));
+        assert_eq!(None, compare_versions(None, Some("1")));
+    }
+}
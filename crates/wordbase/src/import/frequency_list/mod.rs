@@ -0,0 +1,205 @@
+mod parse;
+
+use {
+    super::{
+        ImportContinue, ImportKind, ImportProgress, OpenArchive,
+        source::{self, Source, ZipArchive, open_source},
+    },
+    crate::import::{Batches, continue_with, insert::Inserter},
+    anyhow::{Context, Result, bail},
+    futures::future::BoxFuture,
+    std::{collections::HashSet, path::Path, sync::Arc},
+    tokio::sync::mpsc,
+    tracing::{debug, trace},
+    wordbase_api::{
+        DictionaryKind, DictionaryMeta, FrequencyValue, Term, dict::yomitan::Frequency,
+    },
+};
+
+pub struct FrequencyList;
+
+impl ImportKind for FrequencyList {
+    fn is_of_kind(&self, open_archive: Arc<dyn OpenArchive>) -> BoxFuture<'_, Result<()>> {
+        Box::pin(validate(open_archive))
+    }
+
+    fn start_import(
+        &self,
+        open_archive: Arc<dyn OpenArchive>,
+        progress_tx: mpsc::Sender<ImportProgress>,
+    ) -> BoxFuture<Result<(DictionaryMeta, ImportContinue)>> {
+        Box::pin(start_import(open_archive, progress_tx))
+    }
+}
+
+/// How many bytes of a bare file we read to detect its layout.
+const PROBE_LEN: u64 = 64 * 1024;
+
+const LINES_PER_PROGRESS: usize = 10000;
+
+const LINES_PER_BATCH: usize = 10000;
+
+const DEFAULT_NAME: &str = "Frequency list";
+
+/// Extensions of the text file we look for in a zip archive.
+const EXTENSIONS: &[&str] = &["csv", "tsv", "txt"];
+
+/// Finds the single text file in the zip.
+///
+/// The list may either be imported as a bare or gzipped text file, or as a zip
+/// archive containing a single text file.
+fn zip_entry(archive: &ZipArchive) -> Result<(usize, String)> {
+    let mut entries = source::zip_files(archive).filter_map(|(index, path)| {
+        let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+        EXTENSIONS
+            .contains(&extension.as_str())
+            .then(|| (index, path.to_owned()))
+    });
+    let Some(entry) = entries.next() else {
+        bail!("no text file in archive");
+    };
+    if entries.next().is_some() {
+        bail!("multiple text files in archive");
+    }
+    Ok(entry)
+}
+
+/// Reads the list, along with the name of the file it came from, if known.
+async fn read_list(source: Source, limit: u64) -> Result<(String, Option<String>)> {
+    let (data, path) = match source {
+        Source::Bare(archive) => (source::read_bare(archive, limit).await?, None),
+        Source::Zip(mut archive) => {
+            let (index, path) = zip_entry(&archive)?;
+            let data = source::read_zip_entry(&mut archive, index, &path).await?;
+            (data, Some(path))
+        }
+    };
+    if data.contains(&0) {
+        bail!("not a text file");
+    }
+    // if we only read the start of the file, the last character may be cut off
+    let text = String::from_utf8_lossy(&data);
+    let text = text.strip_prefix('\u{feff}').unwrap_or(&text).to_owned();
+    Ok((text, path))
+}
+
+async fn validate(open_archive: Arc<dyn OpenArchive>) -> Result<()> {
+    let source = open_source(&*open_archive).await?;
+    let (text, _) = read_list(source, PROBE_LEN).await?;
+    parse::detect(&text)?;
+    Ok(())
+}
+
+/// Gets the name of the list, from the name of the file in the archive, or
+/// from a leading `# name` comment line.
+fn list_name(text: &str, path: Option<&str>) -> String {
+    path.and_then(|path| Path::new(path).file_stem()?.to_str())
+        .or_else(|| {
+            text.lines()
+                .next()?
+                .strip_prefix('#')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+        })
+        .unwrap_or(DEFAULT_NAME)
+        .to_owned()
+}
+
+async fn start_import(
+    open_archive: Arc<dyn OpenArchive>,
+    progress_tx: mpsc::Sender<ImportProgress>,
+) -> Result<(DictionaryMeta, ImportContinue)> {
+    // lists are small enough that we can read them all up front
+    let source = open_source(&*open_archive).await?;
+    let (text, path) = read_list(source, u64::MAX).await?;
+    let layout = parse::detect(&text)?;
+    debug!("Detected layout {layout:?}");
+
+    let meta = DictionaryMeta::new(
+        DictionaryKind::FrequencyList,
+        list_name(&text, path.as_deref()),
+    );
+
+    Ok((
+        meta,
+        continue_with(move |mut job| async move {
+            let import = async |insert: &mut Inserter<'_, '_>, (line_num, term, value)| {
+                import_row(insert, term, value)
+                    .await
+                    .with_context(|| format!("failed to import line {line_num}"))
+            };
+
+            let lines = text
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| !parse::is_ignored(line))
+                .skip(usize::from(layout.has_header))
+                .collect::<Vec<_>>();
+            let num_lines = lines.len();
+
+            // lists are sorted from most to least frequent, and some lists repeat
+            // a word, so we only keep its first (most frequent) entry
+            let mut seen = HashSet::new();
+            let mut lines_skipped = 0usize;
+            let mut batches = Batches::new("lines", LINES_PER_BATCH);
+            for (lines_done, (line_index, line)) in lines.into_iter().enumerate() {
+                if let Some((term, value)) = parse_line(&layout, line, &mut seen) {
+                    if let Some(batch) = batches.push((line_index + 1, term, value)) {
+                        job.insert_batch(batch, import).await?;
+                    }
+                } else {
+                    lines_skipped += 1;
+                }
+
+                if (lines_done + 1) % LINES_PER_PROGRESS == 0 {
+                    let frac = ((lines_done + 1) as f64) / (num_lines as f64);
+                    _ = progress_tx.try_send(ImportProgress { frac });
+                    trace!("{} / {num_lines} lines imported", lines_done + 1);
+                }
+            }
+            job.insert_batch(batches.finish(), import).await?;
+            debug!("{num_lines} total lines, {lines_skipped} skipped");
+
+            let tx = job.begin().await?;
+            job.finish(tx).await
+        }),
+    ))
+}
+
+/// Parses a single line of the list into its term and frequency, and adds
+/// the term to `seen`.
+///
+/// Returns [`None`] if the line didn't match the layout, or was a duplicate.
+fn parse_line(
+    layout: &parse::Layout,
+    line: &str,
+    seen: &mut HashSet<Term>,
+) -> Option<(Term, FrequencyValue)> {
+    let row = layout.row(line)?;
+    let term = Term::from_parts(Some(row.word), row.reading)?;
+    seen.insert(term.clone()).then_some((term, row.value))
+}
+
+async fn import_row(
+    insert: &mut Inserter<'_, '_>,
+    term: Term,
+    value: FrequencyValue,
+) -> Result<()> {
+    let record = Frequency {
+        value: Some(value),
+        display: None,
+    };
+    let record_id = insert
+        .record(&record)
+        .await
+        .context("failed to insert record")?;
+    insert
+        .term_record(term.clone(), record_id)
+        .await
+        .context("failed to insert term record")?;
+    insert
+        .frequency(term, value)
+        .await
+        .context("failed to insert frequency record")?;
+    Ok(())
+}
@@ -0,0 +1,314 @@
+//! Detects the layout of a plain frequency list, where each line holds a word
+//! and its frequency, e.g. `1<TAB>の` or `the,23135851162`.
+
+use {
+    anyhow::{Result, bail},
+    wordbase_api::FrequencyValue,
+};
+
+/// Delimiters we try, in order of preference.
+const DELIMITERS: &[char] = &['\t', ',', ';', '|'];
+
+/// How many data lines we look at to detect the layout.
+pub const SAMPLE_LINES: usize = 100;
+
+/// Header names of a column holding the word itself.
+const WORD_HEADERS: &[&str] = &[
+    "word",
+    "term",
+    "headword",
+    "lemma",
+    "expression",
+    "vocab",
+    "kanji",
+];
+
+/// Header names of a column holding the reading of the word.
+const READING_HEADERS: &[&str] = &["reading", "kana", "yomi", "furigana", "lform"];
+
+/// Header names of a column holding the rank of a word.
+const RANK_HEADERS: &[&str] = &["rank", "ranking", "#", "no", "index"];
+
+/// Header names of a column holding how many times a word occurs.
+const OCCURRENCE_HEADERS: &[&str] = &[
+    "count",
+    "freq",
+    "frequency",
+    "occurrence",
+    "occurrences",
+    "occurences",
+    "hits",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueMode {
+    Rank,
+    Occurrence,
+}
+
+/// Which columns of the list hold which data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub delimiter: char,
+    pub has_header: bool,
+    pub word: usize,
+    pub reading: Option<usize>,
+    pub value: usize,
+    pub mode: ValueMode,
+}
+
+/// Checks if a line holds no data, and should be skipped.
+pub fn is_ignored(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
+
+fn fields(line: &str, delimiter: char) -> impl Iterator<Item = &str> {
+    line.trim_end_matches(['\r', '\n'])
+        .split(delimiter)
+        .map(|field| {
+            let field = field.trim();
+            field
+                .strip_prefix('"')
+                .and_then(|field| field.strip_suffix('"'))
+                .unwrap_or(field)
+        })
+}
+
+fn parse_int(field: &str) -> Option<i64> {
+    field.parse().ok()
+}
+
+fn header_column(header: &[&str], names: &[&str]) -> Option<usize> {
+    header
+        .iter()
+        .position(|field| names.contains(&field.to_lowercase().as_str()))
+}
+
+/// Detects the layout of a frequency list from its first lines.
+pub fn detect(text: &str) -> Result<Layout> {
+    let lines = text
+        .lines()
+        .filter(|line| !is_ignored(line))
+        .take(SAMPLE_LINES + 1)
+        .collect::<Vec<_>>();
+    if lines.len() < 2 {
+        bail!("not enough lines");
+    }
+
+    for &delimiter in DELIMITERS {
+        if let Some(layout) = detect_with(&lines, delimiter) {
+            return Ok(layout);
+        }
+    }
+    bail!("no consistent word and frequency columns")
+}
+
+fn detect_with(lines: &[&str], delimiter: char) -> Option<Layout> {
+    let rows = lines
+        .iter()
+        .map(|line| fields(line, delimiter).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let num_columns = rows[1].len();
+    if num_columns < 2 || rows.iter().any(|row| row.len() != num_columns) {
+        return None;
+    }
+
+    // integer columns are found from the lines after the first, since the
+    // first line may be a header
+    let int_columns = (0..num_columns)
+        .filter(|&column| rows[1..].iter().all(|row| parse_int(row[column]).is_some()))
+        .collect::<Vec<_>>();
+    let text_columns = (0..num_columns)
+        .filter(|column| !int_columns.contains(column))
+        .collect::<Vec<_>>();
+    if int_columns.is_empty() || text_columns.is_empty() {
+        return None;
+    }
+
+    let has_header = int_columns
+        .iter()
+        .any(|&column| parse_int(rows[0][column]).is_none());
+    let (header, data) = if has_header {
+        (Some(&rows[0]), &rows[1..])
+    } else {
+        (None, &rows[..])
+    };
+
+    let header_rank = header
+        .and_then(|header| header_column(header, RANK_HEADERS))
+        .filter(|column| int_columns.contains(column));
+    let header_occurrence = header
+        .and_then(|header| header_column(header, OCCURRENCE_HEADERS))
+        .filter(|column| int_columns.contains(column));
+    let (value, mode) = header_rank
+        .map(|column| (column, ValueMode::Rank))
+        .or_else(|| header_occurrence.map(|column| (column, ValueMode::Occurrence)))
+        .unwrap_or_else(|| guess_value_column(&int_columns, &text_columns, data));
+
+    let word = header
+        .and_then(|header| header_column(header, WORD_HEADERS))
+        .filter(|column| text_columns.contains(column))
+        .unwrap_or(text_columns[0]);
+    // without a header, we can only be sure that the other column is a
+    // reading if it's the only other one
+    let reading = header.map_or_else(
+        || (text_columns.len() == 2).then(|| text_columns[1]),
+        |header| {
+            header_column(header, READING_HEADERS)
+                .filter(|column| text_columns.contains(column) && *column != word)
+        },
+    );
+
+    Some(Layout {
+        delimiter,
+        has_header,
+        word,
+        reading,
+        value,
+        mode,
+    })
+}
+
+/// Guesses which integer column holds the frequency value, and whether it's a
+/// rank or an occurrence count, from how the values change down the list.
+///
+/// Lists are sorted from most to least frequent, so ranks go up, and
+/// occurrence counts go down.
+fn guess_value_column(
+    int_columns: &[usize],
+    text_columns: &[usize],
+    data: &[Vec<&str>],
+) -> (usize, ValueMode) {
+    let values = |column: usize| {
+        data.iter()
+            .filter_map(move |row| parse_int(row[column]))
+            .collect::<Vec<_>>()
+    };
+    let ascending = |column: &usize| values(*column).is_sorted();
+    let descending = |column: &usize| values(*column).iter().rev().is_sorted();
+
+    if let Some(&column) = int_columns.iter().find(|column| ascending(column)) {
+        return (column, ValueMode::Rank);
+    }
+    if let Some(&column) = int_columns.iter().find(|column| descending(column)) {
+        return (column, ValueMode::Occurrence);
+    }
+    // the list isn't sorted, so fall back to the usual conventions of
+    // `rank<TAB>word` and `word,count`
+    let column = int_columns[0];
+    if column < text_columns[0] {
+        (column, ValueMode::Rank)
+    } else {
+        (column, ValueMode::Occurrence)
+    }
+}
+
+/// Single row of a frequency list.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Row<'a> {
+    pub word: &'a str,
+    pub reading: Option<&'a str>,
+    pub value: FrequencyValue,
+}
+
+impl Layout {
+    /// Parses a data line of the list.
+    ///
+    /// Returns [`None`] if the line doesn't match the layout.
+    pub fn row<'a>(&self, line: &'a str) -> Option<Row<'a>> {
+        let fields = fields(line, self.delimiter).collect::<Vec<_>>();
+        let word = *fields.get(self.word)?;
+        if word.is_empty() {
+            return None;
+        }
+        let reading = self
+            .reading
+            .and_then(|column| fields.get(column).copied())
+            .filter(|reading| !reading.is_empty() && *reading != word);
+        let value = parse_int(fields.get(self.value)?)?;
+        let value = match self.mode {
+            ValueMode::Rank => FrequencyValue::Rank(value),
+            ValueMode::Occurrence => FrequencyValue::Occurrence(value),
+        };
+        Some(Row {
+            word,
+            reading,
+            value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rank_word_reading() {
+        let layout = detect("1\tの\t\n2\tに\t\n3\t言う\tいう\n").unwrap();
+        assert_eq!(
+            Layout {
+                delimiter: '\t',
+                has_header: false,
+                word: 1,
+                reading: Some(2),
+                value: 0,
+                mode: ValueMode::Rank,
+            },
+            layout
+        );
+        assert_eq!(
+            Some(Row {
+                word: "言う",
+                reading: Some("いう"),
+                value: FrequencyValue::Rank(3),
+            }),
+            layout.row("3\t言う\tいう")
+        );
+    }
+
+    #[test]
+    fn word_count() {
+        let layout = detect("the,23135851162\nof,13151942776\nand,12997637966\n").unwrap();
+        assert_eq!(
+            Layout {
+                delimiter: ',',
+                has_header: false,
+                word: 0,
+                reading: None,
+                value: 1,
+                mode: ValueMode::Occurrence,
+            },
+            layout
+        );
+    }
+
+    #[test]
+    fn header() {
+        let layout =
+            detect("rank\tlForm\tlemma\tpos\tfrequency\n1\tノ\tの\t助詞\t5061558\n2\tニ\tに\t助詞\t3576558\n")
+                .unwrap();
+        assert_eq!(
+            Layout {
+                delimiter: '\t',
+                has_header: true,
+                word: 2,
+                reading: Some(1),
+                value: 0,
+                mode: ValueMode::Rank,
+            },
+            layout
+        );
+
+        let layout = detect("Word,Count\nthe,100\nof,200\n").unwrap();
+        assert_eq!(1, layout.value);
+        assert_eq!(ValueMode::Occurrence, layout.mode);
+    }
+
+    #[test]
+    fn not_frequency_list() {
+        assert!(detect("hello world\nfoo bar\n").is_err());
+        assert!(detect("1\t2\n3\t4\n").is_err());
+        assert!(detect("only one line\t1\n").is_err());
+    }
+}
@@ -44,8 +44,17 @@ pub struct Inserter<'tx, 'c> {
     records: Insert<Record>,
     term_records: Insert<Term>,
     frequencies: Insert<FrequencyValue>,
+    kanji_radicals: Insert<KanjiRadical>,
+    sentence_terms: Insert<SentenceTerm>,
+    skip_media: bool,
 }
 
+/// Marker for batching inserts into `kanji_radical`.
+struct KanjiRadical;
+
+/// Marker for batching inserts into `example_sentence`.
+struct SentenceTerm;
+
 impl<'tx, 'c> Inserter<'tx, 'c> {
     pub async fn new(tx: &'tx mut Transaction<'c, Sqlite>, source: DictionaryId) -> Result<Self> {
         let last_record_id = sqlx::query_scalar!("SELECT MAX(id) FROM record")
@@ -61,9 +70,18 @@ impl<'tx, 'c> Inserter<'tx, 'c> {
             records: Insert::<Record>::new(),
             term_records: Insert::<Term>::new(),
             frequencies: Insert::<FrequencyValue>::new(),
+            kanji_radicals: Insert::<KanjiRadical>::new(),
+            sentence_terms: Insert::<SentenceTerm>::new(),
+            skip_media: false,
         })
     }
 
+    /// Makes [`Inserter::media`] do nothing, so that validating an archive
+    /// doesn't copy all of its media.
+    pub(super) const fn skip_media(&mut self) {
+        self.skip_media = true;
+    }
+
     pub async fn flush(&mut self) -> Result<()> {
         self.records
             .flush(self.tx)
@@ -77,6 +95,14 @@ impl<'tx, 'c> Inserter<'tx, 'c> {
             .flush(self.tx)
             .await
             .context("failed to flush frequencies")?;
+        self.kanji_radicals
+            .flush(self.tx)
+            .await
+            .context("failed to flush kanji radicals")?;
+        self.sentence_terms
+            .flush(self.tx)
+            .await
+            .context("failed to flush example sentences")?;
         Ok(())
     }
 
@@ -101,6 +127,63 @@ impl<'tx, 'c> Inserter<'tx, 'c> {
             .insert(self.tx, self.source, term, frequency)
             .await
     }
+
+    /// Records that `radical` is one of the components which make up `kanji`.
+    pub async fn kanji_radical(&mut self, kanji: char, radical: char) -> Result<()> {
+        self.kanji_radicals
+            .insert(self.tx, self.source, kanji, radical)
+            .await
+    }
+
+    /// Indexes an example sentence record under a term that it contains.
+    ///
+    /// Terms without a headword can't be indexed, and are ignored.
+    pub async fn example_sentence(&mut self, term: Term, record_id: RecordId) -> Result<()> {
+        self.sentence_terms
+            .insert(self.tx, self.source, term, record_id)
+            .await
+    }
+
+    /// Indexes every record which is already indexed under `target` (as a
+    /// headword only) under `headword` too.
+    ///
+    /// This only finds records whose term records were committed or flushed
+    /// before calling this.
+    pub async fn link_headword(&mut self, headword: &str, target: &str) -> Result<()> {
+        sqlx::query!(
+            "INSERT OR IGNORE INTO term_record (source, headword, reading, record)
+            SELECT source, $2, NULL, record FROM term_record
+            WHERE source = $1 AND headword = $3 AND reading IS NULL",
+            self.source.0,
+            headword,
+            target,
+        )
+        .execute(&mut **self.tx)
+        .await
+        .context("failed to insert linked term records")?;
+        Ok(())
+    }
+
+    /// Inserts a media file for this dictionary.
+    ///
+    /// Media files may be large, so these are not batched, and are inserted
+    /// immediately.
+    pub async fn media(&mut self, path: &str, data: &[u8]) -> Result<()> {
+        if self.skip_media {
+            return Ok(());
+        }
+        sqlx::query!(
+            "INSERT OR REPLACE INTO media (source, path, data)
+            VALUES ($1, $2, $3)",
+            self.source.0,
+            path,
+            data,
+        )
+        .execute(&mut **self.tx)
+        .await
+        .context("failed to insert media")?;
+        Ok(())
+    }
 }
 
 struct Insert<T> {
@@ -275,6 +358,83 @@ impl Insert<FrequencyValue> {
     }
 }
 
+impl Insert<KanjiRadical> {
+    pub fn new() -> Self {
+        // compile-time guard to make sure the query is valid
+        _ = sqlx::query!(
+            "INSERT OR IGNORE INTO kanji_radical (source, kanji, radical)
+            VALUES ($1, $2, $3)",
+            DictionaryId(0).0,
+            "",
+            "",
+        );
+        Self {
+            qb: QueryBuilder::new(
+                "INSERT OR IGNORE INTO kanji_radical (source, kanji, radical)
+                VALUES ",
+            ),
+            binds: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub async fn insert(
+        &mut self,
+        tx: &mut Transaction<'_, Sqlite>,
+        source: DictionaryId,
+        kanji: char,
+        radical: char,
+    ) -> Result<()> {
+        self.do_insert::<3>(tx, |mut qb| {
+            qb.push_bind(source.0);
+            qb.push_bind(kanji.to_string());
+            qb.push_bind(radical.to_string());
+        })
+        .await
+    }
+}
+
+impl Insert<SentenceTerm> {
+    pub fn new() -> Self {
+        // compile-time guard to make sure the query is valid
+        _ = sqlx::query!(
+            "INSERT OR IGNORE INTO example_sentence (source, headword, reading, record)
+            VALUES ($1, $2, $3, $4)",
+            DictionaryId(0).0,
+            "",
+            "",
+            RecordId(0).0
+        );
+        Self {
+            qb: QueryBuilder::new(
+                "INSERT OR IGNORE INTO example_sentence (source, headword, reading, record)
+                VALUES ",
+            ),
+            binds: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub async fn insert(
+        &mut self,
+        tx: &mut Transaction<'_, Sqlite>,
+        source: DictionaryId,
+        term: Term,
+        record_id: RecordId,
+    ) -> Result<()> {
+        let (Some(headword), reading) = term.into_parts() else {
+            return Ok(());
+        };
+        self.do_insert::<4>(tx, |mut qb| {
+            qb.push_bind(source.0);
+            qb.push_bind(headword.into_inner());
+            qb.push_bind(reading.map(NormString::into_inner));
+            qb.push_bind(record_id.0);
+        })
+        .await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use sqlx::{Pool, query_scalar};
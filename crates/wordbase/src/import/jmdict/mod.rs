@@ -0,0 +1,173 @@
+mod parse;
+
+use {
+    super::{ImportContinue, ImportKind, ImportProgress, OpenArchive, source},
+    crate::import::{Batches, continue_with, insert::Inserter},
+    anyhow::{Context, Result, bail},
+    futures::future::BoxFuture,
+    parse::{Entry, Parser, Root},
+    std::sync::{Arc, atomic},
+    tokio::sync::mpsc,
+    tokio_util::compat::FuturesAsyncReadCompatExt,
+    tracing::{debug, trace},
+    wordbase_api::{
+        DictionaryKind, DictionaryMeta, Term,
+        dict::jmdict::{Name, Word},
+    },
+};
+
+pub struct Jmdict;
+
+impl ImportKind for Jmdict {
+    fn is_of_kind(&self, open_archive: Arc<dyn OpenArchive>) -> BoxFuture<'_, Result<()>> {
+        Box::pin(validate(open_archive))
+    }
+
+    fn start_import(
+        &self,
+        open_archive: Arc<dyn OpenArchive>,
+        progress_tx: mpsc::Sender<ImportProgress>,
+    ) -> BoxFuture<Result<(DictionaryMeta, ImportContinue)>> {
+        Box::pin(start_import(open_archive, progress_tx))
+    }
+}
+
+const ENTRIES_PER_PROGRESS: usize = 1000;
+
+const ENTRIES_PER_BATCH: usize = 5000;
+
+const ATTRIBUTION: &str = "This publication has included material from the JMdict \
+    (EDICT, etc.) dictionary files in accordance with the licence provisions of \
+    the Electronic Dictionaries Research Group. See \
+    https://www.edrdg.org/edrdg/licence.html";
+
+async fn validate(open_archive: Arc<dyn OpenArchive>) -> Result<()> {
+    let start = source::read_document_start(&*open_archive).await?;
+    if !start.contains("<!DOCTYPE JMdict") && !start.contains("<!DOCTYPE JMnedict") {
+        bail!("no JMdict or JMnedict document type");
+    }
+    Ok(())
+}
+
+async fn start_import(
+    open_archive: Arc<dyn OpenArchive>,
+    progress_tx: mpsc::Sender<ImportProgress>,
+) -> Result<(DictionaryMeta, ImportContinue)> {
+    // the archive may be gzipped, e.g. `JMdict_e.gz`
+    let (reader, cursor_pos, buf_len) = source::document_reader(&*open_archive).await?;
+    let mut parser = Parser::new(reader.compat());
+    let header = parser.header().await.context("failed to parse header")?;
+
+    let mut meta = match header.root {
+        Root::Jmdict => {
+            let mut meta = DictionaryMeta::new(DictionaryKind::Jmdict, "JMdict");
+            meta.description = Some("Japanese-Multilingual Dictionary".into());
+            meta.url = Some("https://www.edrdg.org/jmdict/j_jmdict.html".into());
+            meta
+        }
+        Root::Jmnedict => {
+            let mut meta = DictionaryMeta::new(DictionaryKind::Jmdict, "JMnedict");
+            meta.description = Some("Japanese Proper Names Dictionary".into());
+            meta.url = Some("https://www.edrdg.org/enamdict/enamdict_doc.html".into());
+            meta
+        }
+    };
+    meta.version = header.created;
+    meta.attribution = Some(ATTRIBUTION.into());
+
+    Ok((
+        meta,
+        continue_with(move |mut job| async move {
+            let import = async |insert: &mut Inserter<'_, '_>, entry: Entry| {
+                let ent_seq = entry.ent_seq;
+                import_entry(insert, entry)
+                    .await
+                    .with_context(|| format!("failed to import entry {ent_seq}"))
+            };
+
+            let mut batches = Batches::new("entries", ENTRIES_PER_BATCH);
+            let mut entries_done = 0usize;
+            while let Some(entry) = parser.next_entry().await? {
+                if let Some(batch) = batches.push(entry) {
+                    job.insert_batch(batch, import).await?;
+                }
+
+                entries_done += 1;
+                if entries_done % ENTRIES_PER_PROGRESS == 0 {
+                    let cursor_pos = cursor_pos.load(atomic::Ordering::SeqCst);
+                    let frac = (cursor_pos as f64) / (buf_len as f64);
+                    _ = progress_tx.try_send(ImportProgress { frac });
+                    trace!("{entries_done} entries imported - {cursor_pos} / {buf_len} bytes read");
+                }
+            }
+            job.insert_batch(batches.finish(), import).await?;
+            debug!("{entries_done} total entries");
+
+            let tx = job.begin().await?;
+            job.finish(tx).await
+        }),
+    ))
+}
+
+async fn import_entry(insert: &mut Inserter<'_, '_>, entry: Entry) -> Result<()> {
+    let terms = terms_of(&entry);
+    let mut record_ids = Vec::new();
+    if !entry.senses.is_empty() {
+        let record = Word {
+            ent_seq: entry.ent_seq,
+            senses: entry.senses,
+        };
+        record_ids.push(
+            insert
+                .record(&record)
+                .await
+                .context("failed to insert word record")?,
+        );
+    }
+    if !entry.translations.is_empty() {
+        let record = Name {
+            ent_seq: entry.ent_seq,
+            translations: entry.translations,
+        };
+        record_ids.push(
+            insert
+                .record(&record)
+                .await
+                .context("failed to insert name record")?,
+        );
+    }
+
+    for record_id in record_ids {
+        for term in &terms {
+            insert
+                .term_record(term.clone(), record_id)
+                .await
+                .context("failed to insert term record")?;
+        }
+    }
+    Ok(())
+}
+
+/// Gets all valid headword/reading pairs of an entry.
+fn terms_of(entry: &Entry) -> Vec<Term> {
+    let mut terms = Vec::new();
+    for reading in &entry.readings {
+        if entry.headwords.is_empty() || reading.no_kanji {
+            // kana-only words are stored with the kana as the headword,
+            // matching how Yomitan dictionaries store them
+            terms.extend(Term::from_headword(reading.text.as_str()));
+            continue;
+        }
+
+        terms.extend(
+            entry
+                .headwords
+                .iter()
+                .filter(|headword| {
+                    reading.restrict.is_empty() || reading.restrict.contains(headword)
+                })
+                .filter_map(|headword| Term::from_full(headword.as_str(), reading.text.as_str())),
+        );
+    }
+    terms
+}
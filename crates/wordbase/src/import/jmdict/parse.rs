@@ -0,0 +1,394 @@
+//! Streaming parser for the JMdict/JMnedict XML format.
+//!
+//! The full JMdict release is well over 100 MB of XML, so we never load the
+//! whole document into memory. Instead, we read it event-by-event and yield
+//! each `<entry>` as soon as it is complete.
+//!
+//! The tags used in the dictionary (parts of speech, fields, etc.) are defined
+//! as XML entities in the document's DTD, e.g. `<!ENTITY n "noun (common)">`,
+//! and are used in the document body as `<pos>&n;</pos>`. We keep the entity
+//! name as the tag code, and look up its expansion as the description.
+
+use {
+    anyhow::{Context, Result, bail},
+    quick_xml::{
+        Reader,
+        escape::resolve_predefined_entity,
+        events::{BytesStart, BytesText, Event},
+    },
+    regex::Regex,
+    std::{collections::HashMap, sync::LazyLock},
+    tokio::io::AsyncBufRead,
+    wordbase_api::dict::jmdict::{Gloss, Sense, Tag, Translation},
+};
+
+/// Language of a `<gloss>` which does not specify an `xml:lang`.
+const DEFAULT_LANG: &str = "eng";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Root {
+    Jmdict,
+    Jmnedict,
+}
+
+impl Root {
+    fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"JMdict" => Some(Self::Jmdict),
+            b"JMnedict" => Some(Self::Jmnedict),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Header {
+    pub root: Root,
+    /// Date that this release was generated, taken from the
+    /// `<!-- JMdict created: YYYY-MM-DD -->` comment.
+    pub created: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Entry {
+    pub ent_seq: u64,
+    pub headwords: Vec<String>,
+    pub readings: Vec<Reading>,
+    pub senses: Vec<Sense>,
+    pub translations: Vec<Translation>,
+}
+
+#[derive(Debug, Default)]
+pub struct Reading {
+    pub text: String,
+    /// `<re_nokanji>` - this reading is not a true reading of any headword.
+    pub no_kanji: bool,
+    /// `<re_restr>` - if non-empty, this reading only applies to these
+    /// headwords.
+    pub restrict: Vec<String>,
+}
+
+pub struct Parser<R> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+    entities: HashMap<String, String>,
+    /// If we have already consumed the `<entry>` start tag of the next entry.
+    in_entry: bool,
+}
+
+impl<R: AsyncBufRead + Unpin> Parser<R> {
+    pub fn new(reader: R) -> Self {
+        let mut reader = Reader::from_reader(reader);
+        reader.config_mut().trim_text(true);
+        Self {
+            reader,
+            buf: Vec::new(),
+            entities: HashMap::new(),
+            in_entry: false,
+        }
+    }
+
+    /// Reads the DTD and root element, up to the first `<entry>`.
+    pub async fn header(&mut self) -> Result<Header> {
+        static ENTITY: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(r#"<!ENTITY\s+(\S+)\s+"([^"]*)"\s*>"#).expect("should be valid regex")
+        });
+
+        let mut root = None;
+        let mut created = None;
+        loop {
+            self.buf.clear();
+            match self.reader.read_event_into_async(&mut self.buf).await? {
+                Event::DocType(doctype) => {
+                    let doctype =
+                        std::str::from_utf8(&doctype).context("document type is not UTF-8")?;
+                    for caps in ENTITY.captures_iter(doctype) {
+                        self.entities.insert(caps[1].to_owned(), caps[2].to_owned());
+                    }
+                }
+                Event::Start(start) if root.is_none() => {
+                    root = Some(Root::from_name(start.name().as_ref()).with_context(|| {
+                        format!(
+                            "invalid root element `{}`",
+                            String::from_utf8_lossy(start.name().as_ref())
+                        )
+                    })?);
+                }
+                Event::Start(start) if start.name().as_ref() == b"entry" => {
+                    self.in_entry = true;
+                    break;
+                }
+                Event::Comment(comment) => {
+                    let comment = String::from_utf8_lossy(&comment);
+                    if let Some((_, date)) = comment.split_once("created:") {
+                        created = Some(date.trim().to_owned());
+                    }
+                }
+                Event::End(_) | Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        Ok(Header {
+            root: root.context("no root element")?,
+            created,
+        })
+    }
+
+    /// Reads the next `<entry>`, or returns [`None`] if there are no more
+    /// entries in the document.
+    pub async fn next_entry(&mut self) -> Result<Option<Entry>> {
+        while !self.in_entry {
+            self.buf.clear();
+            match self.reader.read_event_into_async(&mut self.buf).await? {
+                Event::Start(start) if start.name().as_ref() == b"entry" => {
+                    self.in_entry = true;
+                }
+                Event::End(_) | Event::Eof => return Ok(None),
+                _ => {}
+            }
+        }
+        self.in_entry = false;
+
+        let mut entry = Entry::default();
+        // name of the element whose text we are currently reading
+        let mut element = Vec::<u8>::new();
+        loop {
+            self.buf.clear();
+            match self.reader.read_event_into_async(&mut self.buf).await? {
+                Event::Start(start) => {
+                    start_element(&mut entry, &start)?;
+                    element.clear();
+                    element.extend_from_slice(start.name().as_ref());
+                }
+                Event::Empty(start) => {
+                    if start.name().as_ref() == b"re_nokanji" {
+                        if let Some(reading) = entry.readings.last_mut() {
+                            reading.no_kanji = true;
+                        }
+                    }
+                }
+                Event::Text(text) => {
+                    text_element(&mut entry, &element, &text, &self.entities).with_context(
+                        || {
+                            format!(
+                                "failed to read `{}` of entry {}",
+                                String::from_utf8_lossy(&element),
+                                entry.ent_seq
+                            )
+                        },
+                    )?;
+                }
+                Event::End(end) => {
+                    if end.name().as_ref() == b"entry" {
+                        break;
+                    }
+                    element.clear();
+                }
+                Event::Eof => bail!("unexpected end of document in entry {}", entry.ent_seq),
+                _ => {}
+            }
+        }
+
+        // `<pos>` applies to all following senses until another `<pos>` is given
+        let mut last_pos = Vec::new();
+        for sense in &mut entry.senses {
+            if sense.pos.is_empty() {
+                sense.pos.clone_from(&last_pos);
+            } else {
+                last_pos.clone_from(&sense.pos);
+            }
+        }
+
+        Ok(Some(entry))
+    }
+}
+
+fn start_element(entry: &mut Entry, start: &BytesStart<'_>) -> Result<()> {
+    match start.name().as_ref() {
+        b"r_ele" => entry.readings.push(Reading::default()),
+        b"sense" => entry.senses.push(Sense::default()),
+        b"trans" => entry.translations.push(Translation::default()),
+        b"gloss" => {
+            let Some(sense) = entry.senses.last_mut() else {
+                return Ok(());
+            };
+            let mut gloss = Gloss {
+                text: String::new(),
+                lang: DEFAULT_LANG.into(),
+                kind: None,
+            };
+            for attr in start.attributes() {
+                let attr = attr.context("failed to read attribute")?;
+                let value = attr
+                    .unescape_value()
+                    .context("failed to unescape attribute")?;
+                match attr.key.as_ref() {
+                    b"xml:lang" => gloss.lang = value.into_owned(),
+                    b"g_type" => gloss.kind = Some(value.into_owned()),
+                    _ => {}
+                }
+            }
+            sense.glosses.push(gloss);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn text_element(
+    entry: &mut Entry,
+    element: &[u8],
+    text: &BytesText<'_>,
+    entities: &HashMap<String, String>,
+) -> Result<()> {
+    let tag = || -> Result<Tag> {
+        let raw = std::str::from_utf8(text).context("text is not UTF-8")?;
+        let code = raw
+            .strip_prefix('&')
+            .and_then(|s| s.strip_suffix(';'))
+            .unwrap_or(raw);
+        let description = entities
+            .get(code)
+            .cloned()
+            .unwrap_or_else(|| code.to_owned());
+        Ok(Tag {
+            code: code.to_owned(),
+            description,
+        })
+    };
+    let text = || -> Result<String> {
+        let text = text
+            .unescape_with(|entity| {
+                resolve_predefined_entity(entity)
+                    .or_else(|| entities.get(entity).map(String::as_str))
+            })
+            .context("failed to unescape text")?;
+        Ok(text.into_owned())
+    };
+
+    let sense = entry.senses.last_mut();
+    let trans = entry.translations.last_mut();
+    match (element, sense, trans) {
+        (b"ent_seq", _, _) => {
+            entry.ent_seq = text()?.parse().context("invalid sequence number")?;
+        }
+        (b"keb", _, _) => entry.headwords.push(text()?),
+        (b"reb", _, _) => {
+            if let Some(reading) = entry.readings.last_mut() {
+                reading.text = text()?;
+            }
+        }
+        (b"re_restr", _, _) => {
+            if let Some(reading) = entry.readings.last_mut() {
+                reading.restrict.push(text()?);
+            }
+        }
+        (b"stagk", Some(sense), _) => sense.restrict_headwords.push(text()?),
+        (b"stagr", Some(sense), _) => sense.restrict_readings.push(text()?),
+        (b"pos", Some(sense), _) => sense.pos.push(tag()?),
+        (b"field", Some(sense), _) => sense.field.push(tag()?),
+        (b"misc", Some(sense), _) => sense.misc.push(tag()?),
+        (b"dial", Some(sense), _) => sense.dialect.push(tag()?),
+        (b"ant", Some(sense), _) => sense.antonyms.push(text()?),
+        (b"s_inf", Some(sense), _) => sense.info.push(text()?),
+        (b"gloss", Some(sense), _) => {
+            if let Some(gloss) = sense.glosses.last_mut() {
+                gloss.text.push_str(&text()?);
+            }
+        }
+        // `<xref>` can appear in both JMdict `<sense>` and JMnedict `<trans>`,
+        // but an entry will only ever have one of these
+        (b"xref", Some(sense), _) => sense.xref.push(text()?),
+        (b"xref", None, Some(trans)) => trans.xref.push(text()?),
+        (b"name_type", _, Some(trans)) => trans.name_type.push(tag()?),
+        (b"trans_det", _, Some(trans)) => trans.details.push(text()?),
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JMDICT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE JMdict [
+<!ELEMENT JMdict (entry*)>
+<!ENTITY n "noun (common) (futsuumeishi)">
+<!ENTITY uk "word usually written using kana alone">
+]>
+<JMdict>
+<!-- JMdict created: 2025-05-16 -->
+<entry>
+<ent_seq>1467640</ent_seq>
+<k_ele>
+<keb>猫</keb>
+</k_ele>
+<r_ele>
+<reb>ねこ</reb>
+</r_ele>
+<r_ele>
+<reb>ネコ</reb>
+<re_nokanji/>
+</r_ele>
+<sense>
+<pos>&n;</pos>
+<misc>&uk;</misc>
+<gloss>cat</gloss>
+<gloss xml:lang="fre" g_type="lit">chat &amp; co</gloss>
+</sense>
+<sense>
+<gloss>shamisen</gloss>
+</sense>
+</entry>
+</JMdict>
+"#;
+
+    #[tokio::test]
+    async fn parse() {
+        let mut parser = Parser::new(JMDICT.as_bytes());
+        let header = parser.header().await.unwrap();
+        assert_eq!(Root::Jmdict, header.root);
+        assert_eq!(Some("2025-05-16"), header.created.as_deref());
+
+        let entry = parser.next_entry().await.unwrap().unwrap();
+        assert_eq!(1_467_640, entry.ent_seq);
+        assert_eq!(vec!["猫".to_owned()], entry.headwords);
+        assert_eq!("ねこ", entry.readings[0].text);
+        assert!(!entry.readings[0].no_kanji);
+        assert_eq!("ネコ", entry.readings[1].text);
+        assert!(entry.readings[1].no_kanji);
+
+        let [first, second] = entry.senses.as_slice() else {
+            panic!("expected 2 senses, got {:?}", entry.senses);
+        };
+        assert_eq!(
+            vec![Tag {
+                code: "n".into(),
+                description: "noun (common) (futsuumeishi)".into()
+            }],
+            first.pos
+        );
+        assert_eq!("uk", first.misc[0].code);
+        assert_eq!(
+            vec![
+                Gloss {
+                    text: "cat".into(),
+                    lang: "eng".into(),
+                    kind: None,
+                },
+                Gloss {
+                    text: "chat & co".into(),
+                    lang: "fre".into(),
+                    kind: Some("lit".into()),
+                },
+            ],
+            first.glosses
+        );
+        // inherited from the previous sense
+        assert_eq!(first.pos, second.pos);
+
+        assert!(parser.next_entry().await.unwrap().is_none());
+    }
+}
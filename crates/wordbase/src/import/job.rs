@@ -0,0 +1,873 @@
+//! Import jobs, which let an import be cancelled, or resumed after the process
+//! was killed part way through.
+//!
+//! When an import starts, its dictionary row is inserted straight away along
+//! with an `import_job` row. While the job exists, the dictionary is hidden
+//! from [`Engine::dictionaries`]. Importers then insert their data in one or
+//! more transactions, where each transaction commits a *checkpoint* - the
+//! entries of the archive (banks, files, etc.) which it fully imported. The
+//! last transaction deletes the job, making the dictionary visible. If the job
+//! is re-importing a dictionary, that same transaction swaps the new data into
+//! the old dictionary, so that the two are never both visible.
+//!
+//! Since a checkpoint is committed in the same transaction as the data of its
+//! entries, the database only ever holds data for completed entries. Resuming
+//! a job skips these entries, and cancelling a job deletes all of its data.
+//!
+//! A job also remembers its [`ImportOptions`], so that a resumed import
+//! behaves the same as the original one, and the hash of its archive once that
+//! has been computed, so that a resumed import doesn't hash it again.
+//!
+//! Many imports may run at once, but only one of them can insert at a time:
+//! [`ImportJob::begin`] takes the engine's [`InsertLock`], and the returned
+//! [`ImportTx`] holds it until it is committed or dropped. This means that
+//! imports can read and parse their archives in parallel, without failing
+//! because another import is holding the database's write lock.
+//!
+//! Importers which stream their archive, rather than reading it as separate
+//! entries, parse it in fixed-size batches and insert each one with
+//! [`ImportJob::insert_batch`], so that they only hold the lock while
+//! inserting.
+
+use {
+    super::{ImportOptions, ValidationIssue, insert::Inserter},
+    crate::{
+        Engine, NotFound,
+        dictionary::{
+            BEGIN_WITHOUT_FOREIGN_KEYS, delete_dictionary, disable_foreign_keys, swap_in_dictionary,
+        },
+    },
+    anyhow::{Context, Result, bail},
+    foldhash::{HashMap, HashSet},
+    serde::{Deserialize, Serialize},
+    sqlx::{Acquire, Pool, Sqlite, Transaction},
+    std::{
+        mem,
+        ops::{Deref, DerefMut},
+        sync::{Arc, Mutex, MutexGuard, PoisonError},
+    },
+    tokio::{
+        sync::{self, OwnedMutexGuard, mpsc},
+        task::AbortHandle,
+    },
+    tracing::{debug, info},
+    wordbase_api::{DictionaryId, DictionaryMeta},
+};
+
+/// Opaque and unique identifier for an unfinished dictionary import.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ImportJobId(pub i64);
+
+#[cfg(feature = "uniffi")]
+uniffi::custom_newtype!(ImportJobId, i64);
+
+/// Dictionary import which has started but not finished, returned by
+/// [`Engine::import_jobs`].
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct ImportJobInfo {
+    pub id: ImportJobId,
+    /// Meta of the dictionary being imported.
+    pub meta: DictionaryMeta,
+    /// Dictionary which this import will replace once done, if it is a
+    /// re-import.
+    pub replacing: Option<DictionaryId>,
+    /// Number of archive entries which have already been imported.
+    pub entries_done: u64,
+}
+
+/// Lock which is held while writing to the database during an import, so that
+/// concurrent imports insert one at a time.
+pub type InsertLock = Arc<sync::Mutex<()>>;
+
+/// Handle to an import job, passed to an importer's continuation.
+#[derive(Debug)]
+pub struct ImportJob {
+    db: Pool<Sqlite>,
+    insert_lock: InsertLock,
+    id: ImportJobId,
+    dictionary_id: DictionaryId,
+    replacing: Option<DictionaryId>,
+    completed: HashSet<String>,
+    leniency: Leniency,
+    skip_media: bool,
+}
+
+/// Transaction of an import job, returned by [`ImportJob::begin`].
+///
+/// This holds the engine's [`InsertLock`] until it is committed with
+/// [`ImportJob::checkpoint`] or [`ImportJob::finish`], or dropped (which
+/// rolls it back).
+#[derive(Debug)]
+pub struct ImportTx {
+    // dropped first, so that the transaction is rolled back before another
+    // import can start inserting
+    tx: Transaction<'static, Sqlite>,
+    _inserting: OwnedMutexGuard<()>,
+}
+
+impl Deref for ImportTx {
+    type Target = Transaction<'static, Sqlite>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tx
+    }
+}
+
+impl DerefMut for ImportTx {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.tx
+    }
+}
+
+/// Splits rows, which are parsed one at a time from a streamed archive, into
+/// batches for [`ImportJob::insert_batch`].
+#[derive(Debug)]
+pub struct Batches<T> {
+    name: String,
+    size: usize,
+    index: usize,
+    rows: Vec<T>,
+}
+
+/// Rows of a single batch, created by [`Batches`].
+#[derive(Debug)]
+pub struct Batch<T> {
+    /// Name that this batch is checkpointed as.
+    entry: String,
+    rows: Vec<T>,
+}
+
+impl<T> Batches<T> {
+    /// Creates batches of `size` rows, which are checkpointed as `{name} {n}`
+    /// for the `n`th batch.
+    #[must_use]
+    pub fn new(name: impl Into<String>, size: usize) -> Self {
+        Self {
+            name: name.into(),
+            size,
+            index: 0,
+            rows: Vec::with_capacity(size),
+        }
+    }
+
+    /// Adds a row, returning the batch once it is full.
+    pub fn push(&mut self, row: T) -> Option<Batch<T>> {
+        self.rows.push(row);
+        (self.rows.len() >= self.size).then(|| self.take())
+    }
+
+    /// Takes the last batch, which may not be full.
+    #[must_use]
+    pub fn finish(mut self) -> Batch<T> {
+        self.take()
+    }
+
+    fn take(&mut self) -> Batch<T> {
+        let entry = format!("{} {}", self.name, self.index);
+        self.index += 1;
+        let rows = mem::replace(&mut self.rows, Vec::with_capacity(self.size));
+        Batch { entry, rows }
+    }
+}
+
+/// Decides what happens when a single row of an archive fails to import.
+///
+/// This can be cloned and moved into other tasks of the import.
+#[derive(Debug, Clone)]
+pub struct Leniency {
+    lenient: bool,
+    warning_tx: Option<mpsc::Sender<ValidationIssue>>,
+}
+
+impl Leniency {
+    /// Checks if rows which fail to import should be skipped, rather than
+    /// failing the import.
+    #[must_use]
+    pub const fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+
+    /// Handles a row of archive entry `path` failing to import with `err`.
+    ///
+    /// If the import is lenient, this sends a warning and returns [`Ok`], so
+    /// that the caller can skip the row. Otherwise, this returns `err`.
+    pub async fn skip(&self, path: &str, index: Option<usize>, err: anyhow::Error) -> Result<()> {
+        if !self.lenient {
+            return Err(err);
+        }
+        debug!("Skipping row {index:?} of `{path}`: {err:?}");
+        if let Some(warning_tx) = &self.warning_tx {
+            let warning = ValidationIssue::new(Some(path), index, format!("{err:?}"));
+            _ = warning_tx.send(warning).await;
+        }
+        Ok(())
+    }
+}
+
+impl ImportJob {
+    /// Inserts the dictionary and a new job for importing it.
+    ///
+    /// If the archive has already been hashed, `meta` holds its hash.
+    pub(super) async fn create(
+        db: &Pool<Sqlite>,
+        insert_lock: &InsertLock,
+        meta: &DictionaryMeta,
+        replacing: Option<DictionaryId>,
+        options: ImportOptions,
+    ) -> Result<Self> {
+        let _inserting = insert_lock.lock().await;
+        let mut tx = db.begin().await.context("failed to begin transaction")?;
+        let dictionary_id = super::insert_dictionary(&mut tx, meta)
+            .await
+            .context("failed to insert dictionary")?;
+        let replacing_id = replacing.map(|id| id.0);
+        let id = sqlx::query!(
+            "INSERT INTO import_job (dictionary, replacing, lenient, archive_hash)
+            VALUES ($1, $2, $3, $4)",
+            dictionary_id.0,
+            replacing_id,
+            options.lenient,
+            meta.archive_hash,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("failed to insert import job")?
+        .last_insert_rowid();
+        tx.commit().await.context("failed to commit transaction")?;
+
+        Ok(Self {
+            db: db.clone(),
+            insert_lock: insert_lock.clone(),
+            id: ImportJobId(id),
+            dictionary_id,
+            replacing,
+            completed: HashSet::default(),
+            leniency: Leniency {
+                lenient: options.lenient,
+                warning_tx: None,
+            },
+            skip_media: false,
+        })
+    }
+
+    /// Loads an unfinished job, along with the meta of its dictionary.
+    ///
+    /// The meta holds the hash of the job's archive, if it has been hashed.
+    pub(super) async fn load(
+        db: &Pool<Sqlite>,
+        insert_lock: &InsertLock,
+        id: ImportJobId,
+    ) -> Result<(Self, DictionaryMeta)> {
+        let job = sqlx::query!(
+            "SELECT
+                import_job.dictionary, import_job.replacing, import_job.lenient,
+                import_job.archive_hash, dictionary.meta
+            FROM import_job
+            JOIN dictionary ON dictionary.id = import_job.dictionary
+            WHERE import_job.id = $1",
+            id.0
+        )
+        .fetch_optional(db)
+        .await
+        .context("failed to fetch import job")?
+        .ok_or(NotFound)?;
+        let mut meta = serde_json::from_str::<DictionaryMeta>(&job.meta)
+            .context("failed to deserialize dictionary meta")?;
+        meta.archive_hash = job.archive_hash.or(meta.archive_hash);
+
+        let completed =
+            sqlx::query_scalar!("SELECT path FROM import_job_entry WHERE job = $1", id.0)
+                .fetch_all(db)
+                .await
+                .context("failed to fetch completed entries")?
+                .into_iter()
+                .collect();
+
+        Ok((
+            Self {
+                db: db.clone(),
+                insert_lock: insert_lock.clone(),
+                id,
+                dictionary_id: DictionaryId(job.dictionary),
+                replacing: job.replacing.map(DictionaryId),
+                completed,
+                leniency: Leniency {
+                    lenient: job.lenient != 0,
+                    warning_tx: None,
+                },
+                skip_media: false,
+            },
+            meta,
+        ))
+    }
+
+    /// Sends warnings about rows which were skipped to `warning_tx`.
+    pub(super) fn with_warnings(mut self, warning_tx: mpsc::Sender<ValidationIssue>) -> Self {
+        self.leniency.warning_tx = Some(warning_tx);
+        self
+    }
+
+    /// Skips inserting media, for jobs which only validate an archive.
+    pub(super) const fn without_media(mut self) -> Self {
+        self.skip_media = true;
+        self
+    }
+
+    #[must_use]
+    pub const fn id(&self) -> ImportJobId {
+        self.id
+    }
+
+    /// ID of the dictionary that data should be inserted under.
+    #[must_use]
+    pub const fn dictionary_id(&self) -> DictionaryId {
+        self.dictionary_id
+    }
+
+    #[must_use]
+    pub const fn replacing(&self) -> Option<DictionaryId> {
+        self.replacing
+    }
+
+    /// What to do when a row fails to import.
+    #[must_use]
+    pub const fn leniency(&self) -> &Leniency {
+        &self.leniency
+    }
+
+    /// Checks if an archive entry was already imported by a previous run of
+    /// this job.
+    #[must_use]
+    pub fn is_done(&self, entry: &str) -> bool {
+        self.completed.contains(entry)
+    }
+
+    /// Number of archive entries which have been imported.
+    #[must_use]
+    pub fn num_done(&self) -> usize {
+        self.completed.len()
+    }
+
+    /// Begins a transaction to insert the data of some entries into.
+    ///
+    /// This waits until no other import is inserting, and stops other imports
+    /// from inserting until the transaction is committed with
+    /// [`ImportJob::checkpoint`] or [`ImportJob::finish`]. Importers should
+    /// parse as much as they can before calling this.
+    pub async fn begin(&self) -> Result<ImportTx> {
+        let inserting = self.insert_lock.clone().lock_owned().await;
+        let tx = if self.replacing.is_some() {
+            // `finish` swaps the new data in, which deletes in bulk
+            self.db.begin_with(BEGIN_WITHOUT_FOREIGN_KEYS).await
+        } else {
+            self.db.begin().await
+        };
+        Ok(ImportTx {
+            tx: tx.context("failed to begin transaction")?,
+            _inserting: inserting,
+        })
+    }
+
+    /// Starts batching inserts into `tx`, under this job's dictionary.
+    pub async fn inserter<'tx>(&self, tx: &'tx mut ImportTx) -> Result<Inserter<'tx, 'static>> {
+        let mut insert = Inserter::new(tx, self.dictionary_id).await?;
+        if self.skip_media {
+            insert.skip_media();
+        }
+        Ok(insert)
+    }
+
+    /// Marks `entries` as imported, and commits the transaction which holds
+    /// their data.
+    pub async fn checkpoint(
+        &mut self,
+        mut tx: ImportTx,
+        entries: impl IntoIterator<Item = String>,
+    ) -> Result<()> {
+        self.ensure_exists(&mut tx).await?;
+        let entries = entries.into_iter().collect::<Vec<_>>();
+        for entry in &entries {
+            sqlx::query!(
+                "INSERT OR IGNORE INTO import_job_entry (job, path) VALUES ($1, $2)",
+                self.id.0,
+                entry
+            )
+            .execute(&mut **tx)
+            .await
+            .context("failed to insert completed entry")?;
+        }
+        tx.tx
+            .commit()
+            .await
+            .context("failed to commit transaction")?;
+        self.completed.extend(entries);
+        Ok(())
+    }
+
+    /// Inserts a batch of rows, which were parsed before calling this, and
+    /// checkpoints the batch in the same transaction.
+    ///
+    /// The archive must be split into the same batches each time it is read,
+    /// so that a resumed job can skip the batches which were already imported.
+    pub async fn insert_batch<T>(
+        &mut self,
+        batch: Batch<T>,
+        mut insert_row: impl AsyncFnMut(&mut Inserter<'_, 'static>, T) -> Result<()>,
+    ) -> Result<()> {
+        let Batch { entry, rows } = batch;
+        if self.is_done(&entry) {
+            return Ok(());
+        }
+        let mut tx = self.begin().await?;
+        let mut insert = self.inserter(&mut tx).await?;
+        for row in rows {
+            insert_row(&mut insert, row).await?;
+        }
+        insert.flush().await.context("failed to flush inserts")?;
+        drop(insert);
+        self.checkpoint(tx, [entry]).await
+    }
+
+    /// Deletes this job, and commits the transaction which holds the data of
+    /// the remaining entries.
+    ///
+    /// If this job is re-importing a dictionary, the new data is swapped into
+    /// that dictionary in the same transaction.
+    pub async fn finish(self, mut tx: ImportTx) -> Result<()> {
+        self.ensure_exists(&mut tx).await?;
+        delete_job(&mut tx, self.id).await?;
+        if let Some(id) = self.replacing {
+            // the dictionary we're replacing may have been removed in the
+            // meantime, in which case this is imported as a new dictionary
+            swap_in_dictionary(&mut tx, id, self.dictionary_id)
+                .await
+                .context("failed to swap in new data")?;
+        }
+        tx.tx
+            .commit()
+            .await
+            .context("failed to commit transaction")?;
+        Ok(())
+    }
+
+    /// Fails if the job was cancelled while we were inserting, so that we
+    /// never commit data for a dictionary which has already been deleted.
+    async fn ensure_exists(&self, tx: &mut Transaction<'static, Sqlite>) -> Result<()> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM import_job WHERE id = $1)",
+            self.id.0
+        )
+        .fetch_one(&mut **tx)
+        .await
+        .context("failed to fetch if import job exists")?;
+        if exists == 0 {
+            bail!("import job was cancelled");
+        }
+        Ok(())
+    }
+}
+
+/// Tasks of the imports which are currently running, so that they can be
+/// cancelled.
+#[derive(Debug, Default)]
+pub struct ImportTasks(Mutex<HashMap<ImportJobId, AbortHandle>>);
+
+impl ImportTasks {
+    fn lock(&self) -> MutexGuard<'_, HashMap<ImportJobId, AbortHandle>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(super) fn is_running(&self, id: ImportJobId) -> bool {
+        self.lock().contains_key(&id)
+    }
+
+    /// Registers the task running job `id` until the returned guard is
+    /// dropped, or returns [`None`] if this job is already running.
+    pub(super) fn register(
+        &self,
+        id: ImportJobId,
+        task: AbortHandle,
+    ) -> Option<ImportTaskGuard<'_>> {
+        let mut tasks = self.lock();
+        if tasks.contains_key(&id) {
+            return None;
+        }
+        tasks.insert(id, task);
+        drop(tasks);
+        Some(ImportTaskGuard { tasks: self, id })
+    }
+
+    fn abort(&self, id: ImportJobId) -> bool {
+        self.lock().remove(&id).is_some_and(|task| {
+            task.abort();
+            true
+        })
+    }
+}
+
+pub(super) struct ImportTaskGuard<'a> {
+    tasks: &'a ImportTasks,
+    id: ImportJobId,
+}
+
+impl Drop for ImportTaskGuard<'_> {
+    fn drop(&mut self) {
+        self.tasks.lock().remove(&self.id);
+    }
+}
+
+/// Remembers the hash of job `id`'s archive, once it has been hashed alongside
+/// the import.
+pub(super) async fn store_archive_hash(
+    db: &Pool<Sqlite>,
+    insert_lock: &InsertLock,
+    id: ImportJobId,
+    archive_hash: &str,
+) -> Result<()> {
+    let _inserting = insert_lock.lock().await;
+    sqlx::query!(
+        "UPDATE import_job SET archive_hash = $1 WHERE id = $2",
+        archive_hash,
+        id.0
+    )
+    .execute(db)
+    .await
+    .context("failed to update import job")?;
+    Ok(())
+}
+
+async fn delete_job(tx: &mut Transaction<'_, Sqlite>, id: ImportJobId) -> Result<()> {
+    sqlx::query!("DELETE FROM import_job_entry WHERE job = $1", id.0)
+        .execute(&mut **tx)
+        .await
+        .context("failed to delete completed entries")?;
+    let result = sqlx::query!("DELETE FROM import_job WHERE id = $1", id.0)
+        .execute(&mut **tx)
+        .await
+        .context("failed to delete import job")?;
+    if result.rows_affected() == 0 {
+        bail!(NotFound);
+    }
+    Ok(())
+}
+
+impl Engine {
+    /// Lists all imports which have started but not finished.
+    ///
+    /// These are imports which are still running, or which were interrupted
+    /// (e.g. because the app was killed). Interrupted imports can be continued
+    /// with [`Engine::resume_import`], or discarded with
+    /// [`Engine::cancel_import`].
+    pub async fn import_jobs(&self) -> Result<Vec<ImportJobInfo>> {
+        let jobs = sqlx::query!(
+            "SELECT
+                import_job.id, import_job.replacing, dictionary.meta,
+                (SELECT COUNT(*) FROM import_job_entry WHERE job = import_job.id) AS entries_done
+            FROM import_job
+            JOIN dictionary ON dictionary.id = import_job.dictionary
+            ORDER BY import_job.id"
+        )
+        .fetch_all(&self.db)
+        .await
+        .context("failed to fetch import jobs")?;
+
+        jobs.into_iter()
+            .map(|job| {
+                let meta = serde_json::from_str::<DictionaryMeta>(&job.meta)
+                    .context("failed to deserialize dictionary meta")?;
+                Ok(ImportJobInfo {
+                    id: ImportJobId(job.id),
+                    meta,
+                    replacing: job.replacing.map(DictionaryId),
+                    entries_done: u64::try_from(job.entries_done).unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+
+    /// Cancels an import, deleting all of the data that it has inserted so
+    /// far.
+    ///
+    /// If the import is still running, it is stopped, and its event stream
+    /// ends with [`ImportError::Cancelled`]. If it was re-importing a
+    /// dictionary, that dictionary is left untouched.
+    ///
+    /// [`ImportError::Cancelled`]: super::ImportError::Cancelled
+    pub async fn cancel_import(&self, id: ImportJobId) -> Result<()> {
+        if self.import_tasks.abort(id) {
+            info!("Stopped running import {id:?}");
+        }
+        self.discard_import_job(id).await
+    }
+
+    /// Deletes an import job, its dictionary, and all of its data.
+    pub(super) async fn discard_import_job(&self, id: ImportJobId) -> Result<()> {
+        let _inserting = self.insert_lock.lock().await;
+        let mut conn = self.db.acquire().await?;
+        disable_foreign_keys(&mut conn).await?;
+        let mut tx = conn.begin().await.context("failed to begin transaction")?;
+
+        let dictionary_id =
+            sqlx::query_scalar!("SELECT dictionary FROM import_job WHERE id = $1", id.0)
+                .fetch_optional(&mut *tx)
+                .await
+                .context("failed to fetch import job")?
+                .ok_or(NotFound)?;
+
+        info!("Discarding import {id:?} of {dictionary_id:?}");
+        delete_job(&mut tx, id).await?;
+        delete_dictionary(&mut tx, DictionaryId(dictionary_id)).await?;
+        tx.commit().await.context("failed to commit transaction")?;
+        self.vacuum().await?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "uniffi")]
+const _: () = {
+    use crate::{FfiResult, Wordbase};
+
+    #[uniffi::export(async_runtime = "tokio")]
+    impl Wordbase {
+        pub async fn import_jobs(&self) -> FfiResult<Vec<ImportJobInfo>> {
+            Ok(self.0.import_jobs().await?)
+        }
+
+        pub async fn cancel_import(&self, id: ImportJobId) -> FfiResult<()> {
+            Ok(self.0.cancel_import(id).await?)
+        }
+    }
+};
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::import::{ImportError, ImportEvent, yomitan},
+        crate::test_util::{done, import_until_progress, stalling_archive, write_yomitan_zip, zip},
+        futures::{Stream, StreamExt, TryStreamExt},
+        serde_json::json,
+        std::{
+            path::{Path, PathBuf},
+            sync::Arc,
+        },
+        wordbase_api::DictionaryKind,
+    };
+
+    const NUM_BANKS: i64 = 3;
+
+    async fn yomitan_zip(path: &Path) {
+        let index = json!({ "title": "Resumable", "revision": "1", "format": 3 });
+        let banks =
+            ["犬", "猫", "鳥"].map(|headword| json!([[headword, "", "", "", 0, ["def"], 1, ""]]));
+        write_yomitan_zip(path, &index, &banks).await;
+    }
+
+    /// Starts importing the archive at `path`, and waits until exactly one term
+    /// bank has been imported.
+    async fn start_stalled(
+        engine: &Engine,
+        path: PathBuf,
+    ) -> (
+        ImportJobId,
+        impl Stream<Item = Result<ImportEvent, ImportError>>,
+    ) {
+        // progress is only reported once a whole bank has been imported
+        let archive = stalling_archive(path, |name| {
+            name.starts_with("term_bank_") && name != "term_bank_1.json"
+        })
+        .await;
+        import_until_progress(
+            engine,
+            archive,
+            (DictionaryKind::Yomitan, Arc::new(yomitan::Yomitan)),
+        )
+        .await
+    }
+
+    async fn num_records(engine: &Engine) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM record")
+            .fetch_one(&engine.db)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn resume_after_interrupt() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let engine = Engine::new(data_dir.path()).await.unwrap();
+        let path = data_dir.path().join("dictionary.zip");
+        yomitan_zip(&path).await;
+
+        let (job_id, events) = start_stalled(&engine, path.clone()).await;
+        // simulate the process being killed
+        drop(events);
+
+        assert!(engine.dictionaries().is_empty());
+        let jobs = engine.import_jobs().await.unwrap();
+        assert_eq!(1, jobs.len());
+        assert_eq!(job_id, jobs[0].id);
+        assert_eq!("Resumable", jobs[0].meta.name);
+        assert_eq!(1, jobs[0].entries_done);
+        assert_eq!(1, num_records(&engine).await);
+
+        let id = done(engine.resume_import(job_id, Arc::new(path)))
+            .await
+            .unwrap();
+
+        assert!(engine.import_jobs().await.unwrap().is_empty());
+        let dictionaries = engine.dictionaries();
+        assert_eq!(1, dictionaries.len());
+        assert_eq!("Resumable", dictionaries[&id].meta.name);
+        assert_eq!(NUM_BANKS, num_records(&engine).await);
+    }
+
+    #[tokio::test]
+    async fn cancel_removes_data() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let engine = Engine::new(data_dir.path()).await.unwrap();
+        let path = data_dir.path().join("dictionary.zip");
+        yomitan_zip(&path).await;
+
+        let (job_id, mut events) = start_stalled(&engine, path.clone()).await;
+        engine.cancel_import(job_id).await.unwrap();
+        let result = events
+            .next()
+            .await
+            .into_iter()
+            .find(|event| !matches!(event, Ok(ImportEvent::Progress(_))));
+        assert!(matches!(result, Some(Err(ImportError::Cancelled))));
+
+        assert!(engine.import_jobs().await.unwrap().is_empty());
+        assert!(engine.dictionaries().is_empty());
+        assert_eq!(0, num_records(&engine).await);
+        let num_dictionaries: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM dictionary")
+            .fetch_one(&engine.db)
+            .await
+            .unwrap();
+        assert_eq!(0, num_dictionaries);
+
+        // the dictionary can be imported again from scratch
+        engine
+            .import_dictionary(Arc::new(path))
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(NUM_BANKS, num_records(&engine).await);
+    }
+
+    #[tokio::test]
+    async fn lenient_skips_bad_rows() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let engine = Engine::new(data_dir.path()).await.unwrap();
+        let path = data_dir.path().join("dictionary.zip");
+
+        let index = json!({ "title": "Lenient", "revision": "1", "format": 3 });
+        let bank = json!([
+            ["犬", "いぬ", "", "", 0, ["dog"], 1, ""],
+            ["猫", "ねこ", "", "", 0, [{
+                "type": "structured-content",
+                "content": { "tag": "span", "bogus": 1, "content": "cat" },
+            }], 2, ""],
+            ["", "", "", "", 0, ["nothing"], 3, ""],
+        ]);
+        write_yomitan_zip(&path, &index, &[bank]).await;
+
+        let result = engine
+            .import_dictionary(Arc::new(path.clone()))
+            .try_collect::<Vec<_>>()
+            .await;
+        assert!(matches!(result, Err(ImportError::Import { .. })));
+        assert!(engine.dictionaries().is_empty());
+
+        let events = engine
+            .import_dictionary_with_options(Arc::new(path), ImportOptions { lenient: true })
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let warned_rows = events
+            .iter()
+            .filter_map(|event| match event {
+                ImportEvent::Warning(warning) => Some(warning.index),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(vec![Some(1), Some(2)], warned_rows);
+        assert_eq!(1, engine.dictionaries().len());
+        assert_eq!(1, num_records(&engine).await);
+    }
+
+    #[tokio::test]
+    async fn lenient_skips_bad_tag_bank() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let engine = Engine::new(data_dir.path()).await.unwrap();
+        let path = data_dir.path().join("dictionary.zip");
+
+        let index = json!({ "title": "Lenient", "revision": "1", "format": 3 });
+        let bank = json!([["犬", "いぬ", "n", "", 0, ["dog"], 1, ""]]);
+        let archive = zip([
+            ("index.json", index.to_string()),
+            ("term_bank_1.json", bank.to_string()),
+            ("tag_bank_1.json", "not json".to_owned()),
+        ])
+        .await;
+        tokio::fs::write(&path, archive).await.unwrap();
+
+        let result = done(engine.import_dictionary(Arc::new(path.clone()))).await;
+        assert!(matches!(result, Err(ImportError::Import { .. })));
+
+        let events = engine
+            .import_dictionary_with_options(Arc::new(path), ImportOptions { lenient: true })
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let warned_paths = events
+            .iter()
+            .filter_map(|event| match event {
+                ImportEvent::Warning(warning) => Some(warning.path.as_deref()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(vec![Some("tag_bank_1.json")], warned_paths);
+        assert_eq!(1, num_records(&engine).await);
+    }
+
+    #[tokio::test]
+    #[expect(
+        clippy::significant_drop_tightening,
+        reason = "the transaction is moved into `finish`"
+    )]
+    async fn reimport_swaps_when_job_finishes() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let engine = Engine::new(data_dir.path()).await.unwrap();
+        let path = data_dir.path().join("dictionary.zip");
+        yomitan_zip(&path).await;
+        let id = done(engine.import_dictionary(Arc::new(path)))
+            .await
+            .unwrap();
+        assert_eq!(NUM_BANKS, num_records(&engine).await);
+
+        let meta = DictionaryMeta::new(DictionaryKind::Yomitan, "Replacement");
+        let job = ImportJob::create(
+            &engine.db,
+            &engine.insert_lock,
+            &meta,
+            Some(id),
+            ImportOptions::default(),
+        )
+        .await
+        .unwrap();
+        let tx = job.begin().await.unwrap();
+        job.finish(tx).await.unwrap();
+        // the process is killed before the engine gets to do anything else
+        drop(engine);
+
+        let engine = Engine::new(data_dir.path()).await.unwrap();
+        assert!(engine.import_jobs().await.unwrap().is_empty());
+        let dictionaries = engine.dictionaries();
+        assert_eq!(1, dictionaries.len());
+        assert_eq!("Replacement", dictionaries[&id].meta.name);
+        assert_eq!(0, num_records(&engine).await);
+    }
+}
@@ -0,0 +1,110 @@
+mod parse;
+
+use {
+    super::{ImportContinue, ImportKind, ImportProgress, OpenArchive, source},
+    crate::import::{Batches, continue_with, insert::Inserter},
+    anyhow::{Context, Result, bail},
+    futures::future::BoxFuture,
+    parse::Parser,
+    std::sync::{Arc, atomic},
+    tokio::sync::mpsc,
+    tokio_util::compat::FuturesAsyncReadCompatExt,
+    tracing::{debug, trace},
+    wordbase_api::{DictionaryKind, DictionaryMeta, Term, dict::kanjidic::Character},
+};
+
+pub struct Kanjidic;
+
+impl ImportKind for Kanjidic {
+    fn is_of_kind(&self, open_archive: Arc<dyn OpenArchive>) -> BoxFuture<'_, Result<()>> {
+        Box::pin(validate(open_archive))
+    }
+
+    fn start_import(
+        &self,
+        open_archive: Arc<dyn OpenArchive>,
+        progress_tx: mpsc::Sender<ImportProgress>,
+    ) -> BoxFuture<Result<(DictionaryMeta, ImportContinue)>> {
+        Box::pin(start_import(open_archive, progress_tx))
+    }
+}
+
+const CHARACTERS_PER_PROGRESS: usize = 500;
+
+const CHARACTERS_PER_BATCH: usize = 2000;
+
+const ATTRIBUTION: &str = "This publication has included material from the KANJIDIC2 \
+    dictionary file in accordance with the licence provisions of the Electronic \
+    Dictionaries Research Group. See https://www.edrdg.org/edrdg/licence.html";
+
+async fn validate(open_archive: Arc<dyn OpenArchive>) -> Result<()> {
+    let start = source::read_document_start(&*open_archive).await?;
+    if !start.contains("<!DOCTYPE kanjidic2") && !start.contains("<kanjidic2>") {
+        bail!("no KANJIDIC2 document type");
+    }
+    Ok(())
+}
+
+async fn start_import(
+    open_archive: Arc<dyn OpenArchive>,
+    progress_tx: mpsc::Sender<ImportProgress>,
+) -> Result<(DictionaryMeta, ImportContinue)> {
+    // the archive may be gzipped, e.g. `kanjidic2.xml.gz`
+    let (reader, cursor_pos, buf_len) = source::document_reader(&*open_archive).await?;
+    let mut parser = Parser::new(reader.compat());
+    let header = parser.header().await.context("failed to parse header")?;
+
+    let mut meta = DictionaryMeta::new(DictionaryKind::Kanjidic, "KANJIDIC2");
+    meta.description = Some("Japanese kanji dictionary".into());
+    meta.url = Some("https://www.edrdg.org/wiki/index.php/KANJIDIC_Project".into());
+    meta.version = header.database_version.or(header.date_of_creation);
+    meta.attribution = Some(ATTRIBUTION.into());
+
+    Ok((
+        meta,
+        continue_with(move |mut job| async move {
+            let import = async |insert: &mut Inserter<'_, '_>, character: Character| {
+                let literal = character.literal.clone();
+                import_character(insert, character)
+                    .await
+                    .with_context(|| format!("failed to import character {literal:?}"))
+            };
+
+            let mut batches = Batches::new("characters", CHARACTERS_PER_BATCH);
+            let mut characters_done = 0usize;
+            while let Some(character) = parser.next_character().await? {
+                if let Some(batch) = batches.push(character) {
+                    job.insert_batch(batch, import).await?;
+                }
+
+                characters_done += 1;
+                if characters_done % CHARACTERS_PER_PROGRESS == 0 {
+                    let cursor_pos = cursor_pos.load(atomic::Ordering::SeqCst);
+                    let frac = (cursor_pos as f64) / (buf_len as f64);
+                    _ = progress_tx.try_send(ImportProgress { frac });
+                    trace!(
+                        "{characters_done} characters imported - {cursor_pos} / {buf_len} bytes read"
+                    );
+                }
+            }
+            job.insert_batch(batches.finish(), import).await?;
+            debug!("{characters_done} total characters");
+
+            let tx = job.begin().await?;
+            job.finish(tx).await
+        }),
+    ))
+}
+
+async fn import_character(insert: &mut Inserter<'_, '_>, character: Character) -> Result<()> {
+    let term = Term::from_headword(character.literal.as_str()).context("empty literal")?;
+    let record_id = insert
+        .record(&character)
+        .await
+        .context("failed to insert character record")?;
+    insert
+        .term_record(term, record_id)
+        .await
+        .context("failed to insert term record")?;
+    Ok(())
+}
@@ -0,0 +1,263 @@
+//! Streaming parser for the KANJIDIC2 XML format.
+//!
+//! Like JMdict, KANJIDIC2 is distributed as one large XML document, so we read
+//! it event-by-event and yield each `<character>` as soon as it is complete.
+
+use {
+    anyhow::{Context, Result, bail},
+    quick_xml::{
+        Reader,
+        events::{BytesStart, Event},
+    },
+    tokio::io::AsyncBufRead,
+    wordbase_api::dict::kanjidic::Character,
+};
+
+#[derive(Debug, Default)]
+pub struct Header {
+    /// `<database_version>`, e.g. `2025-150`.
+    pub database_version: Option<String>,
+    /// `<date_of_creation>`, e.g. `2025-05-30`.
+    pub date_of_creation: Option<String>,
+}
+
+pub struct Parser<R> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+    /// If we have already consumed the `<character>` start tag of the next
+    /// character.
+    in_character: bool,
+}
+
+impl<R: AsyncBufRead + Unpin> Parser<R> {
+    pub fn new(reader: R) -> Self {
+        let mut reader = Reader::from_reader(reader);
+        reader.config_mut().trim_text(true);
+        Self {
+            reader,
+            buf: Vec::new(),
+            in_character: false,
+        }
+    }
+
+    /// Reads the root element and `<header>`, up to the first `<character>`.
+    pub async fn header(&mut self) -> Result<Header> {
+        let mut header = Header::default();
+        let mut has_root = false;
+        let mut element = Vec::<u8>::new();
+        loop {
+            self.buf.clear();
+            match self.reader.read_event_into_async(&mut self.buf).await? {
+                Event::Start(start) if !has_root => {
+                    if start.name().as_ref() != b"kanjidic2" {
+                        bail!(
+                            "invalid root element `{}`",
+                            String::from_utf8_lossy(start.name().as_ref())
+                        );
+                    }
+                    has_root = true;
+                }
+                Event::Start(start) if start.name().as_ref() == b"character" => {
+                    self.in_character = true;
+                    break;
+                }
+                Event::Start(start) => {
+                    element.clear();
+                    element.extend_from_slice(start.name().as_ref());
+                }
+                Event::Text(text) => {
+                    let text = text.unescape().context("failed to unescape text")?;
+                    match element.as_slice() {
+                        b"database_version" => header.database_version = Some(text.into_owned()),
+                        b"date_of_creation" => header.date_of_creation = Some(text.into_owned()),
+                        _ => {}
+                    }
+                }
+                Event::End(_) if !has_root => break,
+                Event::End(_) => element.clear(),
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        if !has_root {
+            bail!("no root element");
+        }
+        Ok(header)
+    }
+
+    /// Reads the next `<character>`, or returns [`None`] if there are no more
+    /// characters in the document.
+    pub async fn next_character(&mut self) -> Result<Option<Character>> {
+        while !self.in_character {
+            self.buf.clear();
+            match self.reader.read_event_into_async(&mut self.buf).await? {
+                Event::Start(start) if start.name().as_ref() == b"character" => {
+                    self.in_character = true;
+                }
+                Event::End(end) if end.name().as_ref() == b"kanjidic2" => return Ok(None),
+                Event::Eof => return Ok(None),
+                _ => {}
+            }
+        }
+        self.in_character = false;
+
+        let mut character = Character::default();
+        // name and relevant attribute of the element whose text we are
+        // currently reading
+        let mut element = Vec::<u8>::new();
+        let mut attr = None::<String>;
+        loop {
+            self.buf.clear();
+            match self.reader.read_event_into_async(&mut self.buf).await? {
+                Event::Start(start) => {
+                    attr = type_attr(&start)?;
+                    element.clear();
+                    element.extend_from_slice(start.name().as_ref());
+                }
+                Event::Text(text) => {
+                    let text = text.unescape().context("failed to unescape text")?;
+                    text_element(&mut character, &element, attr.as_deref(), &text).with_context(
+                        || {
+                            format!(
+                                "failed to read `{}` of character {:?}",
+                                String::from_utf8_lossy(&element),
+                                character.literal
+                            )
+                        },
+                    )?;
+                }
+                Event::End(end) => {
+                    if end.name().as_ref() == b"character" {
+                        break;
+                    }
+                    element.clear();
+                    attr = None;
+                }
+                Event::Eof => bail!(
+                    "unexpected end of document in character {:?}",
+                    character.literal
+                ),
+                _ => {}
+            }
+        }
+        Ok(Some(character))
+    }
+}
+
+/// Gets the attribute which determines how to interpret an element's text,
+/// e.g. `r_type` for `<reading>`.
+fn type_attr(start: &BytesStart<'_>) -> Result<Option<String>> {
+    let key: &[u8] = match start.name().as_ref() {
+        b"reading" => b"r_type",
+        b"meaning" => b"m_lang",
+        b"rad_value" => b"rad_type",
+        _ => return Ok(None),
+    };
+    for attr in start.attributes() {
+        let attr = attr.context("failed to read attribute")?;
+        if attr.key.as_ref() == key {
+            let value = attr
+                .unescape_value()
+                .context("failed to unescape attribute")?;
+            return Ok(Some(value.into_owned()));
+        }
+    }
+    Ok(None)
+}
+
+fn text_element(
+    character: &mut Character,
+    element: &[u8],
+    attr: Option<&str>,
+    text: &str,
+) -> Result<()> {
+    match (element, attr) {
+        (b"literal", _) => text.clone_into(&mut character.literal),
+        (b"reading", Some("ja_on")) => character.onyomi.push(text.to_owned()),
+        (b"reading", Some("ja_kun")) => character.kunyomi.push(text.to_owned()),
+        (b"nanori", _) => character.nanori.push(text.to_owned()),
+        (b"meaning", None) => character.meanings.push(text.to_owned()),
+        (b"grade", _) => character.grade = Some(text.parse().context("invalid grade")?),
+        (b"jlpt", _) => character.jlpt = Some(text.parse().context("invalid JLPT level")?),
+        // the first stroke count is the accepted one, the rest are miscounts
+        (b"stroke_count", _) if character.stroke_count.is_none() => {
+            character.stroke_count = Some(text.parse().context("invalid stroke count")?);
+        }
+        (b"freq", _) => character.frequency = Some(text.parse().context("invalid frequency")?),
+        (b"rad_value", Some("classical")) => {
+            character.radical = Some(text.parse().context("invalid radical number")?);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KANJIDIC2: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE kanjidic2 [
+<!ELEMENT kanjidic2 (header,character*)>
+]>
+<kanjidic2>
+<header>
+<file_version>4</file_version>
+<database_version>2025-150</database_version>
+<date_of_creation>2025-05-30</date_of_creation>
+</header>
+<!-- Entry for Kanji: 亜 -->
+<character>
+<literal>亜</literal>
+<radical>
+<rad_value rad_type="classical">7</rad_value>
+<rad_value rad_type="nelson_c">1</rad_value>
+</radical>
+<misc>
+<grade>8</grade>
+<stroke_count>7</stroke_count>
+<stroke_count>8</stroke_count>
+<freq>1509</freq>
+<jlpt>1</jlpt>
+</misc>
+<reading_meaning>
+<rmgroup>
+<reading r_type="pinyin">ya4</reading>
+<reading r_type="ja_on">ア</reading>
+<reading r_type="ja_kun">つ.ぐ</reading>
+<meaning>Asia</meaning>
+<meaning>rank next</meaning>
+<meaning m_lang="fr">Asie</meaning>
+</rmgroup>
+<nanori>や</nanori>
+</reading_meaning>
+</character>
+</kanjidic2>
+"#;
+
+    #[tokio::test]
+    async fn parse() {
+        let mut parser = Parser::new(KANJIDIC2.as_bytes());
+        let header = parser.header().await.unwrap();
+        assert_eq!(Some("2025-150"), header.database_version.as_deref());
+        assert_eq!(Some("2025-05-30"), header.date_of_creation.as_deref());
+
+        let character = parser.next_character().await.unwrap().unwrap();
+        assert_eq!("亜", character.literal);
+        assert_eq!(vec!["ア".to_owned()], character.onyomi);
+        assert_eq!(vec!["つ.ぐ".to_owned()], character.kunyomi);
+        assert_eq!(vec!["や".to_owned()], character.nanori);
+        assert_eq!(
+            vec!["Asia".to_owned(), "rank next".to_owned()],
+            character.meanings
+        );
+        assert_eq!(Some(8), character.grade);
+        assert_eq!(Some(1), character.jlpt);
+        assert_eq!(Some(7), character.stroke_count);
+        assert_eq!(Some(1509), character.frequency);
+        assert_eq!(Some(7), character.radical);
+
+        assert!(parser.next_character().await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,138 @@
+mod parse;
+
+use {
+    super::{
+        ImportContinue, ImportKind, ImportProgress, OpenArchive,
+        source::{self, Source, ZipArchive, open_source},
+    },
+    crate::import::continue_with,
+    anyhow::{Context, Result, bail},
+    futures::future::BoxFuture,
+    std::{path::Path, sync::Arc},
+    tokio::sync::mpsc,
+    tracing::debug,
+    wordbase_api::{DictionaryKind, DictionaryMeta},
+};
+
+pub struct Kradfile;
+
+impl ImportKind for Kradfile {
+    fn is_of_kind(&self, open_archive: Arc<dyn OpenArchive>) -> BoxFuture<'_, Result<()>> {
+        Box::pin(validate(open_archive))
+    }
+
+    fn start_import(
+        &self,
+        open_archive: Arc<dyn OpenArchive>,
+        progress_tx: mpsc::Sender<ImportProgress>,
+    ) -> BoxFuture<Result<(DictionaryMeta, ImportContinue)>> {
+        Box::pin(start_import(open_archive, progress_tx))
+    }
+}
+
+/// How many bytes of a bare file we read to find the first line of data.
+///
+/// The files start with a long block of comments, so this is larger than for
+/// other formats.
+const PROBE_LEN: u64 = 16 * 1024;
+
+const URL: &str = "https://www.edrdg.org/krad/kradinf.html";
+
+const ATTRIBUTION: &str = "The KRADFILE and RADKFILE files are the property of the \
+    Electronic Dictionaries Research Group, and are used in conformance with the \
+    Group's licence. See https://www.edrdg.org/edrdg/licence.html";
+
+/// Finds the entries of the zip which are KRADFILE or RADKFILE files.
+///
+/// The files may either be imported one at a time, as bare or gzipped files,
+/// or all together as the `kradzip.zip` release, which contains `kradfile`,
+/// `kradfile2`, `radkfile`, `radkfile2`, and documentation.
+fn zip_entries(archive: &ZipArchive) -> Result<Vec<(usize, String)>> {
+    let entries = source::zip_files(archive)
+        .filter_map(|(index, path)| {
+            let file_name = path.rsplit('/').next().unwrap_or(path).to_lowercase();
+            // the data files have no extension, unlike the documentation
+            let is_data = (file_name.starts_with("kradfile") || file_name.starts_with("radkfile"))
+                && Path::new(&file_name).extension().is_none();
+            is_data.then(|| (index, path.to_owned()))
+        })
+        .collect::<Vec<_>>();
+    if entries.is_empty() {
+        bail!("no KRADFILE or RADKFILE in archive");
+    }
+    Ok(entries)
+}
+
+async fn validate(open_archive: Arc<dyn OpenArchive>) -> Result<()> {
+    match open_source(&*open_archive).await? {
+        Source::Bare(archive) => {
+            let start = source::read_bare(archive, PROBE_LEN).await?;
+            if !parse::is_kradfile(&parse::decode(&start)) {
+                bail!("not a KRADFILE or RADKFILE");
+            }
+        }
+        Source::Zip(archive) => {
+            zip_entries(&archive)?;
+        }
+    }
+    Ok(())
+}
+
+async fn start_import(
+    open_archive: Arc<dyn OpenArchive>,
+    progress_tx: mpsc::Sender<ImportProgress>,
+) -> Result<(DictionaryMeta, ImportContinue)> {
+    let source = open_source(&*open_archive).await?;
+
+    let mut meta = DictionaryMeta::new(DictionaryKind::Kradfile, "KRADFILE");
+    meta.description = Some("Radicals which make up each kanji".into());
+    meta.url = Some(URL.into());
+    meta.attribution = Some(ATTRIBUTION.into());
+
+    Ok((
+        meta,
+        continue_with(move |job| async move {
+            // the files are small enough that we can read them all up front
+            let files = match source {
+                Source::Bare(archive) => {
+                    vec![(
+                        "file".to_owned(),
+                        source::read_bare(archive, u64::MAX).await?,
+                    )]
+                }
+                Source::Zip(mut archive) => {
+                    let mut files = Vec::new();
+                    for (index, path) in zip_entries(&archive)? {
+                        let data = source::read_zip_entry(&mut archive, index, &path).await?;
+                        files.push((path, data));
+                    }
+                    files
+                }
+            };
+
+            let mut pairs = Vec::new();
+            let num_files = files.len();
+            for (files_done, (path, data)) in files.into_iter().enumerate() {
+                let file_pairs = parse::decompositions(&parse::decode(&data))
+                    .with_context(|| format!("failed to parse `{path}`"))?;
+                debug!("{} kanji/radical pairs in `{path}`", file_pairs.len());
+                pairs.extend(file_pairs);
+
+                let frac = ((files_done + 1) as f64) / (num_files as f64);
+                _ = progress_tx.try_send(ImportProgress { frac });
+            }
+
+            let mut tx = job.begin().await?;
+            let mut insert = job.inserter(&mut tx).await?;
+            for (kanji, radical) in pairs {
+                insert
+                    .kanji_radical(kanji, radical)
+                    .await
+                    .context("failed to insert kanji radical")?;
+            }
+            insert.flush().await.context("failed to flush inserts")?;
+            drop(insert);
+            job.finish(tx).await
+        }),
+    ))
+}
@@ -0,0 +1,148 @@
+//! Parser for the KRADFILE and RADKFILE text formats.
+//!
+//! Both files describe the same relation between kanji and the radicals
+//! (components) which make them up, but from opposite directions:
+//!
+//! ```text
+//! # KRADFILE: one kanji per line, followed by its radicals
+//! 亜 : ｜ 一 口
+//!
+//! # RADKFILE: one radical per block, followed by the kanji which contain it
+//! $ 一 1
+//! 亜唖娃阿哀愛挨姶逢葵茜穐悪握渥旭葦芦
+//! ```
+//!
+//! See the [KRADFILE documentation].
+//!
+//! [KRADFILE documentation]: https://www.edrdg.org/krad/kradinf.html
+
+use {
+    anyhow::{Context, Result, bail},
+    encoding_rs::{EUC_JP, UTF_8},
+};
+
+/// Decodes the contents of a file, which is UTF-8 for newer releases
+/// (`kradfile-u`), or EUC-JP for the original files.
+pub fn decode(bytes: &[u8]) -> String {
+    // if we only read the start of a file, it may end in the middle of a
+    // character, which is still valid UTF-8 as far as we're concerned
+    let encoding = match std::str::from_utf8(bytes) {
+        Ok(_) => UTF_8,
+        Err(err) if err.error_len().is_none() => UTF_8,
+        Err(_) => EUC_JP,
+    };
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
+
+/// Checks if the first line of data (ignoring `#` comments) is either a
+/// KRADFILE or RADKFILE line.
+pub fn is_kradfile(text: &str) -> bool {
+    text.lines()
+        .map(str::trim_end)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .is_some_and(|line| {
+            let symbol = line
+                .strip_prefix("$ ")
+                .and_then(|rest| rest.split_whitespace().next())
+                .or_else(|| line.split_once(" : ").map(|(kanji, _)| kanji.trim()));
+            symbol.is_some_and(|symbol| single_char(symbol).is_ok())
+        })
+}
+
+/// Parses every `(kanji, radical)` pair described in a KRADFILE or RADKFILE.
+pub fn decompositions(text: &str) -> Result<Vec<(char, char)>> {
+    let mut pairs = Vec::new();
+    // radical of the RADKFILE block that we are currently in
+    let mut radical = None;
+    for (index, line) in text.lines().enumerate() {
+        let line_num = index + 1;
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix('$') {
+            // `$ 一 1` or `$ 邦 3 js02`, where the trailing fields are the
+            // stroke count and an optional image name
+            let symbol = rest
+                .split_whitespace()
+                .next()
+                .with_context(|| format!("missing radical on line {line_num}"))?;
+            radical = Some(
+                single_char(symbol)
+                    .with_context(|| format!("invalid radical on line {line_num}"))?,
+            );
+        } else if let Some((kanji, radicals)) = line.split_once(" : ") {
+            let kanji = single_char(kanji.trim())
+                .with_context(|| format!("invalid kanji on line {line_num}"))?;
+            for radical in radicals.split_whitespace() {
+                let radical = single_char(radical)
+                    .with_context(|| format!("invalid radical on line {line_num}"))?;
+                pairs.push((kanji, radical));
+            }
+        } else {
+            let radical = radical
+                .with_context(|| format!("line {line_num} is outside of a radical block"))?;
+            pairs.extend(
+                line.chars()
+                    .filter(|c| !c.is_whitespace())
+                    .map(|kanji| (kanji, radical)),
+            );
+        }
+    }
+    Ok(pairs)
+}
+
+fn single_char(text: &str) -> Result<char> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => bail!("`{text}` is not a single character"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn krad() {
+        let text = "# KRADFILE\n亜 : ｜ 一 口\n唖 : ｜ 一 口\n";
+        assert!(is_kradfile(text));
+        assert_eq!(
+            vec![
+                ('亜', '｜'),
+                ('亜', '一'),
+                ('亜', '口'),
+                ('唖', '｜'),
+                ('唖', '一'),
+                ('唖', '口'),
+            ],
+            decompositions(text).unwrap()
+        );
+    }
+
+    #[test]
+    fn radk() {
+        let text = "# RADKFILE\n$ 一 1\n亜唖\n娃\n$ 邦 3 js02\n邦\n";
+        assert!(is_kradfile(text));
+        assert_eq!(
+            vec![('亜', '一'), ('唖', '一'), ('娃', '一'), ('邦', '邦')],
+            decompositions(text).unwrap()
+        );
+    }
+
+    #[test]
+    fn not_kradfile() {
+        assert!(!is_kradfile("# comment\nhello world\n"));
+        assert!(!is_kradfile("word [ipa] : a definition\n"));
+        decompositions("亜唖\n").unwrap_err();
+    }
+
+    #[test]
+    fn decode_euc_jp() {
+        let (bytes, _, _) = EUC_JP.encode("亜 : ｜ 一 口");
+        assert_eq!("亜 : ｜ 一 口", decode(&bytes));
+    }
+}
@@ -0,0 +1,167 @@
+//! Minimal LZO1X decompressor, as used by older MDict files.
+//!
+//! See the [Linux kernel's description] of the LZO1X bitstream.
+//!
+//! [Linux kernel's description]: https://www.kernel.org/doc/Documentation/lzo.txt
+
+use anyhow::{Context, Result, bail};
+
+struct Input<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Input<'_> {
+    fn byte(&mut self) -> Result<u8> {
+        let byte = *self
+            .data
+            .get(self.pos)
+            .context("unexpected end of compressed data")?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn le16(&mut self) -> Result<usize> {
+        let lo = self.byte()?;
+        let hi = self.byte()?;
+        Ok(usize::from(u16::from_le_bytes([lo, hi])))
+    }
+
+    /// Reads a run of zero bytes followed by a non-zero byte, encoding a
+    /// length larger than what fits in the instruction itself.
+    fn extended_len(&mut self, base: usize) -> Result<usize> {
+        let mut len = base;
+        loop {
+            match self.byte()? {
+                0 => len += 255,
+                byte => return Ok(len + usize::from(byte)),
+            }
+        }
+    }
+
+    fn literals(&mut self, out: &mut Vec<u8>, len: usize) -> Result<()> {
+        let literals = self
+            .data
+            .get(self.pos..self.pos + len)
+            .context("literal run extends past end of compressed data")?;
+        out.extend_from_slice(literals);
+        self.pos += len;
+        Ok(())
+    }
+}
+
+fn copy_match(out: &mut Vec<u8>, distance: usize, len: usize) -> Result<()> {
+    if distance == 0 || distance > out.len() {
+        bail!("match distance {distance} is out of bounds");
+    }
+    // the match may overlap with the bytes it produces, so copy byte-by-byte
+    let start = out.len() - distance;
+    for i in 0..len {
+        out.push(out[start + i]);
+    }
+    Ok(())
+}
+
+pub fn decompress(data: &[u8], decompressed_len: usize) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(decompressed_len);
+    let mut input = Input { data, pos: 0 };
+    // number of literals copied by the previous instruction,
+    // where 4 means a literal run of 4 or more
+    let mut state = 0usize;
+
+    if let Some(&first) = data.first() {
+        if first > 17 {
+            input.pos += 1;
+            let len = usize::from(first - 17);
+            input.literals(&mut out, len)?;
+            state = len.min(4);
+        }
+    }
+
+    loop {
+        let inst = usize::from(input.byte()?);
+        let (distance, len, next) = match inst {
+            0..=15 if state == 0 => {
+                let len = if inst == 0 {
+                    input.extended_len(15)?
+                } else {
+                    inst
+                };
+                input.literals(&mut out, len + 3)?;
+                state = 4;
+                continue;
+            }
+            0..=15 if state == 4 => {
+                let high = usize::from(input.byte()?);
+                ((high << 2) + (inst >> 2) + 2049, 3, inst & 3)
+            }
+            0..=15 => {
+                let high = usize::from(input.byte()?);
+                ((high << 2) + (inst >> 2) + 1, 2, inst & 3)
+            }
+            16..=31 => {
+                let len = match inst & 7 {
+                    0 => input.extended_len(7)?,
+                    len => len,
+                };
+                let tail = input.le16()?;
+                let distance = 16384 + ((inst & 8) << 11) + (tail >> 2);
+                if distance == 16384 {
+                    break;
+                }
+                (distance, len + 2, tail & 3)
+            }
+            32..=63 => {
+                let len = match inst & 31 {
+                    0 => input.extended_len(31)?,
+                    len => len,
+                };
+                let tail = input.le16()?;
+                ((tail >> 2) + 1, len + 2, tail & 3)
+            }
+            64..=127 => {
+                let high = usize::from(input.byte()?);
+                (
+                    (high << 3) + ((inst >> 2) & 7) + 1,
+                    3 + ((inst >> 5) & 1),
+                    inst & 3,
+                )
+            }
+            _ => {
+                let high = usize::from(input.byte()?);
+                (
+                    (high << 3) + ((inst >> 2) & 7) + 1,
+                    5 + ((inst >> 5) & 3),
+                    inst & 3,
+                )
+            }
+        };
+        copy_match(&mut out, distance, len)?;
+        input.literals(&mut out, next)?;
+        state = next;
+    }
+
+    if out.len() != decompressed_len {
+        bail!(
+            "decompressed to {} bytes, expected {decompressed_len}",
+            out.len()
+        );
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literals_and_match() {
+        // "abcd" as a first-byte literal run, then a 32..63 match of
+        // length 8 at distance 4, then the end-of-stream marker
+        let compressed = [21, b'a', b'b', b'c', b'd', 32 | 6, 3 << 2, 0, 17, 0, 0];
+        assert_eq!(
+            b"abcdabcdabcd".as_slice(),
+            decompress(&compressed, 12).unwrap()
+        );
+    }
+}
@@ -0,0 +1,433 @@
+mod lzo;
+mod parse;
+
+use {
+    super::{
+        Archive, ImportContinue, ImportKind, ImportProgress, OpenArchive,
+        source::{self, ZipArchive},
+    },
+    crate::import::{Batches, ImportJob, continue_with, insert::Inserter},
+    anyhow::{Context, Result, bail},
+    futures::future::BoxFuture,
+    parse::Header,
+    std::{collections::BTreeMap, iter::Peekable, path::Path, slice, sync::Arc},
+    tokio::{
+        io::{AsyncBufReadExt as _, AsyncRead},
+        sync::mpsc,
+    },
+    tokio_util::compat::FuturesAsyncReadCompatExt,
+    tracing::{debug, trace},
+    wordbase_api::{DictionaryKind, DictionaryMeta, NormString, Term, dict::mdict::Entry},
+};
+
+pub struct Mdict;
+
+impl ImportKind for Mdict {
+    fn is_of_kind(&self, open_archive: Arc<dyn OpenArchive>) -> BoxFuture<'_, Result<()>> {
+        Box::pin(validate(open_archive))
+    }
+
+    fn start_import(
+        &self,
+        open_archive: Arc<dyn OpenArchive>,
+        progress_tx: mpsc::Sender<ImportProgress>,
+    ) -> BoxFuture<Result<(DictionaryMeta, ImportContinue)>> {
+        Box::pin(start_import(open_archive, progress_tx))
+    }
+}
+
+const LINK_PREFIX: &str = "@@@LINK=";
+
+/// How many `@@@LINK=`s we follow in a row before giving up.
+const MAX_LINK_DEPTH: usize = 8;
+
+/// Placeholder title which the MDict authoring tools generate.
+const PLACEHOLDER_TITLE: &str = "Title (No HTML code allowed)";
+
+const RECORDS_PER_BATCH: usize = 5000;
+
+const LINKS_PER_BATCH: usize = 5000;
+
+/// Media files can be large, so we hold fewer of them in memory at once.
+const MEDIA_PER_BATCH: usize = 100;
+
+/// An MDict dictionary may either be imported as a bare `.mdx` file, or as a
+/// zip containing the `.mdx`, `.mdd`s, and any other resource files.
+enum Source {
+    Mdx(Box<dyn Archive>),
+    Zip {
+        archive: ZipArchive,
+        mdx_index: usize,
+        mdx_path: String,
+    },
+}
+
+async fn open_source(open_archive: &dyn OpenArchive) -> Result<Source> {
+    let archive = match source::open_source(open_archive).await? {
+        source::Source::Bare(mut archive) => {
+            let start = archive
+                .fill_buf()
+                .await
+                .context("failed to read start of archive")?;
+            if !parse::is_header_start(start) {
+                bail!("not an `.mdx` file or zip archive");
+            }
+            return Ok(Source::Mdx(archive));
+        }
+        source::Source::Zip(archive) => archive,
+    };
+
+    let mut mdx_paths = source::zip_files(&archive)
+        .filter(|(_, path)| has_extension(path, "mdx"))
+        .map(|(index, path)| (index, path.to_owned()));
+    let (mdx_index, mdx_path) = mdx_paths.next().context("no `.mdx` file in archive")?;
+    if mdx_paths.next().is_some() {
+        bail!("archive contains multiple `.mdx` files");
+    }
+    drop(mdx_paths);
+    Ok(Source::Zip {
+        archive,
+        mdx_index,
+        mdx_path,
+    })
+}
+
+fn has_extension(path: &str, extension: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// Normalizes the path of a resource, so that it can be looked up from the
+/// paths referenced in entries.
+///
+/// `.mdd` keys use Windows-style paths like `\images\cat.png`.
+pub fn media_path(path: &str) -> String {
+    path.replace('\\', "/").trim_start_matches('/').to_owned()
+}
+
+async fn read_mdx_header(source: &mut Source) -> Result<Header> {
+    let header = match source {
+        Source::Mdx(archive) => parse::header(archive).await?,
+        Source::Zip {
+            archive, mdx_index, ..
+        } => {
+            let mut reader = archive
+                .reader_with_entry(*mdx_index)
+                .await
+                .context("failed to start reading `.mdx`")?
+                .compat();
+            parse::header(&mut reader).await?
+        }
+    };
+    if header.is_mdd {
+        bail!("file is an `.mdd` resource file, not an `.mdx` dictionary");
+    }
+    Ok(header)
+}
+
+async fn validate(open_archive: Arc<dyn OpenArchive>) -> Result<()> {
+    let mut source = open_source(&*open_archive).await?;
+    read_mdx_header(&mut source).await?;
+    Ok(())
+}
+
+async fn start_import(
+    open_archive: Arc<dyn OpenArchive>,
+    progress_tx: mpsc::Sender<ImportProgress>,
+) -> Result<(DictionaryMeta, ImportContinue)> {
+    let mut source = open_source(&*open_archive).await?;
+    let header = read_mdx_header(&mut source)
+        .await
+        .context("failed to parse header")?;
+
+    let name = header
+        .attribute("Title")
+        .filter(|title| *title != PLACEHOLDER_TITLE)
+        .map(ToOwned::to_owned)
+        .or_else(|| match &source {
+            Source::Mdx(_) => None,
+            Source::Zip { mdx_path, .. } => Path::new(mdx_path)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .map(ToOwned::to_owned),
+        })
+        .unwrap_or_else(|| "MDict".into());
+    let mut meta = DictionaryMeta::new(DictionaryKind::Mdict, name);
+    meta.version = header.attribute("CreationDate").map(ToOwned::to_owned);
+    meta.description = header.attribute("Description").map(ToOwned::to_owned);
+
+    Ok((
+        meta,
+        continue_with(move |mut job| async move {
+            // re-open the source, since we've already consumed the header
+            match open_source(&*open_archive).await? {
+                Source::Mdx(mut archive) => {
+                    import_mdx(&mut job, &mut archive, |frac| {
+                        _ = progress_tx.try_send(ImportProgress { frac });
+                    })
+                    .await
+                    .context("failed to import `.mdx`")?;
+                }
+                Source::Zip {
+                    mut archive,
+                    mdx_index,
+                    mdx_path,
+                } => {
+                    import_zip(&mut job, &mut archive, mdx_index, &mdx_path, |frac| {
+                        _ = progress_tx.try_send(ImportProgress { frac });
+                    })
+                    .await?;
+                }
+            }
+
+            let tx = job.begin().await?;
+            job.finish(tx).await
+        }),
+    ))
+}
+
+async fn import_zip(
+    job: &mut ImportJob,
+    archive: &mut ZipArchive,
+    mdx_index: usize,
+    mdx_path: &str,
+    progress: impl Fn(f64) + Send + Sync,
+) -> Result<()> {
+    let mut reader = archive
+        .reader_with_entry(mdx_index)
+        .await
+        .context("failed to start reading `.mdx`")?
+        .compat();
+    import_mdx(job, &mut reader, |frac| progress(frac * 0.5))
+        .await
+        .with_context(|| format!("failed to import `{mdx_path}`"))?;
+    drop(reader);
+
+    // resources are referenced relative to the `.mdx`
+    let root = Path::new(mdx_path)
+        .parent()
+        .unwrap_or_else(|| Path::new(""));
+    let entries = source::zip_files(archive)
+        .filter(|(index, _)| *index != mdx_index)
+        .map(|(index, path)| (index, path.to_owned()))
+        .collect::<Vec<_>>();
+    let num_entries = entries.len();
+    let mut batches = Batches::new("media", MEDIA_PER_BATCH);
+    for (entries_done, (index, path)) in entries.into_iter().enumerate() {
+        let mut reader = archive
+            .reader_with_entry(index)
+            .await
+            .with_context(|| format!("failed to start reading `{path}`"))?;
+        if has_extension(&path, "mdd") {
+            import_mdd(job, &mut (&mut reader).compat(), &path)
+                .await
+                .with_context(|| format!("failed to import `{path}`"))?;
+        } else {
+            let mut data = Vec::new();
+            reader
+                .read_to_end_checked(&mut data)
+                .await
+                .with_context(|| format!("failed to read `{path}`"))?;
+            let relative = Path::new(&path)
+                .strip_prefix(root)
+                .ok()
+                .and_then(|path| path.to_str())
+                .unwrap_or(&path);
+            trace!("Importing {path:?} as media");
+            if let Some(batch) = batches.push((media_path(relative), data)) {
+                job.insert_batch(batch, import_media).await?;
+            }
+        }
+
+        progress((entries_done as f64 / num_entries as f64).mul_add(0.5, 0.5));
+    }
+    job.insert_batch(batches.finish(), import_media).await
+}
+
+async fn import_mdx<R: AsyncRead + Unpin>(
+    job: &mut ImportJob,
+    reader: &mut R,
+    progress: impl Fn(f64) + Send + Sync,
+) -> Result<()> {
+    let header = parse::header(reader)
+        .await
+        .context("failed to parse header")?;
+    let keys = parse::keys(reader, &header)
+        .await
+        .context("failed to parse keys")?;
+    debug!("{} keys", keys.len());
+
+    // links are resolved in a consistent order, so that they are always split
+    // into the same batches
+    let mut links = BTreeMap::<&str, String>::new();
+    let mut records = RecordReader::new(reader, &header, &keys).await?;
+    let mut batches = Batches::new("records", RECORDS_PER_BATCH);
+    let mut blocks_done = 0;
+    while let Some((key, data)) = records.next(reader).await? {
+        let text = header.decode_record(data);
+        if let Some(target) = text.strip_prefix(LINK_PREFIX) {
+            links.insert(&key.text, target.trim().to_owned());
+            continue;
+        }
+
+        let row = (key.text.clone(), Entry { html: text });
+        if let Some(batch) = batches.push(row) {
+            job.insert_batch(batch, import_record).await?;
+        }
+
+        if records.blocks_done != blocks_done {
+            blocks_done = records.blocks_done;
+            progress(records.frac());
+        }
+    }
+    job.insert_batch(batches.finish(), import_record).await?;
+
+    // the records that links point to are found in the database, since they
+    // may have been inserted by a previous run of this job
+    debug!("Resolving {} links", links.len());
+    let mut batches = Batches::new("links", LINKS_PER_BATCH);
+    for (&key, target) in &links {
+        let mut target = target.as_str();
+        for _ in 0..MAX_LINK_DEPTH {
+            match links.get(target) {
+                Some(next) => target = next,
+                None => break,
+            }
+        }
+        let (Some(headword), Some(target)) = (NormString::new(key), NormString::new(target)) else {
+            trace!("Link {key:?} -> {target:?} does not have a headword");
+            continue;
+        };
+        if let Some(batch) = batches.push((headword, target)) {
+            job.insert_batch(batch, import_link).await?;
+        }
+    }
+    job.insert_batch(batches.finish(), import_link).await
+}
+
+async fn import_record(insert: &mut Inserter<'_, '_>, (key, entry): (String, Entry)) -> Result<()> {
+    (async {
+        let record_id = insert
+            .record(&entry)
+            .await
+            .context("failed to insert record")?;
+        if let Some(term) = Term::from_headword(key.as_str()) {
+            insert
+                .term_record(term, record_id)
+                .await
+                .context("failed to insert term record")?;
+        }
+        anyhow::Ok(())
+    })
+    .await
+    .with_context(|| format!("failed to import `{key}`"))
+}
+
+async fn import_link(
+    insert: &mut Inserter<'_, '_>,
+    (headword, target): (NormString, NormString),
+) -> Result<()> {
+    insert
+        .link_headword(&headword, &target)
+        .await
+        .with_context(|| format!("failed to link `{headword}` to `{target}`"))
+}
+
+async fn import_media(
+    insert: &mut Inserter<'_, '_>,
+    (path, data): (String, Vec<u8>),
+) -> Result<()> {
+    insert
+        .media(&path, &data)
+        .await
+        .with_context(|| format!("failed to import `{path}`"))
+}
+
+async fn import_mdd<R: AsyncRead + Unpin>(
+    job: &mut ImportJob,
+    reader: &mut R,
+    mdd_path: &str,
+) -> Result<()> {
+    let header = parse::header(reader)
+        .await
+        .context("failed to parse header")?;
+    if !header.is_mdd {
+        bail!("file is not an `.mdd` resource file");
+    }
+    let keys = parse::keys(reader, &header)
+        .await
+        .context("failed to parse keys")?;
+    debug!("{} resources", keys.len());
+
+    let mut records = RecordReader::new(reader, &header, &keys).await?;
+    let mut batches = Batches::new(format!("`{mdd_path}` media"), MEDIA_PER_BATCH);
+    while let Some((key, data)) = records.next(reader).await? {
+        let row = (media_path(&key.text), data.to_vec());
+        if let Some(batch) = batches.push(row) {
+            job.insert_batch(batch, import_media).await?;
+        }
+    }
+    job.insert_batch(batches.finish(), import_media).await
+}
+
+/// Reads record blocks, splitting them up into the records of each key.
+struct RecordReader<'k> {
+    records: parse::Records,
+    keys: Peekable<slice::Iter<'k, parse::Key>>,
+    block_start: u64,
+    block: Vec<u8>,
+    /// How many blocks have been read so far, including the current one.
+    blocks_done: usize,
+}
+
+impl<'k> RecordReader<'k> {
+    async fn new<R: AsyncRead + Unpin>(
+        reader: &mut R,
+        header: &Header,
+        keys: &'k [parse::Key],
+    ) -> Result<Self> {
+        let records = parse::Records::new(reader, header)
+            .await
+            .context("failed to parse record section")?;
+        Ok(Self {
+            records,
+            keys: keys.iter().peekable(),
+            block_start: 0,
+            block: Vec::new(),
+            blocks_done: 0,
+        })
+    }
+
+    fn frac(&self) -> f64 {
+        self.blocks_done as f64 / self.records.num_blocks() as f64
+    }
+
+    async fn next<R: AsyncRead + Unpin>(
+        &mut self,
+        reader: &mut R,
+    ) -> Result<Option<(&'k parse::Key, &[u8])>> {
+        loop {
+            let block_end = self.block_start + self.block.len() as u64;
+            if let Some(key) = self.keys.next_if(|key| key.offset < block_end) {
+                let end = self
+                    .keys
+                    .peek()
+                    .map_or(block_end, |next| next.offset.min(block_end));
+                let data = usize::try_from(key.offset.saturating_sub(self.block_start))
+                    .ok()
+                    .zip(usize::try_from(end - self.block_start).ok())
+                    .and_then(|(start, end)| self.block.get(start..end))
+                    .with_context(|| format!("record of `{}` is out of bounds", key.text))?;
+                return Ok(Some((key, data)));
+            }
+
+            let Some((block_start, block)) = self.records.next_block(reader).await? else {
+                return Ok(None);
+            };
+            self.blocks_done += 1;
+            self.block_start = block_start;
+            self.block = block;
+        }
+    }
+}
@@ -0,0 +1,485 @@
+//! Parser for the MDict `.mdx` and `.mdd` file formats.
+//!
+//! There is no official specification for this format. This is based on the
+//! reverse-engineered description from [`readmdict`].
+//!
+//! Files are read sequentially, so we never have to load the entire file into
+//! memory:
+//! - [`header`] reads the XML header
+//! - [`keys`] reads the keyword section, giving all keys and the offsets of
+//!   their records
+//! - [`Records`] reads the record section one block at a time
+//!
+//! [`readmdict`]: https://github.com/ffreemt/readmdict
+
+use {
+    super::lzo,
+    anyhow::{Context, Result, bail},
+    encoding_rs::{Encoding, GB18030, UTF_8, UTF_16LE},
+    flate2::read::ZlibDecoder,
+    regex::Regex,
+    ripemd::{Digest, Ripemd128},
+    std::{collections::HashMap, io::Read as _, sync::LazyLock},
+    tokio::io::{AsyncRead, AsyncReadExt},
+};
+
+#[derive(Debug)]
+pub struct Header {
+    pub attributes: HashMap<String, String>,
+    /// If this is an `.mdd` resource file, rather than an `.mdx` dictionary.
+    pub is_mdd: bool,
+    version: f32,
+    encoding: &'static Encoding,
+    encrypted: u32,
+    /// Style number to the text inserted before and after styled text.
+    stylesheet: HashMap<String, (String, String)>,
+}
+
+impl Header {
+    /// Gets a non-empty attribute from the header.
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .get(key)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    }
+
+    fn is_v2(&self) -> bool {
+        self.version >= 2.0
+    }
+
+    fn num_width(&self) -> usize {
+        if self.is_v2() { 8 } else { 4 }
+    }
+
+    /// Size in bytes of one unit of text, used for string lengths and null
+    /// terminators.
+    fn char_width(&self) -> usize {
+        if self.encoding == UTF_16LE { 2 } else { 1 }
+    }
+
+    fn decode(&self, bytes: &[u8]) -> String {
+        let (text, _, _) = self.encoding.decode(bytes);
+        text.into_owned()
+    }
+
+    /// Decodes the text of a record, applying the stylesheet.
+    pub fn decode_record(&self, bytes: &[u8]) -> String {
+        let text = self.decode(bytes);
+        let text = text.trim_end_matches('\0');
+        if self.stylesheet.is_empty() {
+            text.to_owned()
+        } else {
+            apply_stylesheet(text, &self.stylesheet)
+        }
+    }
+}
+
+/// Checks if this file starts with an MDict header, without reading it fully.
+pub fn is_header_start(start: &[u8]) -> bool {
+    let tag = |tag: &str| {
+        tag.encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>()
+    };
+    start.get(4..).is_some_and(|rest| {
+        rest.starts_with(&tag("<Dictionary")) || rest.starts_with(&tag("<Library_Data"))
+    })
+}
+
+pub async fn header<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Header> {
+    static ATTRIBUTE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r#"(\w+)="((?s).*?)""#).expect("should be valid regex"));
+
+    let len = reader.read_u32().await.context("failed to read length")?;
+    let bytes = read_vec(reader, u64::from(len)).await?;
+    // adler32 checksum
+    reader
+        .read_u32_le()
+        .await
+        .context("failed to read checksum")?;
+
+    let (text, _, _) = UTF_16LE.decode(&bytes);
+    let is_mdd = text.trim_start().starts_with("<Library_Data");
+    let attributes = ATTRIBUTE
+        .captures_iter(&text)
+        .map(|caps| (caps[1].to_owned(), unescape(&caps[2])))
+        .collect::<HashMap<_, _>>();
+
+    let version = attributes
+        .get("GeneratedByEngineVersion")
+        .context("no engine version")?
+        .trim()
+        .parse::<f32>()
+        .context("invalid engine version")?;
+    let encoding = if is_mdd {
+        UTF_16LE
+    } else {
+        match attributes.get("Encoding").map(|s| s.trim()) {
+            None | Some("") => UTF_8,
+            // GBK and GB2312 are both subsets of GB18030
+            Some(label) if label.eq_ignore_ascii_case("GBK") => GB18030,
+            Some(label) if label.eq_ignore_ascii_case("GB2312") => GB18030,
+            Some(label) if label.eq_ignore_ascii_case("UTF-16") => UTF_16LE,
+            Some(label) => Encoding::for_label(label.as_bytes())
+                .with_context(|| format!("unknown encoding `{label}`"))?,
+        }
+    };
+    let encrypted = match attributes.get("Encrypted").map(|s| s.trim()) {
+        None | Some("" | "No") => 0,
+        Some("Yes") => 1,
+        Some(flags) => flags.parse().context("invalid encryption flags")?,
+    };
+    let stylesheet = attributes
+        .get("StyleSheet")
+        .map(|stylesheet| parse_stylesheet(stylesheet))
+        .unwrap_or_default();
+
+    Ok(Header {
+        attributes,
+        is_mdd,
+        version,
+        encoding,
+        encrypted,
+        stylesheet,
+    })
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+/// The stylesheet is a list of 3-line groups: style number, text to insert
+/// before, and text to insert after.
+fn parse_stylesheet(text: &str) -> HashMap<String, (String, String)> {
+    let lines = text.lines().map(str::trim).collect::<Vec<_>>();
+    lines
+        .chunks_exact(3)
+        .map(|group| {
+            (
+                group[0].to_owned(),
+                (group[1].to_owned(), group[2].to_owned()),
+            )
+        })
+        .collect()
+}
+
+/// Replaces style markers like `` `1` `` with the text from the stylesheet.
+///
+/// Each marker applies until the next marker.
+fn apply_stylesheet(text: &str, stylesheet: &HashMap<String, (String, String)>) -> String {
+    static MARKER: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"`(\d+)`").expect("should be valid regex"));
+
+    let mut styled = String::with_capacity(text.len());
+    let mut last_end = 0;
+    let mut current = None::<&(String, String)>;
+    for caps in MARKER.captures_iter(text) {
+        let marker = caps.get(0).expect("capture 0 should always exist");
+        let segment = &text[last_end..marker.start()];
+        push_styled(&mut styled, segment, current);
+        current = stylesheet.get(&caps[1]);
+        last_end = marker.end();
+    }
+    push_styled(&mut styled, &text[last_end..], current);
+    styled
+}
+
+fn push_styled(styled: &mut String, segment: &str, style: Option<&(String, String)>) {
+    match style {
+        Some((before, after)) => {
+            styled.push_str(before);
+            styled.push_str(segment);
+            styled.push_str(after);
+        }
+        None => styled.push_str(segment),
+    }
+}
+
+#[derive(Debug)]
+pub struct Key {
+    /// Offset of this key's record in the decompressed record data.
+    pub offset: u64,
+    pub text: String,
+}
+
+pub async fn keys<R: AsyncRead + Unpin>(reader: &mut R, header: &Header) -> Result<Vec<Key>> {
+    if header.encrypted & 1 != 0 {
+        bail!("record data is encrypted, which requires a registration key");
+    }
+
+    let num_blocks = read_num(reader, header).await?;
+    let num_entries = read_num(reader, header).await?;
+    if header.is_v2() {
+        // decompressed size of key block info
+        read_num(reader, header).await?;
+    }
+    let info_len = read_num(reader, header).await?;
+    let blocks_len = read_num(reader, header).await?;
+    if header.is_v2() {
+        // adler32 checksum
+        reader.read_u32().await.context("failed to read checksum")?;
+    }
+
+    let info = read_vec(reader, info_len).await?;
+    let info = if header.is_v2() {
+        decode_key_block_info(info, header.encrypted & 2 != 0)?
+    } else {
+        info
+    };
+    let block_sizes = parse_key_block_info(&info, header)?;
+    if block_sizes.len() as u64 != num_blocks {
+        bail!(
+            "expected {num_blocks} key blocks, found {}",
+            block_sizes.len()
+        );
+    }
+
+    let mut keys = Vec::with_capacity(usize::try_from(num_entries).unwrap_or_default());
+    let mut total_len = 0u64;
+    for (compressed_len, decompressed_len) in block_sizes {
+        total_len += compressed_len;
+        let block = read_vec(reader, compressed_len).await?;
+        let block = decode_block(&block, decompressed_len).context("failed to decode key block")?;
+        parse_key_block(&block, header, &mut keys)?;
+    }
+    if total_len != blocks_len {
+        bail!("expected {blocks_len} bytes of key blocks, read {total_len}");
+    }
+    Ok(keys)
+}
+
+fn decode_key_block_info(info: Vec<u8>, encrypted: bool) -> Result<Vec<u8>> {
+    if info.get(..4) != Some(&[2, 0, 0, 0]) {
+        bail!("key block info is not zlib compressed");
+    }
+    let mut info = info;
+    if encrypted {
+        let mut hasher = Ripemd128::new();
+        hasher.update(&info[4..8]);
+        hasher.update(0x3695u32.to_le_bytes());
+        let key = hasher.finalize();
+        fast_decrypt(&mut info[8..], &key);
+    }
+    zlib(&info[8..], None).context("failed to decompress key block info")
+}
+
+fn parse_key_block_info(mut info: &[u8], header: &Header) -> Result<Vec<(u64, u64)>> {
+    let char_width = header.char_width();
+    // v2 strings are null-terminated, v1 strings are not
+    let terminator = if header.is_v2() { char_width } else { 0 };
+    let mut sizes = Vec::new();
+    while !info.is_empty() {
+        // number of entries
+        take_num(&mut info, header)?;
+        for _ in 0..2 {
+            // first and last keys of the block
+            let len = if header.is_v2() {
+                usize::from(u16::from_be_bytes(take(&mut info)?))
+            } else {
+                usize::from(u8::from_be_bytes(take(&mut info)?))
+            };
+            let len = len * char_width + terminator;
+            info = info
+                .get(len..)
+                .context("key extends past end of block info")?;
+        }
+        let compressed_len = take_num(&mut info, header)?;
+        let decompressed_len = take_num(&mut info, header)?;
+        sizes.push((compressed_len, decompressed_len));
+    }
+    Ok(sizes)
+}
+
+fn parse_key_block(mut block: &[u8], header: &Header, keys: &mut Vec<Key>) -> Result<()> {
+    let char_width = header.char_width();
+    while !block.is_empty() {
+        let offset = take_num(&mut block, header)?;
+        let end = block
+            .chunks_exact(char_width)
+            .position(|unit| unit.iter().all(|&b| b == 0))
+            .context("key is not null-terminated")?
+            * char_width;
+        let text = header.decode(&block[..end]);
+        block = &block[end + char_width..];
+        keys.push(Key { offset, text });
+    }
+    Ok(())
+}
+
+/// Reads the record section one decompressed block at a time.
+pub struct Records {
+    block_sizes: std::vec::IntoIter<(u64, u64)>,
+    /// Offset of the next block in the decompressed record data.
+    offset: u64,
+    num_blocks: usize,
+}
+
+impl Records {
+    pub async fn new<R: AsyncRead + Unpin>(reader: &mut R, header: &Header) -> Result<Self> {
+        let num_blocks = read_num(reader, header).await?;
+        // number of entries
+        read_num(reader, header).await?;
+        // record block info size
+        read_num(reader, header).await?;
+        // record block size
+        read_num(reader, header).await?;
+
+        let mut block_sizes = Vec::new();
+        for _ in 0..num_blocks {
+            let compressed_len = read_num(reader, header).await?;
+            let decompressed_len = read_num(reader, header).await?;
+            block_sizes.push((compressed_len, decompressed_len));
+        }
+        Ok(Self {
+            num_blocks: block_sizes.len(),
+            block_sizes: block_sizes.into_iter(),
+            offset: 0,
+        })
+    }
+
+    pub const fn num_blocks(&self) -> usize {
+        self.num_blocks
+    }
+
+    /// Reads the next block, returning its offset in the decompressed record
+    /// data, and its decompressed contents.
+    pub async fn next_block<R: AsyncRead + Unpin>(
+        &mut self,
+        reader: &mut R,
+    ) -> Result<Option<(u64, Vec<u8>)>> {
+        let Some((compressed_len, decompressed_len)) = self.block_sizes.next() else {
+            return Ok(None);
+        };
+        let block = read_vec(reader, compressed_len).await?;
+        let block =
+            decode_block(&block, decompressed_len).context("failed to decode record block")?;
+        let offset = self.offset;
+        self.offset += block.len() as u64;
+        Ok(Some((offset, block)))
+    }
+}
+
+/// Decrypts and decompresses a key or record block.
+fn decode_block(block: &[u8], decompressed_len: u64) -> Result<Vec<u8>> {
+    let (info, rest) = block
+        .split_first_chunk::<4>()
+        .context("block is too short")?;
+    let (checksum, data) = rest
+        .split_first_chunk::<4>()
+        .context("block is too short")?;
+    let info = u32::from_le_bytes(*info);
+    let compression = info & 0xf;
+    let encryption = (info >> 4) & 0xf;
+    let encrypted_len = usize::try_from((info >> 8) & 0xff).unwrap_or_default();
+
+    let mut data = data.to_vec();
+    match encryption {
+        0 => {}
+        1 => {
+            let key = Ripemd128::digest(checksum);
+            let encrypted_len = encrypted_len.min(data.len());
+            fast_decrypt(&mut data[..encrypted_len], &key);
+        }
+        _ => bail!("unsupported block encryption method {encryption}"),
+    }
+
+    let decompressed_len =
+        usize::try_from(decompressed_len).context("block is too large for this platform")?;
+    match compression {
+        0 => Ok(data),
+        1 => lzo::decompress(&data, decompressed_len),
+        2 => zlib(&data, Some(decompressed_len)),
+        _ => bail!("unsupported block compression method {compression}"),
+    }
+}
+
+fn zlib(data: &[u8], decompressed_len: Option<usize>) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(decompressed_len.unwrap_or_default());
+    ZlibDecoder::new(data).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+fn fast_decrypt(data: &mut [u8], key: &[u8]) {
+    let mut previous = 0x36u8;
+    for (i, byte) in data.iter_mut().enumerate() {
+        let encrypted = *byte;
+        #[expect(clippy::cast_possible_truncation, reason = "intentional wrapping")]
+        let decrypted = encrypted.rotate_left(4) ^ previous ^ (i as u8) ^ key[i % key.len()];
+        previous = encrypted;
+        *byte = decrypted;
+    }
+}
+
+async fn read_vec<R: AsyncRead + Unpin>(reader: &mut R, len: u64) -> Result<Vec<u8>> {
+    let len = usize::try_from(len).context("section is too large for this platform")?;
+    let mut buf = vec![0; len];
+    reader
+        .read_exact(&mut buf)
+        .await
+        .context("unexpected end of file")?;
+    Ok(buf)
+}
+
+async fn read_num<R: AsyncRead + Unpin>(reader: &mut R, header: &Header) -> Result<u64> {
+    let num = if header.is_v2() {
+        reader.read_u64().await
+    } else {
+        reader.read_u32().await.map(u64::from)
+    };
+    num.context("unexpected end of file")
+}
+
+fn take<const N: usize>(data: &mut &[u8]) -> Result<[u8; N]> {
+    let (bytes, rest) = data
+        .split_first_chunk::<N>()
+        .context("unexpected end of data")?;
+    *data = rest;
+    Ok(*bytes)
+}
+
+fn take_num(data: &mut &[u8], header: &Header) -> Result<u64> {
+    if header.num_width() == 8 {
+        Ok(u64::from_be_bytes(take(data)?))
+    } else {
+        Ok(u64::from(u32::from_be_bytes(take(data)?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stylesheet() {
+        let stylesheet = parse_stylesheet("1\n<b>\n</b>\n2\n<i>\n</i>");
+        assert_eq!(
+            "plain <b>bold</b><i>italic</i>",
+            apply_stylesheet("plain `1`bold`2`italic", &stylesheet)
+        );
+    }
+
+    #[test]
+    fn decrypt_roundtrip() {
+        // inverse of `fast_decrypt`
+        fn encrypt(data: &mut [u8], key: &[u8]) {
+            let mut previous = 0x36u8;
+            for (i, byte) in data.iter_mut().enumerate() {
+                #[expect(clippy::cast_possible_truncation, reason = "intentional wrapping")]
+                let encrypted = (*byte ^ previous ^ (i as u8) ^ key[i % key.len()]).rotate_left(4);
+                previous = encrypted;
+                *byte = encrypted;
+            }
+        }
+
+        let key = [1, 2, 3, 4];
+        let mut data = b"hello world".to_vec();
+        encrypt(&mut data, &key);
+        assert_ne!(b"hello world".as_slice(), data);
+        fast_decrypt(&mut data, &key);
+        assert_eq!(b"hello world".as_slice(), data);
+    }
+}
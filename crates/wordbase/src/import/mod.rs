@@ -1,13 +1,33 @@
+mod anki;
+mod cedict;
+mod count;
+mod directory;
+mod dsl;
+mod duplicate;
+mod frequency_list;
 mod insert;
+mod jmdict;
+mod job;
+mod kanjidic;
+mod kradfile;
+mod mdict;
+mod queue;
+mod source;
+mod stardict;
+mod tatoeba;
+mod validate;
+mod wiktionary;
 mod yomichan_audio;
 mod yomitan;
 
+pub(crate) use job::{ImportTasks, InsertLock};
 use {
-    crate::{CHANNEL_BUF_CAP, DictionaryEvent, Engine, EngineEvent},
-    anyhow::{Context, Result},
+    crate::{CHANNEL_BUF_CAP, DictionaryEvent, Engine, EngineEvent, NotFound},
+    anyhow::{Context, Result, anyhow},
     derive_more::{Display, Error, From},
-    futures::{Stream, TryStreamExt, future::BoxFuture, stream::FuturesUnordered},
-    sqlx::{Pool, Sqlite, Transaction},
+    duplicate::Existing,
+    futures::{Stream, StreamExt, TryStreamExt, future::BoxFuture, stream::FuturesUnordered},
+    sqlx::{Sqlite, Transaction},
     std::{
         collections::HashMap,
         path::Path,
@@ -19,9 +39,16 @@ use {
         sync::mpsc,
     },
     tokio_util::task::AbortOnDropHandle,
-    tracing::{debug, trace},
+    tracing::{debug, trace, warn},
     wordbase_api::{DictionaryId, DictionaryKind, DictionaryMeta},
 };
+pub use {
+    anki::{AnkiFields, AnkiNoteType, anki_note_types},
+    directory::Directory,
+    job::{Batch, Batches, ImportJob, ImportJobId, ImportJobInfo, ImportTx, Leniency},
+    queue::{ImportBatchId, ImportQueueEvent},
+    validate::{BankCount, RecordCount, ValidationIssue, ValidationReport},
+};
 
 static FORMATS: LazyLock<HashMap<DictionaryKind, Arc<dyn ImportKind>>> = LazyLock::new(|| {
     [
@@ -33,6 +60,20 @@ static FORMATS: LazyLock<HashMap<DictionaryKind, Arc<dyn ImportKind>>> = LazyLoc
             DictionaryKind::YomichanAudio,
             Arc::new(yomichan_audio::YomichanAudio),
         ),
+        (DictionaryKind::Jmdict, Arc::new(jmdict::Jmdict)),
+        (DictionaryKind::Stardict, Arc::new(stardict::Stardict)),
+        (DictionaryKind::Mdict, Arc::new(mdict::Mdict)),
+        (DictionaryKind::Dsl, Arc::new(dsl::Dsl)),
+        (DictionaryKind::Cedict, Arc::new(cedict::Cedict)),
+        (DictionaryKind::Kanjidic, Arc::new(kanjidic::Kanjidic)),
+        (DictionaryKind::Kradfile, Arc::new(kradfile::Kradfile)),
+        (DictionaryKind::Wiktionary, Arc::new(wiktionary::Wiktionary)),
+        (DictionaryKind::Tatoeba, Arc::new(tatoeba::Tatoeba)),
+        (
+            DictionaryKind::FrequencyList,
+            Arc::new(frequency_list::FrequencyList),
+        ),
+        (DictionaryKind::Anki, Arc::new(anki::Anki::default())),
     ]
     .into()
 });
@@ -42,14 +83,31 @@ pub trait ImportKind: Send + Sync {
 
     fn start_import(
         &self,
-        db: Pool<Sqlite>,
         open_archive: Arc<dyn OpenArchive>,
         progress_tx: mpsc::Sender<ImportProgress>,
     ) -> BoxFuture<Result<(DictionaryMeta, ImportContinue)>>;
+
+    /// Parses the whole archive without inserting into the engine's database,
+    /// and adds any problems found to `report`.
+    ///
+    /// By default, this runs the whole import against a scratch database.
+    fn validate<'a>(
+        &'a self,
+        open_archive: Arc<dyn OpenArchive>,
+        report: &'a mut ValidationReport,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(validate::dry_run(self, open_archive, report))
+    }
 }
 
 pub trait OpenArchive: Send + Sync {
     fn open_archive(&self) -> BoxFuture<'_, Result<Box<dyn Archive>>>;
+
+    /// Gets the directory that this archive has been unpacked into, if it is
+    /// not a single archive file.
+    fn directory(&self) -> Option<&Directory> {
+        None
+    }
 }
 
 impl<T, Fut> OpenArchive for T
@@ -76,17 +134,47 @@ pub trait Archive: Send + Sync + AsyncRead + AsyncSeek + AsyncBufRead + Unpin {}
 
 impl<T: Send + Sync + AsyncRead + AsyncSeek + AsyncBufRead + Unpin> Archive for T {}
 
-pub type ImportContinue = BoxFuture<'static, Result<DictionaryId>>;
+/// Rest of an import, which inserts the dictionary's data as part of a job.
+///
+/// This must commit its data with [`ImportJob::checkpoint`] and
+/// [`ImportJob::finish`], and skip any entries which [`ImportJob::is_done`].
+pub type ImportContinue = Box<dyn FnOnce(ImportJob) -> BoxFuture<'static, Result<()>> + Send>;
 
-#[derive(Debug)]
+fn continue_with<F, Fut>(f: F) -> ImportContinue
+where
+    F: FnOnce(ImportJob) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    Box::new(move |job| Box::pin(f(job)))
+}
+
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
 pub enum ImportEvent {
     DeterminedKind(DictionaryKind),
     ParsedMeta(DictionaryMeta),
+    /// Data has started being inserted as this job, which can now be
+    /// cancelled or resumed.
+    JobStarted(ImportJobId),
     Progress(ImportProgress),
+    /// A row failed to import and was skipped, because the import is
+    /// [lenient].
+    ///
+    /// [lenient]: ImportOptions::lenient
+    Warning(ValidationIssue),
     Done(DictionaryId),
 }
 
+/// Options for how a dictionary is imported.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct ImportOptions {
+    /// Skip rows which fail to import, instead of failing the whole import.
+    ///
+    /// Each skipped row is reported as an [`ImportEvent::Warning`].
+    pub lenient: bool,
+}
+
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
 pub struct ImportProgress {
@@ -166,8 +254,18 @@ pub enum ImportError {
         kind: DictionaryKind,
         source: anyhow::Error,
     },
-    #[display("dictionary with this name already exists")]
-    AlreadyExists,
+    #[display("this archive has already been imported as {id:?}")]
+    AlreadyImported { id: DictionaryId },
+    #[display(
+        "the same or a newer revision of this dictionary has already been imported as {id:?}"
+    )]
+    AlreadyExists { id: DictionaryId },
+    #[display("an older revision of this dictionary has already been imported as {id:?}")]
+    NewerRevision { id: DictionaryId },
+    #[display("import is already running")]
+    AlreadyRunning,
+    #[display("import was cancelled")]
+    Cancelled,
     #[display("failed to import as `{kind:?}`")]
     Import {
         kind: DictionaryKind,
@@ -188,68 +286,308 @@ impl Engine {
     pub fn import_dictionary_arc(
         &self,
         open_archive: Arc<dyn OpenArchive>,
+    ) -> impl Stream<Item = Result<ImportEvent, ImportError>> {
+        self.import_with(open_archive, None, None, ImportOptions::default())
+    }
+
+    /// Imports a dictionary like [`Engine::import_dictionary`], but with
+    /// non-default options.
+    pub fn import_dictionary_with_options(
+        &self,
+        open_archive: impl OpenArchive + 'static,
+        options: ImportOptions,
+    ) -> impl Stream<Item = Result<ImportEvent, ImportError>> {
+        self.import_with(Arc::new(open_archive), None, None, options)
+    }
+
+    /// Imports a newer revision of dictionary `id` from an archive, replacing
+    /// its data and meta in place.
+    ///
+    /// The new data is imported first, then swapped in atomically under the
+    /// same [`DictionaryId`], so the dictionary keeps its position, the
+    /// profiles it's enabled in, and any profiles which sort by it. If the
+    /// import fails, the existing dictionary is left untouched.
+    ///
+    /// Once done, [`DictionaryEvent::Updated`] is sent instead of
+    /// [`DictionaryEvent::Added`].
+    pub fn reimport_dictionary(
+        &self,
+        id: DictionaryId,
+        open_archive: impl OpenArchive + 'static,
+        options: ImportOptions,
+    ) -> impl Stream<Item = Result<ImportEvent, ImportError>> {
+        self.import_with(Arc::new(open_archive), None, Some(id), options)
+    }
+
+    /// Imports an Anki package (`.apkg` or `.colpkg`), using the given fields
+    /// of each note.
+    ///
+    /// Use [`anki_note_types`] to find which fields the package's notes have.
+    /// If you import the package with [`Engine::import_dictionary`] instead,
+    /// the fields are guessed from their names.
+    pub fn import_anki_deck(
+        &self,
+        open_archive: impl OpenArchive + 'static,
+        fields: AnkiFields,
+        options: ImportOptions,
+    ) -> impl Stream<Item = Result<ImportEvent, ImportError>> {
+        let importer = Arc::new(anki::Anki {
+            fields: Some(fields),
+        });
+        self.import_with(
+            Arc::new(open_archive),
+            Some((DictionaryKind::Anki, importer)),
+            None,
+            options,
+        )
+    }
+
+    /// Imports a dictionary with a specific importer, or with the importer for
+    /// whichever kind the archive is, if [`None`].
+    ///
+    /// If `replacing` is set, the new data is swapped into that dictionary once
+    /// it has been imported (see [`Engine::reimport_dictionary`]). It may then
+    /// have the same name.
+    pub(crate) fn import_with(
+        &self,
+        open_archive: Arc<dyn OpenArchive>,
+        importer: Option<(DictionaryKind, Arc<dyn ImportKind>)>,
+        replacing: Option<DictionaryId>,
+        options: ImportOptions,
     ) -> impl Stream<Item = Result<ImportEvent, ImportError>> {
         async_stream::try_stream! {
-            debug!("Attempting to determine dictionary kind");
-            let kind = kind_of(open_archive.clone())
-                .await
-                .map_err(ImportError::GetKind)?;
+            if let Some(id) = replacing {
+                if !self.dictionaries().contains_key(&id) {
+                    Err(anyhow::Error::new(NotFound))?;
+                }
+            }
+
+            let (kind, importer) = if let Some(importer) = importer {
+                importer
+            } else {
+                debug!("Attempting to determine dictionary kind");
+                let kind = kind_of(open_archive.clone())
+                    .await
+                    .map_err(ImportError::GetKind)?;
+                let importer = FORMATS.get(&kind).ok_or(ImportError::NoImporter { kind })?;
+                (kind, importer.clone())
+            };
             debug!("Importing as {kind:?} dictionary");
             yield ImportEvent::DeterminedKind(kind);
 
-            let importer = FORMATS.get(&kind).ok_or(ImportError::NoImporter { kind })?;
-            let (progress_tx, mut progress_rx) = mpsc::channel(CHANNEL_BUF_CAP);
-
-            let (meta, continue_task) = importer
-                .start_import(self.db.clone(), open_archive, progress_tx)
+            let (progress_tx, progress_rx) = mpsc::channel(CHANNEL_BUF_CAP);
+            let (mut meta, continue_task) = importer
+                .start_import(open_archive.clone(), progress_tx)
                 .await
                 .map_err(|source| ImportError::ParseMeta { kind, source })?;
+            if meta.index_hash.is_none() {
+                meta.index_hash = Some(duplicate::hash_index(&meta, ()));
+            }
             debug!(
                 "Importing {:?} dictionary {:?} version {:?}",
                 meta.kind, meta.name, meta.version
             );
-            let name = meta.name.clone();
-            yield ImportEvent::ParsedMeta(meta);
+            yield ImportEvent::ParsedMeta(meta.clone());
 
-            let already_exists = dictionary_exists_by_name(&self.db, &name)
+            let candidates = duplicate::candidates(&self.db, &meta, replacing)
                 .await
                 .context("failed to fetch if this dictionary already exists")?;
-            if already_exists {
-                Err(ImportError::AlreadyExists)?;
-                return;
+            if duplicate::needs_archive_hash(&candidates) {
+                let archive_hash = duplicate::hash_archive(&*open_archive)
+                    .await
+                    .context("failed to hash archive")?;
+                meta.archive_hash = Some(archive_hash);
+            }
+            match duplicate::find_existing(&candidates, &meta) {
+                Some(Existing::Identical(id)) => Err(ImportError::AlreadyImported { id })?,
+                Some(Existing::Revision(id)) => Err(ImportError::AlreadyExists { id })?,
+                Some(Existing::Older(id)) => Err(ImportError::NewerRevision { id })?,
+                None => {}
             }
             trace!("Dictionary does not exist yet, spawning import continuation");
 
-            let continue_task = AbortOnDropHandle::new(tokio::spawn(continue_task));
-            while let Some(progress) = progress_rx.recv().await {
-                yield ImportEvent::Progress(progress);
+            let job = ImportJob::create(&self.db, &self.insert_lock, &meta, replacing, options)
+                .await
+                .context("failed to create import job")?;
+            yield ImportEvent::JobStarted(job.id());
+
+            let events = self.run_import_job(
+                kind,
+                job,
+                open_archive,
+                meta.archive_hash,
+                continue_task,
+                progress_rx,
+            );
+            tokio::pin!(events);
+            while let Some(event) = events.next().await {
+                yield event?;
             }
+        }
+    }
 
-            let id = continue_task
+    /// Resumes an import which was interrupted, skipping the entries of the
+    /// archive which it had already imported.
+    ///
+    /// `open_archive` must be the same archive that the import was started
+    /// with, although this only checks that it parses to the same dictionary
+    /// name and version, to avoid hashing the archive again. The import keeps
+    /// the [`ImportOptions`] it was started with, but always uses the default
+    /// importer for its kind, so an Anki deck imported with
+    /// [`Engine::import_anki_deck`] will be resumed with guessed fields.
+    pub fn resume_import(
+        &self,
+        id: ImportJobId,
+        open_archive: impl OpenArchive + 'static,
+    ) -> impl Stream<Item = Result<ImportEvent, ImportError>> {
+        let open_archive = Arc::new(open_archive) as Arc<dyn OpenArchive>;
+        async_stream::try_stream! {
+            if self.import_tasks.is_running(id) {
+                Err(ImportError::AlreadyRunning)?;
+            }
+            let (job, meta) = ImportJob::load(&self.db, &self.insert_lock, id)
                 .await
-                .map_err(|source| ImportError::Import {
-                    kind,
-                    source: source.into(),
-                })?
-                .map_err(|source| ImportError::Import { kind, source })?;
+                .context("failed to load import job")?;
+            let kind = meta.kind;
+            let importer = FORMATS.get(&kind).ok_or(ImportError::NoImporter { kind })?;
+            debug!(
+                "Resuming import {id:?} of {:?} with {} entries done",
+                meta.name,
+                job.num_done()
+            );
+            yield ImportEvent::DeterminedKind(kind);
 
-            self.sync_dictionaries().await?;
-            _ = self
-                .event_tx
-                .send(EngineEvent::Dictionary(DictionaryEvent::Added { id }));
-            yield ImportEvent::Done(id);
+            let (progress_tx, progress_rx) = mpsc::channel(CHANNEL_BUF_CAP);
+            let (archive_meta, continue_task) = importer
+                .start_import(open_archive.clone(), progress_tx)
+                .await
+                .map_err(|source| ImportError::ParseMeta { kind, source })?;
+            if archive_meta.name != meta.name || archive_meta.version != meta.version {
+                Err(anyhow!(
+                    "archive is {:?} version {:?}, but this job imports {:?} version {:?}",
+                    archive_meta.name,
+                    archive_meta.version,
+                    meta.name,
+                    meta.version,
+                ))?;
+            }
+            let archive_hash = meta.archive_hash.clone();
+            yield ImportEvent::ParsedMeta(meta);
+            yield ImportEvent::JobStarted(id);
+
+            let events = self.run_import_job(
+                kind,
+                job,
+                open_archive,
+                archive_hash,
+                continue_task,
+                progress_rx,
+            );
+            tokio::pin!(events);
+            while let Some(event) = events.next().await {
+                yield event?;
+            }
         }
     }
-}
 
-async fn dictionary_exists_by_name(db: &Pool<Sqlite>, name: &str) -> Result<bool> {
-    let result = sqlx::query_scalar!(
-        "SELECT EXISTS(SELECT 1 FROM dictionary WHERE json_extract(meta, '$.name') = $1)",
-        name
-    )
-    .fetch_one(db)
-    .await?;
-    Ok(result > 0)
+    /// Runs the continuation of an import as `job` in a new task, and finishes
+    /// the import once it is done.
+    ///
+    /// If the archive hasn't been hashed yet, it is hashed in another task
+    /// alongside the import, rather than reading all of it before the import
+    /// starts.
+    ///
+    /// If the continuation fails, the job and all of its data is discarded.
+    /// If this stream is dropped, the task is stopped but the job is kept, so
+    /// it can be resumed later.
+    fn run_import_job(
+        &self,
+        kind: DictionaryKind,
+        job: ImportJob,
+        open_archive: Arc<dyn OpenArchive>,
+        archive_hash: Option<String>,
+        continue_task: ImportContinue,
+        mut progress_rx: mpsc::Receiver<ImportProgress>,
+    ) -> impl Stream<Item = Result<ImportEvent, ImportError>> {
+        async_stream::try_stream! {
+            let job_id = job.id();
+            let new_id = job.dictionary_id();
+            let replacing = job.replacing();
+
+            let hash_task = archive_hash.is_none().then(|| {
+                let db = self.db.clone();
+                let insert_lock = self.insert_lock.clone();
+                AbortOnDropHandle::new(tokio::spawn(async move {
+                    let archive_hash = duplicate::hash_archive(&*open_archive).await?;
+                    job::store_archive_hash(&db, &insert_lock, job_id, &archive_hash).await?;
+                    anyhow::Ok(archive_hash)
+                }))
+            });
+
+            let (warning_tx, mut warning_rx) = mpsc::channel(CHANNEL_BUF_CAP);
+            let job = job.with_warnings(warning_tx);
+            let continue_task = AbortOnDropHandle::new(tokio::spawn(continue_task(job)));
+            let _registered = self
+                .import_tasks
+                .register(job_id, continue_task.abort_handle())
+                .ok_or(ImportError::AlreadyRunning)?;
+            // both channels close once the task, and any tasks it spawned, are done
+            loop {
+                let event = tokio::select! {
+                    Some(progress) = progress_rx.recv() => ImportEvent::Progress(progress),
+                    Some(warning) = warning_rx.recv() => ImportEvent::Warning(warning),
+                    else => break,
+                };
+                yield event;
+            }
+
+            let source = match continue_task.await {
+                Ok(Ok(())) => None,
+                // `cancel_import` has already discarded the job
+                Err(err) if err.is_cancelled() => Err(ImportError::Cancelled)?,
+                Err(err) => Some(anyhow::Error::new(err)),
+                Ok(Err(err)) => Some(err),
+            };
+            if let Some(source) = source {
+                if let Err(err) = self.discard_import_job(job_id).await {
+                    warn!("Failed to discard failed import {job_id:?}: {err:?}");
+                }
+                Err(ImportError::Import { kind, source })?;
+            }
+
+            // the job has already swapped the new data in, unless the
+            // dictionary we're replacing was removed in the meantime
+            let swapped_into = replacing.filter(|id| self.dictionaries().contains_key(id));
+            if let Some(hash_task) = hash_task {
+                let id = swapped_into.unwrap_or(new_id);
+                // the data is already imported, so if we can't hash the
+                // archive, we just won't know if the same one is imported again
+                match hash_task.await.map_err(anyhow::Error::new) {
+                    Ok(Ok(archive_hash)) => {
+                        duplicate::store_archive_hash(&self.db, &self.insert_lock, id, &archive_hash)
+                            .await?;
+                    }
+                    Ok(Err(err)) | Err(err) => {
+                        warn!("Failed to hash archive of {job_id:?}: {err:?}");
+                    }
+                }
+            }
+
+            self.sync_dictionaries().await?;
+            if let Some(id) = swapped_into {
+                self.vacuum().await?;
+                _ = self
+                    .event_tx
+                    .send(EngineEvent::Dictionary(DictionaryEvent::Updated { id }));
+                yield ImportEvent::Done(id);
+            } else {
+                _ = self
+                    .event_tx
+                    .send(EngineEvent::Dictionary(DictionaryEvent::Added { id: new_id }));
+                yield ImportEvent::Done(new_id);
+            }
+        }
+    }
 }
 
 async fn insert_dictionary(
@@ -281,6 +619,41 @@ const _: () = {
         fn open_archive_file(&self) -> FfiResult<RawFd>;
 
         fn on_event(&self, event: ImportEvent) -> FfiResult<()>;
+
+        /// Called when this archive fails to import as part of
+        /// [`Wordbase::import_dictionaries`].
+        ///
+        /// Single imports return the error instead of calling this.
+        fn on_failed(&self, error: String) -> FfiResult<()>;
+    }
+
+    fn callback_archive(callback: Arc<dyn ImportDictionaryCallback>) -> impl OpenArchive + 'static {
+        move || {
+            let callback = callback.clone();
+            async move {
+                let fd = callback.open_archive_file()?;
+                // SAFETY: it is the FFI caller's responsibility
+                // to ensure that this fd is valid and open
+                let file = unsafe { File::from_raw_fd(fd) };
+                Ok(Box::new(BufReader::new(file)) as Box<dyn Archive>)
+            }
+        }
+    }
+
+    async fn forward_events(
+        callback: Arc<dyn ImportDictionaryCallback>,
+        events: impl Stream<Item = Result<ImportEvent, ImportError>>,
+    ) -> FfiResult<DictionaryId> {
+        tokio::pin!(events);
+        while let Some(event) = events.try_next().await.map_err(anyhow::Error::new)? {
+            match event {
+                ImportEvent::Done(id) => return Ok(id),
+                event => {
+                    _ = callback.on_event(event);
+                }
+            }
+        }
+        unreachable!();
     }
 
     #[uniffi::export(async_runtime = "tokio")]
@@ -289,29 +662,113 @@ const _: () = {
             &self,
             callback: Arc<dyn ImportDictionaryCallback>,
         ) -> FfiResult<DictionaryId> {
-            let events = self.0.import_dictionary({
-                let callback = callback.clone();
-                move || {
-                    let callback = callback.clone();
-                    async move {
-                        let fd = callback.open_archive_file()?;
-                        // SAFETY: it is the FFI caller's responsibility
-                        // to ensure that this fd is valid and open
-                        let file = unsafe { File::from_raw_fd(fd) };
-                        Ok(Box::new(BufReader::new(file)) as Box<dyn Archive>)
-                    }
-                }
+            let events = self.0.import_dictionary(callback_archive(callback.clone()));
+            forward_events(callback, events).await
+        }
+
+        pub async fn import_dictionary_with_options(
+            &self,
+            callback: Arc<dyn ImportDictionaryCallback>,
+            options: ImportOptions,
+        ) -> FfiResult<DictionaryId> {
+            let events = self
+                .0
+                .import_dictionary_with_options(callback_archive(callback.clone()), options);
+            forward_events(callback, events).await
+        }
+
+        pub async fn reimport_dictionary(
+            &self,
+            id: DictionaryId,
+            callback: Arc<dyn ImportDictionaryCallback>,
+            options: ImportOptions,
+        ) -> FfiResult<DictionaryId> {
+            let events =
+                self.0
+                    .reimport_dictionary(id, callback_archive(callback.clone()), options);
+            forward_events(callback, events).await
+        }
+
+        pub async fn import_anki_deck(
+            &self,
+            callback: Arc<dyn ImportDictionaryCallback>,
+            fields: AnkiFields,
+            options: ImportOptions,
+        ) -> FfiResult<DictionaryId> {
+            let events =
+                self.0
+                    .import_anki_deck(callback_archive(callback.clone()), fields, options);
+            forward_events(callback, events).await
+        }
+
+        pub async fn resume_import(
+            &self,
+            id: ImportJobId,
+            callback: Arc<dyn ImportDictionaryCallback>,
+        ) -> FfiResult<DictionaryId> {
+            let events = self.0.resume_import(id, callback_archive(callback.clone()));
+            forward_events(callback, events).await
+        }
+
+        /// Imports many dictionaries at once, sending the events of each
+        /// import to its own callback.
+        ///
+        /// Returns the ID of each imported dictionary, or [`None`] if that
+        /// import failed.
+        pub async fn import_dictionaries(
+            &self,
+            callbacks: Vec<Arc<dyn ImportDictionaryCallback>>,
+            options: ImportOptions,
+        ) -> Vec<Option<DictionaryId>> {
+            let archives = callbacks.iter().map(|callback| {
+                Arc::new(callback_archive(callback.clone())) as Arc<dyn OpenArchive>
             });
+            let events = self.0.import_dictionaries(archives, options);
             tokio::pin!(events);
-            while let Some(event) = events.try_next().await.map_err(anyhow::Error::new)? {
+            let mut ids = vec![None; callbacks.len()];
+            while let Some(event) = events.next().await {
                 match event {
-                    ImportEvent::Done(id) => return Ok(id),
-                    event => {
-                        _ = callback.on_event(event);
+                    ImportQueueEvent::Job { index, event, .. } => {
+                        let Ok(index) = usize::try_from(index) else {
+                            continue;
+                        };
+                        match event {
+                            ImportEvent::Done(id) => ids[index] = Some(id),
+                            event => {
+                                _ = callbacks[index].on_event(event);
+                            }
+                        }
                     }
+                    ImportQueueEvent::Failed { index, error, .. } => {
+                        let Ok(index) = usize::try_from(index) else {
+                            continue;
+                        };
+                        _ = callbacks[index].on_failed(error);
+                    }
+                    ImportQueueEvent::Started { .. }
+                    | ImportQueueEvent::Progress { .. }
+                    | ImportQueueEvent::Done { .. } => {}
                 }
             }
-            unreachable!();
+            ids
+        }
+
+        pub async fn validate_dictionary(
+            &self,
+            callback: Arc<dyn ImportDictionaryCallback>,
+        ) -> FfiResult<ValidationReport> {
+            Ok(self
+                .0
+                .validate_dictionary(callback_archive(callback))
+                .await
+                .map_err(anyhow::Error::new)?)
+        }
+
+        pub async fn anki_note_types(
+            &self,
+            callback: Arc<dyn ImportDictionaryCallback>,
+        ) -> FfiResult<Vec<AnkiNoteType>> {
+            Ok(anki_note_types(&callback_archive(callback)).await?)
         }
     }
 };
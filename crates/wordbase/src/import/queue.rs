@@ -0,0 +1,246 @@
+//! Importing many dictionaries at once.
+//!
+//! Imports in a batch run concurrently, so that while one import is inserting
+//! its data, the others can read and parse their archives. Only inserting is
+//! done one import at a time - see [`ImportJob::begin`].
+//!
+//! [`ImportJob::begin`]: super::ImportJob::begin
+
+use {
+    super::{ImportEvent, ImportOptions, OpenArchive},
+    crate::{Engine, EngineEvent},
+    futures::{Stream, StreamExt, stream},
+    serde::{Deserialize, Serialize},
+    std::sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+/// Maximum number of imports in a batch which run at once.
+///
+/// Each running import keeps some of its parsed archive in memory, so we
+/// don't want to run all of them at once.
+const MAX_CONCURRENT_IMPORTS: usize = 4;
+
+static NEXT_BATCH_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Opaque and unique identifier for a batch of imports started by
+/// [`Engine::import_dictionaries`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ImportBatchId(pub u64);
+
+#[cfg(feature = "uniffi")]
+uniffi::custom_newtype!(ImportBatchId, u64);
+
+/// Event of a batch of imports started by [`Engine::import_dictionaries`].
+///
+/// Archives in the batch are identified by their `index` in the list of
+/// archives which the batch was started with.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+pub enum ImportQueueEvent {
+    /// Batch has been queued, and will import `total` archives.
+    Started { batch: ImportBatchId, total: u64 },
+    /// Import of a single archive sent an event.
+    ///
+    /// Once an archive has been imported, this is sent with
+    /// [`ImportEvent::Done`].
+    Job {
+        batch: ImportBatchId,
+        index: u64,
+        event: ImportEvent,
+    },
+    /// Import of a single archive failed.
+    Failed {
+        batch: ImportBatchId,
+        index: u64,
+        error: String,
+    },
+    /// Progress of the whole batch, where `done` archives have either been
+    /// imported or failed.
+    Progress {
+        batch: ImportBatchId,
+        frac: f64,
+        done: u64,
+        total: u64,
+    },
+    /// All archives in the batch have either been imported or failed.
+    Done {
+        batch: ImportBatchId,
+        imported: u64,
+        failed: u64,
+    },
+}
+
+impl Engine {
+    /// Imports many dictionaries at once, using the importer for whichever
+    /// kind each archive is.
+    ///
+    /// A few archives are read and parsed in parallel, but only one import
+    /// inserts into the database at a time. If one import fails, the rest of
+    /// the batch carries on.
+    ///
+    /// Each event is yielded from this stream, and also sent as
+    /// [`EngineEvent::Import`], so that every listener can show the progress
+    /// of the batch. If this stream is dropped, all running imports in the
+    /// batch are stopped, but their jobs are kept so that they can be resumed.
+    pub fn import_dictionaries(
+        &self,
+        archives: impl IntoIterator<Item = Arc<dyn OpenArchive>>,
+        options: ImportOptions,
+    ) -> impl Stream<Item = ImportQueueEvent> {
+        let archives = archives.into_iter().collect::<Vec<_>>();
+        let batch = ImportBatchId(NEXT_BATCH_ID.fetch_add(1, Ordering::Relaxed));
+        async_stream::stream! {
+            let total = archives.len() as u64;
+            yield self.send_queue_event(ImportQueueEvent::Started { batch, total });
+
+            let mut fracs = vec![0.0; archives.len()];
+            let (mut imported, mut failed) = (0, 0);
+            // imports don't start until they're polled, so we can create all
+            // of them up front
+            let imports = archives
+                .into_iter()
+                .enumerate()
+                .map(|(index, open_archive)| {
+                    let events = self.import_with(open_archive, None, None, options);
+                    Box::pin(events.map(move |event| (index, event)))
+                })
+                .collect::<Vec<_>>();
+            let events = stream::iter(imports).flatten_unordered(MAX_CONCURRENT_IMPORTS);
+            tokio::pin!(events);
+            while let Some((index, event)) = events.next().await {
+                let (event, progressed) = match event {
+                    Ok(event) => {
+                        let progressed = match &event {
+                            ImportEvent::Progress(progress) => {
+                                fracs[index] = progress.frac;
+                                true
+                            }
+                            ImportEvent::Done(_) => {
+                                fracs[index] = 1.0;
+                                imported += 1;
+                                true
+                            }
+                            _ => false,
+                        };
+                        let event = ImportQueueEvent::Job {
+                            batch,
+                            index: index as u64,
+                            event,
+                        };
+                        (event, progressed)
+                    }
+                    Err(err) => {
+                        fracs[index] = 1.0;
+                        failed += 1;
+                        let event = ImportQueueEvent::Failed {
+                            batch,
+                            index: index as u64,
+                            error: format!("{:?}", anyhow::Error::new(err)),
+                        };
+                        (event, true)
+                    }
+                };
+                yield self.send_queue_event(event);
+
+                if progressed {
+                    let frac = fracs.iter().sum::<f64>() / fracs.len() as f64;
+                    yield self.send_queue_event(ImportQueueEvent::Progress {
+                        batch,
+                        frac,
+                        done: imported + failed,
+                        total,
+                    });
+                }
+            }
+
+            yield self.send_queue_event(ImportQueueEvent::Done {
+                batch,
+                imported,
+                failed,
+            });
+        }
+    }
+
+    fn send_queue_event(&self, event: ImportQueueEvent) -> ImportQueueEvent {
+        _ = self.event_tx.send(EngineEvent::Import(event.clone()));
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::test_util::write_yomitan_zip, serde_json::json,
+        tokio::sync::broadcast::error::TryRecvError,
+    };
+
+    #[tokio::test]
+    async fn batch_continues_after_failure() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let engine = Engine::new(data_dir.path()).await.unwrap();
+        let mut archives = Vec::new();
+        for title in ["First", "Second", "Third"] {
+            let path = data_dir.path().join(format!("{title}.zip"));
+            write_yomitan_zip(
+                &path,
+                &json!({ "title": title, "revision": "1", "format": 3 }),
+                &[json!([["犬", "いぬ", "", "", 0, ["dog"], 1, ""]])],
+            )
+            .await;
+            archives.push(Arc::new(Arc::new(path)) as Arc<dyn OpenArchive>);
+        }
+        let bad_path = data_dir.path().join("bad.zip");
+        tokio::fs::write(&bad_path, b"not a dictionary")
+            .await
+            .unwrap();
+        archives.insert(1, Arc::new(Arc::new(bad_path)));
+
+        let mut engine_events = engine.event_rx();
+        let events = engine
+            .import_dictionaries(archives, ImportOptions::default())
+            .collect::<Vec<_>>()
+            .await;
+
+        let failed = events
+            .iter()
+            .filter_map(|event| match event {
+                ImportQueueEvent::Failed { index, .. } => Some(*index),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(vec![1], failed);
+        assert!(matches!(
+            events.last(),
+            Some(ImportQueueEvent::Done {
+                imported: 3,
+                failed: 1,
+                ..
+            })
+        ));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            ImportQueueEvent::Progress { done: 4, total: 4, frac, .. } if (frac - 1.0).abs() < f64::EPSILON
+        )));
+        assert_eq!(3, engine.dictionaries().len());
+
+        // the channel is too small to hold every event, but holds the last one
+        let mut last_event = None;
+        loop {
+            match engine_events.try_recv() {
+                Ok(event) => last_event = Some(event),
+                Err(TryRecvError::Lagged(_)) => {}
+                Err(_) => break,
+            }
+        }
+        assert!(matches!(
+            last_event,
+            Some(EngineEvent::Import(ImportQueueEvent::Done {
+                imported: 3,
+                ..
+            }))
+        ));
+    }
+}
@@ -0,0 +1,194 @@
+//! Opening archives which may be bare files, gzipped files, or zips.
+//!
+//! Many formats are distributed in more than one of these forms (e.g.
+//! `JMdict_e` and `JMdict_e.gz`, or a `.dsl` file and a zip of it along with
+//! its media), so importers use these to read an archive without caring which
+//! form it came in.
+
+use {
+    super::{Archive, OpenArchive, count::Count},
+    anyhow::{Context, Result},
+    async_compression::futures::bufread::GzipDecoder,
+    async_zip::base::read::seek::ZipFileReader,
+    futures::{AsyncBufRead, AsyncBufReadExt as _, AsyncReadExt as _},
+    std::{
+        io::SeekFrom,
+        sync::{Arc, atomic::AtomicU64},
+    },
+    tokio::io::{AsyncBufReadExt as _, AsyncSeekExt as _},
+    tokio_util::compat::{Compat, TokioAsyncReadCompatExt},
+};
+
+pub const ZIP_MAGIC: [u8; 4] = [0x50, 0x4b, 0x03, 0x04];
+
+pub const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// How many bytes of the (decompressed) document we read to find the header
+/// which identifies its format.
+pub const VALIDATE_LEN: u64 = 4096;
+
+pub type ZipArchive = ZipFileReader<Compat<Box<dyn Archive>>>;
+
+/// Archive which is either a single bare (and possibly gzipped) file, or a zip
+/// of files.
+pub enum Source {
+    Bare(Box<dyn Archive>),
+    Zip(ZipArchive),
+}
+
+pub async fn open_source(open_archive: &dyn OpenArchive) -> Result<Source> {
+    let mut archive = open_archive
+        .open_archive()
+        .await
+        .context("failed to open archive")?;
+    let magic = archive
+        .fill_buf()
+        .await
+        .context("failed to read start of archive")?;
+
+    if magic.starts_with(&ZIP_MAGIC) {
+        let archive = ZipFileReader::with_tokio(archive)
+            .await
+            .context("failed to open zip archive")?;
+        Ok(Source::Zip(archive))
+    } else {
+        Ok(Source::Bare(archive))
+    }
+}
+
+/// Opens an archive which must be a zip.
+pub async fn open_zip(open_archive: &dyn OpenArchive) -> Result<ZipArchive> {
+    let archive = open_archive
+        .open_archive()
+        .await
+        .context("failed to open archive")?;
+    ZipFileReader::with_tokio(archive)
+        .await
+        .context("failed to open zip archive")
+}
+
+/// Lists the index and path of each file in the zip, skipping directories and
+/// paths which aren't UTF-8.
+pub fn zip_files(archive: &ZipArchive) -> impl Iterator<Item = (usize, &str)> {
+    archive
+        .file()
+        .entries()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| {
+            if entry.dir().unwrap_or(true) {
+                return None;
+            }
+            Some((index, entry.filename().as_str().ok()?))
+        })
+}
+
+pub async fn read_zip_entry(archive: &mut ZipArchive, index: usize, path: &str) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    archive
+        .reader_with_entry(index)
+        .await
+        .with_context(|| format!("failed to start reading `{path}`"))?
+        .read_to_end_checked(&mut data)
+        .await
+        .with_context(|| format!("failed to read `{path}`"))?;
+    Ok(data)
+}
+
+/// Reads a bare file, decompressing it if it is gzipped, and reading at most
+/// `limit` bytes.
+pub async fn read_bare(mut archive: Box<dyn Archive>, limit: u64) -> Result<Vec<u8>> {
+    let magic = archive
+        .fill_buf()
+        .await
+        .context("failed to read start of file")?;
+    let mut data = Vec::new();
+    if magic.starts_with(&GZIP_MAGIC) {
+        GzipDecoder::new(archive.compat())
+            .take(limit)
+            .read_to_end(&mut data)
+            .await
+            .context("failed to decompress file")?;
+    } else {
+        archive
+            .compat()
+            .take(limit)
+            .read_to_end(&mut data)
+            .await
+            .context("failed to read file")?;
+    }
+    Ok(data)
+}
+
+/// Decompresses a gzipped (or dictzip `.dz`, which is compatible with gzip)
+/// file which has already been read into memory.
+pub async fn gunzip(data: &[u8]) -> Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    GzipDecoder::new(data)
+        .read_to_end(&mut decompressed)
+        .await?;
+    Ok(decompressed)
+}
+
+/// Opens the archive for reading from the start, counting how many bytes have
+/// been read from it.
+///
+/// Along with the reader, this returns the count of bytes read so far and the
+/// total length of the archive, so that progress can be reported while
+/// streaming it.
+pub async fn counted_reader(
+    open_archive: &dyn OpenArchive,
+) -> Result<(Count<Compat<Box<dyn Archive>>>, Arc<AtomicU64>, u64)> {
+    let mut archive = open_archive
+        .open_archive()
+        .await
+        .context("failed to open archive")?;
+    let buf_len = archive
+        .seek(SeekFrom::End(0))
+        .await
+        .context("failed to seek to end")?;
+    archive
+        .seek(SeekFrom::Start(0))
+        .await
+        .context("failed to seek to start")?;
+
+    let count = Count::new(archive.compat());
+    let cursor_pos = count.pos();
+    Ok((count, cursor_pos, buf_len))
+}
+
+pub type DocumentReader = Box<dyn AsyncBufRead + Send + Unpin>;
+
+/// Opens the archive as a single document, transparently decompressing it if
+/// it is gzipped.
+///
+/// See [`counted_reader`] for the other values returned.
+pub async fn document_reader(
+    open_archive: &dyn OpenArchive,
+) -> Result<(DocumentReader, Arc<AtomicU64>, u64)> {
+    let (mut count, cursor_pos, buf_len) = counted_reader(open_archive).await?;
+    let is_gzip = count
+        .fill_buf()
+        .await
+        .context("failed to read start of archive")?
+        .starts_with(&GZIP_MAGIC);
+    let reader: DocumentReader = if is_gzip {
+        Box::new(futures::io::BufReader::new(GzipDecoder::new(count)))
+    } else {
+        Box::new(count)
+    };
+    Ok((reader, cursor_pos, buf_len))
+}
+
+/// Reads the first [`VALIDATE_LEN`] bytes of the document, decompressing it
+/// if needed, to check which format it is.
+pub async fn read_document_start(open_archive: &dyn OpenArchive) -> Result<String> {
+    let (reader, _, _) = document_reader(open_archive).await?;
+    let mut start = Vec::new();
+    reader
+        .take(VALIDATE_LEN)
+        .read_to_end(&mut start)
+        .await
+        .context("failed to read start of document")?;
+    Ok(String::from_utf8_lossy(&start).into_owned())
+}
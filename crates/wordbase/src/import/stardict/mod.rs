@@ -0,0 +1,364 @@
+mod parse;
+
+use {
+    super::{
+        ImportContinue, ImportKind, ImportProgress, OpenArchive,
+        source::{self, GZIP_MAGIC, Source},
+    },
+    crate::import::{Batches, continue_with, insert::Inserter},
+    anyhow::{Context, Result, bail},
+    async_compression::futures::bufread::{GzipDecoder, XzDecoder},
+    futures::{AsyncRead, AsyncReadExt as _, StreamExt, future::BoxFuture},
+    std::{iter, path::Path, sync::Arc},
+    tokio::{io::AsyncBufReadExt as _, sync::mpsc},
+    tokio_util::compat::TokioAsyncReadCompatExt,
+    tracing::debug,
+    wordbase_api::{DictionaryKind, DictionaryMeta, Term, dict::stardict::Definition},
+};
+
+pub struct Stardict;
+
+impl ImportKind for Stardict {
+    fn is_of_kind(&self, open_archive: Arc<dyn OpenArchive>) -> BoxFuture<'_, Result<()>> {
+        Box::pin(validate(open_archive))
+    }
+
+    fn start_import(
+        &self,
+        open_archive: Arc<dyn OpenArchive>,
+        progress_tx: mpsc::Sender<ImportProgress>,
+    ) -> BoxFuture<Result<(DictionaryMeta, ImportContinue)>> {
+        Box::pin(start_import(open_archive, progress_tx))
+    }
+}
+
+const XZ_MAGIC: [u8; 6] = [0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00];
+
+/// When checking if a tar archive is a StarDict dictionary, how many entries
+/// we look at before giving up.
+///
+/// Unlike zips, tars have no central directory, so finding a file means
+/// reading (and decompressing) everything before it. StarDict archives only
+/// contain a handful of files, so we don't need to look far - and this avoids
+/// reading the entirety of large, unrelated tars.
+const TAR_PROBE_ENTRIES: usize = 32;
+
+const RECORDS_PER_PROGRESS: usize = 1000;
+
+const RECORDS_PER_BATCH: usize = 5000;
+
+/// Which files of the archive to read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Read {
+    /// Only find the paths of the files, leaving the contents empty.
+    Paths,
+    /// Only find and read the first `.ifo` file.
+    Ifo,
+    /// Read the paths and contents of all files.
+    Contents,
+}
+
+impl Read {
+    const fn wants(self, kind: FileKind) -> bool {
+        !matches!(self, Self::Ifo) || matches!(kind, FileKind::Ifo)
+    }
+
+    const fn reads_contents(self) -> bool {
+        !matches!(self, Self::Paths)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    Ifo,
+    Idx,
+    Syn,
+    Dict,
+}
+
+/// Determines what StarDict file this path is, and if it is compressed
+/// (`.gz` or dictzip `.dz`).
+fn file_kind(path: &str) -> Option<(FileKind, bool)> {
+    let ext = |path: &Path| path.extension()?.to_str().map(str::to_ascii_lowercase);
+    let path = Path::new(path);
+    let (path, compressed) = match ext(path)?.as_str() {
+        "gz" | "dz" => (Path::new(path.file_stem()?), true),
+        _ => (path, false),
+    };
+    let kind = match ext(path)?.as_str() {
+        "ifo" => FileKind::Ifo,
+        "idx" => FileKind::Idx,
+        "syn" => FileKind::Syn,
+        "dict" => FileKind::Dict,
+        _ => return None,
+    };
+    Some((kind, compressed))
+}
+
+/// Reads all StarDict files out of a zip or (optionally compressed) tar.
+async fn read_files(open_archive: &dyn OpenArchive, read: Read) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut files = Vec::new();
+    let mut archive = match source::open_source(open_archive).await? {
+        Source::Zip(mut archive) => {
+            let paths = source::zip_files(&archive)
+                .filter(|(_, path)| file_kind(path).is_some_and(|(kind, _)| read.wants(kind)))
+                .map(|(index, path)| (index, path.to_owned()))
+                .collect::<Vec<_>>();
+            for (index, path) in paths {
+                let data = if read.reads_contents() {
+                    source::read_zip_entry(&mut archive, index, &path).await?
+                } else {
+                    Vec::new()
+                };
+                files.push((path, data));
+                if read == Read::Ifo {
+                    break;
+                }
+            }
+            return Ok(files);
+        }
+        Source::Bare(archive) => archive,
+    };
+
+    let magic = archive
+        .fill_buf()
+        .await
+        .context("failed to read start of archive")?;
+    let reader: Box<dyn AsyncRead + Send + Unpin> = if magic.starts_with(&GZIP_MAGIC) {
+        Box::new(GzipDecoder::new(archive.compat()))
+    } else if magic.starts_with(&XZ_MAGIC) {
+        Box::new(XzDecoder::new(archive.compat()))
+    } else {
+        Box::new(archive.compat())
+    };
+    let mut entries = async_tar::Archive::new(reader)
+        .entries()
+        .context("failed to read archive entries")?;
+    let mut num_entries = 0usize;
+    while let Some(entry) = entries.next().await {
+        num_entries += 1;
+        if read == Read::Paths && num_entries > TAR_PROBE_ENTRIES {
+            break;
+        }
+
+        let mut entry = entry.context("failed to read archive entry")?;
+        let path = entry.path().context("failed to read entry file path")?;
+        let path = path
+            .to_str()
+            .with_context(|| format!("path {path:?} is not UTF-8"))?
+            .to_owned();
+        if !file_kind(&path).is_some_and(|(kind, _)| read.wants(kind)) {
+            continue;
+        }
+
+        let mut data = Vec::new();
+        if read.reads_contents() {
+            entry
+                .read_to_end(&mut data)
+                .await
+                .with_context(|| format!("failed to read `{path}`"))?;
+        }
+        files.push((path, data));
+        if read == Read::Ifo {
+            break;
+        }
+    }
+    Ok(files)
+}
+
+/// Decompresses the file if it is `.gz` or dictzip `.dz`.
+async fn decompress(path: &str, data: Vec<u8>, compressed: bool) -> Result<Vec<u8>> {
+    if compressed {
+        // `.dz` is dictzip, which is compatible with gzip
+        source::gunzip(&data)
+            .await
+            .with_context(|| format!("failed to decompress `{path}`"))
+    } else {
+        Ok(data)
+    }
+}
+
+/// Files of a single StarDict dictionary, with compressed files already
+/// decompressed.
+#[derive(Debug)]
+struct Files {
+    idx: Vec<u8>,
+    syn: Option<Vec<u8>>,
+    dict: Vec<u8>,
+}
+
+async fn take_files(files: Vec<(String, Vec<u8>)>) -> Result<Files> {
+    let mut has_ifo = false;
+    let mut idx = None;
+    let mut syn = None;
+    let mut dict = None;
+    for (path, data) in files {
+        let Some((kind, compressed)) = file_kind(&path) else {
+            continue;
+        };
+        let data = decompress(&path, data, compressed).await?;
+        match kind {
+            FileKind::Ifo => {
+                if has_ifo {
+                    bail!("archive contains multiple dictionaries");
+                }
+                has_ifo = true;
+            }
+            FileKind::Idx => idx = Some(data),
+            FileKind::Syn => syn = Some(data),
+            FileKind::Dict => dict = Some(data),
+        }
+    }
+
+    if !has_ifo {
+        bail!("no `.ifo` file in archive");
+    }
+    Ok(Files {
+        idx: idx.context("no `.idx` file in archive")?,
+        syn,
+        dict: dict.context("no `.dict` file in archive")?,
+    })
+}
+
+async fn validate(open_archive: Arc<dyn OpenArchive>) -> Result<()> {
+    let files = read_files(&*open_archive, Read::Paths).await?;
+    let has = |kind: FileKind| {
+        files
+            .iter()
+            .any(|(path, _)| file_kind(path).is_some_and(|(k, _)| k == kind))
+    };
+    if !has(FileKind::Ifo) {
+        bail!("no `.ifo` file in archive");
+    }
+    if !has(FileKind::Idx) {
+        bail!("no `.idx` file in archive");
+    }
+    Ok(())
+}
+
+async fn start_import(
+    open_archive: Arc<dyn OpenArchive>,
+    progress_tx: mpsc::Sender<ImportProgress>,
+) -> Result<(DictionaryMeta, ImportContinue)> {
+    // only the `.ifo` is needed for the meta - the rest of the archive, which
+    // can be large, is read once the import actually runs
+    let (ifo_path, ifo) = read_files(&*open_archive, Read::Ifo)
+        .await?
+        .into_iter()
+        .next()
+        .context("no `.ifo` file in archive")?;
+    let compressed = file_kind(&ifo_path).is_some_and(|(_, compressed)| compressed);
+    let ifo = decompress(&ifo_path, ifo, compressed).await?;
+    let ifo = String::from_utf8(ifo).context("`.ifo` is not UTF-8")?;
+    let ifo = parse::ifo(&ifo).context("failed to parse `.ifo`")?;
+
+    let mut meta = DictionaryMeta::new(DictionaryKind::Stardict, ifo.bookname.clone());
+    ifo.date.clone_into(&mut meta.version);
+    ifo.description.clone_into(&mut meta.description);
+    ifo.website.clone_into(&mut meta.url);
+    meta.attribution = match (&ifo.author, &ifo.email) {
+        (Some(author), Some(email)) => Some(format!("{author} <{email}>")),
+        (Some(author), None) => Some(author.clone()),
+        (None, Some(email)) => Some(email.clone()),
+        (None, None) => None,
+    };
+
+    Ok((
+        meta,
+        continue_with(move |mut job| async move {
+            let files = read_files(&*open_archive, Read::Contents).await?;
+            let files = take_files(files).await?;
+
+            let idx = parse::idx(&files.idx, ifo.idxoffsetbits.unwrap_or(32))
+                .context("failed to parse `.idx`")?;
+            let syn = files
+                .syn
+                .as_deref()
+                .map(parse::syn)
+                .transpose()
+                .context("failed to parse `.syn`")?
+                .unwrap_or_default();
+            debug!("{} words, {} synonyms", idx.len(), syn.len());
+
+            // synonyms are inserted along with the word they point to, so that
+            // each batch of words doesn't depend on the records of other batches
+            let mut synonyms = vec![Vec::new(); idx.len()];
+            for entry in syn {
+                usize::try_from(entry.index)
+                    .ok()
+                    .and_then(|index| synonyms.get_mut(index))
+                    .with_context(|| {
+                        format!(
+                            "synonym `{}` points to invalid index {}",
+                            entry.word, entry.index
+                        )
+                    })?
+                    .push(entry.word);
+            }
+
+            let import = async |insert: &mut Inserter<'_, '_>, (word, record, terms)| {
+                import_definition(insert, &record, terms)
+                    .await
+                    .with_context(|| format!("failed to import `{word}`"))
+            };
+
+            let num_words = idx.len();
+            let mut batches = Batches::new("records", RECORDS_PER_BATCH);
+            for (index, (entry, synonyms)) in idx.into_iter().zip(synonyms).enumerate() {
+                let record = definition(&files.dict, &entry, ifo.sametypesequence.as_deref())
+                    .with_context(|| format!("failed to import `{}`", entry.word))?;
+                let terms = iter::once(entry.word)
+                    .chain(synonyms)
+                    .filter_map(Term::from_headword)
+                    .collect::<Vec<_>>();
+                let row = (entry.word.to_owned(), record, terms);
+                if let Some(batch) = batches.push(row) {
+                    job.insert_batch(batch, import).await?;
+                }
+
+                if index % RECORDS_PER_PROGRESS == 0 {
+                    let frac = (index as f64) / (num_words as f64);
+                    _ = progress_tx.try_send(ImportProgress { frac });
+                }
+            }
+            job.insert_batch(batches.finish(), import).await?;
+
+            let tx = job.begin().await?;
+            job.finish(tx).await
+        }),
+    ))
+}
+
+/// Parses the definition of an `.idx` entry out of the `.dict`.
+fn definition(
+    dict: &[u8],
+    entry: &parse::IdxEntry<'_>,
+    sametypesequence: Option<&str>,
+) -> Result<Definition> {
+    let data = usize::try_from(entry.offset)
+        .ok()
+        .and_then(|start| {
+            let end = start.checked_add(usize::try_from(entry.size).ok()?)?;
+            dict.get(start..end)
+        })
+        .context("entry extends past end of `.dict`")?;
+    parse::definition(data, sametypesequence).context("failed to parse definition")
+}
+
+async fn import_definition(
+    insert: &mut Inserter<'_, '_>,
+    record: &Definition,
+    terms: Vec<Term>,
+) -> Result<()> {
+    let record_id = insert
+        .record(record)
+        .await
+        .context("failed to insert record")?;
+    for term in terms {
+        insert
+            .term_record(term, record_id)
+            .await
+            .context("failed to insert term record")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,248 @@
+//! Parsers for the individual files of a StarDict dictionary.
+//!
+//! See the [StarDict file format].
+//!
+//! [StarDict file format]: https://github.com/huzheng001/stardict-3/blob/master/dict/doc/StarDictFileFormat
+
+use {
+    anyhow::{Context, Result, bail},
+    wordbase_api::dict::stardict::{Definition, DefinitionPart},
+};
+
+const IFO_MAGIC: &str = "StarDict's dict ifo file";
+
+#[derive(Debug, Default)]
+pub struct Ifo {
+    pub bookname: String,
+    pub wordcount: Option<usize>,
+    pub synwordcount: Option<usize>,
+    pub idxoffsetbits: Option<u32>,
+    pub author: Option<String>,
+    pub email: Option<String>,
+    pub website: Option<String>,
+    pub description: Option<String>,
+    pub date: Option<String>,
+    pub sametypesequence: Option<String>,
+}
+
+pub fn ifo(text: &str) -> Result<Ifo> {
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) != Some(IFO_MAGIC) {
+        bail!("missing `{IFO_MAGIC}` header");
+    }
+
+    let mut ifo = Ifo::default();
+    for line in lines {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().to_owned();
+        match key.trim() {
+            "bookname" => ifo.bookname = value,
+            "wordcount" => {
+                ifo.wordcount = Some(value.parse().context("invalid `wordcount`")?);
+            }
+            "synwordcount" => {
+                ifo.synwordcount = Some(value.parse().context("invalid `synwordcount`")?);
+            }
+            "idxoffsetbits" => {
+                ifo.idxoffsetbits = Some(value.parse().context("invalid `idxoffsetbits`")?);
+            }
+            "author" => ifo.author = Some(value),
+            "email" => ifo.email = Some(value),
+            "website" => ifo.website = Some(value),
+            "description" => ifo.description = Some(value),
+            "date" => ifo.date = Some(value),
+            "sametypesequence" => ifo.sametypesequence = Some(value),
+            _ => {}
+        }
+    }
+
+    if ifo.bookname.is_empty() {
+        bail!("missing `bookname`");
+    }
+    Ok(ifo)
+}
+
+#[derive(Debug)]
+pub struct IdxEntry<'a> {
+    pub word: &'a str,
+    pub offset: u64,
+    pub size: u32,
+}
+
+pub fn idx(mut data: &[u8], offset_bits: u32) -> Result<Vec<IdxEntry<'_>>> {
+    let mut entries = Vec::new();
+    while !data.is_empty() {
+        let word = take_str(&mut data).context("invalid word")?;
+        let offset = match offset_bits {
+            32 => u64::from(take_u32(&mut data).context("invalid offset")?),
+            64 => take_u64(&mut data).context("invalid offset")?,
+            _ => bail!("unsupported `idxoffsetbits` {offset_bits}"),
+        };
+        let size = take_u32(&mut data).context("invalid size")?;
+        entries.push(IdxEntry { word, offset, size });
+    }
+    Ok(entries)
+}
+
+#[derive(Debug)]
+pub struct SynEntry<'a> {
+    pub word: &'a str,
+    /// Index of the word in the `.idx` file that this is a synonym of.
+    pub index: u32,
+}
+
+pub fn syn(mut data: &[u8]) -> Result<Vec<SynEntry<'_>>> {
+    let mut entries = Vec::new();
+    while !data.is_empty() {
+        let word = take_str(&mut data).context("invalid word")?;
+        let index = take_u32(&mut data).context("invalid index")?;
+        entries.push(SynEntry { word, index });
+    }
+    Ok(entries)
+}
+
+/// Parses a single entry from the `.dict` file.
+///
+/// If `sametypesequence` is set, each field's type is not stored in the data,
+/// and the last field is not terminated - it extends to the end of the entry.
+pub fn definition(mut data: &[u8], sametypesequence: Option<&str>) -> Result<Definition> {
+    let mut parts = Vec::new();
+    if let Some(types) = sametypesequence {
+        let mut types = types.chars().peekable();
+        while let Some(kind) = types.next() {
+            let is_last = types.peek().is_none();
+            if let Some(part) = field(&mut data, kind, is_last)? {
+                parts.push(part);
+            }
+        }
+    } else {
+        while let Some((&kind, rest)) = data.split_first() {
+            data = rest;
+            if let Some(part) = field(&mut data, char::from(kind), false)? {
+                parts.push(part);
+            }
+        }
+    }
+    Ok(Definition { parts })
+}
+
+fn field(data: &mut &[u8], kind: char, is_last: bool) -> Result<Option<DefinitionPart>> {
+    if kind.is_ascii_uppercase() {
+        // binary data, which we don't store
+        if is_last {
+            *data = &[];
+        } else {
+            let len = take_u32(data).context("invalid binary field length")?;
+            let len = usize::try_from(len).unwrap_or(usize::MAX);
+            *data = data
+                .get(len..)
+                .context("binary field extends past end of entry")?;
+        }
+        return Ok(None);
+    }
+
+    let text = if is_last {
+        let text = std::str::from_utf8(data).context("field is not UTF-8")?;
+        *data = &[];
+        // some dictionaries still terminate the last field
+        text.trim_end_matches('\0')
+    } else {
+        take_str(data).context("invalid text field")?
+    };
+    let text = text.to_owned();
+    Ok(Some(match kind {
+        'g' => DefinitionPart::Pango(text),
+        'h' => DefinitionPart::Html(text),
+        't' | 'y' => DefinitionPart::Phonetic(text),
+        // `m`, `l`, and any other textual formats which we don't render
+        // specially (XDXF, KingSoft, MediaWiki, etc.)
+        _ => DefinitionPart::Text(text),
+    }))
+}
+
+fn take_str<'a>(data: &mut &'a [u8]) -> Result<&'a str> {
+    let end = data
+        .iter()
+        .position(|&b| b == 0)
+        .context("missing null terminator")?;
+    let text = std::str::from_utf8(&data[..end]).context("text is not UTF-8")?;
+    *data = &data[end + 1..];
+    Ok(text)
+}
+
+fn take_u32(data: &mut &[u8]) -> Result<u32> {
+    let (bytes, rest) = data
+        .split_first_chunk::<4>()
+        .context("unexpected end of data")?;
+    *data = rest;
+    Ok(u32::from_be_bytes(*bytes))
+}
+
+fn take_u64(data: &mut &[u8]) -> Result<u64> {
+    let (bytes, rest) = data
+        .split_first_chunk::<8>()
+        .context("unexpected end of data")?;
+    *data = rest;
+    Ok(u64::from_be_bytes(*bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ifo() {
+        let ifo = ifo("StarDict's dict ifo file\n\
+            version=2.4.2\n\
+            bookname=Test Dictionary\n\
+            wordcount=2\n\
+            sametypesequence=tm\n")
+        .unwrap();
+        assert_eq!("Test Dictionary", ifo.bookname);
+        assert_eq!(Some(2), ifo.wordcount);
+        assert_eq!(Some("tm"), ifo.sametypesequence.as_deref());
+    }
+
+    #[test]
+    fn parse_idx() {
+        let data = b"cat\0\0\0\0\0\0\0\0\x05dog\0\0\0\0\x05\0\0\0\x03";
+        let entries = idx(data, 32).unwrap();
+        assert_eq!(2, entries.len());
+        assert_eq!(
+            ("cat", 0, 5),
+            (entries[0].word, entries[0].offset, entries[0].size)
+        );
+        assert_eq!(
+            ("dog", 5, 3),
+            (entries[1].word, entries[1].offset, entries[1].size)
+        );
+    }
+
+    #[test]
+    fn definition_same_type_sequence() {
+        assert_eq!(
+            vec![
+                DefinitionPart::Phonetic("kæt".into()),
+                DefinitionPart::Text("a small mammal".into()),
+            ],
+            definition("kæt\0a small mammal".as_bytes(), Some("tm"))
+                .unwrap()
+                .parts
+        );
+    }
+
+    #[test]
+    fn definition_typed() {
+        assert_eq!(
+            vec![
+                DefinitionPart::Html("<b>cat</b>".into()),
+                DefinitionPart::Pango("<i>feline</i>".into()),
+            ],
+            definition(b"h<b>cat</b>\0W\0\0\0\x02abg<i>feline</i>\0", None)
+                .unwrap()
+                .parts
+        );
+    }
+}
@@ -0,0 +1,419 @@
+mod parse;
+
+use {
+    super::{
+        ImportContinue, ImportKind, ImportProgress, OpenArchive,
+        source::{self, ZipArchive},
+    },
+    crate::import::{Batches, continue_with, insert::Inserter},
+    anyhow::{Context, Result, bail},
+    futures::{AsyncBufReadExt as _, StreamExt as _, future::BoxFuture, io::BufReader},
+    std::{
+        collections::{HashMap, HashSet},
+        iter,
+        path::Path,
+        sync::Arc,
+    },
+    tokio::sync::mpsc,
+    tracing::{debug, trace},
+    unicode_segmentation::UnicodeSegmentation,
+    wordbase_api::{
+        DictionaryKind, DictionaryMeta, Term,
+        dict::tatoeba::{Sentence, SentenceTranslation},
+    },
+};
+
+pub struct Tatoeba;
+
+impl ImportKind for Tatoeba {
+    fn is_of_kind(&self, open_archive: Arc<dyn OpenArchive>) -> BoxFuture<'_, Result<()>> {
+        Box::pin(validate(open_archive))
+    }
+
+    fn start_import(
+        &self,
+        open_archive: Arc<dyn OpenArchive>,
+        progress_tx: mpsc::Sender<ImportProgress>,
+    ) -> BoxFuture<Result<(DictionaryMeta, ImportContinue)>> {
+        Box::pin(start_import(open_archive, progress_tx))
+    }
+}
+
+const SENTENCES_PER_PROGRESS: usize = 1000;
+
+const SENTENCES_PER_BATCH: usize = 5000;
+
+/// Fraction of the progress bar taken up by reading through the files, before
+/// we start inserting sentences.
+const READ_FRAC: f64 = 0.2;
+
+const URL: &str = "https://tatoeba.org/";
+
+const ATTRIBUTION: &str = "Sentences from Tatoeba (https://tatoeba.org), \
+    licensed under CC BY 2.0 FR";
+
+/// Languages which aren't written with spaces between words, so we can't index
+/// their sentences without a separate word index (like `jpn_indices.csv`).
+const UNSPACED_LANGS: &[&str] = &[
+    "jpn", "cmn", "yue", "wuu", "lzh", "hak", "nan", "tha", "lao", "khm", "mya",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    Sentences,
+    Links,
+    Indices,
+}
+
+/// Finds the export files in the zip, by their file names.
+///
+/// Exports may be downloaded as either `.csv` or `.tsv` files, and may be
+/// prefixed with a language, e.g. `jpn_sentences.tsv` or `jpn-eng_links.tsv`.
+fn zip_entries(archive: &ZipArchive) -> Result<Vec<(usize, String, FileKind)>> {
+    let entries = source::zip_files(archive)
+        .filter_map(|(index, path)| {
+            let file_name = path.rsplit('/').next().unwrap_or(path).to_lowercase();
+            let extension = Path::new(&file_name).extension()?;
+            if extension != "csv" && extension != "tsv" {
+                return None;
+            }
+            // check `indices` first, since `jpn_indices` doesn't contain
+            // `sentences`, but other files might contain `indices`
+            let kind = if file_name.contains("indices") {
+                FileKind::Indices
+            } else if file_name.contains("links") {
+                FileKind::Links
+            } else if file_name.contains("sentences") {
+                FileKind::Sentences
+            } else {
+                return None;
+            };
+            Some((index, path.to_owned(), kind))
+        })
+        .collect::<Vec<_>>();
+
+    if !entries
+        .iter()
+        .any(|(_, _, kind)| *kind == FileKind::Sentences)
+    {
+        bail!("no sentences file in archive");
+    }
+    if !entries.iter().any(|(_, _, kind)| *kind == FileKind::Links) {
+        bail!("no links file in archive");
+    }
+    Ok(entries)
+}
+
+/// Gets the languages which a file is limited to, from its name.
+///
+/// Per-language exports are named like `jpn_sentences.tsv` or
+/// `jpn-eng_links.tsv`, whereas the full export's files have no prefix, and may
+/// contain every language.
+fn file_langs(path: &str, kind: FileKind) -> Vec<String> {
+    let file_name = path.rsplit('/').next().unwrap_or(path).to_lowercase();
+    let suffix = match kind {
+        FileKind::Sentences => "_sentences",
+        FileKind::Links => "_links",
+        FileKind::Indices => return Vec::new(),
+    };
+    file_name
+        .split_once(suffix)
+        .map(|(prefix, _)| {
+            prefix
+                .split('-')
+                .filter(|lang| !lang.is_empty())
+                .map(str::to_owned)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Streams the lines of a file in the zip, without reading the whole file
+/// into memory.
+async fn for_each_line(
+    archive: &mut ZipArchive,
+    index: usize,
+    path: &str,
+    mut f: impl FnMut(&str),
+) -> Result<()> {
+    let reader = archive
+        .reader_with_entry(index)
+        .await
+        .with_context(|| format!("failed to start reading `{path}`"))?;
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next().await {
+        let line = line.with_context(|| format!("failed to read `{path}`"))?;
+        f(&line);
+    }
+    Ok(())
+}
+
+/// Text of a sentence which we will insert, either as a sentence record or as
+/// a translation of one.
+struct SentenceText {
+    lang: Option<String>,
+    text: String,
+}
+
+async fn validate(open_archive: Arc<dyn OpenArchive>) -> Result<()> {
+    let archive = source::open_zip(&*open_archive).await?;
+    zip_entries(&archive)?;
+    Ok(())
+}
+
+async fn start_import(
+    open_archive: Arc<dyn OpenArchive>,
+    progress_tx: mpsc::Sender<ImportProgress>,
+) -> Result<(DictionaryMeta, ImportContinue)> {
+    let mut archive = source::open_zip(&*open_archive).await?;
+    let entries = zip_entries(&archive)?;
+
+    let mut meta = DictionaryMeta::new(DictionaryKind::Tatoeba, "Tatoeba");
+    meta.description = Some("Example sentences and their translations".into());
+    meta.url = Some(URL.into());
+    meta.attribution = Some(ATTRIBUTION.into());
+
+    Ok((
+        meta,
+        continue_with(move |mut job| async move {
+            // the full export has tens of millions of sentences and links,
+            // which we can't hold in memory at once, so we make a few passes
+            // over the files, each time only keeping what we actually need
+            let files_of = |kind: FileKind| {
+                entries
+                    .iter()
+                    .filter(move |(_, _, k)| *k == kind)
+                    .map(|(index, path, _)| (*index, path.as_str()))
+                    .collect::<Vec<_>>()
+            };
+            let sentence_files = files_of(FileKind::Sentences);
+            let link_files = files_of(FileKind::Links);
+            let index_files = files_of(FileKind::Indices);
+            let num_passes = sentence_files.len() * 2 + link_files.len() + index_files.len();
+            let mut passes_done = 0usize;
+            let mut pass_done = || {
+                passes_done += 1;
+                let frac = (passes_done as f64) / (num_passes as f64) * READ_FRAC;
+                _ = progress_tx.try_send(ImportProgress { frac });
+            };
+
+            // if the files are limited to some languages, only sentences in
+            // those languages can be linked to each other
+            let langs = entries
+                .iter()
+                .flat_map(|(_, path, kind)| file_langs(path, *kind))
+                .collect::<HashSet<_>>();
+            debug!("Importing sentences in languages {langs:?}");
+
+            let mut sentence_ids = HashSet::<u64>::new();
+            for &(index, path) in &sentence_files {
+                for_each_line(&mut archive, index, path, |line| {
+                    if let Some(sentence) = parse::sentence(line) {
+                        if langs.is_empty()
+                            || sentence.lang.is_some_and(|lang| langs.contains(lang))
+                        {
+                            sentence_ids.insert(sentence.id);
+                        }
+                    }
+                })
+                .await?;
+                pass_done();
+            }
+            debug!("{} sentences in export", sentence_ids.len());
+
+            let mut translations = HashMap::<u64, Vec<u64>>::new();
+            for &(index, path) in &link_files {
+                for_each_line(&mut archive, index, path, |line| {
+                    let Some((sentence_id, translation_id)) = parse::link(line) else {
+                        return;
+                    };
+                    // the full links file links every sentence on the site,
+                    // but we only care about the ones in this export
+                    if sentence_ids.contains(&sentence_id) && sentence_ids.contains(&translation_id)
+                    {
+                        translations
+                            .entry(sentence_id)
+                            .or_default()
+                            .push(translation_id);
+                    }
+                })
+                .await?;
+                pass_done();
+            }
+            drop(sentence_ids);
+            debug!("{} sentences with translations", translations.len());
+
+            let mut indices = HashMap::<u64, Vec<Term>>::new();
+            for &(index, path) in &index_files {
+                for_each_line(&mut archive, index, path, |line| {
+                    let Some((sentence_id, words)) = parse::index(line) else {
+                        return;
+                    };
+                    if !translations.contains_key(&sentence_id) {
+                        return;
+                    }
+                    // a sentence has one index per translation, which usually
+                    // list the same words
+                    indices.entry(sentence_id).or_default().extend(
+                        words
+                            .into_iter()
+                            .filter_map(|word| Term::from_parts(Some(word.headword), word.reading)),
+                    );
+                })
+                .await?;
+                pass_done();
+            }
+            debug!("{} indexed sentences", indices.len());
+
+            let needed_ids = translations
+                .iter()
+                .flat_map(|(sentence_id, translation_ids)| {
+                    iter::once(sentence_id).chain(translation_ids)
+                })
+                .copied()
+                .collect::<HashSet<_>>();
+            let mut sentences = HashMap::<u64, SentenceText>::new();
+            for &(index, path) in &sentence_files {
+                for_each_line(&mut archive, index, path, |line| {
+                    let Some(sentence) = parse::sentence(line) else {
+                        return;
+                    };
+                    if needed_ids.contains(&sentence.id) {
+                        sentences.insert(
+                            sentence.id,
+                            SentenceText {
+                                lang: sentence.lang.map(str::to_owned),
+                                text: sentence.text.to_owned(),
+                            },
+                        );
+                    }
+                })
+                .await?;
+                pass_done();
+            }
+            drop(needed_ids);
+
+            let mut batches = Batches::new("sentences", SENTENCES_PER_BATCH);
+            let mut sentence_ids = translations.keys().copied().collect::<Vec<_>>();
+            sentence_ids.sort_unstable();
+            let num_sentences = sentence_ids.len();
+            let mut sentences_done = 0usize;
+            for sentence_id in sentence_ids {
+                let sentence = &sentences[&sentence_id];
+                let terms = sentence_terms(sentence, indices.get(&sentence_id));
+                if !terms.is_empty() {
+                    let record = Sentence {
+                        id: sentence_id,
+                        lang: sentence.lang.clone().unwrap_or_default(),
+                        text: sentence.text.clone(),
+                        translations: translations[&sentence_id]
+                            .iter()
+                            .map(|translation_id| {
+                                let translation = &sentences[translation_id];
+                                SentenceTranslation {
+                                    id: *translation_id,
+                                    lang: translation.lang.clone().unwrap_or_default(),
+                                    text: translation.text.clone(),
+                                }
+                            })
+                            .collect(),
+                    };
+                    if let Some(batch) = batches.push((record, terms)) {
+                        job.insert_batch(batch, import_sentence).await?;
+                    }
+                }
+
+                sentences_done += 1;
+                if sentences_done % SENTENCES_PER_PROGRESS == 0 {
+                    let frac = ((sentences_done as f64) / (num_sentences as f64))
+                        .mul_add(1.0 - READ_FRAC, READ_FRAC);
+                    _ = progress_tx.try_send(ImportProgress { frac });
+                    trace!("{sentences_done} / {num_sentences} sentences imported");
+                }
+            }
+            job.insert_batch(batches.finish(), import_sentence).await?;
+
+            let tx = job.begin().await?;
+            job.finish(tx).await
+        }),
+    ))
+}
+
+async fn import_sentence(
+    insert: &mut Inserter<'_, '_>,
+    (record, terms): (Sentence, Vec<Term>),
+) -> Result<()> {
+    let record_id = insert
+        .record(&record)
+        .await
+        .context("failed to insert sentence record")?;
+    for term in terms {
+        insert
+            .example_sentence(term, record_id)
+            .await
+            .context("failed to insert example sentence")?;
+    }
+    Ok(())
+}
+
+/// Gets the terms which a sentence is indexed under.
+///
+/// If the sentence has a word index, we use the words from that. Otherwise,
+/// we split the sentence into words, if its language uses spaces.
+fn sentence_terms(sentence: &SentenceText, index: Option<&Vec<Term>>) -> Vec<Term> {
+    let terms = match index {
+        Some(index) => index.clone(),
+        None if sentence
+            .lang
+            .as_deref()
+            .is_some_and(|lang| !UNSPACED_LANGS.contains(&lang)) =>
+        {
+            sentence
+                .text
+                .unicode_words()
+                .filter_map(|word| Term::from_headword(word.to_lowercase()))
+                .collect::<Vec<_>>()
+        }
+        None => Vec::new(),
+    };
+
+    // a word may appear multiple times in the same sentence
+    let mut seen = HashSet::new();
+    terms
+        .into_iter()
+        .filter(|term| seen.insert(term.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn langs_from_file_names() {
+        assert_eq!(
+            vec!["jpn"],
+            file_langs("jpn_sentences.tsv", FileKind::Sentences)
+        );
+        assert_eq!(
+            vec!["jpn"],
+            file_langs("export/jpn_sentences_detailed.tsv", FileKind::Sentences)
+        );
+        assert_eq!(
+            vec!["jpn", "eng"],
+            file_langs("jpn-eng_links.tsv", FileKind::Links)
+        );
+        assert_eq!(
+            Vec::<String>::new(),
+            file_langs("sentences.csv", FileKind::Sentences)
+        );
+        assert_eq!(
+            Vec::<String>::new(),
+            file_langs("links.csv", FileKind::Links)
+        );
+        assert_eq!(
+            Vec::<String>::new(),
+            file_langs("jpn_indices.csv", FileKind::Indices)
+        );
+    }
+}
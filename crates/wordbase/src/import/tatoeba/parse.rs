@@ -0,0 +1,158 @@
+//! Parsers for the tab-separated files of a Tatoeba export.
+//!
+//! See the [Tatoeba downloads page] for a description of each file.
+//!
+//! [Tatoeba downloads page]: https://tatoeba.org/en/downloads
+
+/// Language code which Tatoeba uses for sentences whose language has not been
+/// set yet.
+const UNKNOWN_LANG: &str = "\\N";
+
+/// Line of `sentences.csv`, or of `sentences_detailed.csv`, which has extra
+/// columns after these.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SentenceLine<'a> {
+    pub id: u64,
+    pub lang: Option<&'a str>,
+    pub text: &'a str,
+}
+
+pub fn sentence(line: &str) -> Option<SentenceLine<'_>> {
+    let mut columns = line.split('\t');
+    let id = columns.next()?.parse().ok()?;
+    let lang = columns.next()?;
+    let text = columns.next()?.trim();
+    if text.is_empty() {
+        return None;
+    }
+    Some(SentenceLine {
+        id,
+        lang: (lang != UNKNOWN_LANG && !lang.is_empty()).then_some(lang),
+        text,
+    })
+}
+
+/// Line of `links.csv`, linking a sentence to one of its translations.
+pub fn link(line: &str) -> Option<(u64, u64)> {
+    let mut columns = line.split('\t');
+    let sentence_id = columns.next()?.parse().ok()?;
+    let translation_id = columns.next()?.trim().parse().ok()?;
+    Some((sentence_id, translation_id))
+}
+
+/// Word listed in the index of a Japanese sentence.
+#[derive(Debug, PartialEq, Eq)]
+pub struct IndexWord<'a> {
+    pub headword: &'a str,
+    pub reading: Option<&'a str>,
+}
+
+/// Line of `jpn_indices.csv`, which lists the words used in a Japanese
+/// sentence, in the format of the [Tanaka Corpus] `B` lines.
+///
+/// Returns the ID of the Japanese sentence, and the words in it.
+///
+/// [Tanaka Corpus]: https://www.edrdg.org/wiki/index.php/Tanaka_Corpus
+pub fn index(line: &str) -> Option<(u64, Vec<IndexWord<'_>>)> {
+    let mut columns = line.split('\t');
+    let sentence_id = columns.next()?.parse().ok()?;
+    let _meaning_id = columns.next()?;
+    let words = columns
+        .next()?
+        .split_whitespace()
+        .filter_map(index_word)
+        .collect();
+    Some((sentence_id, words))
+}
+
+/// Parses a single word of an index, e.g. `為る(する){しました}~`.
+///
+/// After the headword, a word may have:
+/// - `|1` - the number of the dictionary entry, where there are multiple
+/// - `(...)` - the reading of the headword
+/// - `[01]` - the number of the sense used in the sentence
+/// - `{...}` - the form that the word takes in the sentence
+/// - `~` - marker that this is a good example of the word
+fn index_word(token: &str) -> Option<IndexWord<'_>> {
+    let headword_end = token.find(['|', '(', '[', '{', '~']).unwrap_or(token.len());
+    let headword = &token[..headword_end];
+    if headword.is_empty() {
+        return None;
+    }
+
+    let rest = &token[headword_end..];
+    // the reading may come after the entry number
+    let reading = rest.find('(').and_then(|start| {
+        let reading = &rest[start + 1..];
+        reading.find(')').map(|end| &reading[..end])
+    });
+    Some(IndexWord {
+        headword,
+        reading: reading.filter(|reading| !reading.is_empty()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sentences() {
+        assert_eq!(
+            Some(SentenceLine {
+                id: 4705,
+                lang: Some("jpn"),
+                text: "彼は二十歳になりました。",
+            }),
+            sentence("4705\tjpn\t彼は二十歳になりました。")
+        );
+        assert_eq!(
+            Some(SentenceLine {
+                id: 1,
+                lang: None,
+                text: "Hello.",
+            }),
+            sentence("1\t\\N\tHello.\tuser\t2010-01-01\t2010-01-01")
+        );
+        assert_eq!(None, sentence("1\tjpn\t"));
+        assert_eq!(None, sentence("id\tlang\ttext"));
+        assert_eq!(Some((4705, 1276)), link("4705\t1276"));
+        assert_eq!(None, link("4705"));
+    }
+
+    #[test]
+    fn index_words() {
+        let (id, words) =
+            index("4705\t1276\t彼(かれ)[01] は 二十歳(はたち){二十歳} になる[01]{になりました}~")
+                .unwrap();
+        assert_eq!(4705, id);
+        assert_eq!(
+            vec![
+                IndexWord {
+                    headword: "彼",
+                    reading: Some("かれ"),
+                },
+                IndexWord {
+                    headword: "は",
+                    reading: None,
+                },
+                IndexWord {
+                    headword: "二十歳",
+                    reading: Some("はたち"),
+                },
+                IndexWord {
+                    headword: "になる",
+                    reading: None,
+                },
+            ],
+            words
+        );
+        assert_eq!(
+            Some(IndexWord {
+                headword: "其の",
+                reading: Some("その"),
+            }),
+            index_word("其の|1(その){その}")
+        );
+    }
+}
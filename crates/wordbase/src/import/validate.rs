@@ -0,0 +1,220 @@
+//! Dry-run validation of dictionary archives.
+//!
+//! Validating an archive runs an importer's parse path over the whole archive,
+//! but never inserts into the engine's database. Importers which parse their
+//! archive in banks of rows (i.e. Yomitan) report problems per row; all other
+//! importers are run in full against a scratch database, which is thrown away
+//! afterwards.
+
+use {
+    super::{ImportError, ImportJob, ImportKind, ImportOptions, InsertLock, OpenArchive, kind_of},
+    crate::{CHANNEL_BUF_CAP, Engine, db},
+    anyhow::{Context, Result},
+    serde::Serialize,
+    std::sync::Arc,
+    tokio::sync::mpsc,
+    tracing::debug,
+    wordbase_api::{DictionaryKind, DictionaryMeta, RecordKind},
+};
+
+/// Result of [`Engine::validate_dictionary`].
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct ValidationReport {
+    /// Kind of dictionary that the archive was detected as.
+    pub kind: DictionaryKind,
+    /// Meta of the dictionary, if it could be parsed.
+    pub meta: Option<DictionaryMeta>,
+    /// Banks in the archive, and how many rows each one has.
+    ///
+    /// Only importers which parse their archive in banks report these.
+    pub banks: Vec<BankCount>,
+    /// How many records of each kind the archive would be imported as.
+    pub records: Vec<RecordCount>,
+    /// Rows which failed to parse or import, with the error message.
+    ///
+    /// For formats other than Yomitan, validation stops at the first error,
+    /// so there is at most one of these.
+    pub errors: Vec<ValidationIssue>,
+    /// Fields which the importer does not recognize, with the field name.
+    ///
+    /// Rows with unknown fields usually fail to parse, in which case they
+    /// are also reported in [`ValidationReport::errors`].
+    pub unknown_fields: Vec<ValidationIssue>,
+    /// References to media files which are not in the archive, with the path
+    /// of the media.
+    pub missing_media: Vec<ValidationIssue>,
+    /// References to tags which no tag bank defines, with the tag name.
+    pub missing_tags: Vec<ValidationIssue>,
+}
+
+/// Number of rows in a single bank of an archive.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct BankCount {
+    /// Path of the bank in the archive, e.g. `term_bank_1.json`.
+    pub path: String,
+    pub rows: u64,
+}
+
+/// Number of records of a single kind.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct RecordCount {
+    pub kind: RecordKind,
+    pub count: u64,
+}
+
+/// Single problem found in an archive, while validating it or while leniently
+/// importing it.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct ValidationIssue {
+    /// Path of the entry in the archive which has this problem, if it is known.
+    pub path: Option<String>,
+    /// Index of the row in the entry which has this problem, if it is known.
+    pub index: Option<u64>,
+    /// Details of the problem, depending on what kind of problem it is.
+    pub message: String,
+}
+
+impl ValidationReport {
+    #[must_use]
+    pub const fn new(kind: DictionaryKind) -> Self {
+        Self {
+            kind,
+            meta: None,
+            banks: Vec::new(),
+            records: Vec::new(),
+            errors: Vec::new(),
+            unknown_fields: Vec::new(),
+            missing_media: Vec::new(),
+            missing_tags: Vec::new(),
+        }
+    }
+
+    /// Checks if no problems were found, so the archive should import
+    /// successfully.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.meta.is_some()
+            && self.errors.is_empty()
+            && self.unknown_fields.is_empty()
+            && self.missing_media.is_empty()
+            && self.missing_tags.is_empty()
+    }
+
+    /// Adds `count` records of this kind to the totals.
+    pub(super) fn add_records(&mut self, kind: RecordKind, count: u64) {
+        if let Some(total) = self.records.iter_mut().find(|total| total.kind == kind) {
+            total.count += count;
+        } else {
+            self.records.push(RecordCount { kind, count });
+        }
+    }
+}
+
+impl ValidationIssue {
+    pub(super) fn new(
+        path: Option<&str>,
+        index: Option<usize>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            path: path.map(ToOwned::to_owned),
+            index: index.map(|index| index as u64),
+            message: message.into(),
+        }
+    }
+}
+
+/// Validates an archive by running its whole import against a scratch
+/// database.
+///
+/// Media isn't copied into the scratch database, since nothing checks it.
+/// Since the import stops at the first error, at most one error is reported.
+pub(super) async fn dry_run<I: ImportKind + ?Sized>(
+    importer: &I,
+    open_archive: Arc<dyn OpenArchive>,
+    report: &mut ValidationReport,
+) -> Result<()> {
+    // nobody listens to progress, and importers ignore failing to send it
+    let (progress_tx, _) = mpsc::channel(CHANNEL_BUF_CAP);
+    let (meta, continue_task) = match importer.start_import(open_archive, progress_tx).await {
+        Ok(started) => started,
+        Err(err) => {
+            report
+                .errors
+                .push(ValidationIssue::new(None, None, format!("{err:?}")));
+            return Ok(());
+        }
+    };
+    report.meta = Some(meta.clone());
+
+    let scratch_dir = tempfile::tempdir().context("failed to create scratch directory")?;
+    let db = db::setup(&scratch_dir.path().join("validate.db"))
+        .await
+        .context("failed to create scratch database")?;
+    let job = ImportJob::create(
+        &db,
+        &InsertLock::default(),
+        &meta,
+        None,
+        ImportOptions::default(),
+    )
+    .await
+    .context("failed to create import job")?
+    .without_media();
+    if let Err(err) = continue_task(job).await {
+        report
+            .errors
+            .push(ValidationIssue::new(None, None, format!("{err:?}")));
+    }
+
+    let counts = sqlx::query_as::<_, (u32, i64)>("SELECT kind, COUNT(*) FROM record GROUP BY kind")
+        .fetch_all(&db)
+        .await
+        .context("failed to count records")?;
+    for (kind, count) in counts {
+        let kind = RecordKind::ALL
+            .iter()
+            .find(|record_kind| **record_kind as u32 == kind)
+            .with_context(|| format!("invalid record kind {kind}"))?;
+        report.add_records(*kind, u64::try_from(count).unwrap_or_default());
+    }
+    db.close().await;
+    Ok(())
+}
+
+impl Engine {
+    /// Parses a whole dictionary archive without importing it, and reports
+    /// any problems that an import would run into.
+    ///
+    /// This never modifies the engine's database.
+    ///
+    /// Only Yomitan dictionaries are checked row by row, and have every
+    /// problem in the archive reported. Other formats are validated by
+    /// importing the whole archive into a scratch database, which stops at
+    /// the first error - so for these, at most one error is reported, and
+    /// fixing it may reveal more.
+    pub async fn validate_dictionary(
+        &self,
+        open_archive: impl OpenArchive + 'static,
+    ) -> Result<ValidationReport, ImportError> {
+        let open_archive = Arc::new(open_archive) as Arc<dyn OpenArchive>;
+        let kind = kind_of(open_archive.clone())
+            .await
+            .map_err(ImportError::GetKind)?;
+        let importer = super::FORMATS
+            .get(&kind)
+            .ok_or(ImportError::NoImporter { kind })?;
+        debug!("Validating {kind:?} dictionary");
+
+        let mut report = ValidationReport::new(kind);
+        importer
+            .validate(open_archive, &mut report)
+            .await
+            .map_err(|source| ImportError::Import { kind, source })?;
+        Ok(report)
+    }
+}
@@ -0,0 +1,280 @@
+mod parse;
+
+use {
+    super::{
+        ImportContinue, ImportKind, ImportProgress, OpenArchive,
+        source::{self, DocumentReader},
+    },
+    crate::import::{Batches, continue_with, insert::Inserter},
+    anyhow::{Context, Result, bail},
+    futures::{AsyncBufReadExt as _, AsyncReadExt as _, future::BoxFuture},
+    std::{
+        collections::HashSet,
+        iter,
+        sync::{Arc, atomic},
+    },
+    tokio::sync::mpsc,
+    tracing::{debug, trace},
+    wordbase_api::{
+        DictionaryKind, DictionaryMeta, Term,
+        dict::{
+            wiktionary::{Lexeme, LexemeSense},
+            yomitan::{GlossaryTag, Phonetic},
+        },
+    },
+};
+
+pub struct Wiktionary;
+
+impl ImportKind for Wiktionary {
+    fn is_of_kind(&self, open_archive: Arc<dyn OpenArchive>) -> BoxFuture<'_, Result<()>> {
+        Box::pin(validate(open_archive))
+    }
+
+    fn start_import(
+        &self,
+        open_archive: Arc<dyn OpenArchive>,
+        progress_tx: mpsc::Sender<ImportProgress>,
+    ) -> BoxFuture<Result<(DictionaryMeta, ImportContinue)>> {
+        Box::pin(start_import(open_archive, progress_tx))
+    }
+}
+
+/// Maximum length of the first line of the file that we read to validate it.
+///
+/// A single entry can be very long, since it contains every form and sense
+/// of the word, so this is much larger than for other formats.
+const FIRST_LINE_LEN: u64 = 16 * 1024 * 1024;
+
+const LINES_PER_PROGRESS: usize = 1000;
+
+const LINES_PER_BATCH: usize = 2000;
+
+const URL: &str = "https://kaikki.org/";
+
+const ATTRIBUTION: &str = "Wiktionary content, licensed under CC BY-SA 4.0 and GFDL. \
+    Extracted by Wiktextract and published by kaikki.org";
+
+/// Reads and parses the first entry of the file.
+async fn first_entry(reader: &mut DocumentReader) -> Result<parse::Entry> {
+    let start = reader
+        .fill_buf()
+        .await
+        .context("failed to read start of file")?;
+    if !start.starts_with(b"{") {
+        bail!("not a JSON object");
+    }
+
+    let mut line = Vec::new();
+    (&mut *reader)
+        .take(FIRST_LINE_LEN)
+        .read_until(b'\n', &mut line)
+        .await
+        .context("failed to read first line")?;
+    let entry = serde_json::from_slice::<parse::Entry>(&line)
+        .context("first line is not a Wiktionary entry")?;
+    if entry.word.is_empty() {
+        bail!("first line has no `word`");
+    }
+    Ok(entry)
+}
+
+async fn validate(open_archive: Arc<dyn OpenArchive>) -> Result<()> {
+    let (mut reader, _, _) = source::document_reader(&*open_archive).await?;
+    first_entry(&mut reader).await?;
+    Ok(())
+}
+
+async fn start_import(
+    open_archive: Arc<dyn OpenArchive>,
+    progress_tx: mpsc::Sender<ImportProgress>,
+) -> Result<(DictionaryMeta, ImportContinue)> {
+    let (mut reader, cursor_pos, buf_len) = source::document_reader(&*open_archive).await?;
+
+    // dumps don't have a header, but each dump is usually of a single
+    // language, so we name the dictionary after the first entry's language
+    let first_entry = first_entry(&mut reader).await?;
+    let name = first_entry.lang.as_ref().map_or_else(
+        || "Wiktionary".to_owned(),
+        |lang| format!("Wiktionary ({lang})"),
+    );
+    let mut meta = DictionaryMeta::new(DictionaryKind::Wiktionary, name);
+    meta.url = Some(URL.into());
+    meta.attribution = Some(ATTRIBUTION.into());
+
+    Ok((
+        meta,
+        continue_with(move |mut job| async move {
+            let import = async |insert: &mut Inserter<'_, '_>, (line_num, records)| {
+                import_records(insert, records)
+                    .await
+                    .with_context(|| format!("failed to import line {line_num}"))
+            };
+
+            // the same word may have multiple entries (one per part of speech),
+            // which all list the same pronunciations
+            let mut seen_ipa = HashSet::<(String, String)>::new();
+            let mut batches = Batches::new("lines", LINES_PER_BATCH);
+            if let Some(records) = entry_records(first_entry, &mut seen_ipa) {
+                if let Some(batch) = batches.push((1, records)) {
+                    job.insert_batch(batch, import).await?;
+                }
+            }
+
+            let mut lines_done = 1usize;
+            let mut line_num = 1usize;
+            let mut line = String::new();
+            loop {
+                line.clear();
+                if reader
+                    .read_line(&mut line)
+                    .await
+                    .context("failed to read line")?
+                    == 0
+                {
+                    break;
+                }
+                line_num += 1;
+                let text = line.trim();
+                if text.is_empty() {
+                    continue;
+                }
+
+                let entry = serde_json::from_str::<parse::Entry>(text)
+                    .with_context(|| format!("failed to parse line {line_num}"))?;
+                if let Some(records) = entry_records(entry, &mut seen_ipa) {
+                    if let Some(batch) = batches.push((line_num, records)) {
+                        job.insert_batch(batch, import).await?;
+                    }
+                }
+
+                lines_done += 1;
+                if lines_done % LINES_PER_PROGRESS == 0 {
+                    let cursor_pos = cursor_pos.load(atomic::Ordering::SeqCst);
+                    let frac = (cursor_pos as f64) / (buf_len as f64);
+                    _ = progress_tx.try_send(ImportProgress { frac });
+                    trace!("{lines_done} lines imported - {cursor_pos} / {buf_len} bytes read");
+                }
+            }
+            job.insert_batch(batches.finish(), import).await?;
+            debug!("{lines_done} total lines");
+
+            let tx = job.begin().await?;
+            job.finish(tx).await
+        }),
+    ))
+}
+
+/// Records of a single entry, which are inserted under the entry's word.
+struct EntryRecords {
+    term: Term,
+    phonetics: Vec<Phonetic>,
+    /// Lexeme of the entry, and the inflected forms which it is also found
+    /// under.
+    lexeme: Option<(Lexeme, Vec<Term>)>,
+}
+
+/// Converts an entry into the records which will be inserted for it.
+///
+/// Pronunciations which were already in `seen_ipa` are left out.
+fn entry_records(
+    entry: parse::Entry,
+    seen_ipa: &mut HashSet<(String, String)>,
+) -> Option<EntryRecords> {
+    let term = Term::from_headword(entry.word.as_str())?;
+
+    let phonetics = entry
+        .sounds
+        .into_iter()
+        .filter_map(|sound| {
+            let ipa = sound.ipa?;
+            if !seen_ipa.insert((entry.word.clone(), ipa.clone())) {
+                return None;
+            }
+            Some(Phonetic {
+                ipa,
+                tags: sound.tags.into_iter().map(to_tag).collect(),
+            })
+        })
+        .collect();
+
+    let senses = entry
+        .senses
+        .into_iter()
+        .filter(|sense| !sense.glosses.is_empty())
+        .map(|sense| LexemeSense {
+            glosses: sense.glosses,
+            tags: sense.tags,
+            examples: sense
+                .examples
+                .into_iter()
+                .filter_map(|example| example.text)
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    let lexeme = if senses.is_empty() {
+        None
+    } else {
+        let forms = entry
+            .forms
+            .into_iter()
+            .filter(|form| form.is_inflection(&entry.word))
+            .filter_map(|form| Term::from_headword(form.form))
+            .collect::<Vec<_>>();
+        let record = Lexeme {
+            word: entry.word,
+            pos: entry.pos,
+            etymology: entry.etymology_text,
+            senses,
+        };
+        Some((record, forms))
+    };
+
+    Some(EntryRecords {
+        term,
+        phonetics,
+        lexeme,
+    })
+}
+
+async fn import_records(insert: &mut Inserter<'_, '_>, records: EntryRecords) -> Result<()> {
+    let EntryRecords {
+        term,
+        phonetics,
+        lexeme,
+    } = records;
+    for record in phonetics {
+        let record_id = insert
+            .record(&record)
+            .await
+            .context("failed to insert phonetic record")?;
+        insert
+            .term_record(term.clone(), record_id)
+            .await
+            .context("failed to insert term record")?;
+    }
+
+    let Some((record, forms)) = lexeme else {
+        return Ok(());
+    };
+    let record_id = insert
+        .record(&record)
+        .await
+        .context("failed to insert lexeme record")?;
+    for term in iter::once(term).chain(forms) {
+        insert
+            .term_record(term, record_id)
+            .await
+            .context("failed to insert term record")?;
+    }
+    Ok(())
+}
+
+fn to_tag(name: String) -> GlossaryTag {
+    GlossaryTag {
+        name,
+        category: String::new(),
+        description: String::new(),
+        order: 0,
+    }
+}
@@ -0,0 +1,114 @@
+//! Schema of a single line of a kaikki.org JSONL dump.
+//!
+//! Each line is a JSON object describing one part of speech of one word. Only
+//! the fields that we import are listed here.
+//!
+//! See the [Wiktextract JSON schema].
+//!
+//! [Wiktextract JSON schema]: https://github.com/tatuylonen/wiktextract#format-of-the-extracted-word-entries
+
+use serde::Deserialize;
+
+/// Tags on a [`Form`] which mark it as metadata about the inflection table,
+/// rather than an actual form of the word.
+const META_FORM_TAGS: &[&str] = &["table-tags", "inflection-template", "class", "romanization"];
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Entry {
+    /// Empty for lines which don't describe a word, such as redirect pages in
+    /// the full Wiktionary dump.
+    #[serde(default)]
+    pub word: String,
+    #[serde(default)]
+    pub pos: String,
+    /// Name of the language that this word is in, e.g. `English`.
+    pub lang: Option<String>,
+    #[serde(default)]
+    pub forms: Vec<Form>,
+    #[serde(default)]
+    pub senses: Vec<Sense>,
+    #[serde(default)]
+    pub sounds: Vec<Sound>,
+    pub etymology_text: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Form {
+    pub form: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl Form {
+    /// Checks if this is a single-word inflected form of the entry's word,
+    /// which can be looked up directly.
+    ///
+    /// Multi-word forms (e.g. `has run`) are excluded, since lookups are only
+    /// ever done on a single word of the sentence.
+    pub fn is_inflection(&self, word: &str) -> bool {
+        !self.form.is_empty()
+            && self.form != "-"
+            && self.form != word
+            && !self.form.contains(char::is_whitespace)
+            && !self
+                .tags
+                .iter()
+                .any(|tag| META_FORM_TAGS.contains(&tag.as_str()))
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Sense {
+    #[serde(default)]
+    pub glosses: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub examples: Vec<Example>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Example {
+    pub text: Option<String>,
+}
+
+/// Pronunciation of the word, which may either be an IPA transcription, or
+/// a link to an audio file (which we don't import).
+#[derive(Debug, Default, Deserialize)]
+pub struct Sound {
+    pub ipa: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINE: &str = r#"{"pos": "verb", "head_templates": [{"name": "en-verb", "args": {"1": "runs"}}], "forms": [{"form": "runs", "tags": ["present", "singular", "third-person"]}, {"form": "running", "tags": ["participle", "present"]}, {"form": "ran", "tags": ["past"]}, {"form": "has run", "tags": ["perfect"]}, {"form": "en-verb", "source": "conjugation", "tags": ["inflection-template"]}], "etymology_text": "From Middle English rinnen.", "sounds": [{"ipa": "/ɹʌn/", "tags": ["General-American"]}, {"audio": "en-us-run.ogg"}], "word": "run", "lang": "English", "lang_code": "en", "senses": [{"glosses": ["To move swiftly."], "tags": ["intransitive"], "examples": [{"text": "She ran to the store.", "type": "example"}]}, {"tags": ["no-gloss"]}]}"#;
+
+    #[test]
+    fn entry() {
+        let entry = serde_json::from_str::<Entry>(LINE).unwrap();
+        assert_eq!("run", entry.word);
+        assert_eq!("verb", entry.pos);
+        assert_eq!(Some("English"), entry.lang.as_deref());
+        assert_eq!(
+            vec!["runs", "running", "ran"],
+            entry
+                .forms
+                .iter()
+                .filter(|form| form.is_inflection(&entry.word))
+                .map(|form| form.form.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(vec!["To move swiftly."], entry.senses[0].glosses);
+        assert_eq!(
+            Some("She ran to the store."),
+            entry.senses[0].examples[0].text.as_deref()
+        );
+        assert_eq!(0, entry.senses[1].glosses.len());
+        assert_eq!(Some("/ɹʌn/"), entry.sounds[0].ipa.as_deref());
+        assert_eq!(None, entry.sounds[1].ipa);
+    }
+}
@@ -0,0 +1,49 @@
+//! Reads the `entries.db` SQLite index which the local-audio-yomichan Anki
+//! add-on builds from the JSON indexes of each audio source.
+//!
+//! This index maps every audio file to the terms that it pronounces, but
+//! unlike the JSON indexes, it does not hold any pitch accent information.
+
+use {
+    anyhow::{Context, Result},
+    sqlx::{
+        Connection, SqliteConnection,
+        sqlite::{SqliteConnectOptions, SqliteJournalMode},
+    },
+    std::path::Path,
+};
+
+#[derive(Debug)]
+pub struct Entry {
+    pub expression: String,
+    pub reading: Option<String>,
+    /// ID of the source that this file is from, e.g. `nhk16`.
+    pub source: String,
+    /// Path of the file relative to the source's directory.
+    pub file: String,
+}
+
+pub async fn read(path: &Path) -> Result<Vec<Entry>> {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true)
+        .journal_mode(SqliteJournalMode::Off);
+    let mut conn = SqliteConnection::connect_with(&options)
+        .await
+        .context("failed to open entries database")?;
+    let rows = sqlx::query_as::<_, (String, Option<String>, String, String)>(
+        "SELECT expression, reading, source, file FROM entries",
+    )
+    .fetch_all(&mut conn)
+    .await
+    .context("failed to fetch entries")?;
+    Ok(rows
+        .into_iter()
+        .map(|(expression, reading, source, file)| Entry {
+            expression,
+            reading,
+            source,
+            file,
+        })
+        .collect())
+}
@@ -1,47 +1,51 @@
+mod entries;
 mod schema;
 
 use {
-    super::{Archive, ImportContinue, ImportKind, ImportProgress, OpenArchive},
+    super::{
+        Archive, Directory, ImportContinue, ImportKind, ImportProgress, OpenArchive, count::Count,
+        source,
+    },
     crate::{
         IndexSet,
-        import::{insert::Inserter, insert_dictionary},
+        import::{ImportJob, continue_with, insert::Inserter},
     },
     anyhow::{Context, Result, bail},
     async_compression::futures::bufread::XzDecoder,
     async_tar::EntryType,
     bytes::Bytes,
-    derive_more::Deref,
+    derive_more::{Deref, From},
     foldhash::{HashMap, HashMapExt, HashSet},
-    futures::{
-        AsyncBufRead, AsyncRead, AsyncReadExt as _, AsyncSeek, AsyncSeekExt as _, StreamExt,
-        future::BoxFuture,
-    },
-    pin_project::pin_project,
+    futures::{AsyncRead, AsyncReadExt as _, StreamExt, future::BoxFuture},
     schema::{
-        FORVO_PATH, JPOD_INDEX, JPOD_MEDIA, MARKER_PATHS, NHK16_AUDIO, NHK16_INDEX,
+        DAIJISEN_INDEX, DAIJISEN_MEDIA, ENTRIES_DB, FORVO_PATH, JPOD_INDEX, JPOD_MEDIA,
+        MARKER_PATHS, NHK16_AUDIO, NHK16_INDEX, OUBUNSHA_INDEX, OUBUNSHA_MEDIA, ROOT_PATH,
         SHINMEIKAI8_INDEX, SHINMEIKAI8_MEDIA,
     },
     serde::de::DeserializeOwned,
-    sqlx::{Pool, Sqlite},
     std::{
         any::type_name,
-        io::SeekFrom,
+        mem,
         path::Path,
-        pin::Pin,
         sync::{
             Arc,
             atomic::{self, AtomicU64},
         },
-        task::Poll,
     },
-    tokio::sync::mpsc,
-    tokio_util::compat::{Compat, TokioAsyncReadCompatExt},
+    tempfile::NamedTempFile,
+    tokio::{
+        fs::{self, File},
+        sync::mpsc,
+    },
+    tokio_util::compat::{Compat, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt},
     tracing::{debug, trace, warn},
     wordbase_api::{
-        DictionaryId, DictionaryKind, DictionaryMeta, NormString, RecordType, Term,
+        DictionaryKind, DictionaryMeta, NormString, Term,
         dict::{
             jpn::PitchPosition,
-            yomichan_audio::{Audio, AudioFormat, Forvo, Jpod, Nhk16, Shinmeikai8},
+            yomichan_audio::{
+                Audio, AudioFormat, Daijisen, Forvo, Jpod, Nhk16, Oubunsha, Shinmeikai8,
+            },
         },
     },
 };
@@ -55,7 +59,6 @@ impl ImportKind for YomichanAudio {
 
     fn start_import(
         &self,
-        db: Pool<Sqlite>,
         open_archive: Arc<dyn OpenArchive>,
         progress_tx: mpsc::Sender<ImportProgress>,
     ) -> BoxFuture<Result<(DictionaryMeta, ImportContinue)>> {
@@ -66,8 +69,8 @@ impl ImportKind for YomichanAudio {
             );
             meta.url = Some("https://github.com/yomidevs/local-audio-yomichan".into());
             Ok((
-                meta.clone(),
-                Box::pin(import(db, open_archive, meta, progress_tx)) as ImportContinue,
+                meta,
+                continue_with(move |job| import(job, open_archive, progress_tx)),
             ))
         })
     }
@@ -80,126 +83,120 @@ async fn archive_reader(
     Arc<AtomicU64>,
     u64,
 )> {
-    let mut archive = open_archive
-        .open_archive()
-        .await
-        .context("failed to open archive")?
-        .compat();
-    let buf_len = archive
-        .seek(SeekFrom::End(0))
-        .await
-        .context("failed to seek to end")?;
-    archive
-        .seek(SeekFrom::Start(0))
-        .await
-        .context("failed to seek to start")?;
-
-    let count = Count::new(archive);
-    let cursor_pos = count.pos();
+    let (count, cursor_pos, buf_len) = source::counted_reader(open_archive).await?;
     let archive = async_tar::Archive::new(XzDecoder::new(count));
     Ok((archive, cursor_pos, buf_len))
 }
 
-async fn validate(open_archive: Arc<dyn OpenArchive>) -> Result<()> {
-    let (archive, _, _) = archive_reader(&*open_archive).await?;
-    let mut entries = archive
-        .entries()
-        .context("failed to read archive entries")?;
-    while let Some(entry) = entries.next().await {
-        let entry = entry.context("failed to read entry")?;
-        let path = entry.path().context("failed to read entry path")?;
-        let path = path
-            .to_str()
-            .with_context(|| format!("path {path:?} is not UTF-8"))?;
-
-        if MARKER_PATHS.contains(&path) {
-            return Ok(());
-        }
-    }
-    bail!("missing one of {MARKER_PATHS:?}");
-}
-
-#[pin_project]
-struct Count<T> {
-    #[pin]
-    inner: T,
-    pos: Arc<AtomicU64>,
+/// Directory of an unpacked local audio collection.
+///
+/// This is either the `user_files` directory itself, or a directory which
+/// contains it (like the `.tar.xz` archive does).
+struct AudioDir {
+    dir: Directory,
+    /// Prefix which is stripped from an archive entry path to get the path of
+    /// the same file in the directory.
+    prefix: &'static str,
 }
 
-impl<T> Count<T> {
-    pub fn new(inner: T) -> Self {
+impl AudioDir {
+    async fn new(dir: &Directory) -> Self {
+        let prefix = if dir.contains(ROOT_PATH).await {
+            ""
+        } else {
+            ROOT_PATH
+        };
         Self {
-            inner,
-            pos: Arc::new(AtomicU64::new(0)),
+            dir: dir.clone(),
+            prefix,
         }
     }
 
-    pub fn pos(&self) -> Arc<AtomicU64> {
-        self.pos.clone()
+    /// Gets the path of a file in the directory from its archive entry path.
+    fn dir_path<'a>(&self, entry_path: &'a str) -> Option<&'a str> {
+        entry_path.strip_prefix(self.prefix)
     }
-}
 
-impl<T: AsyncSeek + Unpin> AsyncSeek for Count<T> {
-    fn poll_seek(
-        self: Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-        pos: std::io::SeekFrom,
-    ) -> Poll<std::io::Result<u64>> {
-        self.project().inner.poll_seek(cx, pos)
+    /// Lists all files, as archive entry paths.
+    async fn entry_paths(&self) -> Result<Vec<String>> {
+        let files = self.dir.files().await?;
+        Ok(files
+            .into_iter()
+            .map(|path| format!("{}{path}", self.prefix))
+            .collect())
     }
-}
 
-impl<T: AsyncRead + Unpin> AsyncRead for Count<T> {
-    fn poll_read(
-        self: Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-        buf: &mut [u8],
-    ) -> Poll<std::io::Result<usize>> {
-        self.project().inner.poll_read(cx, buf)
+    async fn contains(&self, entry_path: &str) -> bool {
+        match self.dir_path(entry_path) {
+            Some(path) => self.dir.contains(path).await,
+            None => false,
+        }
     }
 
-    fn poll_read_vectored(
-        self: Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-        bufs: &mut [std::io::IoSliceMut<'_>],
-    ) -> Poll<std::io::Result<usize>> {
-        self.project().inner.poll_read_vectored(cx, bufs)
+    async fn open(&self, entry_path: &str) -> Result<Compat<File>> {
+        let path = self
+            .dir_path(entry_path)
+            .with_context(|| format!("`{entry_path}` is outside of `{ROOT_PATH}`"))?;
+        Ok(self.dir.open(path).await?.compat())
     }
 }
 
-impl<T: AsyncBufRead + Unpin> AsyncBufRead for Count<T> {
-    fn poll_fill_buf<'a>(
-        self: Pin<&'a mut Self>,
-        cx: &mut std::task::Context<'_>,
-    ) -> Poll<std::io::Result<&'a [u8]>> {
-        self.project().inner.poll_fill_buf(cx)
+async fn validate(open_archive: Arc<dyn OpenArchive>) -> Result<()> {
+    if let Some(dir) = open_archive.directory() {
+        let dir = AudioDir::new(dir).await;
+        for path in MARKER_PATHS {
+            if dir.contains(path).await {
+                return Ok(());
+            }
+        }
+        bail!("missing one of {MARKER_PATHS:?}");
     }
 
-    fn consume(self: Pin<&mut Self>, amt: usize) {
-        let this = self.project();
-        this.pos.fetch_add(amt as u64, atomic::Ordering::SeqCst);
-        this.inner.consume(amt);
+    let (archive, _, _) = archive_reader(&*open_archive).await?;
+    let mut entries = archive
+        .entries()
+        .context("failed to read archive entries")?;
+    while let Some(entry) = entries.next().await {
+        let entry = entry.context("failed to read entry")?;
+        let path = entry.path().context("failed to read entry path")?;
+        let path = path
+            .to_str()
+            .with_context(|| format!("path {path:?} is not UTF-8"))?;
+
+        if MARKER_PATHS.contains(&path) {
+            return Ok(());
+        }
     }
+    bail!("missing one of {MARKER_PATHS:?}");
 }
 
 async fn import(
-    db: Pool<Sqlite>,
+    job: ImportJob,
     open_archive: Arc<dyn OpenArchive>,
-    meta: DictionaryMeta,
     progress_tx: mpsc::Sender<ImportProgress>,
-) -> Result<DictionaryId> {
-    let mut tx = db.begin().await.context("failed to begin transaction")?;
-    let dictionary_id = insert_dictionary(&mut tx, &meta)
-        .await
-        .context("failed to insert dictionary")?;
-    let mut insert = Inserter::new(&mut tx, dictionary_id).await?;
+) -> Result<()> {
+    if let Some(dir) = open_archive.directory() {
+        import_dir(job, &AudioDir::new(dir).await, &progress_tx).await
+    } else {
+        import_archive(job, &*open_archive, &progress_tx).await
+    }
+}
+
+/// Number of files inserted in each transaction, before checkpointing the job.
+///
+/// The audio of each file is read before the transaction begins, so this also
+/// limits how much audio we hold in memory at once.
+const FILES_PER_CHECKPOINT: usize = 500;
 
+async fn import_archive(
+    mut job: ImportJob,
+    open_archive: &dyn OpenArchive,
+    progress_tx: &mpsc::Sender<ImportProgress>,
+) -> Result<()> {
     debug!("Counting entries and parsing indexes");
-    let mut jpod_rev_index = None::<RevIndex<GenericInfo>>;
-    let mut nhk16_rev_index = None::<RevIndex<Nhk16Info>>;
-    let mut shinmeikai8_rev_index = None::<RevIndex<GenericInfo>>;
+    let mut indexes = PartialIndexes::default();
 
-    let (archive, cursor_pos, buf_len) = archive_reader(&*open_archive).await?;
+    let (archive, cursor_pos, buf_len) = archive_reader(open_archive).await?;
     let mut entries = archive
         .entries()
         .context("failed to read archive entries")?;
@@ -212,31 +209,10 @@ async fn import(
             .with_context(|| format!("path {path:?} is not UTF-8"))?
             .to_owned();
 
-        (async {
-            match path.as_str() {
-                JPOD_INDEX => {
-                    jpod_rev_index = Some(
-                        parse_rev_index::<_, schema::generic::Index, GenericInfo>(&mut entry)
-                            .await?,
-                    );
-                }
-                NHK16_INDEX => {
-                    nhk16_rev_index = Some(
-                        parse_rev_index::<_, schema::nhk16::Index, Nhk16Info>(&mut entry).await?,
-                    );
-                }
-                SHINMEIKAI8_INDEX => {
-                    shinmeikai8_rev_index = Some(
-                        parse_rev_index::<_, schema::generic::Index, GenericInfo>(&mut entry)
-                            .await?,
-                    );
-                }
-                _ => {}
-            }
-            anyhow::Ok(())
-        })
-        .await
-        .with_context(|| format!("failed to process `{path}`"))?;
+        indexes
+            .parse(&path, &mut entry)
+            .await
+            .with_context(|| format!("failed to process `{path}`"))?;
 
         num_entries += 1;
         if num_entries % 2000 == 0 {
@@ -253,19 +229,14 @@ async fn import(
     }
     debug!("{num_entries} total entries");
     _ = progress_tx.try_send(ImportProgress { frac: 0.5 });
+    let indexes = indexes.finish();
 
-    let jpod_rev_index =
-        jpod_rev_index.with_context(|| format!("no JPod index at `{JPOD_INDEX}`"))?;
-    let nhk16_rev_index =
-        nhk16_rev_index.with_context(|| format!("no NHK index at `{NHK16_INDEX}`"))?;
-    let shinmeikai8_rev_index = shinmeikai8_rev_index
-        .with_context(|| format!("no Shinmeikai index at `{SHINMEIKAI8_INDEX}`"))?;
-
-    let (archive, _, _) = archive_reader(&*open_archive).await?;
+    let (archive, _, _) = archive_reader(open_archive).await?;
     let mut entries = archive
         .entries()
         .context("failed to read archive entries")?;
     let mut entries_done = 0usize;
+    let mut batch = Vec::new();
     while let Some(entry) = entries.next().await {
         let mut entry = entry.context("failed to read archive entry")?;
         if entry.header().entry_type() != EntryType::Regular {
@@ -278,66 +249,15 @@ async fn import(
             .with_context(|| format!("path {path:?} is not UTF-8"))?
             .to_owned();
 
-        trace!("Importing {path:?}");
-        (async {
-            if let Some(path) = path.strip_prefix(FORVO_PATH) {
-                trace!("Importing as Forvo");
-                import_forvo(&mut insert, path, &mut entry)
-                    .await
-                    .context("failed to import Forvo file")?;
-            } else if let Some(path) = path.strip_prefix(JPOD_MEDIA) {
-                trace!("Importing as JPod");
-                import_by_rev_index(
-                    &mut insert,
-                    path,
-                    &mut entry,
-                    &jpod_rev_index,
-                    |info| info.term.as_ref().into_iter(),
-                    |audio, _info| Jpod { audio },
-                )
-                .await?;
-            } else if let Some(path) = path.strip_prefix(NHK16_AUDIO) {
-                trace!("Importing as NHK");
-                import_by_rev_index(
-                    &mut insert,
-                    path,
-                    &mut entry,
-                    &nhk16_rev_index,
-                    |info| info.terms.iter(),
-                    |audio, info| Nhk16 {
-                        audio,
-                        pitch_positions: info
-                            .pitch_positions
-                            .iter()
-                            .copied()
-                            .map(PitchPosition)
-                            // remove duplicates
-                            .collect::<HashSet<_>>()
-                            .into_iter()
-                            .collect(),
-                    },
-                )
-                .await?;
-            } else if let Some(path) = path.strip_prefix(SHINMEIKAI8_MEDIA) {
-                trace!("Importing as Shinmeikai");
-                import_by_rev_index(
-                    &mut insert,
-                    path,
-                    &mut entry,
-                    &shinmeikai8_rev_index,
-                    |info| info.term.as_ref().into_iter(),
-                    |audio, info| Shinmeikai8 {
-                        audio,
-                        pitch_number: info.pitch_number.map(PitchPosition),
-                        pitch_pattern: info.pitch_pattern.clone(),
-                    },
-                )
-                .await?;
-            }
-            anyhow::Ok(())
-        })
-        .await
-        .with_context(|| format!("failed to process `{path}`"))?;
+        if !job.is_done(&path) {
+            let file = parse_file(&path, &mut entry, &indexes)
+                .await
+                .with_context(|| format!("failed to process `{path}`"))?;
+            batch.push((path, file));
+        }
+        if batch.len() >= FILES_PER_CHECKPOINT {
+            insert_files(&mut job, mem::take(&mut batch)).await?;
+        }
 
         entries_done += 1;
         if entries_done % 2000 == 0 {
@@ -348,10 +268,389 @@ async fn import(
             });
         }
     }
+    insert_files(&mut job, batch).await?;
+
+    let tx = job.begin().await?;
+    job.finish(tx).await
+}
+
+async fn import_dir(
+    mut job: ImportJob,
+    dir: &AudioDir,
+    progress_tx: &mpsc::Sender<ImportProgress>,
+) -> Result<()> {
+    debug!("Listing files and parsing indexes");
+    let mut indexes = PartialIndexes::default();
+    for path in INDEX_PATHS {
+        if !dir.contains(path).await {
+            continue;
+        }
+        let mut file = dir.open(path).await?;
+        indexes
+            .parse(path, &mut file)
+            .await
+            .with_context(|| format!("failed to process `{path}`"))?;
+    }
+    if let Some(path) = dir.dir_path(ENTRIES_DB) {
+        let path = dir.dir.root().join(path);
+        if fs::try_exists(&path).await.unwrap_or(false) {
+            indexes.entries = entries::read(&path)
+                .await
+                .with_context(|| format!("failed to process `{ENTRIES_DB}`"))?;
+        }
+    }
+    let indexes = indexes.finish();
+
+    let paths = dir.entry_paths().await?;
+    let num_files = paths.len();
+    debug!("{num_files} total files");
+
+    let mut batch = Vec::new();
+    for (index, path) in paths.into_iter().enumerate() {
+        if !job.is_done(&path) {
+            let mut reader = dir.open(&path).await?;
+            let file = parse_file(&path, &mut reader, &indexes)
+                .await
+                .with_context(|| format!("failed to process `{path}`"))?;
+            batch.push((path, file));
+        }
+        if batch.len() >= FILES_PER_CHECKPOINT {
+            insert_files(&mut job, mem::take(&mut batch)).await?;
+        }
 
-    insert.flush().await.context("failed to flush records")?;
-    tx.commit().await.context("failed to commit transaction")?;
-    Ok(dictionary_id)
+        let files_done = index + 1;
+        if files_done % 2000 == 0 {
+            trace!("{files_done} / {num_files} files inserted");
+            let frac = (files_done as f64) / (num_files as f64);
+            _ = progress_tx.try_send(ImportProgress { frac });
+        }
+    }
+    insert_files(&mut job, batch).await?;
+
+    let tx = job.begin().await?;
+    job.finish(tx).await
+}
+
+/// Inserts files which have already been parsed in a single transaction, and
+/// checkpoints them.
+///
+/// Files which aren't audio of any source are still checkpointed, so that they
+/// are skipped when the job is resumed.
+async fn insert_files(job: &mut ImportJob, files: Vec<(String, Option<AudioFile>)>) -> Result<()> {
+    let mut tx = job.begin().await?;
+    let mut insert = job.inserter(&mut tx).await?;
+    let mut paths = Vec::with_capacity(files.len());
+    for (path, file) in files {
+        if let Some(file) = file {
+            insert_file(&mut insert, file)
+                .await
+                .with_context(|| format!("failed to import `{path}`"))?;
+        }
+        paths.push(path);
+    }
+    drop(insert);
+    job.checkpoint(tx, paths)
+        .await
+        .context("failed to checkpoint files")
+}
+
+/// Paths of the JSON indexes of each source.
+const INDEX_PATHS: [&str; 5] = [
+    JPOD_INDEX,
+    NHK16_INDEX,
+    SHINMEIKAI8_INDEX,
+    DAIJISEN_INDEX,
+    OUBUNSHA_INDEX,
+];
+
+/// Reverse indexes of each source, while they are still being read.
+#[derive(Default)]
+struct PartialIndexes {
+    jpod: Option<RevIndex<GenericInfo>>,
+    nhk16: Option<RevIndex<Nhk16Info>>,
+    shinmeikai8: Option<RevIndex<GenericInfo>>,
+    daijisen: Option<RevIndex<GenericInfo>>,
+    oubunsha: Option<RevIndex<GenericInfo>>,
+    /// Rows of the add-on's `entries.db`, if it exists.
+    entries: Vec<entries::Entry>,
+}
+
+/// Reverse index of each source, or [`None`] if the source isn't included.
+///
+/// Files from a source without an index are skipped, since we don't know
+/// which terms they are for.
+struct Indexes {
+    jpod: Option<RevIndex<GenericInfo>>,
+    nhk16: Option<RevIndex<Nhk16Info>>,
+    shinmeikai8: Option<RevIndex<GenericInfo>>,
+    daijisen: Option<RevIndex<GenericInfo>>,
+    oubunsha: Option<RevIndex<GenericInfo>>,
+}
+
+impl PartialIndexes {
+    /// Parses the file at `path` if it is one of the source indexes.
+    async fn parse<R: AsyncRead + Unpin>(&mut self, path: &str, reader: &mut R) -> Result<()> {
+        match path {
+            JPOD_INDEX => {
+                self.jpod =
+                    Some(parse_rev_index::<_, schema::generic::Index, GenericInfo>(reader).await?);
+            }
+            NHK16_INDEX => {
+                self.nhk16 =
+                    Some(parse_rev_index::<_, schema::nhk16::Index, Nhk16Info>(reader).await?);
+            }
+            SHINMEIKAI8_INDEX => {
+                self.shinmeikai8 =
+                    Some(parse_rev_index::<_, schema::generic::Index, GenericInfo>(reader).await?);
+            }
+            DAIJISEN_INDEX => {
+                self.daijisen =
+                    Some(parse_rev_index::<_, schema::generic::Index, GenericInfo>(reader).await?);
+            }
+            OUBUNSHA_INDEX => {
+                self.oubunsha =
+                    Some(parse_rev_index::<_, schema::generic::Index, GenericInfo>(reader).await?);
+            }
+            ENTRIES_DB => {
+                // SQLite can only open databases from a file, so we extract it first
+                let db_file = NamedTempFile::new().context("failed to create temporary file")?;
+                let mut writer = File::create(db_file.path())
+                    .await
+                    .context("failed to open temporary file")?
+                    .compat_write();
+                futures::io::copy(reader, &mut writer)
+                    .await
+                    .context("failed to extract database")?;
+                drop(writer);
+                self.entries = entries::read(db_file.path()).await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Uses the `entries.db` index for any sources which don't have their own
+    /// JSON index.
+    ///
+    /// The JSON indexes are preferred, since they also hold pitch accent
+    /// information.
+    fn finish(self) -> Indexes {
+        let from_entries = |source: &str, media: &str| {
+            let mut for_path = HashMap::<String, GenericInfo>::new();
+            for entry in &self.entries {
+                if entry.source != source {
+                    continue;
+                }
+                let Some(term) = Term::from_parts(
+                    NormString::new(entry.expression.clone()),
+                    entry.reading.clone().and_then(NormString::new),
+                ) else {
+                    continue;
+                };
+                // the path may or may not include the media directory
+                let path = entry.file.strip_prefix(media).unwrap_or(&entry.file);
+                for_path
+                    .entry(path.to_owned())
+                    .or_default()
+                    .terms
+                    .insert(term);
+            }
+            if for_path.is_empty() {
+                None
+            } else {
+                debug!("Using `{ENTRIES_DB}` as the index for `{source}`");
+                Some(RevIndex { for_path })
+            }
+        };
+
+        let jpod = self.jpod.or_else(|| from_entries("jpod", "media/"));
+        let nhk16 = self.nhk16.or_else(|| {
+            from_entries("nhk16", "audio/").map(|index| RevIndex {
+                for_path: index
+                    .for_path
+                    .into_iter()
+                    .map(|(path, info)| {
+                        let info = Nhk16Info {
+                            terms: info.terms,
+                            pitch_positions: Vec::new(),
+                        };
+                        (path, info)
+                    })
+                    .collect(),
+            })
+        });
+        let shinmeikai8 = self
+            .shinmeikai8
+            .or_else(|| from_entries("shinmeikai8", "media/"));
+        let daijisen = self.daijisen.or_else(|| from_entries("daijisen", "media/"));
+        let oubunsha = self.oubunsha.or_else(|| from_entries("oubunsha", "media/"));
+
+        for (index, name) in [
+            (jpod.is_some(), "JPod"),
+            (nhk16.is_some(), "NHK"),
+            (shinmeikai8.is_some(), "Shinmeikai"),
+            (daijisen.is_some(), "Daijisen"),
+            (oubunsha.is_some(), "Oubunsha"),
+        ] {
+            if !index {
+                debug!("No {name} index, skipping its files");
+            }
+        }
+        Indexes {
+            jpod,
+            nhk16,
+            shinmeikai8,
+            daijisen,
+            oubunsha,
+        }
+    }
+}
+
+/// Audio record of a single file, and the terms which it is found under.
+struct AudioFile {
+    record: AudioRecord,
+    terms: Vec<Term>,
+}
+
+#[derive(From)]
+enum AudioRecord {
+    Forvo(Forvo),
+    Jpod(Jpod),
+    Nhk16(Nhk16),
+    Shinmeikai8(Shinmeikai8),
+    Daijisen(Daijisen),
+    Oubunsha(Oubunsha),
+}
+
+/// Reads the audio of the file at `path`, or returns [`None`] if it isn't
+/// audio of any source that we have an index for.
+async fn parse_file<R: AsyncRead + Unpin>(
+    path: &str,
+    reader: &mut R,
+    indexes: &Indexes,
+) -> Result<Option<AudioFile>> {
+    trace!("Importing {path:?}");
+    if let Some(path) = path.strip_prefix(FORVO_PATH) {
+        trace!("Importing as Forvo");
+        parse_forvo(path, reader)
+            .await
+            .map(Some)
+            .context("failed to import Forvo file")
+    } else if let Some(path) = path.strip_prefix(JPOD_MEDIA) {
+        let Some(index) = &indexes.jpod else {
+            return Ok(None);
+        };
+        trace!("Importing as JPod");
+        parse_by_rev_index(
+            path,
+            reader,
+            index,
+            |info| info.terms.iter(),
+            |audio, _info| Jpod { audio },
+        )
+        .await
+    } else if let Some(path) = path.strip_prefix(NHK16_AUDIO) {
+        let Some(index) = &indexes.nhk16 else {
+            return Ok(None);
+        };
+        trace!("Importing as NHK");
+        parse_by_rev_index(
+            path,
+            reader,
+            index,
+            |info| info.terms.iter(),
+            |audio, info| Nhk16 {
+                audio,
+                pitch_positions: info
+                    .pitch_positions
+                    .iter()
+                    .copied()
+                    .map(PitchPosition)
+                    // remove duplicates
+                    .collect::<HashSet<_>>()
+                    .into_iter()
+                    .collect(),
+            },
+        )
+        .await
+    } else if let Some(path) = path.strip_prefix(SHINMEIKAI8_MEDIA) {
+        let Some(index) = &indexes.shinmeikai8 else {
+            return Ok(None);
+        };
+        trace!("Importing as Shinmeikai");
+        parse_by_rev_index(
+            path,
+            reader,
+            index,
+            |info| info.terms.iter(),
+            |audio, info| Shinmeikai8 {
+                audio,
+                pitch_number: info.pitch_number.map(PitchPosition),
+                pitch_pattern: info.pitch_pattern.clone(),
+            },
+        )
+        .await
+    } else if let Some(path) = path.strip_prefix(DAIJISEN_MEDIA) {
+        let Some(index) = &indexes.daijisen else {
+            return Ok(None);
+        };
+        trace!("Importing as Daijisen");
+        parse_by_rev_index(
+            path,
+            reader,
+            index,
+            |info| info.terms.iter(),
+            |audio, info| Daijisen {
+                audio,
+                pitch_number: info.pitch_number.map(PitchPosition),
+                pitch_pattern: info.pitch_pattern.clone(),
+            },
+        )
+        .await
+    } else if let Some(path) = path.strip_prefix(OUBUNSHA_MEDIA) {
+        let Some(index) = &indexes.oubunsha else {
+            return Ok(None);
+        };
+        trace!("Importing as Oubunsha");
+        parse_by_rev_index(
+            path,
+            reader,
+            index,
+            |info| info.terms.iter(),
+            |audio, info| Oubunsha {
+                audio,
+                pitch_number: info.pitch_number.map(PitchPosition),
+                pitch_pattern: info.pitch_pattern.clone(),
+            },
+        )
+        .await
+    } else {
+        Ok(None)
+    }
+}
+
+async fn insert_file(insert: &mut Inserter<'_, '_>, file: AudioFile) -> Result<()> {
+    let record_id = match &file.record {
+        AudioRecord::Forvo(record) => insert.record(record).await,
+        AudioRecord::Jpod(record) => insert.record(record).await,
+        AudioRecord::Nhk16(record) => insert.record(record).await,
+        AudioRecord::Shinmeikai8(record) => insert.record(record).await,
+        AudioRecord::Daijisen(record) => insert.record(record).await,
+        AudioRecord::Oubunsha(record) => insert.record(record).await,
+    }
+    .context("failed to insert record")?;
+
+    for term in file.terms {
+        insert
+            .term_record(term.clone(), record_id)
+            .await
+            .with_context(|| format!("failed to insert term record {term:?}"))?;
+    }
+
+    // we need to flush after each insert, otherwise we get stuck while importing NHK
+    // for some godforsaken unknown reason
+    insert.flush().await.context("failed to flush")?;
+    Ok(())
 }
 
 #[derive(Debug, Deref)]
@@ -359,7 +658,7 @@ struct RevIndex<Rev> {
     for_path: HashMap<String, Rev>,
 }
 
-async fn parse_rev_index<R, Fwd, Rev>(entry: &mut async_tar::Entry<R>) -> Result<RevIndex<Rev>>
+async fn parse_rev_index<R, Fwd, Rev>(entry: &mut R) -> Result<RevIndex<Rev>>
 where
     Fwd: DeserializeOwned + TryInto<RevIndex<Rev>, Error = anyhow::Error>,
     R: AsyncRead + Unpin,
@@ -376,7 +675,7 @@ where
 
 #[derive(Debug, Default)]
 struct GenericInfo {
-    term: Option<Term>,
+    terms: IndexSet<Term>,
     pitch_pattern: Option<NormString>,
     pitch_number: Option<u64>,
 }
@@ -392,21 +691,25 @@ impl TryFrom<schema::generic::Index> for RevIndex<GenericInfo> {
             };
             for path in paths {
                 let entry = for_path.entry(path).or_default();
-                entry.term = Some(term.clone());
+                entry.terms.insert(term.clone());
             }
         }
         for (path, info) in value.files {
             let entry = for_path.entry(path).or_default();
 
             if let Some(reading) = info.kana_reading.and_then(NormString::new) {
-                match &mut entry.term {
-                    Some(term) => {
-                        term.set_reading(reading);
-                    }
-                    None => {
-                        entry.term = Term::from_reading(reading);
-                    }
-                }
+                entry.terms = if entry.terms.is_empty() {
+                    Term::from_reading(reading).into_iter().collect()
+                } else {
+                    entry
+                        .terms
+                        .drain(..)
+                        .map(|mut term| {
+                            term.set_reading(reading.clone());
+                            term
+                        })
+                        .collect()
+                };
             }
 
             if let Some(pitch_pattern) = info.pitch_pattern.and_then(NormString::new) {
@@ -475,11 +778,7 @@ impl TryFrom<schema::nhk16::Index> for RevIndex<Nhk16Info> {
     }
 }
 
-pub async fn import_forvo<R: AsyncRead + Unpin>(
-    insert: &mut Inserter<'_, '_>,
-    path: &str,
-    entry: &mut async_tar::Entry<R>,
-) -> Result<()> {
+async fn parse_forvo<R: AsyncRead + Unpin>(path: &str, entry: &mut R) -> Result<AudioFile> {
     let mut parts = path.split('/');
     let username = parts
         .next()
@@ -491,21 +790,17 @@ pub async fn import_forvo<R: AsyncRead + Unpin>(
         .and_then(|(name, _)| Term::from_headword(name))
         .context("no headword in path")?;
 
-    let record_id = insert
-        .record(&Forvo {
-            username,
-            audio: Audio {
-                format: format_of(path)?,
-                data: encode(entry).await?,
-            },
-        })
-        .await
-        .context("failed to insert record")?;
-    insert
-        .term_record(term, record_id)
-        .await
-        .context("failed to insert term record")?;
-    Ok(())
+    let record = Forvo {
+        username,
+        audio: Audio {
+            format: format_of(path)?,
+            data: encode(entry).await?,
+        },
+    };
+    Ok(AudioFile {
+        record: record.into(),
+        terms: vec![term],
+    })
 }
 
 fn format_of(path: &str) -> Result<AudioFormat> {
@@ -518,17 +813,16 @@ fn format_of(path: &str) -> Result<AudioFormat> {
 }
 
 #[expect(clippy::future_not_send, reason = "we don't care about non-send here")]
-async fn import_by_rev_index<'a, R, Rev, T, Terms>(
-    insert: &mut Inserter<'_, '_>,
+async fn parse_by_rev_index<'a, R, Rev, T, Terms>(
     path: &str,
-    entry: &mut async_tar::Entry<R>,
+    entry: &mut R,
     index: &'a RevIndex<Rev>,
     terms_of: impl FnOnce(&'a Rev) -> Terms,
     into_record: impl FnOnce(Audio, &Rev) -> T,
-) -> Result<()>
+) -> Result<Option<AudioFile>>
 where
     R: AsyncRead + Unpin,
-    T: RecordType,
+    T: Into<AudioRecord>,
     Terms: Iterator<Item = &'a Term>,
 {
     let Some(info) = index.for_path.get(path) else {
@@ -538,34 +832,20 @@ where
             "{path} of type `{}` does not have an index entry, skipping",
             type_name::<T>()
         );
-        return Ok(());
+        return Ok(None);
     };
 
     let audio = Audio {
         format: format_of(path)?,
         data: encode(entry).await?,
     };
-
-    let record = into_record(audio, info);
-    let record_id = insert
-        .record(&record)
-        .await
-        .context("failed to insert record")?;
-
-    for term in terms_of(info) {
-        insert
-            .term_record(term.clone(), record_id)
-            .await
-            .with_context(|| format!("failed to insert term record {term:?}"))?;
-    }
-
-    // we need to flush after each insert, otherwise we get stuck while importing NHK
-    // for some godforsaken unknown reason
-    insert.flush().await.context("failed to flush")?;
-    Ok(())
+    Ok(Some(AudioFile {
+        record: into_record(audio, info).into(),
+        terms: terms_of(info).cloned().collect(),
+    }))
 }
 
-async fn encode<R>(entry: &mut async_tar::Entry<R>) -> Result<Bytes>
+async fn encode<R>(entry: &mut R) -> Result<Bytes>
 where
     R: AsyncRead + Unpin,
 {
@@ -2,6 +2,7 @@
 #![expect(dead_code, reason = "we include all fields for completeness")]
 
 // very important: trailing `/`!!!
+pub const ROOT_PATH: &str = "user_files/";
 pub const FORVO_PATH: &str = "user_files/forvo_files/";
 pub const JPOD_INDEX: &str = "user_files/jpod_files/index.json";
 pub const JPOD_MEDIA: &str = "user_files/jpod_files/media/";
@@ -9,6 +10,12 @@ pub const NHK16_INDEX: &str = "user_files/nhk16_files/entries.json";
 pub const NHK16_AUDIO: &str = "user_files/nhk16_files/audio/";
 pub const SHINMEIKAI8_INDEX: &str = "user_files/shinmeikai8_files/index.json";
 pub const SHINMEIKAI8_MEDIA: &str = "user_files/shinmeikai8_files/media/";
+pub const DAIJISEN_INDEX: &str = "user_files/daijisen_files/index.json";
+pub const DAIJISEN_MEDIA: &str = "user_files/daijisen_files/media/";
+pub const OUBUNSHA_INDEX: &str = "user_files/oubunsha_files/index.json";
+pub const OUBUNSHA_MEDIA: &str = "user_files/oubunsha_files/media/";
+/// Index of every source, built by the local-audio-yomichan Anki add-on.
+pub const ENTRIES_DB: &str = "user_files/entries.db";
 
 pub const MARKER_PATHS: &[&str] = &[
     FORVO_PATH,
@@ -18,6 +25,11 @@ pub const MARKER_PATHS: &[&str] = &[
     NHK16_AUDIO,
     SHINMEIKAI8_INDEX,
     SHINMEIKAI8_MEDIA,
+    DAIJISEN_INDEX,
+    DAIJISEN_MEDIA,
+    OUBUNSHA_INDEX,
+    OUBUNSHA_MEDIA,
+    ENTRIES_DB,
 ];
 
 pub mod generic {
@@ -1,9 +1,13 @@
 mod schema;
+mod validate;
 
 use {
-    super::{Archive, ImportContinue, ImportKind, ImportProgress, OpenArchive},
-    crate::import::{insert::Inserter, insert_dictionary},
-    anyhow::{Context, Result},
+    super::{
+        Archive, Directory, ImportContinue, ImportKind, ImportProgress, OpenArchive,
+        ValidationReport, duplicate,
+    },
+    crate::import::{ImportJob, Leniency, continue_with, insert::Inserter},
+    anyhow::{Context, Result, bail},
     async_zip::base::read::seek::ZipFileReader,
     derive_more::From,
     futures::future::BoxFuture,
@@ -12,7 +16,7 @@ use {
         TERM_BANK_PATTERN, TERM_META_BANK_PATTERN,
     },
     serde::de::DeserializeOwned,
-    sqlx::{Pool, Sqlite},
+    serde_json::json,
     std::{iter, sync::Arc},
     tokio::{
         sync::{Semaphore, mpsc},
@@ -21,11 +25,14 @@ use {
     tokio_util::compat::Compat,
     tracing::{debug, trace},
     wordbase_api::{
-        DictionaryId, DictionaryKind, DictionaryMeta, FrequencyValue, NoHeadwordOrReading,
-        NormString, Term,
+        DictionaryKind, DictionaryMeta, FrequencyValue, NoHeadwordOrReading, NormString,
+        RecordType, Term,
         dict::{
             jpn::PitchPosition,
-            yomitan::{Frequency, Glossary, GlossaryTag, Pitch, structured},
+            yomitan::{
+                Frequency, Glossary, GlossaryTag, Kanji, KanjiFrequency, KanjiStat, Phonetic,
+                Pitch, Redirect, structured,
+            },
         },
     },
 };
@@ -39,116 +46,176 @@ impl ImportKind for Yomitan {
 
     fn start_import(
         &self,
-        db: Pool<Sqlite>,
         open_archive: Arc<dyn OpenArchive>,
         progress_tx: mpsc::Sender<ImportProgress>,
     ) -> BoxFuture<Result<(DictionaryMeta, ImportContinue)>> {
-        Box::pin(async move {
-            let (meta, continuation) = start_import(db, open_archive, progress_tx).await?;
-            Ok((meta, Box::pin(continuation) as ImportContinue))
-        })
+        Box::pin(start_import(open_archive, progress_tx))
+    }
+
+    fn validate<'a>(
+        &'a self,
+        open_archive: Arc<dyn OpenArchive>,
+        report: &'a mut ValidationReport,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(validate::validate(open_archive, report))
     }
 }
 
-async fn archive_reader(
-    open_archive: &dyn OpenArchive,
-) -> Result<ZipFileReader<Compat<Box<dyn Archive>>>> {
-    let archive = open_archive
-        .open_archive()
-        .await
-        .context("failed to open archive")?;
-    let archive = ZipFileReader::with_tokio(archive)
-        .await
-        .context("failed to open zip archive")?;
-    Ok(archive)
+/// Files of a Yomitan dictionary, either in a zip archive or unpacked into a
+/// directory.
+enum Files {
+    Zip(ZipFileReader<Compat<Box<dyn Archive>>>),
+    Dir(Directory),
+}
+
+impl Files {
+    async fn open(open_archive: &dyn OpenArchive) -> Result<Self> {
+        if let Some(dir) = open_archive.directory() {
+            return Ok(Self::Dir(dir.clone()));
+        }
+
+        let archive = open_archive
+            .open_archive()
+            .await
+            .context("failed to open archive")?;
+        let archive = ZipFileReader::with_tokio(archive)
+            .await
+            .context("failed to open zip archive")?;
+        Ok(Self::Zip(archive))
+    }
+
+    /// Lists the index and path of every file, excluding directories.
+    async fn paths(&self) -> Result<Vec<(usize, String)>> {
+        match self {
+            Self::Zip(archive) => archive
+                .file()
+                .entries()
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| !entry.dir().unwrap_or(false))
+                .map(|(index, entry)| {
+                    let filename = entry.filename();
+                    let path = filename
+                        .as_str()
+                        .with_context(|| format!("`{filename:?}` is not a UTF-8 file name"))?;
+                    Ok((index, path.to_owned()))
+                })
+                .collect(),
+            Self::Dir(dir) => Ok(dir.files().await?.into_iter().enumerate().collect()),
+        }
+    }
+
+    async fn contains(&self, path: &str) -> bool {
+        match self {
+            Self::Zip(archive) => archive
+                .file()
+                .entries()
+                .iter()
+                .any(|entry| entry.filename().as_str().is_ok_and(|name| name == path)),
+            Self::Dir(dir) => dir.contains(path).await,
+        }
+    }
+
+    /// Reads the file with the given index and path (from [`Files::paths`])
+    /// into memory.
+    async fn read(&mut self, index: usize, path: &str) -> Result<Vec<u8>> {
+        match self {
+            Self::Zip(archive) => {
+                let mut entry = archive
+                    .reader_with_entry(index)
+                    .await
+                    .context("failed to read entry")?;
+                let mut data = Vec::new();
+                entry
+                    .read_to_end_checked(&mut data)
+                    .await
+                    .context("failed to read entry into memory")?;
+                Ok(data)
+            }
+            Self::Dir(dir) => dir.read(path).await,
+        }
+    }
+
+    async fn read_index(&mut self) -> Result<schema::Index> {
+        let index_index = self
+            .paths()
+            .await?
+            .into_iter()
+            .find(|(_, path)| path == INDEX_PATH)
+            .map(|(index, _)| index)
+            .with_context(|| format!("no `{INDEX_PATH}` in archive"))?;
+        let index = self
+            .read(index_index, INDEX_PATH)
+            .await
+            .context("failed to read index")?;
+        serde_json::from_slice::<schema::Index>(&index).context("failed to parse index")
+    }
 }
 
 async fn validate(open_archive: Arc<dyn OpenArchive>) -> Result<()> {
-    archive_reader(&*open_archive)
-        .await?
-        .file()
-        .entries()
-        .iter()
-        .find(|entry| {
-            let Ok(path) = entry.filename().as_str() else {
-                return false;
-            };
-            path == INDEX_PATH
-        })
-        .with_context(|| format!("no `{INDEX_PATH}` in archive"))?;
+    let files = Files::open(&*open_archive).await?;
+    if !files.contains(INDEX_PATH).await {
+        bail!("no `{INDEX_PATH}` in archive");
+    }
     Ok(())
 }
 
 async fn start_import(
-    db: Pool<Sqlite>,
     open_archive: Arc<dyn OpenArchive>,
     progress_tx: mpsc::Sender<ImportProgress>,
-) -> Result<(DictionaryMeta, impl Future<Output = Result<DictionaryId>>)> {
-    let mut archive = archive_reader(&*open_archive).await?;
-    let index_index = archive
-        .file()
-        .entries()
-        .iter()
-        .enumerate()
-        .filter(|(_, entry)| {
-            entry
-                .filename()
-                .as_str()
-                .is_ok_and(|name| name == INDEX_PATH)
-        })
-        .map(|(index, _)| index)
-        .next()
-        .with_context(|| format!("no `{INDEX_PATH}` in archive"))?;
-    let mut index_entry = archive
-        .reader_with_entry(index_index)
-        .await
-        .context("failed to start reading index")?;
-    let mut index = Vec::new();
-    index_entry
-        .read_to_end_checked(&mut index)
-        .await
-        .context("failed to read index into memory")?;
-    let index = serde_json::from_slice::<schema::Index>(&index).context("failed to parse index")?;
+) -> Result<(DictionaryMeta, ImportContinue)> {
+    let index = Files::open(&*open_archive).await?.read_index().await?;
+    Ok((
+        to_meta(&index),
+        continue_with(move |job| continue_import(job, open_archive, index, progress_tx)),
+    ))
+}
 
+fn to_meta(index: &schema::Index) -> DictionaryMeta {
     let mut meta = DictionaryMeta::new(DictionaryKind::Yomitan, index.title.clone());
     meta.version = Some(index.revision.clone());
     index.description.clone_into(&mut meta.description);
     index.url.clone_into(&mut meta.url);
     index.attribution.clone_into(&mut meta.attribution);
-
-    Ok((
-        meta.clone(),
-        continue_import(db, open_archive, meta, index, progress_tx),
-    ))
+    meta.is_updatable = index.is_updatable.unwrap_or(false);
+    index.index_url.clone_into(&mut meta.index_url);
+    index.download_url.clone_into(&mut meta.download_url);
+    let extra = json!([
+        index.author,
+        index.source_language.as_ref().map(|lang| &lang.0),
+        index.target_language.as_ref().map(|lang| &lang.0),
+        index.format as u8,
+    ]);
+    meta.index_hash = Some(duplicate::hash_index(&meta, extra));
+    meta
 }
 
 // TODO: make this configurable somehow
 const BANK_BUF_CAP: usize = 1;
 
+#[expect(
+    clippy::significant_drop_tightening,
+    reason = "each bank's transaction is moved into `checkpoint`"
+)]
 async fn continue_import(
-    db: Pool<Sqlite>,
+    mut job: ImportJob,
     open_archive: Arc<dyn OpenArchive>,
-    meta: DictionaryMeta,
     index: schema::Index,
     progress_tx: mpsc::Sender<ImportProgress>,
-) -> Result<DictionaryId> {
+) -> Result<()> {
     trace!("Importing Yomitan");
 
     // stage 1: read dictionary meta and find what banks we have
-    let archive = archive_reader(&*open_archive).await?;
+    let mut files = Files::open(&*open_archive).await?;
 
     let mut tag_bank_paths = Vec::<(usize, String)>::new();
     let mut term_bank_paths = Vec::<(usize, String)>::new();
     let mut term_meta_bank_paths = Vec::<(usize, String)>::new();
     let mut kanji_bank_paths = Vec::<(usize, String)>::new();
     let mut kanji_meta_bank_paths = Vec::<(usize, String)>::new();
-    for (index, entry) in archive.file().entries().iter().enumerate() {
-        let filename = entry.filename();
-        let path = filename
-            .as_str()
-            .with_context(|| format!("`{filename:?}` is not a UTF-8 file name"))?
-            .to_owned();
-
+    // any other files are media (mostly images) referenced by glossaries
+    let mut media_paths = Vec::<(usize, String)>::new();
+    for (index, path) in files.paths().await? {
         if TAG_BANK_PATTERN.is_match(&path) {
             tag_bank_paths.push((index, path));
         } else if TERM_BANK_PATTERN.is_match(&path) {
@@ -159,12 +226,32 @@ async fn continue_import(
             kanji_bank_paths.push((index, path));
         } else if KANJI_META_BANK_PATTERN.is_match(&path) {
             kanji_meta_bank_paths.push((index, path));
+        } else if path != INDEX_PATH {
+            media_paths.push((index, path));
         }
     }
+    // all media is counted as a single bank for progress
     let num_banks = term_bank_paths.len()
         + term_meta_bank_paths.len()
         + kanji_bank_paths.len()
-        + kanji_meta_bank_paths.len();
+        + kanji_meta_bank_paths.len()
+        + usize::from(!media_paths.is_empty());
+
+    // banks which were inserted by a previous run of this job are skipped
+    let mut banks_done = 0usize;
+    for bank_paths in [
+        &mut term_bank_paths,
+        &mut term_meta_bank_paths,
+        &mut kanji_bank_paths,
+        &mut kanji_meta_bank_paths,
+    ] {
+        let len = bank_paths.len();
+        bank_paths.retain(|(_, path)| !job.is_done(path));
+        banks_done += len - bank_paths.len();
+    }
+    if banks_done > 0 {
+        debug!("Skipping {banks_done} banks which were already imported");
+    }
 
     // stage 2: spawn tasks to parse banks
     // - parse a bank in another task
@@ -173,12 +260,13 @@ async fn continue_import(
     // - do NOT parse all banks first, then insert them in bulk -
     //   this will use a lot of memory! (Jitendex would use ~4 GB)
     let parse_permits = Arc::new(Semaphore::new(BANK_BUF_CAP));
-    let (to_insert_tx, mut to_insert_rx) = mpsc::channel::<Bank>(BANK_BUF_CAP * 2);
+    let (to_insert_tx, mut to_insert_rx) = mpsc::channel::<(String, Bank)>(BANK_BUF_CAP * 2);
     let mut tasks = JoinSet::new();
 
     spawn_parse_tasks::<schema::Term>(
         term_bank_paths,
         &open_archive,
+        job.leniency(),
         &parse_permits,
         &to_insert_tx,
         &mut tasks,
@@ -186,6 +274,7 @@ async fn continue_import(
     spawn_parse_tasks::<schema::TermMeta>(
         term_meta_bank_paths,
         &open_archive,
+        job.leniency(),
         &parse_permits,
         &to_insert_tx,
         &mut tasks,
@@ -193,6 +282,7 @@ async fn continue_import(
     spawn_parse_tasks::<schema::Kanji>(
         kanji_bank_paths,
         &open_archive,
+        job.leniency(),
         &parse_permits,
         &to_insert_tx,
         &mut tasks,
@@ -200,6 +290,7 @@ async fn continue_import(
     spawn_parse_tasks::<schema::KanjiMeta>(
         kanji_meta_bank_paths,
         &open_archive,
+        job.leniency(),
         &parse_permits,
         &to_insert_tx,
         &mut tasks,
@@ -210,21 +301,18 @@ async fn continue_import(
     // since we need them for inserting term banks later
     let mut tag_bank = Vec::new();
     for (entry_index, entry_path) in tag_bank_paths {
-        let mut bank = parse_bank::<schema::Tag>(&*open_archive, entry_index)
+        match parse_bank::<schema::Tag>(&*open_archive, entry_index, &entry_path, job.leniency())
             .await
-            .with_context(|| format!("failed to parse tag bank `{entry_path}`"))?;
-        tag_bank.append(&mut bank);
+            .with_context(|| format!("failed to parse tag bank `{entry_path}`"))
+        {
+            Ok(bank) => tag_bank.extend(bank.into_iter().map(|(_, tag)| tag)),
+            // terms which use these tags are imported without them
+            Err(err) => job.leniency().skip(&entry_path, None, err).await?,
+        }
     }
-    let mut all_tags = tag_bank.into_iter().map(to_term_tag).collect::<Vec<_>>();
-    all_tags.sort_by_key(|tag| tag.name.len());
+    let all_tags = to_all_tags(tag_bank);
 
     // stage 4: start inserting
-    let mut tx = db.begin().await.context("failed to begin transaction")?;
-    let dictionary_id = insert_dictionary(&mut tx, &meta)
-        .await
-        .context("failed to insert dictionary")?;
-    let mut insert = Inserter::new(&mut tx, dictionary_id).await?;
-
     let notify_progress = |banks_done: usize, rows_done: usize, rows_len: usize| {
         // +1 to not trigger on the first row
         if (rows_done + 1) % 500 == 0 {
@@ -234,39 +322,84 @@ async fn continue_import(
         }
     };
 
-    let mut banks_done = 0usize;
-    while let Some(to_insert) = to_insert_rx.recv().await {
+    let leniency = job.leniency().clone();
+    while let Some((entry_path, to_insert)) = to_insert_rx.recv().await {
+        // each bank is inserted in its own transaction, which checkpoints it
+        let mut tx = job.begin().await?;
+        let mut insert = job.inserter(&mut tx).await?;
         match to_insert {
             Bank::TermMeta(bank) => {
                 let rows_len = bank.len();
-                for (row_idx, term_meta) in bank.into_iter().enumerate() {
+                for (rows_done, (row_idx, term_meta)) in bank.into_iter().enumerate() {
                     let headword = term_meta.expression.clone();
-                    import_term_meta(&mut insert, &index, term_meta)
+                    let records = to_term_meta_records(&index, term_meta, &all_tags);
+                    if let Err(err) = async { import_term_meta(&mut insert, records?).await }
                         .await
-                        .with_context(|| format!("failed to import term meta {headword:?}"))?;
-                    notify_progress(banks_done, row_idx, rows_len);
+                        .with_context(|| format!("failed to import term meta {headword:?}"))
+                    {
+                        leniency.skip(&entry_path, Some(row_idx), err).await?;
+                    }
+                    notify_progress(banks_done, rows_done, rows_len);
                 }
             }
             Bank::Term(bank) => {
                 let rows_len = bank.len();
-                for (row_idx, term) in bank.into_iter().enumerate() {
+                for (rows_done, (row_idx, term)) in bank.into_iter().enumerate() {
                     let headword = term.expression.clone();
                     let reading = term.reading.clone();
-                    import_term(&mut insert, term, &all_tags)
+                    let records = to_term_records(term, &all_tags);
+                    if let Err(err) = async { import_term(&mut insert, records?).await }
                         .await
                         .with_context(|| {
                             format!("failed to import term ({headword:?}, {reading:?})")
-                        })?;
-                    notify_progress(banks_done, row_idx, rows_len);
+                        })
+                    {
+                        leniency.skip(&entry_path, Some(row_idx), err).await?;
+                    }
+                    notify_progress(banks_done, rows_done, rows_len);
                 }
             }
             Bank::Kanji(bank) => {
-                _ = bank;
+                let rows_len = bank.len();
+                for (rows_done, (row_idx, kanji)) in bank.into_iter().enumerate() {
+                    let character = kanji.character.clone();
+                    let record = to_kanji_record(kanji, &all_tags);
+                    if let Err(err) = async {
+                        let (term, record) = record?;
+                        import_record(&mut insert, term, &record).await
+                    }
+                    .await
+                    .with_context(|| format!("failed to import kanji {character:?}"))
+                    {
+                        leniency.skip(&entry_path, Some(row_idx), err).await?;
+                    }
+                    notify_progress(banks_done, rows_done, rows_len);
+                }
             }
             Bank::KanjiMeta(bank) => {
-                _ = bank;
+                let rows_len = bank.len();
+                for (rows_done, (row_idx, kanji_meta)) in bank.into_iter().enumerate() {
+                    let character = kanji_meta.character.clone();
+                    let record = to_kanji_frequency(&index, kanji_meta);
+                    if let Err(err) = async {
+                        let (term, record) = record?;
+                        import_record(&mut insert, term, &record).await
+                    }
+                    .await
+                    .with_context(|| format!("failed to import kanji meta {character:?}"))
+                    {
+                        leniency.skip(&entry_path, Some(row_idx), err).await?;
+                    }
+                    notify_progress(banks_done, rows_done, rows_len);
+                }
             }
         }
+        insert.flush().await.context("failed to flush inserts")?;
+        drop(insert);
+        job.checkpoint(tx, [entry_path])
+            .await
+            .context("failed to checkpoint bank")?;
+
         banks_done += 1;
         let frac = banks_done as f64 / num_banks as f64;
         _ = progress_tx.try_send(ImportProgress { frac });
@@ -275,60 +408,109 @@ async fn continue_import(
         res.context("task canceled")??;
     }
 
+    // stage 5: copy media out of the archive,
+    // in the same transaction which finishes the job
+    let mut tx = job.begin().await?;
+    let mut insert = job.inserter(&mut tx).await?;
+    let media_len = media_paths.len();
+    for (row_idx, (entry_index, entry_path)) in media_paths.into_iter().enumerate() {
+        let data = files
+            .read(entry_index, &entry_path)
+            .await
+            .with_context(|| format!("failed to read media `{entry_path}`"))?;
+        insert
+            .media(&entry_path, &data)
+            .await
+            .with_context(|| format!("failed to insert media `{entry_path}`"))?;
+        notify_progress(banks_done, row_idx, media_len);
+    }
+
     debug!("Insert complete, flushing");
     insert.flush().await.context("failed to flush inserts")?;
-    tx.commit().await.context("failed to commit transaction")?;
-    Ok(dictionary_id)
+    drop(insert);
+    job.finish(tx).await
 }
 
+/// Rows of a bank, along with their index in the bank.
+///
+/// In lenient mode, rows which fail to parse are left out, so the position of
+/// a row in this list may not be its index in the bank.
+type Rows<T> = Vec<(usize, T)>;
+
 #[derive(Debug, From)]
 enum Bank {
-    Term(Vec<schema::Term>),
-    TermMeta(Vec<schema::TermMeta>),
-    Kanji(Vec<schema::Kanji>),
-    KanjiMeta(Vec<schema::KanjiMeta>),
+    Term(Rows<schema::Term>),
+    TermMeta(Rows<schema::TermMeta>),
+    Kanji(Rows<schema::Kanji>),
+    KanjiMeta(Rows<schema::KanjiMeta>),
 }
 
 fn spawn_parse_tasks<T>(
     bank_entries: impl IntoIterator<Item = (usize, String)>,
     open_archive: &Arc<dyn OpenArchive>,
+    leniency: &Leniency,
     parse_permits: &Arc<Semaphore>,
-    to_insert_tx: &mpsc::Sender<Bank>,
+    to_insert_tx: &mpsc::Sender<(String, Bank)>,
     tasks: &mut JoinSet<Result<()>>,
 ) where
-    Bank: From<Vec<T>>,
-    Vec<T>: DeserializeOwned,
+    Bank: From<Rows<T>>,
+    T: DeserializeOwned + Send + 'static,
 {
     for (entry_index, entry_path) in bank_entries {
         let open_archive = open_archive.clone();
+        let leniency = leniency.clone();
         let parse_permits = parse_permits.clone();
         let to_insert_tx = to_insert_tx.clone();
         tasks.spawn(async move {
             let _permit = parse_permits.acquire().await?;
-            let bank = parse_bank::<T>(&*open_archive, entry_index)
+            let bank = match parse_bank::<T>(&*open_archive, entry_index, &entry_path, &leniency)
                 .await
-                .with_context(|| format!("failed to parse bank `{entry_path}`"))?;
-            to_insert_tx.send(Bank::from(bank)).await?;
+                .with_context(|| format!("failed to parse bank `{entry_path}`"))
+            {
+                Ok(bank) => bank,
+                Err(err) => {
+                    // the bank isn't checkpointed, so resuming will try it again
+                    leniency.skip(&entry_path, None, err).await?;
+                    return Ok(());
+                }
+            };
+            to_insert_tx.send((entry_path, Bank::from(bank))).await?;
             anyhow::Ok(())
         });
     }
 }
 
-async fn parse_bank<T>(open_archive: &dyn OpenArchive, entry_index: usize) -> Result<Vec<T>>
+async fn parse_bank<T>(
+    open_archive: &dyn OpenArchive,
+    entry_index: usize,
+    entry_path: &str,
+    leniency: &Leniency,
+) -> Result<Rows<T>>
 where
-    Vec<T>: DeserializeOwned,
+    T: DeserializeOwned,
 {
-    let mut archive = archive_reader(open_archive).await?;
-    let mut entry = archive
-        .reader_with_entry(entry_index)
-        .await
-        .context("failed to read entry")?;
-    let mut bank_data = Vec::new();
-    entry
-        .read_to_end_checked(&mut bank_data)
-        .await
-        .context("failed to read bank into memory")?;
-    let bank = serde_json::from_slice::<Vec<T>>(&bank_data).context("failed to parse bank")?;
+    let bank_data = Files::open(open_archive)
+        .await?
+        .read(entry_index, entry_path)
+        .await?;
+    if !leniency.is_lenient() {
+        let bank = serde_json::from_slice::<Vec<T>>(&bank_data).context("failed to parse bank")?;
+        return Ok(bank.into_iter().enumerate().collect());
+    }
+
+    // parse each row on its own, so that one bad row doesn't fail the whole bank
+    let rows = serde_json::from_slice::<Vec<serde_json::Value>>(&bank_data)
+        .context("failed to parse bank")?;
+    let mut bank = Vec::with_capacity(rows.len());
+    for (row_idx, row) in rows.into_iter().enumerate() {
+        match T::deserialize(row) {
+            Ok(row) => bank.push((row_idx, row)),
+            Err(err) => {
+                let err = anyhow::Error::new(err).context("failed to parse row");
+                leniency.skip(entry_path, Some(row_idx), err).await?;
+            }
+        }
+    }
     Ok(bank)
 }
 
@@ -341,11 +523,24 @@ fn to_term_tag(raw: schema::Tag) -> GlossaryTag {
     }
 }
 
-async fn import_term(
-    insert: &mut Inserter<'_, '_>,
-    term_data: schema::Term,
-    all_tags: &[GlossaryTag],
-) -> Result<()> {
+/// Converts the rows of all tag banks into the tags which other rows can
+/// reference.
+fn to_all_tags(tags: Vec<schema::Tag>) -> Vec<GlossaryTag> {
+    let mut all_tags = tags.into_iter().map(to_term_tag).collect::<Vec<_>>();
+    all_tags.sort_by_key(|tag| tag.name.len());
+    all_tags
+}
+
+/// Records converted from a single row of a term bank.
+struct TermRecords {
+    term: Term,
+    redirects: Vec<Redirect>,
+    /// [`None`] for inflected forms, which usually have no glossary content
+    /// of their own.
+    glossary: Option<Glossary>,
+}
+
+fn to_term_records(term_data: schema::Term, all_tags: &[GlossaryTag]) -> Result<TermRecords> {
     let term =
         Term::from_full(term_data.expression, term_data.reading).ok_or(NoHeadwordOrReading)?;
 
@@ -355,29 +550,76 @@ async fn import_term(
     )
     .cloned()
     .collect::<Vec<_>>();
-    let content = term_data
-        .glossary
-        .into_iter()
-        .filter_map(to_content)
-        .collect();
-    let record = Glossary {
+    let term_tags = match_tags(all_tags, &term_data.term_tags)
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut content = Vec::new();
+    let mut redirects = Vec::new();
+    for glossary in term_data.glossary {
+        if let schema::Glossary::Deinflection(deinflection) = glossary {
+            redirects.push(Redirect {
+                uninflected: deinflection.uninflected,
+                rule_chain: deinflection.inflection_rule_chain,
+            });
+        } else {
+            content.extend(to_content(glossary));
+        }
+    }
+
+    let has_glossary = !content.is_empty() || redirects.is_empty();
+    let glossary = has_glossary.then(|| Glossary {
         popularity: term_data.score,
         tags,
         content,
+        term_tags,
+        rules: term_data
+            .rules
+            .split_whitespace()
+            .map(ToOwned::to_owned)
+            .collect(),
+        sequence: term_data.sequence,
+    });
+    Ok(TermRecords {
+        term,
+        redirects,
+        glossary,
+    })
+}
+
+async fn import_term(insert: &mut Inserter<'_, '_>, records: TermRecords) -> Result<()> {
+    for redirect in &records.redirects {
+        import_record(insert, records.term.clone(), redirect)
+            .await
+            .context("failed to insert redirect record")?;
+    }
+    let Some(glossary) = records.glossary else {
+        return Ok(());
     };
 
+    import_record(insert, records.term.clone(), &glossary).await?;
+    insert
+        .frequency(
+            records.term,
+            FrequencyValue::Occurrence(glossary.popularity),
+        )
+        .await
+        .context("failed to insert frequency record")?;
+    Ok(())
+}
+
+async fn import_record(
+    insert: &mut Inserter<'_, '_>,
+    term: Term,
+    record: &impl RecordType,
+) -> Result<()> {
     let record_id = insert
-        .record(&record)
+        .record(record)
         .await
         .context("failed to insert record")?;
     insert
-        .term_record(term.clone(), record_id)
+        .term_record(term, record_id)
         .await
         .context("failed to insert term record")?;
-    insert
-        .frequency(term, FrequencyValue::Occurrence(term_data.score))
-        .await
-        .context("failed to insert frequency record")?;
     Ok(())
 }
 
@@ -398,28 +640,76 @@ fn match_tags<'a>(
     })
 }
 
-async fn import_term_meta(
-    insert: &mut Inserter<'_, '_>,
+/// Gets the tag with exactly this name, or a tag with only a name if the
+/// dictionary does not define one.
+fn find_tag(all_tags: &[GlossaryTag], name: String) -> GlossaryTag {
+    all_tags
+        .iter()
+        .find(|tag| tag.name == name)
+        .cloned()
+        .unwrap_or_else(|| GlossaryTag {
+            name,
+            category: String::new(),
+            description: String::new(),
+            order: 0,
+        })
+}
+
+/// Records converted from a single row of a term meta bank.
+enum TermMetaRecords {
+    Frequency(Term, Frequency),
+    Pitch(Vec<(Term, Pitch)>),
+    Phonetic(Term, Vec<Phonetic>),
+}
+
+fn to_term_meta_records(
     index: &schema::Index,
     term_meta: schema::TermMeta,
-) -> Result<()> {
+    all_tags: &[GlossaryTag],
+) -> Result<TermMetaRecords> {
     let headword = NormString::new(term_meta.expression);
-    match term_meta.data {
+    Ok(match term_meta.data {
         schema::TermMetaData::Frequency(frequency) => {
             // Yomitan dictionaries like VN Freq v2 seem to default to rank-based
             let frequency_mode = index.frequency_mode.unwrap_or(FrequencyMode::RankBased);
             let (record, reading) = to_frequency_and_reading(frequency_mode, frequency);
             let term = Term::from_parts(headword, reading).ok_or(NoHeadwordOrReading)?;
+            TermMetaRecords::Frequency(term, record)
+        }
+        schema::TermMetaData::Pitch(pitch) => {
+            let pitches = to_pitches_and_readings(pitch)
+                .map(|(record, reading)| {
+                    let term = Term::from_parts(headword.clone(), Some(reading))
+                        .ok_or(NoHeadwordOrReading)?;
+                    Ok((term, record))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            TermMetaRecords::Pitch(pitches)
+        }
+        schema::TermMetaData::Phonetic(phonetic) => {
+            let term =
+                Term::from_parts(headword, Some(phonetic.reading)).ok_or(NoHeadwordOrReading)?;
+            let records = phonetic
+                .transcriptions
+                .into_iter()
+                .map(|transcription| Phonetic {
+                    ipa: transcription.ipa,
+                    tags: transcription
+                        .tags
+                        .into_iter()
+                        .map(|name| find_tag(all_tags, name))
+                        .collect(),
+                })
+                .collect();
+            TermMetaRecords::Phonetic(term, records)
+        }
+    })
+}
 
-            let record_id = insert
-                .record(&record)
-                .await
-                .context("failed to insert record")?;
-            insert
-                .term_record(term.clone(), record_id)
-                .await
-                .context("failed to insert term record")?;
-
+async fn import_term_meta(insert: &mut Inserter<'_, '_>, records: TermMetaRecords) -> Result<()> {
+    match records {
+        TermMetaRecords::Frequency(term, record) => {
+            import_record(insert, term.clone(), &record).await?;
             if let Some(value) = record.value {
                 insert
                     .frequency(term, value)
@@ -427,26 +717,61 @@ async fn import_term_meta(
                     .context("failed to insert frequency record")?;
             }
         }
-        schema::TermMetaData::Pitch(pitch) => {
-            for (record, reading) in to_pitches_and_readings(pitch) {
-                let term =
-                    Term::from_parts(headword.clone(), Some(reading)).ok_or(NoHeadwordOrReading)?;
-
-                let record_id = insert
-                    .record(&record)
-                    .await
-                    .context("failed to insert record")?;
-                insert
-                    .term_record(term, record_id)
-                    .await
-                    .context("failed to insert term record")?;
+        TermMetaRecords::Pitch(pitches) => {
+            for (term, record) in pitches {
+                import_record(insert, term, &record).await?;
+            }
+        }
+        TermMetaRecords::Phonetic(term, records) => {
+            for record in records {
+                import_record(insert, term.clone(), &record).await?;
             }
         }
-        schema::TermMetaData::Phonetic(_) => {}
     }
     Ok(())
 }
 
+fn to_kanji_record(kanji: schema::Kanji, all_tags: &[GlossaryTag]) -> Result<(Term, Kanji)> {
+    let term = Term::from_headword(kanji.character).ok_or(NoHeadwordOrReading)?;
+
+    let tags = match_tags(all_tags, &kanji.tags).cloned().collect();
+    let mut stats = kanji
+        .stats
+        .into_iter()
+        .map(|(name, value)| KanjiStat {
+            tag: find_tag(all_tags, name),
+            value,
+        })
+        .collect::<Vec<_>>();
+    stats.sort_by(|a, b| (a.tag.order, &a.tag.name).cmp(&(b.tag.order, &b.tag.name)));
+    let record = Kanji {
+        onyomi: kanji
+            .onyomi
+            .split_whitespace()
+            .map(ToOwned::to_owned)
+            .collect(),
+        kunyomi: kanji
+            .kunyomi
+            .split_whitespace()
+            .map(ToOwned::to_owned)
+            .collect(),
+        tags,
+        meanings: kanji.meanings,
+        stats,
+    };
+    Ok((term, record))
+}
+
+fn to_kanji_frequency(
+    index: &schema::Index,
+    kanji_meta: schema::KanjiMeta,
+) -> Result<(Term, KanjiFrequency)> {
+    let term = Term::from_headword(kanji_meta.character).ok_or(NoHeadwordOrReading)?;
+    let frequency_mode = index.frequency_mode.unwrap_or(FrequencyMode::RankBased);
+    let Frequency { value, display } = to_frequency(frequency_mode, kanji_meta.data);
+    Ok((term, KanjiFrequency { value, display }))
+}
+
 fn to_content(raw: schema::Glossary) -> Option<structured::Content> {
     match raw {
         schema::Glossary::Deinflection(_) => None,
@@ -476,13 +801,19 @@ fn to_frequency_and_reading(
         schema::TermMetaFrequency::Generic(generic) => (None, generic),
         schema::TermMetaFrequency::WithReading { reading, frequency } => (Some(reading), frequency),
     };
+    (to_frequency(frequency_mode, generic), reading)
+}
 
+fn to_frequency(
+    frequency_mode: schema::FrequencyMode,
+    generic: schema::GenericFrequencyData,
+) -> Frequency {
     let value_from = |n: i64| match frequency_mode {
         schema::FrequencyMode::OccurrenceBased => FrequencyValue::Occurrence(n),
         schema::FrequencyMode::RankBased => FrequencyValue::Rank(n),
     };
 
-    let frequency = match generic {
+    match generic {
         schema::GenericFrequencyData::Number(rank) => Frequency {
             value: Some(value_from(rank)),
             display: None,
@@ -504,8 +835,7 @@ fn to_frequency_and_reading(
             value: Some(value_from(value)),
             display: display_value,
         },
-    };
-    (frequency, reading)
+    }
 }
 
 fn to_pitches_and_readings(raw: schema::TermMetaPitch) -> impl Iterator<Item = (Pitch, String)> {
@@ -0,0 +1,533 @@
+//! Validates Yomitan archives row by row, so that every bad row is reported
+//! rather than only the first one.
+//!
+//! Rows are converted into records in the same way as the importer does, but
+//! the records are counted instead of inserted.
+
+use {
+    super::{
+        Files, TermMetaRecords,
+        schema::{
+            self, INDEX_PATH, KANJI_BANK_PATTERN, KANJI_META_BANK_PATTERN, TAG_BANK_PATTERN,
+            TERM_BANK_PATTERN, TERM_META_BANK_PATTERN,
+        },
+    },
+    crate::import::{BankCount, OpenArchive, ValidationIssue, ValidationReport},
+    anyhow::Result,
+    foldhash::HashSet,
+    serde::{Deserialize, de::DeserializeOwned},
+    std::sync::Arc,
+    wordbase_api::{
+        RecordKind,
+        dict::yomitan::{GlossaryTag, structured},
+    },
+};
+
+/// Top-level fields of the index which [`schema::Index`] knows about.
+const INDEX_FIELDS: &[&str] = &[
+    "format",
+    "version",
+    "title",
+    "revision",
+    "minimumYomitanVersion",
+    "sequenced",
+    "isUpdatable",
+    "indexUrl",
+    "downloadUrl",
+    "author",
+    "url",
+    "description",
+    "attribution",
+    "sourceLanguage",
+    "targetLanguage",
+    "frequencyMode",
+];
+
+pub async fn validate(
+    open_archive: Arc<dyn OpenArchive>,
+    report: &mut ValidationReport,
+) -> Result<()> {
+    let files = Files::open(&*open_archive).await?;
+    let paths = files.paths().await?;
+    let mut validator = Validator {
+        media_paths: paths.iter().map(|(_, path)| path.clone()).collect(),
+        files,
+        report,
+        all_tags: Vec::new(),
+        tag_names: HashSet::default(),
+        reported: HashSet::default(),
+    };
+
+    let Some(index) = validator.index(&paths).await else {
+        return Ok(());
+    };
+    validator.report.meta = Some(super::to_meta(&index));
+
+    // tags must be known before we can check the banks which reference them
+    let mut tags = Vec::new();
+    for (entry_index, path) in &paths {
+        if TAG_BANK_PATTERN.is_match(path) {
+            let rows = validator.rows::<schema::Tag>(*entry_index, path).await;
+            tags.extend(rows.into_iter().map(|(_, tag)| tag));
+        }
+    }
+    validator.all_tags = super::to_all_tags(tags);
+    validator.tag_names = validator
+        .all_tags
+        .iter()
+        .map(|tag| tag.name.clone())
+        .collect();
+
+    for (entry_index, path) in &paths {
+        if TERM_BANK_PATTERN.is_match(path) {
+            for (row, term) in validator.rows::<schema::Term>(*entry_index, path).await {
+                validator.term(path, row, term);
+            }
+        } else if TERM_META_BANK_PATTERN.is_match(path) {
+            for (row, term_meta) in validator.rows::<schema::TermMeta>(*entry_index, path).await {
+                validator.term_meta(path, row, &index, term_meta);
+            }
+        } else if KANJI_BANK_PATTERN.is_match(path) {
+            for (row, kanji) in validator.rows::<schema::Kanji>(*entry_index, path).await {
+                validator.kanji(path, row, kanji);
+            }
+        } else if KANJI_META_BANK_PATTERN.is_match(path) {
+            for (row, kanji_meta) in validator
+                .rows::<schema::KanjiMeta>(*entry_index, path)
+                .await
+            {
+                validator.kanji_meta(path, row, &index, kanji_meta);
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Validator<'a> {
+    files: Files,
+    report: &'a mut ValidationReport,
+    all_tags: Vec<GlossaryTag>,
+    tag_names: HashSet<String>,
+    media_paths: HashSet<String>,
+    /// Missing tags and media which have already been reported, so that each
+    /// one is only reported at the first row which references it.
+    reported: HashSet<String>,
+}
+
+impl Validator<'_> {
+    async fn read(&mut self, entry_index: usize, path: &str) -> Option<Vec<u8>> {
+        match self.files.read(entry_index, path).await {
+            Ok(data) => Some(data),
+            Err(err) => {
+                self.report.errors.push(ValidationIssue::new(
+                    Some(path),
+                    None,
+                    format!("failed to read entry: {err:?}"),
+                ));
+                None
+            }
+        }
+    }
+
+    async fn index(&mut self, paths: &[(usize, String)]) -> Option<schema::Index> {
+        let Some((entry_index, _)) = paths.iter().find(|(_, path)| path == INDEX_PATH) else {
+            self.report.errors.push(ValidationIssue::new(
+                None,
+                None,
+                format!("no `{INDEX_PATH}` in archive"),
+            ));
+            return None;
+        };
+        let data = self.read(*entry_index, INDEX_PATH).await?;
+        let index = match serde_json::from_slice::<serde_json::Value>(&data) {
+            Ok(index) => index,
+            Err(err) => {
+                self.report.errors.push(ValidationIssue::new(
+                    Some(INDEX_PATH),
+                    None,
+                    format!("failed to parse index: {err}"),
+                ));
+                return None;
+            }
+        };
+
+        if let Some(fields) = index.as_object() {
+            for field in fields.keys() {
+                if !INDEX_FIELDS.contains(&field.as_str()) {
+                    self.report.unknown_fields.push(ValidationIssue::new(
+                        Some(INDEX_PATH),
+                        None,
+                        field,
+                    ));
+                }
+            }
+        }
+        match schema::Index::deserialize(&index) {
+            Ok(index) => Some(index),
+            Err(err) => {
+                self.report.errors.push(ValidationIssue::new(
+                    Some(INDEX_PATH),
+                    None,
+                    format!("failed to parse index: {err}"),
+                ));
+                None
+            }
+        }
+    }
+
+    /// Parses each row of a bank separately, and returns the rows which could
+    /// be parsed along with their index in the bank.
+    async fn rows<T: DeserializeOwned>(
+        &mut self,
+        entry_index: usize,
+        path: &str,
+    ) -> Vec<(usize, T)> {
+        let Some(data) = self.read(entry_index, path).await else {
+            return Vec::new();
+        };
+        let rows = match serde_json::from_slice::<Vec<serde_json::Value>>(&data) {
+            Ok(rows) => rows,
+            Err(err) => {
+                self.report.errors.push(ValidationIssue::new(
+                    Some(path),
+                    None,
+                    format!("failed to parse bank: {err}"),
+                ));
+                return Vec::new();
+            }
+        };
+        self.report.banks.push(BankCount {
+            path: path.to_owned(),
+            rows: rows.len() as u64,
+        });
+
+        let mut parsed = Vec::with_capacity(rows.len());
+        for (row, value) in rows.into_iter().enumerate() {
+            match T::deserialize(&value) {
+                Ok(value) => parsed.push((row, value)),
+                Err(err) => {
+                    let mut fields = unknown_field(&err.to_string())
+                        .into_iter()
+                        .collect::<Vec<_>>();
+                    find_unknown_fields(&value, &mut fields);
+                    fields.dedup();
+                    for field in fields {
+                        self.report.unknown_fields.push(ValidationIssue::new(
+                            Some(path),
+                            Some(row),
+                            field,
+                        ));
+                    }
+                    self.report.errors.push(ValidationIssue::new(
+                        Some(path),
+                        Some(row),
+                        err.to_string(),
+                    ));
+                }
+            }
+        }
+        parsed
+    }
+
+    fn term(&mut self, path: &str, row: usize, term: schema::Term) {
+        self.tags(
+            path,
+            row,
+            term.definition_tags.as_deref().unwrap_or_default(),
+        );
+        self.tags(path, row, &term.term_tags);
+
+        for glossary in &term.glossary {
+            match glossary {
+                schema::Glossary::Content(schema::GlossaryContent::Image(image)) => {
+                    self.media(path, row, &image.path);
+                }
+                schema::Glossary::Content(schema::GlossaryContent::StructuredContent {
+                    content,
+                }) => {
+                    let mut image_paths = Vec::new();
+                    content_images(content, &mut image_paths);
+                    for image_path in image_paths {
+                        self.media(path, row, image_path);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        match super::to_term_records(term, &self.all_tags) {
+            Ok(records) => {
+                self.report
+                    .add_records(RecordKind::YomitanRedirect, records.redirects.len() as u64);
+                if records.glossary.is_some() {
+                    self.report.add_records(RecordKind::YomitanGlossary, 1);
+                }
+            }
+            Err(err) => self.error(path, row, &err),
+        }
+    }
+
+    fn term_meta(
+        &mut self,
+        path: &str,
+        row: usize,
+        index: &schema::Index,
+        term_meta: schema::TermMeta,
+    ) {
+        if let schema::TermMetaData::Phonetic(phonetic) = &term_meta.data {
+            for transcription in &phonetic.transcriptions {
+                for tag in &transcription.tags {
+                    self.tag(path, row, tag);
+                }
+            }
+        }
+
+        match super::to_term_meta_records(index, term_meta, &self.all_tags) {
+            Ok(TermMetaRecords::Frequency(..)) => {
+                self.report.add_records(RecordKind::YomitanFrequency, 1);
+            }
+            Ok(TermMetaRecords::Pitch(pitches)) => {
+                self.report
+                    .add_records(RecordKind::YomitanPitch, pitches.len() as u64);
+            }
+            Ok(TermMetaRecords::Phonetic(_, records)) => {
+                self.report
+                    .add_records(RecordKind::YomitanPhonetic, records.len() as u64);
+            }
+            Err(err) => self.error(path, row, &err),
+        }
+    }
+
+    fn kanji(&mut self, path: &str, row: usize, kanji: schema::Kanji) {
+        self.tags(path, row, &kanji.tags);
+        for stat in kanji.stats.keys() {
+            self.tag(path, row, stat);
+        }
+
+        match super::to_kanji_record(kanji, &self.all_tags) {
+            Ok(_) => self.report.add_records(RecordKind::YomitanKanji, 1),
+            Err(err) => self.error(path, row, &err),
+        }
+    }
+
+    fn kanji_meta(
+        &mut self,
+        path: &str,
+        row: usize,
+        index: &schema::Index,
+        kanji_meta: schema::KanjiMeta,
+    ) {
+        match super::to_kanji_frequency(index, kanji_meta) {
+            Ok(_) => self
+                .report
+                .add_records(RecordKind::YomitanKanjiFrequency, 1),
+            Err(err) => self.error(path, row, &err),
+        }
+    }
+
+    /// Reports a row which parsed, but can't be converted into records.
+    fn error(&mut self, path: &str, row: usize, err: &anyhow::Error) {
+        self.report.errors.push(ValidationIssue::new(
+            Some(path),
+            Some(row),
+            format!("{err:#}"),
+        ));
+    }
+
+    fn tags(&mut self, path: &str, row: usize, tags: &str) {
+        for tag in tags.split_whitespace() {
+            self.tag(path, row, tag);
+        }
+    }
+
+    fn tag(&mut self, path: &str, row: usize, tag: &str) {
+        if !self.tag_names.contains(tag) && self.reported.insert(format!("tag:{tag}")) {
+            self.report
+                .missing_tags
+                .push(ValidationIssue::new(Some(path), Some(row), tag));
+        }
+    }
+
+    fn media(&mut self, path: &str, row: usize, media_path: &str) {
+        if !self.media_paths.contains(media_path)
+            && self.reported.insert(format!("media:{media_path}"))
+        {
+            self.report
+                .missing_media
+                .push(ValidationIssue::new(Some(path), Some(row), media_path));
+        }
+    }
+}
+
+/// Gets the field name out of a serde "unknown field" error message.
+fn unknown_field(message: &str) -> Option<String> {
+    let (field, _) = message.strip_prefix("unknown field `")?.split_once('`')?;
+    Some(field.to_owned())
+}
+
+/// Finds unknown fields in structured content elements.
+///
+/// Glossaries are untagged enums, so serde only tells us that a row didn't
+/// match any variant. Elements which fail because of a bad child fail with a
+/// generic error too, so only the element with the unknown field is reported.
+fn find_unknown_fields(value: &serde_json::Value, fields: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Array(values) => {
+            for value in values {
+                find_unknown_fields(value, fields);
+            }
+        }
+        serde_json::Value::Object(object) => {
+            let err = if object.contains_key("tag") {
+                structured::Element::deserialize(value).err()
+            } else if object.contains_key("type") {
+                schema::GlossaryContent::deserialize(value).err()
+            } else {
+                None
+            };
+            fields.extend(err.and_then(|err| unknown_field(&err.to_string())));
+            for value in object.values() {
+                find_unknown_fields(value, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Collects the paths of all images in some structured content.
+fn content_images<'a>(content: &'a structured::Content, paths: &mut Vec<&'a str>) {
+    use structured::Element;
+
+    let element = match content {
+        structured::Content::String(_) => return,
+        structured::Content::Content(contents) => {
+            for content in contents {
+                content_images(content, paths);
+            }
+            return;
+        }
+        structured::Content::Element(element) => &**element,
+    };
+    let children = match element {
+        Element::Img(image) => {
+            paths.push(&image.base.path);
+            return;
+        }
+        Element::Br(_) => return,
+        Element::Ruby(element)
+        | Element::Rt(element)
+        | Element::Rp(element)
+        | Element::Table(element)
+        | Element::Thead(element)
+        | Element::Tbody(element)
+        | Element::Tfoot(element)
+        | Element::Tr(element) => &element.content,
+        Element::Td(element) | Element::Th(element) => &element.content,
+        Element::Span(element)
+        | Element::Div(element)
+        | Element::Ol(element)
+        | Element::Ul(element)
+        | Element::Li(element)
+        | Element::Details(element)
+        | Element::Summary(element) => &element.content,
+        Element::A(element) => &element.content,
+    };
+    if let Some(children) = children {
+        content_images(children, paths);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::test_util::zip, wordbase_api::DictionaryKind};
+
+    #[tokio::test]
+    async fn reports_bad_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dictionary.zip");
+        let archive = zip([
+            (
+                "index.json",
+                serde_json::json!({
+                    "title": "Broken",
+                    "revision": "1",
+                    "format": 3,
+                    "extra": true,
+                }),
+            ),
+            (
+                "tag_bank_1.json",
+                serde_json::json!([["n", "partOfSpeech", 0, "noun", 0]]),
+            ),
+            (
+                "term_bank_1.json",
+                serde_json::json!([
+                    ["犬", "いぬ", "n", "", 0, ["dog"], 1, ""],
+                    ["猫", "ねこ", "v5", "", 0, [{
+                        "type": "structured-content",
+                        "content": { "tag": "span", "bogus": 1, "content": "cat" },
+                    }], 2, ""],
+                    ["鳥", "とり", "", "", 0, [{
+                        "type": "image",
+                        "path": "img/bird.png",
+                    }], 3, ""],
+                ]),
+            ),
+            (
+                "term_meta_bank_1.json",
+                serde_json::json!([["", "freq", 5], ["犬", "freq", 10]]),
+            ),
+        ]
+        .map(|(name, data)| (name, data.to_string())))
+        .await;
+        tokio::fs::write(&path, archive).await.unwrap();
+
+        let mut report = ValidationReport::new(DictionaryKind::Yomitan);
+        validate(Arc::new(Arc::new(path)), &mut report)
+            .await
+            .unwrap();
+
+        assert_eq!("Broken", report.meta.as_ref().unwrap().name);
+        assert!(!report.is_valid());
+        assert_eq!(3, report.banks.len());
+        assert_eq!(3, report.banks[1].rows);
+
+        let errors = report
+            .errors
+            .iter()
+            .map(|issue| (issue.path.as_deref(), issue.index))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (Some("term_bank_1.json"), Some(1)),
+                (Some("term_meta_bank_1.json"), Some(0)),
+            ],
+            errors
+        );
+
+        let unknown_fields = report
+            .unknown_fields
+            .iter()
+            .map(|issue| issue.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["extra", "bogus"], unknown_fields);
+
+        assert_eq!(1, report.missing_media.len());
+        assert_eq!("img/bird.png", report.missing_media[0].message);
+        assert_eq!(Some(2), report.missing_media[0].index);
+        assert!(report.missing_tags.is_empty());
+
+        let glossaries = report
+            .records
+            .iter()
+            .find(|count| count.kind == RecordKind::YomitanGlossary)
+            .unwrap();
+        assert_eq!(2, glossaries.count);
+        let frequencies = report
+            .records
+            .iter()
+            .find(|count| count.kind == RecordKind::YomitanFrequency)
+            .unwrap();
+        assert_eq!(1, frequencies.count);
+    }
+}
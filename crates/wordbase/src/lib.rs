@@ -5,11 +5,17 @@ pub mod anki;
 mod db;
 pub mod deinflect;
 pub mod dictionary;
+pub mod export;
 pub mod import;
 pub mod lang;
 pub mod lookup;
+pub mod media;
 pub mod profile;
 pub mod render;
+pub mod sentence;
+#[cfg(test)]
+mod test_util;
+pub mod update;
 // #[cfg(feature = "desktop")]
 // pub mod texthook;
 
@@ -20,6 +26,7 @@ use {
     deinflect::Deinflectors,
     derive_more::{Display, Error},
     dictionary::Dictionaries,
+    import::{ImportQueueEvent, ImportTasks, InsertLock},
     profile::Profiles,
     render::Renderer,
     sqlx::{Pool, Sqlite},
@@ -40,6 +47,11 @@ pub struct Engine {
     // texthookers: texthook::Texthookers,
     deinflectors: Deinflectors,
     event_tx: broadcast::Sender<EngineEvent>,
+    http_client: reqwest::Client,
+    /// Tasks of imports which are running, so that they can be cancelled.
+    import_tasks: ImportTasks,
+    /// Held while an import or dictionary removal writes to the database.
+    insert_lock: InsertLock,
     db: Pool<Sqlite>,
 }
 
@@ -64,6 +76,8 @@ pub enum EngineEvent {
     TexthookerConnected,
     TexthookerDisconnected,
     Sentence(TexthookerSentence),
+    /// Progress of a batch of imports started by [`Engine::import_dictionaries`].
+    Import(ImportQueueEvent),
 }
 
 #[derive(Debug, Clone)]
@@ -93,6 +107,10 @@ pub enum DictionaryEvent {
     Removed {
         id: DictionaryId,
     },
+    /// Dictionary was re-imported, replacing its data and meta in place.
+    Updated {
+        id: DictionaryId,
+    },
     PositionsSwapped {
         a_id: DictionaryId,
         b_id: DictionaryId,
@@ -133,6 +151,10 @@ impl Engine {
         let db = db?;
 
         let (event_tx, _) = broadcast::channel(CHANNEL_BUF_CAP);
+        let http_client = reqwest::Client::builder()
+            .user_agent(concat!("wordbase/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .context("failed to create HTTP client")?;
         Ok(Self {
             profiles: ArcSwap::from_pointee(
                 Profiles::fetch(&db)
@@ -154,6 +176,9 @@ impl Engine {
             //     .await
             //     .context("failed to create Anki integration")?,
             event_tx,
+            http_client,
+            import_tasks: ImportTasks::default(),
+            insert_lock: InsertLock::default(),
             db,
         })
     }
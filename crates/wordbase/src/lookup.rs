@@ -46,8 +46,10 @@ impl Engine {
                 record.data,
                 base.headword,
                 base.reading,
-                profile_frequency.mode AS 'profile_frequency_mode?',
-                profile_frequency.value AS 'profile_frequency_value?',
+                COALESCE(profile_frequency_exact.mode, profile_frequency_any.mode)
+                    AS 'profile_frequency_mode?: i64',
+                COALESCE(profile_frequency_exact.value, profile_frequency_any.value)
+                    AS 'profile_frequency_value?: i64',
                 source_frequency.mode AS 'source_frequency_mode?',
                 source_frequency.value AS 'source_frequency_value?'
             FROM record
@@ -59,14 +61,30 @@ impl Engine {
                 ON (ped.profile = $1 AND ped.dictionary = dictionary.id)
 
             -- join on profile-global frequency information, for the `ORDER BY` below
-            LEFT JOIN frequency profile_frequency ON (
-                -- only use frequency info from the currently selected sorting dict in this profile
-                profile_frequency.source = (
+            -- only use frequency info from the currently selected sorting dict in this profile
+            LEFT JOIN frequency profile_frequency_exact
+            INDEXED BY frequency_query
+            ON (
+                profile_frequency_exact.source = (
                     SELECT sorting_dictionary FROM profile
                     WHERE id = $1
                 )
-                AND profile_frequency.headword = base.headword
-                AND profile_frequency.reading = base.reading
+                AND profile_frequency_exact.headword = base.headword
+                AND profile_frequency_exact.reading = base.reading
+            )
+            -- frequency lists may not specify readings,
+            -- in which case the frequency applies to all readings of the headword,
+            -- but we prefer an exact match
+            -- these are 2 separate joins, so that both are lookups on `frequency_query`
+            LEFT JOIN frequency profile_frequency_any
+            INDEXED BY frequency_query
+            ON (
+                profile_frequency_any.source = (
+                    SELECT sorting_dictionary FROM profile
+                    WHERE id = $1
+                )
+                AND profile_frequency_any.headword = base.headword
+                AND profile_frequency_any.reading IS NULL
             )
 
             -- join on frequency information for this source
@@ -96,15 +114,18 @@ impl Engine {
                 dictionary.position,
                 -- put entries without an explicit frequency value last
                 CASE
-                    WHEN profile_frequency.mode IS NULL THEN 1
+                    WHEN COALESCE(profile_frequency_exact.mode, profile_frequency_any.mode) IS NULL
+                        THEN 1
                     ELSE 0
                 END,
                 -- sort by profile-global frequency info
                 CASE
                     -- frequency rank
-                    WHEN profile_frequency.mode = 0 THEN  profile_frequency.value
+                    WHEN COALESCE(profile_frequency_exact.mode, profile_frequency_any.mode) = 0
+                        THEN  COALESCE(profile_frequency_exact.value, profile_frequency_any.value)
                     -- frequency occurrence
-                    WHEN profile_frequency.mode = 1 THEN -profile_frequency.value
+                    WHEN COALESCE(profile_frequency_exact.mode, profile_frequency_any.mode) = 1
+                        THEN -COALESCE(profile_frequency_exact.value, profile_frequency_any.value)
                     ELSE 0
                 END,
                 -- sort by source-specific frequency info
@@ -178,6 +199,7 @@ impl Engine {
                         record.source_frequency_mode,
                         record.source_frequency_value,
                     ),
+                    inflection_rule_chain: Vec::new(),
                 })
             });
         result.try_collect::<Vec<_>>().await
@@ -201,6 +223,7 @@ impl Engine {
             })
             .collect::<FuturesOrdered<_>>();
         while let Some((deinflection, entries)) = lookup_tasks.try_next().await? {
+            let entries = self.follow_redirects(profile_id, entries).await?;
             let span_bytes =
                 Span::try_from(deinflection.span.clone()).context("byte span too large")?;
 
@@ -232,6 +255,127 @@ impl Engine {
 
         Ok(records)
     }
+
+    /// Replaces each [`Redirect`] record in `entries` with the records of the
+    /// term it redirects to.
+    ///
+    /// Redirects are only followed one level deep, so that a dictionary can't
+    /// send us into a loop.
+    ///
+    /// [`Redirect`]: wordbase_api::dict::yomitan::Redirect
+    async fn follow_redirects(
+        &self,
+        profile_id: ProfileId,
+        entries: Vec<RecordEntry>,
+    ) -> Result<Vec<RecordEntry>> {
+        let mut followed = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let Record::YomitanRedirect(redirect) = &entry.record else {
+                followed.push(entry);
+                continue;
+            };
+
+            let targets = self
+                .lookup_lemma(profile_id, &redirect.uninflected)
+                .await
+                .with_context(|| {
+                    format!("failed to follow redirect to {:?}", redirect.uninflected)
+                })?;
+            followed.extend(
+                targets
+                    .into_iter()
+                    .filter(|target| !matches!(target.record, Record::YomitanRedirect(_)))
+                    .map(|target| RecordEntry {
+                        inflection_rule_chain: redirect.rule_chain.clone(),
+                        ..target
+                    }),
+            );
+        }
+        Ok(followed)
+    }
+
+    /// Looks up information about a single kanji character, such as its
+    /// readings, meanings and frequency.
+    ///
+    /// Only kanji records are returned, not the terms which this character
+    /// forms on its own.
+    pub async fn lookup_kanji(
+        &self,
+        profile_id: ProfileId,
+        kanji: char,
+    ) -> Result<Vec<RecordEntry>> {
+        let mut buf = [0; 4];
+        let mut records = self
+            .lookup_lemma(profile_id, &*kanji.encode_utf8(&mut buf))
+            .await?;
+        records.retain(|record| is_kanji(&record.record));
+        Ok(records)
+    }
+
+    /// Finds all kanji which contain every one of `radicals` as a component,
+    /// using the radical decompositions imported from KRADFILE or RADKFILE
+    /// dictionaries which are enabled in this profile.
+    ///
+    /// This lets users find a kanji which they can't type, by picking the
+    /// parts that they can see in it. Simpler kanji (with fewer components)
+    /// are returned first.
+    ///
+    /// If `radicals` is empty, no kanji are returned.
+    pub async fn kanji_by_radicals(
+        &self,
+        profile_id: ProfileId,
+        radicals: &[char],
+    ) -> Result<Vec<char>> {
+        if radicals.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let num_radicals = i64::try_from(radicals.len()).context("too many radicals")?;
+        let radicals = serde_json::to_string(radicals).context("failed to serialize radicals")?;
+        let kanji = sqlx::query_scalar!(
+            "SELECT kanji
+            FROM kanji_radical
+            -- make sure the dictionary we're getting this radical from is enabled,
+            -- and isn't still being imported
+            INNER JOIN profile_enabled_dictionary ped
+                ON (ped.profile = $1 AND ped.dictionary = kanji_radical.source)
+            WHERE radical IN (SELECT value FROM json_each($2))
+            AND NOT EXISTS (
+                SELECT 1 FROM import_job WHERE import_job.dictionary = kanji_radical.source
+            )
+            GROUP BY kanji
+            HAVING COUNT(DISTINCT radical) = $3
+            ORDER BY (
+                SELECT COUNT(DISTINCT all_radicals.radical)
+                FROM kanji_radical all_radicals
+                INNER JOIN profile_enabled_dictionary all_ped
+                    ON (all_ped.profile = $1 AND all_ped.dictionary = all_radicals.source)
+                WHERE all_radicals.kanji = kanji_radical.kanji
+                AND NOT EXISTS (
+                    SELECT 1 FROM import_job WHERE import_job.dictionary = all_radicals.source
+                )
+            ), kanji",
+            profile_id.0,
+            radicals,
+            num_radicals,
+        )
+        .fetch_all(&self.db)
+        .await
+        .context("failed to fetch kanji")?;
+        Ok(kanji
+            .into_iter()
+            .filter_map(|kanji| kanji.chars().next())
+            .collect())
+    }
+}
+
+/// Checks if this record describes a single kanji character, rather than a
+/// term.
+pub(crate) const fn is_kanji(record: &Record) -> bool {
+    matches!(
+        record,
+        Record::YomitanKanji(_) | Record::YomitanKanjiFrequency(_) | Record::KanjidicCharacter(_)
+    )
 }
 
 fn to_frequency_value(mode: Option<i64>, value: Option<i64>) -> Option<FrequencyValue> {
@@ -257,5 +401,29 @@ const _: () = {
             let cursor = usize::try_from(cursor).context("cursor too large")?;
             Ok(self.0.lookup(profile_id, sentence, cursor).await?)
         }
+
+        pub async fn lookup_kanji(
+            &self,
+            profile_id: ProfileId,
+            kanji: String,
+        ) -> FfiResult<Vec<RecordEntry>> {
+            let kanji = kanji.chars().next().context("no kanji character")?;
+            Ok(self.0.lookup_kanji(profile_id, kanji).await?)
+        }
+
+        pub async fn kanji_by_radicals(
+            &self,
+            profile_id: ProfileId,
+            radicals: String,
+        ) -> FfiResult<Vec<String>> {
+            let radicals = radicals.chars().collect::<Vec<_>>();
+            Ok(self
+                .0
+                .kanji_by_radicals(profile_id, &radicals)
+                .await?
+                .into_iter()
+                .map(String::from)
+                .collect())
+        }
     }
 };
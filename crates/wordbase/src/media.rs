@@ -0,0 +1,137 @@
+//! Resource files (images, stylesheets, audio) which dictionaries ship
+//! alongside their records, and which records reference by path.
+
+use {
+    crate::Engine,
+    anyhow::{Context, Result},
+    data_encoding::BASE64,
+    foldhash::HashMap,
+    regex::{Captures, Regex},
+    std::{path::Path, sync::LazyLock},
+    wordbase_api::DictionaryId,
+};
+
+impl Engine {
+    /// Gets the contents of a media file which was imported as part of a
+    /// dictionary.
+    ///
+    /// `path` is relative to the root of the dictionary, using `/` as the
+    /// separator, e.g. `images/cat.png`.
+    pub async fn media(&self, source: DictionaryId, path: &str) -> Result<Option<Vec<u8>>> {
+        let data = sqlx::query_scalar!(
+            "SELECT data FROM media WHERE source = $1 AND path = $2",
+            source.0,
+            path,
+        )
+        .fetch_optional(&self.db)
+        .await
+        .context("failed to fetch media")?;
+        Ok(data)
+    }
+
+    /// Replaces references to media files in `html` (`src` and `href`
+    /// attributes) with `data:` URIs containing the file contents, so that the
+    /// HTML can be displayed without access to the dictionary's files.
+    ///
+    /// References to files which don't exist in `source` are left untouched.
+    pub(crate) async fn inline_media(
+        &self,
+        source: DictionaryId,
+        html: &str,
+        cache: &mut HashMap<(DictionaryId, String), Option<String>>,
+    ) -> Result<String> {
+        static MEDIA_REF: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(r#"(?P<attr>\b(?:src|href)=)"(?P<path>[^"]*)""#)
+                .expect("should be a valid regex")
+        });
+
+        for caps in MEDIA_REF.captures_iter(html) {
+            let Some(path) = media_path(&caps["path"]) else {
+                continue;
+            };
+            let key = (source, path);
+            if cache.contains_key(&key) {
+                continue;
+            }
+            let uri = self
+                .media(source, &key.1)
+                .await?
+                .map(|data| data_uri(&key.1, &data));
+            cache.insert(key, uri);
+        }
+
+        Ok(MEDIA_REF
+            .replace_all(html, |caps: &Captures| {
+                media_path(&caps["path"])
+                    .and_then(|path| cache.get(&(source, path)).cloned().flatten())
+                    .map_or_else(
+                        || caps[0].to_owned(),
+                        |uri| format!("{}\"{uri}\"", &caps["attr"]),
+                    )
+            })
+            .into_owned())
+    }
+}
+
+/// Converts a reference to a file in HTML to the path that the file would be
+/// stored under, if it refers to a file inside the dictionary.
+fn media_path(reference: &str) -> Option<String> {
+    if reference.is_empty() || reference.starts_with('#') || reference.starts_with("data:") {
+        return None;
+    }
+    let path = ["file://", "sound://"]
+        .iter()
+        .find_map(|scheme| reference.strip_prefix(scheme))
+        .unwrap_or(reference);
+    if path.contains("://") {
+        return None;
+    }
+    Some(path.replace('\\', "/").trim_start_matches('/').to_owned())
+}
+
+fn data_uri(path: &str, data: &[u8]) -> String {
+    let mime_type = mime_type(path);
+    let data = BASE64.encode(data);
+    format!("data:{mime_type};base64,{data}")
+}
+
+fn mime_type(path: &str) -> &'static str {
+    let ext = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    match ext.as_deref() {
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("webp") => "image/webp",
+        Some("bmp") => "image/bmp",
+        Some("css") => "text/css",
+        Some("js") => "text/javascript",
+        Some("mp3") => "audio/mpeg",
+        Some("ogg") => "audio/ogg",
+        Some("opus") => "audio/opus",
+        Some("spx") => "audio/speex",
+        Some("wav") => "audio/wav",
+        Some("ttf") => "font/ttf",
+        Some("otf") => "font/otf",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn media_paths() {
+        assert_eq!(Some("img/cat.png".into()), media_path("/img/cat.png"));
+        assert_eq!(Some("img/cat.png".into()), media_path("\\img\\cat.png"));
+        assert_eq!(Some("cat.mp3".into()), media_path("sound://cat.mp3"));
+        assert_eq!(None, media_path("https://example.com/cat.png"));
+        assert_eq!(None, media_path("#top"));
+    }
+}
@@ -1,12 +1,12 @@
 use {
-    crate::{Engine, IndexMap, lang},
+    crate::{Engine, IndexMap, lang, lookup},
     anyhow::{Context, Result},
     arc_swap::ArcSwap,
     data_encoding::BASE64,
-    foldhash::HashSet,
+    foldhash::{HashMap, HashSet},
     serde::Serialize,
     tera::Tera,
-    wordbase_api::{DictionaryId, Record, RecordEntry, RecordKind, Term, dict},
+    wordbase_api::{DictionaryId, ProfileId, Record, RecordEntry, RecordKind, Term, dict},
 };
 
 #[derive(Debug)]
@@ -25,6 +25,10 @@ impl Renderer {
     }
 }
 
+/// Maximum number of example sentences rendered for each term by
+/// [`Engine::render_html_body`].
+pub const EXAMPLE_SENTENCES_PER_TERM: u32 = 3;
+
 impl Engine {
     /// Renders the results of [`Engine::lookup`] to the `<body>` contents of
     /// an HTML document, so you can display it to the user in a web view or
@@ -38,22 +42,60 @@ impl Engine {
     /// You can also include your own HTML to further customize the output,
     /// such as adding your own `<style>` block.
     ///
+    /// Any media files referenced by glossaries are inlined as `data:` URIs.
+    ///
+    /// Up to [`EXAMPLE_SENTENCES_PER_TERM`] example sentences are fetched for
+    /// each term, from the dictionaries enabled in `profile_id`, and rendered
+    /// below its glossaries.
+    ///
     /// # Errors
     ///
-    /// Errors if the HTML template cannot be rendered by [`tera`]. This should
-    /// not happen normally, but if you are modifying the template and
+    /// Errors if media or example sentences cannot be fetched from the
+    /// database, or if the HTML template cannot be rendered by [`tera`]. This
+    /// should not happen normally, but if you are modifying the template and
     /// hot-reloading it, then this may error. It is usually safe to just
     /// `expect` this to be [`Ok`].
-    pub fn render_html_body(
+    pub async fn render_html_body(
         &self,
+        profile_id: ProfileId,
         entries: &[RecordEntry],
         config: &RenderConfig,
     ) -> Result<String> {
-        let terms = group_terms(entries);
+        let mut terms = group_terms(entries);
+        let kanji = group_kanji(entries);
+
+        // glossaries may reference images and stylesheets shipped with the
+        // dictionary, which the web view can't access by path
+        let mut media_cache = HashMap::default();
+        for term in &mut terms {
+            for (&source, glossaries) in &mut term.info.glossary_groups {
+                for content in glossaries.iter_mut().flat_map(|g| &mut g.content) {
+                    *content = self.inline_media(source, content, &mut media_cache).await?;
+                }
+            }
+        }
+
+        let term_keys = terms
+            .iter()
+            .map(|term| term.term.clone())
+            .collect::<Vec<_>>();
+        let examples = self
+            .example_sentences_of_terms(profile_id, &term_keys, EXAMPLE_SENTENCES_PER_TERM)
+            .await?;
+        for (term, examples) in terms.iter_mut().zip(examples) {
+            for example in examples {
+                term.info
+                    .example_sentences
+                    .entry(example.source)
+                    .or_default()
+                    .push(example.sentence);
+            }
+        }
 
         let mut context = tera::Context::new();
         context.insert("dictionaries", &self.dictionaries().0);
         context.insert("terms", &terms);
+        context.insert("kanji", &kanji);
         context.insert("config", config);
         let body = self.renderer.tera.load().render("records.html", &context)?;
 
@@ -106,6 +148,10 @@ pub fn group_terms(entries: &[RecordEntry]) -> Vec<RecordTerm> {
     // we enable `tera/preserve_order` to make sure that order stays.
     let mut groups = IndexMap::<Term, TermInfo>::default();
     for record in entries {
+        if lookup::is_kanji(&record.record) {
+            continue;
+        }
+
         let source = record.source;
         let term = &record.term;
         let info = groups.entry(term.clone()).or_insert_with(|| TermInfo {
@@ -122,9 +168,26 @@ pub fn group_terms(entries: &[RecordEntry]) -> Vec<RecordTerm> {
             }),
             ..Default::default()
         });
+        if !record.inflection_rule_chain.is_empty()
+            && !info
+                .inflection_rule_chains
+                .contains(&record.inflection_rule_chain)
+        {
+            info.inflection_rule_chains
+                .push(record.inflection_rule_chain.clone());
+        }
 
         match &record.record {
             Record::YomitanGlossary(glossary) => {
+                for tag in &glossary.term_tags {
+                    if !info
+                        .term_tags
+                        .iter()
+                        .any(|existing| existing.name == tag.name)
+                    {
+                        info.term_tags.push(tag);
+                    }
+                }
                 info.glossary_groups
                     .entry(source)
                     .or_default()
@@ -137,6 +200,64 @@ pub fn group_terms(entries: &[RecordEntry]) -> Vec<RecordTerm> {
                             .collect(),
                     });
             }
+            Record::JmdictWord(word) => {
+                info.glossary_groups
+                    .entry(source)
+                    .or_default()
+                    .push(Glossary {
+                        tags: &[],
+                        content: word
+                            .senses
+                            .iter()
+                            .map(|sense| dict::jmdict::render_sense_html(sense).0)
+                            .collect(),
+                    });
+            }
+            Record::JmdictName(name) => {
+                info.glossary_groups
+                    .entry(source)
+                    .or_default()
+                    .push(Glossary {
+                        tags: &[],
+                        content: vec![dict::jmdict::render_name_html(name).0],
+                    });
+            }
+            Record::StardictDefinition(definition) => {
+                info.glossary_groups
+                    .entry(source)
+                    .or_default()
+                    .push(Glossary {
+                        tags: &[],
+                        content: vec![dict::stardict::render_html(definition).0],
+                    });
+            }
+            Record::MdictEntry(entry) => {
+                info.glossary_groups
+                    .entry(source)
+                    .or_default()
+                    .push(Glossary {
+                        tags: &[],
+                        content: vec![entry.html.clone()],
+                    });
+            }
+            Record::CedictMeaning(meaning) => {
+                info.glossary_groups
+                    .entry(source)
+                    .or_default()
+                    .push(Glossary {
+                        tags: &[],
+                        content: vec![dict::cedict::render_html(meaning).0],
+                    });
+            }
+            Record::WiktionaryLexeme(lexeme) => {
+                info.glossary_groups
+                    .entry(source)
+                    .or_default()
+                    .push(Glossary {
+                        tags: &[],
+                        content: vec![dict::wiktionary::render_html(lexeme).0],
+                    });
+            }
             Record::YomitanFrequency(frequency) => {
                 info.frequencies.entry(source).or_default().push(frequency);
             }
@@ -146,6 +267,18 @@ pub fn group_terms(entries: &[RecordEntry]) -> Vec<RecordTerm> {
                     .or_insert_with(|| base_pitch(term, pitch.position))
                     .info = Some(pitch);
             }
+            Record::YomitanPhonetic(phonetic) => {
+                info.phonetics.push(phonetic);
+            }
+            Record::AnkiNote(note) => {
+                info.glossary_groups
+                    .entry(source)
+                    .or_default()
+                    .push(Glossary {
+                        tags: &[],
+                        content: vec![note.definition.clone()],
+                    });
+            }
             Record::YomichanAudioForvo(audio) => {
                 info.audio_no_pitch.entry(source).or_default().push(Audio {
                     kind: RecordKind::YomichanAudioForvo,
@@ -158,6 +291,12 @@ pub fn group_terms(entries: &[RecordEntry]) -> Vec<RecordTerm> {
                     blob: audio_blob(&audio.audio),
                 });
             }
+            Record::AnkiNoteAudio(audio) => {
+                info.audio_no_pitch.entry(source).or_default().push(Audio {
+                    kind: RecordKind::AnkiNoteAudio,
+                    blob: audio_blob(&audio.audio),
+                });
+            }
             Record::YomichanAudioNhk16(audio) => {
                 let conv = Audio {
                     kind: RecordKind::YomichanAudioNhk16,
@@ -186,15 +325,21 @@ pub fn group_terms(entries: &[RecordEntry]) -> Vec<RecordTerm> {
                     kind: RecordKind::YomichanAudioShinmeikai8,
                     blob: audio_blob(&audio.audio),
                 };
-                if let Some(pos) = audio.pitch_number {
-                    info.pitches
-                        .entry(pos)
-                        .or_insert_with(|| base_pitch(term, pos))
-                        .audio
-                        .push(conv);
-                } else {
-                    info.audio_no_pitch.entry(source).or_default().push(conv);
-                }
+                add_audio_with_pitch(info, term, source, conv, audio.pitch_number);
+            }
+            Record::YomichanAudioDaijisen(audio) => {
+                let conv = Audio {
+                    kind: RecordKind::YomichanAudioDaijisen,
+                    blob: audio_blob(&audio.audio),
+                };
+                add_audio_with_pitch(info, term, source, conv, audio.pitch_number);
+            }
+            Record::YomichanAudioOubunsha(audio) => {
+                let conv = Audio {
+                    kind: RecordKind::YomichanAudioOubunsha,
+                    blob: audio_blob(&audio.audio),
+                };
+                add_audio_with_pitch(info, term, source, conv, audio.pitch_number);
             }
             _ => {}
         }
@@ -206,6 +351,64 @@ pub fn group_terms(entries: &[RecordEntry]) -> Vec<RecordTerm> {
         .collect()
 }
 
+/// Groups the kanji records in the results of [`Engine::lookup_kanji`] by
+/// the character they describe.
+pub fn group_kanji(entries: &[RecordEntry]) -> Vec<RecordKanji<'_>> {
+    let mut groups = IndexMap::<Term, KanjiInfo>::default();
+    for record in entries {
+        let source = record.source;
+        match &record.record {
+            Record::YomitanKanji(kanji) => {
+                groups
+                    .entry(record.term.clone())
+                    .or_default()
+                    .kanji_groups
+                    .entry(source)
+                    .or_default()
+                    .push(kanji);
+            }
+            Record::YomitanKanjiFrequency(frequency) => {
+                groups
+                    .entry(record.term.clone())
+                    .or_default()
+                    .frequencies
+                    .entry(source)
+                    .or_default()
+                    .push(frequency);
+            }
+            Record::KanjidicCharacter(character) => {
+                groups
+                    .entry(record.term.clone())
+                    .or_default()
+                    .characters
+                    .entry(source)
+                    .or_default()
+                    .push(character);
+            }
+            _ => {}
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(term, info)| RecordKanji { term, info })
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecordKanji<'a> {
+    pub term: Term,
+    #[serde(flatten)]
+    pub info: KanjiInfo<'a>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct KanjiInfo<'a> {
+    pub kanji_groups: IndexMap<DictionaryId, Vec<&'a dict::yomitan::Kanji>>,
+    pub frequencies: IndexMap<DictionaryId, Vec<&'a dict::yomitan::KanjiFrequency>>,
+    pub characters: IndexMap<DictionaryId, Vec<&'a dict::kanjidic::Character>>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct RecordTerm<'a> {
     pub term: Term,
@@ -217,15 +420,19 @@ pub struct RecordTerm<'a> {
 pub struct TermInfo<'a> {
     pub furigana_parts: Vec<(String, String)>,
     pub morae: Vec<String>,
+    pub term_tags: Vec<&'a dict::yomitan::GlossaryTag>,
+    pub inflection_rule_chains: Vec<Vec<String>>,
     pub glossary_groups: IndexMap<DictionaryId, Vec<Glossary<'a>>>,
     pub frequencies: IndexMap<DictionaryId, Vec<&'a dict::yomitan::Frequency>>,
     pub pitches: IndexMap<dict::jpn::PitchPosition, Pitch<'a>>,
+    pub phonetics: Vec<&'a dict::yomitan::Phonetic>,
     pub audio_no_pitch: IndexMap<DictionaryId, Vec<Audio>>,
+    pub example_sentences: IndexMap<DictionaryId, Vec<dict::tatoeba::Sentence>>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct Glossary<'a> {
-    pub tags: &'a Vec<dict::yomitan::GlossaryTag>,
+    pub tags: &'a [dict::yomitan::GlossaryTag],
     pub content: Vec<String>,
 }
 
@@ -237,6 +444,26 @@ pub struct Pitch<'a> {
     pub audio: Vec<Audio>,
 }
 
+/// Adds an audio file under the pitch accent that it is pronounced with, or
+/// under its dictionary if the pitch accent isn't known.
+fn add_audio_with_pitch(
+    info: &mut TermInfo<'_>,
+    term: &Term,
+    source: DictionaryId,
+    audio: Audio,
+    pitch: Option<dict::jpn::PitchPosition>,
+) {
+    if let Some(pos) = pitch {
+        info.pitches
+            .entry(pos)
+            .or_insert_with(|| base_pitch(term, pos))
+            .audio
+            .push(audio);
+    } else {
+        info.audio_no_pitch.entry(source).or_default().push(audio);
+    }
+}
+
 #[must_use]
 pub fn base_pitch<'a>(term: &Term, downstep: dict::jpn::PitchPosition) -> Pitch<'a> {
     let Some(reading) = term.reading() else {
@@ -278,14 +505,15 @@ fn audio_blob(audio: &dict::yomichan_audio::Audio) -> String {
 const _: () = {
     use crate::{FfiResult, Wordbase};
 
-    #[uniffi::export]
+    #[uniffi::export(async_runtime = "tokio")]
     impl Wordbase {
-        pub fn render_html_body(
+        pub async fn render_html_body(
             &self,
+            profile_id: ProfileId,
             entries: &[RecordEntry],
             config: &RenderConfig,
         ) -> FfiResult<String> {
-            Ok(self.0.render_html_body(entries, config)?)
+            Ok(self.0.render_html_body(profile_id, entries, config).await?)
         }
     }
 };
@@ -0,0 +1,135 @@
+//! Example sentences, which show how a term is used in context.
+
+use {
+    crate::{Engine, db},
+    anyhow::{Context, Result},
+    serde::Serialize,
+    std::slice,
+    wordbase_api::{DictionaryId, ProfileId, Term, dict::tatoeba::Sentence},
+};
+
+/// Example sentence returned by [`Engine::example_sentences`].
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct ExampleSentence {
+    /// ID of the dictionary that this sentence is from.
+    pub source: DictionaryId,
+    /// The sentence and its translations.
+    pub sentence: Sentence,
+}
+
+impl Engine {
+    /// Fetches up to `limit` example sentences which contain `term`, from the
+    /// dictionaries enabled in this profile.
+    ///
+    /// If the sentence index specifies which reading a word is used with, then
+    /// only sentences using the same reading as `term` are returned.
+    ///
+    /// Sentences are ordered by the position of their dictionary, then in the
+    /// order that they were imported.
+    pub async fn example_sentences(
+        &self,
+        profile_id: ProfileId,
+        term: &Term,
+        limit: u32,
+    ) -> Result<Vec<ExampleSentence>> {
+        let mut sentences = self
+            .example_sentences_of_terms(profile_id, slice::from_ref(term), limit)
+            .await?;
+        Ok(sentences.pop().unwrap_or_default())
+    }
+
+    /// Fetches up to `limit` example sentences for each one of `terms`, in a
+    /// single query.
+    ///
+    /// The returned list has the sentences of each term at the same index as
+    /// the term. See [`Engine::example_sentences`].
+    pub async fn example_sentences_of_terms(
+        &self,
+        profile_id: ProfileId,
+        terms: &[Term],
+        limit: u32,
+    ) -> Result<Vec<Vec<ExampleSentence>>> {
+        // sentences are always indexed by headword, even for kana-only words
+        let queries = terms
+            .iter()
+            .map(|term| match (term.headword(), term.reading()) {
+                (Some(headword), reading) => Some((headword, reading)),
+                (None, Some(reading)) => Some((reading, None)),
+                (None, None) => None,
+            })
+            .collect::<Vec<_>>();
+        let queries = serde_json::to_string(&queries).context("failed to serialize terms")?;
+
+        let records = sqlx::query!(
+            r#"WITH query AS (
+                SELECT CAST(key AS INTEGER) AS term_index, value FROM json_each($2)
+            )
+            SELECT
+                query.term_index AS "term_index!: i64",
+                record.source,
+                record.data
+            FROM query
+            JOIN record ON record.id IN (
+                SELECT example_sentence.record
+                FROM example_sentence
+                INDEXED BY example_sentence_query
+
+                -- make sure the dictionary we're getting this sentence from is enabled
+                INNER JOIN dictionary ON example_sentence.source = dictionary.id
+                INNER JOIN profile_enabled_dictionary ped
+                    ON (ped.profile = $1 AND ped.dictionary = dictionary.id)
+
+                WHERE
+                    example_sentence.headword = json_extract(query.value, '$[0]')
+                    AND (
+                        json_extract(query.value, '$[1]') IS NULL
+                        OR example_sentence.reading IS NULL
+                        OR example_sentence.reading = json_extract(query.value, '$[1]')
+                    )
+                ORDER BY dictionary.position, example_sentence.record
+                LIMIT $3
+            )
+            JOIN dictionary ON record.source = dictionary.id
+            ORDER BY query.term_index, dictionary.position, record.id"#,
+            profile_id.0,
+            queries,
+            limit,
+        )
+        .fetch_all(&self.db)
+        .await
+        .context("failed to fetch example sentences")?;
+
+        let mut sentences = vec![Vec::new(); terms.len()];
+        for record in records {
+            let sentence = db::deserialize::<Sentence>(&record.data)
+                .context("failed to deserialize sentence")?;
+            usize::try_from(record.term_index)
+                .ok()
+                .and_then(|index| sentences.get_mut(index))
+                .context("sentence returned for invalid term index")?
+                .push(ExampleSentence {
+                    source: DictionaryId(record.source),
+                    sentence,
+                });
+        }
+        Ok(sentences)
+    }
+}
+
+#[cfg(feature = "uniffi")]
+const _: () = {
+    use crate::{FfiResult, Wordbase};
+
+    #[uniffi::export(async_runtime = "tokio")]
+    impl Wordbase {
+        pub async fn example_sentences(
+            &self,
+            profile_id: ProfileId,
+            term: &Term,
+            limit: u32,
+        ) -> FfiResult<Vec<ExampleSentence>> {
+            Ok(self.0.example_sentences(profile_id, term, limit).await?)
+        }
+    }
+};
@@ -0,0 +1,185 @@
+//! Fixtures and helpers shared between tests.
+
+use {
+    crate::{
+        Engine,
+        import::{
+            Archive, ImportError, ImportEvent, ImportJobId, ImportKind, ImportOptions, OpenArchive,
+        },
+    },
+    async_zip::{
+        Compression, StoredZipEntry, ZipEntryBuilder,
+        base::{read::mem, write::ZipFileWriter},
+    },
+    futures::{Stream, TryStreamExt},
+    serde_json::Value,
+    std::{
+        io::{self, SeekFrom},
+        path::{Path, PathBuf},
+        pin::Pin,
+        sync::Arc,
+        task::{Context, Poll, ready},
+    },
+    tokio::{
+        fs::File,
+        io::{AsyncBufRead, AsyncRead, AsyncSeek, BufReader, ReadBuf},
+    },
+    wordbase_api::{DictionaryId, DictionaryKind},
+};
+
+/// Creates an uncompressed zip of `(path, data)` files.
+pub async fn zip(files: impl IntoIterator<Item = (impl AsRef<str>, impl AsRef<[u8]>)>) -> Vec<u8> {
+    let mut writer = ZipFileWriter::new(Vec::new());
+    for (name, data) in files {
+        let entry = ZipEntryBuilder::new(name.as_ref().to_owned().into(), Compression::Stored);
+        writer
+            .write_entry_whole(entry, data.as_ref())
+            .await
+            .unwrap();
+    }
+    writer.close().await.unwrap()
+}
+
+/// Creates a Yomitan dictionary with this `index.json`, and one term bank per
+/// element of `term_banks`.
+pub async fn yomitan_zip(index: &Value, term_banks: &[Value]) -> Vec<u8> {
+    let term_banks = term_banks
+        .iter()
+        .enumerate()
+        .map(|(i, bank)| (format!("term_bank_{}.json", i + 1), bank.to_string()));
+    zip(std::iter::once(("index.json".to_owned(), index.to_string())).chain(term_banks)).await
+}
+
+/// Writes a [`yomitan_zip`] to `path`.
+pub async fn write_yomitan_zip(path: &Path, index: &Value, term_banks: &[Value]) {
+    tokio::fs::write(path, yomitan_zip(index, term_banks).await)
+        .await
+        .unwrap();
+}
+
+/// Runs an import to completion, returning the ID of the dictionary it
+/// imported into.
+pub async fn done(
+    events: impl Stream<Item = Result<ImportEvent, ImportError>>,
+) -> Result<DictionaryId, ImportError> {
+    let ids = events
+        .try_filter_map(|event| async move {
+            Ok(match event {
+                ImportEvent::Done(id) => Some(id),
+                _ => None,
+            })
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+    Ok(ids[0])
+}
+
+/// Archive file which stalls forever once it seeks to any offset in
+/// `stall_at`.
+struct StallingArchive {
+    file: BufReader<File>,
+    stall_at: Arc<[u64]>,
+    stalled: bool,
+}
+
+impl AsyncRead for StallingArchive {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if self.stalled {
+            return Poll::Pending;
+        }
+        Pin::new(&mut self.file).poll_read(cx, buf)
+    }
+}
+
+impl AsyncBufRead for StallingArchive {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if this.stalled {
+            return Poll::Pending;
+        }
+        Pin::new(&mut this.file).poll_fill_buf(cx)
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        Pin::new(&mut self.file).consume(amt);
+    }
+}
+
+impl AsyncSeek for StallingArchive {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        Pin::new(&mut self.file).start_seek(position)
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let pos = ready!(Pin::new(&mut self.file).poll_complete(cx))?;
+        if self.stall_at.contains(&pos) {
+            self.stalled = true;
+            return Poll::Pending;
+        }
+        Poll::Ready(Ok(pos))
+    }
+}
+
+/// Opens the zip at `path` as an archive which stalls forever once it seeks
+/// to the start of any file where `stall` is true, so that an import can be
+/// interrupted part way through.
+pub async fn stalling_archive(
+    path: PathBuf,
+    stall: impl Fn(&str) -> bool,
+) -> impl OpenArchive + 'static {
+    let zip = mem::ZipFileReader::new(tokio::fs::read(&path).await.unwrap())
+        .await
+        .unwrap();
+    let stall_at = zip
+        .file()
+        .entries()
+        .iter()
+        .filter(|entry| stall(entry.filename().as_str().unwrap()))
+        .map(StoredZipEntry::header_offset)
+        .collect::<Arc<[u64]>>();
+
+    let path = Arc::new(path);
+    move || {
+        let path = path.clone();
+        let stall_at = stall_at.clone();
+        async move {
+            let file = File::open(&*path).await?;
+            Ok(Box::new(StallingArchive {
+                file: BufReader::new(file),
+                stall_at,
+                stalled: false,
+            }) as Box<dyn Archive>)
+        }
+    }
+}
+
+/// Starts importing `archive` with `importer`, and waits until it first
+/// reports progress.
+pub async fn import_until_progress(
+    engine: &Engine,
+    archive: impl OpenArchive + 'static,
+    importer: (DictionaryKind, Arc<dyn ImportKind>),
+) -> (
+    ImportJobId,
+    impl Stream<Item = Result<ImportEvent, ImportError>>,
+) {
+    let mut events = Box::pin(engine.import_with(
+        Arc::new(archive),
+        Some(importer),
+        None,
+        ImportOptions::default(),
+    ));
+    let mut job_id = None;
+    loop {
+        match events.try_next().await.unwrap().unwrap() {
+            ImportEvent::JobStarted(id) => job_id = Some(id),
+            ImportEvent::Progress(_) => break,
+            _ => {}
+        }
+    }
+    (job_id.unwrap(), events)
+}
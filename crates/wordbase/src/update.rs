@@ -0,0 +1,331 @@
+//! Checking for and installing newer revisions of dictionaries.
+//!
+//! Only dictionaries which are [`DictionaryMeta::is_updatable`] and have an
+//! [`DictionaryMeta::index_url`] can be updated. These are Yomitan
+//! dictionaries which publish their latest `index.json` online.
+
+use {
+    crate::{
+        Engine, NotFound,
+        import::{ImportError, ImportEvent, ImportOptions},
+    },
+    anyhow::{Context, Result, bail},
+    futures::{Stream, StreamExt, future},
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+    tempfile::NamedTempFile,
+    tokio::{fs::File, io::AsyncWriteExt},
+    tracing::{debug, info, warn},
+    wordbase_api::{Dictionary, DictionaryId},
+};
+
+/// Newer revision of a dictionary which is available to download.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct DictionaryUpdate {
+    /// ID of the dictionary which can be updated.
+    pub id: DictionaryId,
+    /// Version of the dictionary which is currently imported.
+    pub current_version: Option<String>,
+    /// Revision of the latest version, as listed in its index.
+    pub latest_version: String,
+    /// URL to download the latest version from.
+    pub download_url: String,
+}
+
+/// Fields of a remote Yomitan `index.json` which we use to check for updates.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RemoteIndex {
+    revision: String,
+    download_url: Option<String>,
+}
+
+impl Engine {
+    /// Checks every updatable dictionary for a newer revision.
+    ///
+    /// Dictionaries which fail to be checked (e.g. because their index can't
+    /// be fetched) are logged and skipped.
+    pub async fn check_dictionary_updates(&self) -> Result<Vec<DictionaryUpdate>> {
+        let dictionaries = self.dictionaries();
+        let checks = dictionaries
+            .values()
+            .filter(|dictionary| dictionary.meta.is_updatable)
+            .map(|dictionary| async move {
+                let result = self.check_update(dictionary).await;
+                (dictionary, result)
+            });
+
+        let mut updates = Vec::new();
+        for (dictionary, result) in future::join_all(checks).await {
+            match result {
+                Ok(Some(update)) => updates.push(update),
+                Ok(None) => {}
+                Err(err) => {
+                    warn!(
+                        "Failed to check {:?} for updates: {err:?}",
+                        dictionary.meta.name
+                    );
+                }
+            }
+        }
+        Ok(updates)
+    }
+
+    /// Checks a single dictionary for a newer revision.
+    ///
+    /// Returns [`None`] if the imported revision is already the latest one.
+    pub async fn check_dictionary_update(
+        &self,
+        id: DictionaryId,
+    ) -> Result<Option<DictionaryUpdate>> {
+        let dictionary = self.dictionaries().get(&id).cloned().ok_or(NotFound)?;
+        self.check_update(&dictionary).await
+    }
+
+    async fn check_update(&self, dictionary: &Dictionary) -> Result<Option<DictionaryUpdate>> {
+        let meta = &dictionary.meta;
+        if !meta.is_updatable {
+            bail!("dictionary is not updatable");
+        }
+        let index_url = meta
+            .index_url
+            .as_ref()
+            .context("dictionary has no index URL")?;
+
+        debug!("Checking {:?} for updates at {index_url:?}", meta.name);
+        let index = self
+            .http_client
+            .get(index_url)
+            .send()
+            .await
+            .context("failed to send index request")?
+            .error_for_status()
+            .context("failed to fetch index")?
+            .bytes()
+            .await
+            .context("failed to read index")?;
+        let index =
+            serde_json::from_slice::<RemoteIndex>(&index).context("failed to parse index")?;
+
+        // like Yomitan, any revision which is different is treated as newer
+        if meta.version.as_ref() == Some(&index.revision) {
+            return Ok(None);
+        }
+        let download_url = index
+            .download_url
+            .or_else(|| meta.download_url.clone())
+            .context("dictionary has no download URL")?;
+        Ok(Some(DictionaryUpdate {
+            id: dictionary.id,
+            current_version: meta.version.clone(),
+            latest_version: index.revision,
+            download_url,
+        }))
+    }
+
+    /// Downloads the newer revision of a dictionary, and re-imports it in
+    /// place with [`Engine::reimport_dictionary`].
+    pub fn update_dictionary(
+        &self,
+        update: DictionaryUpdate,
+        options: ImportOptions,
+    ) -> impl Stream<Item = Result<ImportEvent, ImportError>> {
+        async_stream::try_stream! {
+            info!(
+                "Updating {:?} to {:?} from {:?}",
+                update.id, update.latest_version, update.download_url
+            );
+            let archive = self
+                .download(&update.download_url)
+                .await
+                .context("failed to download dictionary")?;
+
+            let events = self.reimport_dictionary(update.id, Arc::new(archive), options);
+            tokio::pin!(events);
+            while let Some(event) = events.next().await {
+                yield event?;
+            }
+        }
+    }
+
+    /// Downloads a file into a temporary file.
+    async fn download(&self, url: &str) -> Result<NamedTempFile> {
+        let mut response = self
+            .http_client
+            .get(url)
+            .send()
+            .await
+            .context("failed to send request")?
+            .error_for_status()
+            .context("failed to fetch file")?;
+
+        let file = NamedTempFile::new().context("failed to create temporary file")?;
+        let mut writer = File::create(file.path())
+            .await
+            .context("failed to open temporary file")?;
+        while let Some(chunk) = response.chunk().await.context("failed to read response")? {
+            writer
+                .write_all(&chunk)
+                .await
+                .context("failed to write to temporary file")?;
+        }
+        writer
+            .flush()
+            .await
+            .context("failed to flush temporary file")?;
+        Ok(file)
+    }
+}
+
+#[cfg(feature = "uniffi")]
+const _: () = {
+    use crate::{FfiResult, Wordbase};
+
+    #[uniffi::export(async_runtime = "tokio")]
+    impl Wordbase {
+        pub async fn check_dictionary_updates(&self) -> FfiResult<Vec<DictionaryUpdate>> {
+            Ok(self.0.check_dictionary_updates().await?)
+        }
+
+        pub async fn check_dictionary_update(
+            &self,
+            id: DictionaryId,
+        ) -> FfiResult<Option<DictionaryUpdate>> {
+            Ok(self.0.check_dictionary_update(id).await?)
+        }
+
+        pub async fn update_dictionary(
+            &self,
+            update: DictionaryUpdate,
+            options: ImportOptions,
+        ) -> FfiResult<DictionaryId> {
+            let events = self.0.update_dictionary(update, options);
+            tokio::pin!(events);
+            while let Some(event) = events.next().await {
+                if let ImportEvent::Done(id) = event.map_err(anyhow::Error::new)? {
+                    return Ok(id);
+                }
+            }
+            unreachable!();
+        }
+    }
+};
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::test_util::{self, done},
+        tokio::{
+            io::AsyncReadExt,
+            net::{TcpListener, TcpStream},
+        },
+    };
+
+    async fn yomitan_zip(revision: &str, base_url: &str) -> Vec<u8> {
+        let index = serde_json::json!({
+            "title": "Updatable",
+            "revision": revision,
+            "format": 3,
+            "isUpdatable": true,
+            "indexUrl": format!("{base_url}/index.json"),
+            "downloadUrl": format!("{base_url}/dictionary.zip"),
+        });
+        let term_bank = serde_json::json!([["犬", "いぬ", "", "", 0, [revision], 1, ""]]);
+        test_util::yomitan_zip(&index, &[term_bank]).await
+    }
+
+    /// Minimal HTTP server which serves a fixed set of files.
+    async fn serve(listener: TcpListener, files: Vec<(&'static str, Vec<u8>)>) {
+        async fn respond(mut stream: TcpStream, files: &[(&str, Vec<u8>)]) {
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                let mut buf = [0u8; 1024];
+                let n = stream.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    return;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            let request = String::from_utf8_lossy(&request);
+            let path = request.split_whitespace().nth(1).unwrap_or_default();
+            let response = match files.iter().find(|(file_path, _)| *file_path == path) {
+                Some((_, body)) => {
+                    let mut response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    )
+                    .into_bytes();
+                    response.extend_from_slice(body);
+                    response
+                }
+                None => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_vec(),
+            };
+            stream.write_all(&response).await.unwrap();
+        }
+
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            respond(stream, &files).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn update_preserves_config() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let index = serde_json::json!({
+            "title": "Updatable",
+            "revision": "2",
+            "format": 3,
+        });
+        let files = vec![
+            ("/index.json", index.to_string().into_bytes()),
+            ("/dictionary.zip", yomitan_zip("2", &base_url).await),
+        ];
+        tokio::spawn(serve(listener, files));
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let engine = Engine::new(data_dir.path()).await.unwrap();
+        let old_path = data_dir.path().join("old.zip");
+        tokio::fs::write(&old_path, yomitan_zip("1", &base_url).await)
+            .await
+            .unwrap();
+        let old_id = done(engine.import_dictionary(Arc::new(old_path)))
+            .await
+            .unwrap();
+
+        let profile_id = *engine.profiles().keys().next().unwrap();
+        engine.enable_dictionary(profile_id, old_id).await.unwrap();
+        engine
+            .set_sorting_dictionary(profile_id, Some(old_id))
+            .await
+            .unwrap();
+        let old_position = engine.dictionaries()[&old_id].position;
+
+        let updates = engine.check_dictionary_updates().await.unwrap();
+        assert_eq!(1, updates.len());
+        assert_eq!(old_id, updates[0].id);
+        assert_eq!(Some("1"), updates[0].current_version.as_deref());
+        assert_eq!("2", updates[0].latest_version);
+
+        let id = done(engine.update_dictionary(updates[0].clone(), ImportOptions::default()))
+            .await
+            .unwrap();
+
+        assert_eq!(old_id, id);
+
+        let dictionaries = engine.dictionaries();
+        assert_eq!(1, dictionaries.len());
+        let dictionary = &dictionaries[&id];
+        assert_eq!(Some("2"), dictionary.meta.version.as_deref());
+        assert_eq!(old_position, dictionary.position);
+        let profile = engine.profiles()[&profile_id].clone();
+        assert_eq!(vec![id], profile.enabled_dictionaries);
+        assert_eq!(Some(id), profile.sorting_dictionary);
+
+        assert!(engine.check_dictionary_updates().await.unwrap().is_empty());
+    }
+}
@@ -245,6 +245,8 @@ fun AppManagePage(modifier: Modifier = Modifier) {
                 override fun onEvent(event: ImportEvent) {
                     when (event) {
                         is ImportEvent.DeterminedKind -> {}
+                        is ImportEvent.JobStarted -> {}
+                        is ImportEvent.Warning -> {}
                         is ImportEvent.Done -> {}
                         is ImportEvent.ParsedMeta -> {
                             meta = event.v1
@@ -266,6 +268,10 @@ fun AppManagePage(modifier: Modifier = Modifier) {
                         }
                     }
                 }
+
+                override fun onFailed(error: String) {
+                    importState = importState?.withError(error)
+                }
             }
 
             try {
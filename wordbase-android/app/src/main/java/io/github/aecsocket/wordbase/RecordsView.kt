@@ -329,7 +329,9 @@ fun RawRecordsView(
     val sAddNote = stringResource(R.string.add_note)
     val sViewNote = stringResource(R.string.view_note)
     val sAddDuplicateNote = stringResource(R.string.add_duplicate_note)
-    val document by derivedStateOf {
+    val app = LocalContext.current.app()
+    var body by remember { mutableStateOf("") }
+    LaunchedEffect(entries, app.profileId) {
         // why do we need `wait_for_wordbase`?
         // because the JS bridge is injected too late
         // <https://github.com/KevinnZou/compose-webview-multiplatform/issues/238>
@@ -344,7 +346,8 @@ fun RawRecordsView(
             ))
         """.trimIndent()
 
-        val body = wordbase.renderHtmlBody(
+        body = wordbase.renderHtmlBody(
+            profileId = app.profileId,
             entries = entries,
             config = RenderConfig(
                 sAddNote = sAddNote,
@@ -356,7 +359,9 @@ fun RawRecordsView(
                 fnViewNote = jsCall("view_note"),
             ),
         )
+    }
 
+    val document by derivedStateOf {
         """
         <!doctype html>
         <html>